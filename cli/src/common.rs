@@ -1,5 +1,6 @@
 use probe_rs::{
-    architecture::arm::ap::AccessPortError, flashing::FileDownloadError, DebugProbeError, Error,
+    architecture::arm::ap::AccessPortError, flashing::FileDownloadError, CoreDumpError,
+    DebugProbeError, Error,
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -22,4 +23,6 @@ pub enum CliError {
     },
     #[error(transparent)]
     ProbeRs(#[from] Error),
+    #[error(transparent)]
+    CoreDump(#[from] CoreDumpError),
 }
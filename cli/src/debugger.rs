@@ -1,12 +1,14 @@
 use crate::common::CliError;
 
+use anyhow::anyhow;
 use capstone::Capstone;
 use num_traits::Num;
-use probe_rs::architecture::arm::Dump;
+use probe_rs::architecture::arm::fault;
 use probe_rs::debug::{DebugInfo, Registers, VariableName};
-use probe_rs::{Core, CoreRegisterAddress, MemoryInterface};
-use std::fs::File;
-use std::{io::prelude::*, time::Duration};
+use probe_rs::{Core, CoreDump, CoreType, MemoryInterface};
+use std::ops::Range;
+use std::path::Path;
+use std::time::Duration;
 
 use parse_int::parse;
 
@@ -56,7 +58,14 @@ impl DebugCli {
 
                 let mut code = [0u8; 16 * 2];
 
-                cli_data.core.read(cpu_info.pc, &mut code)?;
+                let pc = u32::try_from(cpu_info.pc).map_err(|_| {
+                    probe_rs::Error::Other(anyhow!(
+                        "Unable to read code at {:#x}, which is outside of the 32 bit address space",
+                        cpu_info.pc
+                    ))
+                })?;
+
+                cli_data.core.read(pc, &mut code)?;
 
                 /*
                 let instructions = cli_data
@@ -72,7 +81,7 @@ impl DebugCli {
                 for (offset, instruction) in code.iter().enumerate() {
                     println!(
                         "{:#010x}: {:010x}",
-                        cpu_info.pc + offset as u32,
+                        cpu_info.pc + offset as u64,
                         instruction
                     );
                 }
@@ -96,63 +105,11 @@ impl DebugCli {
                         .read_core_reg(cli_data.core.registers().program_counter())?;
                     println!("Core halted at address {:#010x}", pc);
 
-                    // determine if the target is handling an interupt
-
-                    // TODO: Proper address
-                    let xpsr = cli_data.core.read_core_reg(
-                        16,
-                    )?;
-
-                    println!("XPSR: {:#010x}", xpsr);
-
-                    let exception_number = xpsr & 0xff;
-
-                    if exception_number != 0 {
-                        println!("Currently handling exception {}", exception_number);
-
-                        if exception_number == 3 {
-                            println!("Hard Fault!");
-
-
-                            let return_address = cli_data.core.read_core_reg(cli_data.core.registers().return_address())?;
-
-                            println!("Return address (LR): {:#010x}", return_address);
-
-                            // Get reason for hard fault
-                            let hfsr = cli_data.core.read_word_32(0xE000_ED2C)?;
-
-                            if hfsr & (1 << 30) == (1 << 30) {
-                                println!("-> configurable priority exception has been escalated to hard fault!");
-
-
-                                // read cfsr 
-                                let cfsr = cli_data.core.read_word_32(0xE000_ED28)?;
-
-                                let ufsr = (cfsr >> 16) & 0xffff;
-                                let bfsr = (cfsr >> 8) & 0xff;
-                                let mmfsr = cfsr & 0xff;
-
-
-                                if ufsr != 0 {
-                                    println!("\tUsage Fault     - UFSR: {:#06x}", ufsr);
-                                }
-
-                                if bfsr != 0 {
-                                    println!("\tBus Fault       - BFSR: {:#04x}", bfsr);
-
-                                    if bfsr & (1 << 7) == (1 << 7) {
-                                        // Read address from BFAR
-                                        let bfar = cli_data.core.read_word_32(0xE000_ED38)?;
-                                        println!("\t Location       - BFAR: {:#010x}", bfar);
-                                    }
-                                }
-
-                                if mmfsr != 0 {
-                                    println!("\tMemManage Fault - BFSR: {:04x}", bfsr);
-                                }
-
-                            }
-                        }
+                    if let Some(report) =
+                        fault::analyze_fault(&mut cli_data.core, cli_data.core_type)?
+                    {
+                        println!("Core is handling a fault:");
+                        print!("{}", report);
                     }
                 }
 
@@ -449,44 +406,16 @@ impl DebugCli {
 
         cli.add_command(Command {
             name: "dump",
-            help_text: "Store a dump of the current CPU state",
-
-            function: |cli_data, _args| {
-                // dump all relevant data, stack and regs for now..
-                //
-                // stack beginning -> assume beginning to be hardcoded
-
-                let stack_top: u32 = 0x2000_0000 + 0x4000;
+            help_text: "Store an ELF core dump of the registers and RAM to the given file (default: core.elf)",
 
-                let regs = cli_data.core.registers();
-
-                let stack_bot: u32 = cli_data.core.read_core_reg(regs.stack_pointer())?;
-                let pc: u32 = cli_data.core.read_core_reg(regs.program_counter())?;
-
-                let mut stack = vec![0u8; (stack_top - stack_bot) as usize];
-
-                cli_data.core.read(stack_bot, &mut stack[..])?;
-
-                let mut dump = Dump::new(stack_bot, stack);
-
-                for i in 0..12 {
-                    dump.regs[i as usize] =
-                        cli_data
-                            .core
-                            .read_core_reg(Into::<CoreRegisterAddress>::into(i))?;
-                }
-
-                dump.regs[13] = stack_bot;
-                dump.regs[14] = cli_data.core.read_core_reg(regs.return_address())?;
-                dump.regs[15] = pc;
-
-                let serialized = ron::ser::to_string(&dump).expect("Failed to serialize dump");
+            function: |cli_data, args| {
+                let path = Path::new(args.get(0).copied().unwrap_or("core.elf"));
 
-                let mut dump_file = File::create("dump.txt").expect("Failed to create file");
+                let dump =
+                    CoreDump::capture(&mut cli_data.core, cli_data.core_type, &cli_data.ram_ranges)?;
+                dump.store(path)?;
 
-                dump_file
-                    .write_all(serialized.as_bytes())
-                    .expect("Failed to write dump file");
+                println!("Stored core dump to {}", path.display());
 
                 Ok(CliState::Continue)
             },
@@ -571,6 +500,10 @@ pub struct CliData<'p> {
     pub core: Core<'p>,
     pub debug_info: Option<DebugInfo>,
     pub capstone: Capstone,
+    /// Type of the debugged core, stored in core dumps.
+    pub core_type: CoreType,
+    /// RAM accessible by the debugged core, stored in core dumps.
+    pub ram_ranges: Vec<Range<u32>>,
 
     state: DebugState,
 }
@@ -580,6 +513,8 @@ impl<'p> CliData<'p> {
         mut core: Core<'p>,
         debug_info: Option<DebugInfo>,
         capstone: Capstone,
+        core_type: CoreType,
+        ram_ranges: Vec<Range<u32>>,
     ) -> Result<CliData, CliError> {
         let status = core.status()?;
 
@@ -603,6 +538,8 @@ impl<'p> CliData<'p> {
             core,
             debug_info,
             capstone,
+            core_type,
+            ram_ranges,
             state: debug_state,
         })
     }
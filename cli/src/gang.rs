@@ -0,0 +1,68 @@
+use anyhow::{anyhow, Context, Result};
+use probe_rs::config::{get_target_by_name, TargetSelector};
+use probe_rs::flashing::{gang_program, FileDownloadError, Format, GangOptions, GangResult};
+use probe_rs::DebugProbeSelector;
+use std::fs::File;
+
+/// Programs the image at `path` into the targets connected to `probes`, and prints a summary.
+///
+/// Fails if any target could not be programmed.
+pub fn gang(
+    chip: &str,
+    probes: &[DebugProbeSelector],
+    path: &str,
+    format: Format,
+    options: &GangOptions,
+    json: bool,
+) -> Result<()> {
+    if probes.is_empty() {
+        return Err(anyhow!("At least one probe has to be given with '--probe'"));
+    }
+
+    let target = get_target_by_name(chip)?;
+
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) => return Err(FileDownloadError::IO(e)).context("Failed to open the image."),
+    };
+
+    let mut loader = target.flash_loader();
+    loader.load(format, &mut file)?;
+
+    let results = gang_program(probes, TargetSelector::Specified(target), loader, options)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    } else {
+        print_summary(&results);
+    }
+
+    let failed = results.iter().filter(|result| !result.passed).count();
+    if failed > 0 {
+        return Err(anyhow!(
+            "{} of {} targets could not be programmed",
+            failed,
+            results.len()
+        ));
+    }
+
+    Ok(())
+}
+
+fn print_summary(results: &[GangResult]) {
+    for result in results {
+        let status = if result.passed { "PASS" } else { "FAIL" };
+
+        println!(
+            "{} {} (serial number {}) in {} ms",
+            status,
+            result.probe,
+            result.serial_number.as_deref().unwrap_or("unknown"),
+            result.duration_ms
+        );
+
+        if let Some(error) = &result.error {
+            println!("    {}", error);
+        }
+    }
+}
@@ -1,12 +1,33 @@
-use std::{sync::Mutex, time::Duration};
+use std::{path::Path, sync::Mutex, time::Duration};
 
+use probe_rs::CoreDump;
 use probe_rs_cli_util::common_options::ProbeOptions;
 
 pub fn run_gdb_server(
     common: ProbeOptions,
     connection_string: Option<&str>,
     reset_halt: bool,
+    core_dump: Option<&Path>,
 ) -> anyhow::Result<()> {
+    let gdb_connection_string = connection_string.unwrap_or("localhost:1337");
+
+    if let Some(path) = core_dump {
+        let dump = Mutex::new(CoreDump::load(path)?);
+
+        println!(
+            "Firing up GDB stub for {} at {}",
+            path.display(),
+            gdb_connection_string
+        );
+
+        if let Err(e) = probe_rs_gdb_server::run(Some(gdb_connection_string.to_owned()), &dump) {
+            eprintln!("During the execution of GDB an error was encountered:");
+            eprintln!("{:?}", e);
+        }
+
+        return Ok(());
+    }
+
     let session = Mutex::new(common.simple_attach()?);
 
     if reset_halt {
@@ -17,7 +38,6 @@ pub fn run_gdb_server(
             .reset_and_halt(Duration::from_millis(100))?;
     }
 
-    let gdb_connection_string = connection_string.unwrap_or("localhost:1337");
     println!("Firing up GDB stub at {}", gdb_connection_string);
 
     if let Err(e) = probe_rs_gdb_server::run(Some(gdb_connection_string.to_owned()), &session) {
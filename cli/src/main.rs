@@ -1,15 +1,21 @@
 mod common;
 mod debugger;
+mod gang;
 mod gdb;
 mod info;
+mod read;
 mod run;
 
 use debugger::CliState;
 
 use probe_rs::{
+    config::MemoryRegion,
     debug::DebugInfo,
-    flashing::{erase_all, BinOptions, FileDownloadError, Format},
-    MemoryInterface, Probe,
+    flashing::{
+        erase_all, erase_range, erase_region, BinOptions, FileDownloadError, Format, GangOptions,
+        Uf2Options,
+    },
+    CoreDump, DebugProbeSelector, MemoryInterface, Permissions, Probe, WireProtocol,
 };
 
 use probe_rs_cli_util::{
@@ -17,6 +23,7 @@ use probe_rs_cli_util::{
     clap::Parser,
     common_options::{print_chip_info, print_families, CargoOptions, FlashOptions, ProbeOptions},
     flash::run_flash_download,
+    rtt,
 };
 
 use capstone::{arch::arm::ArchMode, prelude::*, Capstone, Endian};
@@ -26,7 +33,7 @@ use anyhow::{anyhow, Context, Result};
 
 use std::{fs::File, path::PathBuf};
 use std::{io, time::Instant};
-use std::{num::ParseIntError, path::Path};
+use std::{num::ParseIntError, ops::Range, path::Path};
 
 #[derive(clap::Parser)]
 #[clap(
@@ -67,6 +74,11 @@ enum Cli {
             help = "Use this flag to reset and halt (instead of just a halt) the attached core after attaching to the target."
         )]
         reset_halt: bool,
+
+        #[clap(long, parse(from_os_str))]
+        /// Serve an ELF core dump instead of attaching to a target
+        core_dump: Option<PathBuf>,
+
         #[clap(flatten)]
         common: ProbeOptions,
     },
@@ -81,6 +93,10 @@ enum Cli {
         #[structopt(long, parse(from_os_str))]
         /// Binary to debug
         exe: Option<PathBuf>,
+
+        #[clap(long, parse(from_os_str))]
+        /// Debug an ELF core dump instead of attaching to a target
+        core_dump: Option<PathBuf>,
     },
     /// Dump memory from attached target
     Dump {
@@ -112,6 +128,9 @@ enum Cli {
         /// The number of bytes to skip at the start of the binary file. This is only considered when `bin` is selected as the format.
         #[structopt(long, parse(try_from_str = parse_u32))]
         skip_bytes: Option<u32>,
+        /// Only download the blocks of this family ID. This is only considered when `uf2` is selected as the format.
+        #[structopt(long, parse(try_from_str = parse_u32))]
+        family_id: Option<u32>,
 
         /// The path to the file to be downloaded to the flash
         path: String,
@@ -128,10 +147,124 @@ enum Cli {
         #[structopt(long = "disable-double-buffering")]
         disable_double_buffering: bool,
     },
-    /// Erase all nonvolatile memory of attached target
+    /// Read memory from the attached target into a file
+    Read {
+        #[structopt(flatten)]
+        common: ProbeOptions,
+
+        /// Format of the file the memory is stored in. Possible values are case-insensitive.
+        #[clap(arg_enum, ignore_case = true, default_value = "bin", long)]
+        format: read::ReadFileType,
+
+        /// An address range to read, e.g. '0x08000000..0x08010000'. Can be given multiple times.
+        #[structopt(long, parse(try_from_str = parse_range))]
+        range: Vec<Range<u32>>,
+        /// The name of an NVM region to read. Can be given multiple times.
+        /// If neither a range nor a region is given, all NVM regions are read.
+        #[structopt(long)]
+        region: Vec<String>,
+
+        /// Compare the memory with this image, and report the differences.
+        /// Without a range or region, only the memory covered by the image is read.
+        #[structopt(long)]
+        compare: Option<String>,
+        /// Format of the image to compare with. Possible values are case-insensitive.
+        #[clap(arg_enum, ignore_case = true, default_value = "elf", long)]
+        compare_format: DownloadFileType,
+        /// The address of the image to compare with. This is only considered when `bin` is selected as the format.
+        #[structopt(long, parse(try_from_str = parse_u32))]
+        compare_base_address: Option<u32>,
+
+        /// Whether to disable fancy progress reporting
+        #[structopt(long)]
+        disable_progressbars: bool,
+
+        /// The path to the file the memory is stored in
+        path: PathBuf,
+    },
+    /// Erase all nonvolatile memory of attached target, or only the given ranges and regions
     Erase {
         #[structopt(flatten)]
         common: ProbeOptions,
+
+        /// Only erase this address range, e.g. '0x08000000..0x08010000'. Can be given multiple times.
+        #[structopt(long, parse(try_from_str = parse_range))]
+        range: Vec<Range<u32>>,
+        /// Only erase the NVM region with this name. Can be given multiple times.
+        #[structopt(long)]
+        region: Vec<String>,
+        /// Erase all sectors overlapping the given ranges, instead of rejecting ranges which do not start and end at sector boundaries.
+        #[structopt(long)]
+        extend_to_sectors: bool,
+    },
+    /// Download the same image to several targets in parallel, one probe per target
+    Gang {
+        /// The name of the chip on all boards.
+        #[structopt(long)]
+        chip: String,
+
+        /// A probe connected to one of the boards, as 'VID:PID:Serial'. Has to be given once per board.
+        /// The serial number can be left out if only one connected probe has this VID and PID.
+        #[structopt(long = "probe", required = true)]
+        probes: Vec<DebugProbeSelector>,
+
+        /// Protocol used to connect to the chips. Possible options: [swd, jtag]
+        #[structopt(long)]
+        protocol: Option<WireProtocol>,
+
+        /// The protocol speed in kHz.
+        #[structopt(long)]
+        speed: Option<u32>,
+
+        /// Use this flag to assert the nreset & ntrst pins during attaching the probes to the chips.
+        #[structopt(long)]
+        connect_under_reset: bool,
+
+        /// Use this flag to allow all memory, including security keys and 3rd party firmware, to be erased
+        /// even when it has read-only protection.
+        #[structopt(long)]
+        allow_erase_all: bool,
+
+        /// Format of the file to be downloaded to the flash. Possible values are case-insensitive.
+        #[clap(arg_enum, ignore_case = true, default_value = "elf", long)]
+        format: DownloadFileType,
+
+        /// The address in memory where the binary will be put at. This is only considered when `bin` is selected as the format.
+        #[structopt(long, parse(try_from_str = parse_u32))]
+        base_address: Option<u32>,
+        /// The number of bytes to skip at the start of the binary file. This is only considered when `bin` is selected as the format.
+        #[structopt(long, parse(try_from_str = parse_u32))]
+        skip_bytes: Option<u32>,
+        /// Only download the blocks of this family ID. This is only considered when `uf2` is selected as the format.
+        #[structopt(long, parse(try_from_str = parse_u32))]
+        family_id: Option<u32>,
+
+        /// The path to the file to be downloaded to the flash
+        path: String,
+
+        /// Whether to erase the entire chips before downloading
+        #[structopt(long)]
+        chip_erase: bool,
+
+        /// Verify the flash contents after downloading
+        #[structopt(long)]
+        verify: bool,
+
+        /// Skip sectors whose contents already match the image
+        #[structopt(long)]
+        skip_unchanged: bool,
+
+        /// Disable double-buffering when downloading flash.  If downloading times out, try this option.
+        #[structopt(long = "disable-double-buffering")]
+        disable_double_buffering: bool,
+
+        /// Reset the targets after downloading
+        #[structopt(long)]
+        reset: bool,
+
+        /// Print the summary as JSON
+        #[structopt(long)]
+        json: bool,
     },
     /// Flash and run an ELF program
     #[structopt(name = "run")]
@@ -149,6 +282,24 @@ enum Cli {
         /// Disable double-buffering when downloading flash.  If downloading times out, try this option.
         #[structopt(long = "disable-double-buffering")]
         disable_double_buffering: bool,
+
+        /// How the standard input is forwarded to the RTT down channel.
+        #[clap(arg_enum, ignore_case = true, default_value = "line", long)]
+        rtt_input: run::RttInputMode,
+
+        /// The RTT down channel which receives the standard input. Defaults to down channel 0.
+        #[clap(long)]
+        rtt_down_channel: Option<usize>,
+
+        /// Serve each RTT channel on a socket, instead of printing the output and forwarding the standard input.
+        /// Specify a port, where channel N is served on the port plus N, an '<ip>:<port>' address, or 'unix:<directory>'.
+        #[clap(long)]
+        rtt_server: Option<rtt::RttServerAddress>,
+
+        /// Switch the RTT up channels to blocking mode for this many milliseconds after attaching,
+        /// so the logs of the target are not dropped while it boots.
+        #[clap(long)]
+        rtt_block_if_full_ms: Option<u64>,
     },
     /// Trace a memory location on the target
     #[structopt(name = "trace")]
@@ -201,7 +352,13 @@ fn main() -> Result<()> {
             gdb_connection_string,
             common,
             reset_halt,
-        } => gdb::run_gdb_server(common, gdb_connection_string.as_deref(), reset_halt),
+            core_dump,
+        } => gdb::run_gdb_server(
+            common,
+            gdb_connection_string.as_deref(),
+            reset_halt,
+            core_dump.as_deref(),
+        ),
         Cli::Reset {
             shared,
             common,
@@ -211,7 +368,8 @@ fn main() -> Result<()> {
             shared,
             common,
             exe,
-        } => debug(&shared, &common, exe),
+            core_dump,
+        } => debug(&shared, &common, exe, core_dump.as_deref()),
         Cli::Dump {
             shared,
             common,
@@ -223,25 +381,115 @@ fn main() -> Result<()> {
             format,
             base_address,
             skip_bytes,
+            family_id,
             path,
             chip_erase,
             disable_progressbars,
             disable_double_buffering,
         } => download_program_fast(
             common,
-            format.into(base_address, skip_bytes),
+            format.into(base_address, skip_bytes, family_id),
             &path,
             chip_erase,
             disable_progressbars,
             disable_double_buffering,
         ),
+        Cli::Gang {
+            chip,
+            probes,
+            protocol,
+            speed,
+            connect_under_reset,
+            allow_erase_all,
+            format,
+            base_address,
+            skip_bytes,
+            family_id,
+            path,
+            chip_erase,
+            verify,
+            skip_unchanged,
+            disable_double_buffering,
+            reset,
+            json,
+        } => {
+            let mut permissions = Permissions::new();
+            if allow_erase_all {
+                permissions = permissions.allow_erase_all();
+            }
+
+            gang::gang(
+                &chip,
+                &probes,
+                &path,
+                format.into(base_address, skip_bytes, family_id),
+                &GangOptions {
+                    protocol,
+                    speed_khz: speed,
+                    connect_under_reset,
+                    permissions,
+                    do_chip_erase: chip_erase,
+                    verify,
+                    skip_unchanged_sectors: skip_unchanged,
+                    disable_double_buffering,
+                    reset,
+                },
+                json,
+            )
+        }
         Cli::Run {
             common,
             path,
             chip_erase,
             disable_double_buffering,
-        } => run::run(common, &path, chip_erase, disable_double_buffering),
-        Cli::Erase { common } => erase(&common),
+            rtt_input,
+            rtt_down_channel,
+            rtt_server,
+            rtt_block_if_full_ms,
+        } => {
+            let exit_code = run::run(
+                common,
+                &path,
+                chip_erase,
+                disable_double_buffering,
+                run::RttOptions {
+                    input: rtt_input,
+                    down_channel: rtt_down_channel,
+                    server: rtt_server,
+                    block_if_full_ms: rtt_block_if_full_ms,
+                },
+            )?;
+            // Forward the exit code of the target application, reported using semihosting.
+            std::process::exit(exit_code)
+        }
+        Cli::Read {
+            common,
+            format,
+            range,
+            region,
+            compare,
+            compare_format,
+            compare_base_address,
+            disable_progressbars,
+            path,
+        } => read::read(
+            common,
+            &path,
+            read::ReadOptions {
+                format,
+                ranges: range,
+                regions: region,
+                compare: compare
+                    .map(|image| (image, compare_format.into(compare_base_address, None, None))),
+                disable_progressbars,
+            },
+        ),
+        Cli::Erase {
+            common,
+            range,
+            region,
+            extend_to_sectors,
+        } => erase(&common, &range, &region, extend_to_sectors),
         Cli::Trace {
             shared,
             common,
@@ -320,11 +568,7 @@ fn download_program_fast(
 
     let mut loader = session.target().flash_loader();
 
-    match format {
-        Format::Bin(options) => loader.load_bin_data(&mut file, options),
-        Format::Elf => loader.load_elf_data(&mut file),
-        Format::Hex => loader.load_hex_data(&mut file),
-    }?;
+    loader.load(format, &mut file)?;
 
     run_flash_download(
         &mut session,
@@ -338,6 +582,7 @@ fn download_program_fast(
             reset_halt: false,
             log: None,
             restore_unwritten: false,
+            skip_unchanged: false,
             flash_layout_output_path: None,
             elf: None,
             work_dir: None,
@@ -351,10 +596,25 @@ fn download_program_fast(
     Ok(())
 }
 
-fn erase(common: &ProbeOptions) -> Result<()> {
+fn erase(
+    common: &ProbeOptions,
+    ranges: &[Range<u32>],
+    regions: &[String],
+    extend_to_sectors: bool,
+) -> Result<()> {
     let mut session = common.simple_attach()?;
 
-    erase_all(&mut session)?;
+    if ranges.is_empty() && regions.is_empty() {
+        erase_all(&mut session)?;
+        return Ok(());
+    }
+
+    for range in ranges {
+        erase_range(&mut session, range.clone(), extend_to_sectors)?;
+    }
+    for region in regions {
+        erase_region(&mut session, region)?;
+    }
 
     Ok(())
 }
@@ -419,9 +679,12 @@ fn trace_u32_on_target(
     }
 }
 
-fn debug(shared_options: &CoreOptions, common: &ProbeOptions, exe: Option<PathBuf>) -> Result<()> {
-    let mut session = common.simple_attach()?;
-
+fn debug(
+    shared_options: &CoreOptions,
+    common: &ProbeOptions,
+    exe: Option<PathBuf>,
+    core_dump: Option<&Path>,
+) -> Result<()> {
     let cs = Capstone::new()
         .arm()
         .mode(ArchMode::Thumb)
@@ -433,11 +696,39 @@ fn debug(shared_options: &CoreOptions, common: &ProbeOptions, exe: Option<PathBu
         .as_ref()
         .and_then(|path| DebugInfo::from_file(path).ok());
 
-    let cli = debugger::DebugCli::new();
+    if let Some(path) = core_dump {
+        let mut dump = CoreDump::load(path)?;
+        let core_type = dump.core_type();
+        let ram_ranges = dump.memory_ranges().collect();
+
+        let cli_data = debugger::CliData::new(dump.core(), di, cs, core_type, ram_ranges)?;
+
+        return run_debug_cli(cli_data);
+    }
+
+    let mut session = common.simple_attach()?;
+
+    let (_, core_type) = session.list_cores()[shared_options.core];
+    let core_name = &session.target().cores[shared_options.core].name;
+    let ram_ranges = session
+        .target()
+        .memory_map
+        .iter()
+        .filter_map(|region| match region {
+            MemoryRegion::Ram(ram) if ram.cores.contains(core_name) => Some(ram.range.clone()),
+            _ => None,
+        })
+        .collect();
 
     let core = session.core(shared_options.core)?;
 
-    let mut cli_data = debugger::CliData::new(core, di, cs)?;
+    let cli_data = debugger::CliData::new(core, di, cs, core_type, ram_ranges)?;
+
+    run_debug_cli(cli_data)
+}
+
+fn run_debug_cli(mut cli_data: debugger::CliData) -> Result<()> {
+    let cli = debugger::DebugCli::new();
 
     let mut rl = Editor::<()>::new();
 
@@ -480,10 +771,12 @@ enum DownloadFileType {
     Elf,
     Hex,
     Bin,
+    Uf2,
+    Srec,
 }
 
 impl DownloadFileType {
-    fn into(self, base_address: Option<u32>, skip: Option<u32>) -> Format {
+    fn into(self, base_address: Option<u32>, skip: Option<u32>, family_id: Option<u32>) -> Format {
         match self {
             DownloadFileType::Elf => Format::Elf,
             DownloadFileType::Hex => Format::Hex,
@@ -491,6 +784,8 @@ impl DownloadFileType {
                 base_address,
                 skip: skip.unwrap_or(0),
             }),
+            DownloadFileType::Uf2 => Format::Uf2(Uf2Options { family_id }),
+            DownloadFileType::Srec => Format::Srec,
         }
     }
 }
@@ -498,3 +793,19 @@ impl DownloadFileType {
 fn parse_u32(input: &str) -> Result<u32, ParseIntError> {
     parse_int::parse(input)
 }
+
+/// Parses an address range like `0x1000..0x2000`.
+fn parse_range(input: &str) -> Result<Range<u32>, String> {
+    let (start, end) = input
+        .split_once("..")
+        .ok_or_else(|| format!("'{}' is not a range like '0x1000..0x2000'", input))?;
+
+    let start = parse_u32(start).map_err(|e| e.to_string())?;
+    let end = parse_u32(end).map_err(|e| e.to_string())?;
+
+    if start >= end {
+        return Err(format!("The range '{}' is empty", input));
+    }
+
+    Ok(start..end)
+}
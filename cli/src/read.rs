@@ -0,0 +1,204 @@
+use anyhow::{anyhow, Context, Result};
+use probe_rs::config::MemoryRegion;
+use probe_rs::flashing::{FileDownloadError, Format, ImageDifference, ImageFormat, MemoryImage};
+use probe_rs_cli_util::clap;
+use probe_rs_cli_util::common_options::ProbeOptions;
+use probe_rs_cli_util::indicatif::{ProgressBar, ProgressStyle};
+use std::fs::File;
+use std::ops::Range;
+use std::path::Path;
+use std::time::Instant;
+
+/// The number of bytes printed for each difference.
+const MAX_PRINTED_BYTES: usize = 16;
+
+/// Format of the file the memory is stored in.
+#[derive(clap::ArgEnum, Debug, Clone, Copy)]
+pub enum ReadFileType {
+    Bin,
+    Hex,
+    Elf,
+}
+
+impl From<ReadFileType> for ImageFormat {
+    fn from(file_type: ReadFileType) -> Self {
+        match file_type {
+            ReadFileType::Bin => ImageFormat::Bin,
+            ReadFileType::Hex => ImageFormat::Hex,
+            ReadFileType::Elf => ImageFormat::Elf,
+        }
+    }
+}
+
+/// What is read, and how it is stored.
+pub struct ReadOptions {
+    /// The format of the output file.
+    pub format: ReadFileType,
+    /// Address ranges to read.
+    pub ranges: Vec<Range<u32>>,
+    /// Names of NVM regions to read.
+    pub regions: Vec<String>,
+    /// An image to compare the memory with, and its format.
+    pub compare: Option<(String, Format)>,
+    /// Whether to disable fancy progress reporting.
+    pub disable_progressbars: bool,
+}
+
+/// Reads the memory given in `options`, stores it at `path`, and compares it with an image.
+///
+/// If no ranges or regions are given, the ranges of the compared image are read, or all NVM
+/// regions if there is no image.
+pub fn read(common: ProbeOptions, path: &Path, options: ReadOptions) -> Result<()> {
+    let mut session = common.simple_attach()?;
+
+    let expected = match &options.compare {
+        Some((image_path, format)) => {
+            let mut file = match File::open(image_path) {
+                Ok(file) => file,
+                Err(e) => {
+                    return Err(FileDownloadError::IO(e)).context("Failed to open the image.")
+                }
+            };
+
+            let mut loader = session.target().flash_loader();
+            loader.load(format.clone(), &mut file)?;
+
+            let mut expected = MemoryImage::new();
+            for (address, data) in loader.data() {
+                expected.add(address, data)?;
+            }
+            Some(expected)
+        }
+        None => None,
+    };
+
+    let mut ranges = options.ranges;
+    for name in &options.regions {
+        let region = session
+            .target()
+            .nvm_region_by_name(name)
+            .ok_or_else(|| anyhow!("The target has no NVM region named '{}'", name))?;
+        ranges.push(region.range.clone());
+    }
+
+    if ranges.is_empty() {
+        ranges = match &expected {
+            Some(expected) => expected
+                .segments()
+                .map(|(address, data)| address..address + data.len() as u32)
+                .collect(),
+            None => session
+                .target()
+                .memory_map
+                .iter()
+                .filter_map(|region| match region {
+                    MemoryRegion::Nvm(region) => Some(region.range.clone()),
+                    _ => None,
+                })
+                .collect(),
+        };
+    }
+
+    // Overlapping ranges are read once.
+    let ranges = MemoryImage::merge_ranges(&ranges);
+    let total_size: u64 = ranges
+        .iter()
+        .map(|range| u64::from(range.end - range.start))
+        .sum();
+
+    let progress_bar = if options.disable_progressbars {
+        None
+    } else {
+        let progress_bar = ProgressBar::new(total_size);
+        progress_bar.set_style(ProgressStyle::default_bar()
+            .progress_chars("##-")
+            .template("{msg:.green.bold} [{elapsed_precise}] [{wide_bar}] {bytes:>8}/{total_bytes:>8} @ {bytes_per_sec:>10} (eta {eta:3})"));
+        progress_bar.set_message("    Reading memory");
+        Some(progress_bar)
+    };
+
+    let instant = Instant::now();
+
+    let image = MemoryImage::read(&mut session, &ranges, |size| {
+        if let Some(progress_bar) = &progress_bar {
+            progress_bar.inc(size.into());
+        }
+    });
+
+    if let Some(progress_bar) = &progress_bar {
+        progress_bar.finish();
+    }
+    let image = image?;
+
+    std::fs::write(
+        path,
+        image.encode(options.format.into(), session.target().architecture())?,
+    )
+    .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    println!(
+        "Read {} bytes to {} in {:?}",
+        total_size,
+        path.display(),
+        instant.elapsed()
+    );
+
+    if let (Some(expected), Some((image_path, _))) = (expected, options.compare) {
+        let differences = image.differences(&expected);
+        if differences.is_empty() {
+            println!("The memory matches {}", image_path);
+            return Ok(());
+        }
+
+        println!("The memory differs from {}:", image_path);
+        for difference in &differences {
+            print_difference(difference);
+        }
+
+        let differing_bytes: usize = differences
+            .iter()
+            .map(|difference| difference.expected.len())
+            .sum();
+        return Err(anyhow!(
+            "{} bytes in {} ranges differ from {}",
+            differing_bytes,
+            differences.len(),
+            image_path
+        ));
+    }
+
+    Ok(())
+}
+
+fn print_difference(difference: &ImageDifference) {
+    let size = difference.expected.len();
+
+    let actual = match &difference.actual {
+        Some(actual) => format!("read {}", hex_bytes(actual)),
+        None => "not read".to_string(),
+    };
+
+    println!(
+        "    {:#010x}..{:#010x} ({} bytes): expected {}, {}",
+        difference.address,
+        difference.address + size as u32,
+        size,
+        hex_bytes(&difference.expected),
+        actual
+    );
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    let mut hex = bytes
+        .iter()
+        .take(MAX_PRINTED_BYTES)
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if bytes.len() > MAX_PRINTED_BYTES {
+        hex.push_str(" ...");
+    }
+
+    hex
+}
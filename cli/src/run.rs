@@ -1,18 +1,63 @@
 use anyhow::{Context, Result};
+use crossterm::terminal;
+use crossterm::tty::IsTty;
 use probe_rs::flashing::FileDownloadError;
+use probe_rs::semihosting::{elf_uses_semihosting, Semihosting, SemihostingStatus};
+use probe_rs::{Core, CoreStatus, HaltReason};
 use probe_rs_cli_util::common_options::{CargoOptions, FlashOptions, ProbeOptions};
 use probe_rs_cli_util::flash::run_flash_download;
-use probe_rs_cli_util::rtt;
+use probe_rs_cli_util::{clap, rtt};
 use std::fs::File;
-use std::io::Write;
+use std::io::{BufRead, Cursor, Read, Write};
 use std::path::Path;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+/// How the standard input is forwarded to the RTT down channel.
+#[derive(clap::ArgEnum, Debug, Clone, Copy, PartialEq)]
+pub enum RttInputMode {
+    /// Send complete lines, with `\r\n` line endings converted to `\n`.
+    Line,
+    /// Send the input as soon as it is read, without any conversion.
+    ///
+    /// A terminal is switched to raw mode while the program runs, so every key press is sent.
+    /// Ctrl+C still stops `run`.
+    Raw,
+    /// Don't forward the standard input.
+    Off,
+}
+
+/// The number of inputs which are queued until they are sent to the target. The standard input
+/// is not read while the queue is full.
+const INPUT_QUEUE_SIZE: usize = 16;
+
+/// The byte sent by Ctrl+C when the terminal is in raw mode.
+const CTRL_C: u8 = 0x03;
+
+/// How long the RTT stream may read the data which the program sent before it stopped.
+const RTT_DRAIN_TIME: Duration = Duration::from_millis(100);
+
+/// How RTT is used while the program runs.
+pub struct RttOptions {
+    /// How the standard input is forwarded to the down channel.
+    pub input: RttInputMode,
+    /// The down channel which receives the standard input.
+    pub down_channel: Option<usize>,
+    /// Serve the channels on sockets at this address, instead of using the standard input and output.
+    pub server: Option<rtt::RttServerAddress>,
+    /// Switch the up channels to blocking mode for this many milliseconds after attaching.
+    pub block_if_full_ms: Option<u64>,
+}
 
 pub fn run(
     common: ProbeOptions,
     path: &str,
     chip_erase: bool,
     disable_double_buffering: bool,
-) -> Result<()> {
+    rtt_options: RttOptions,
+) -> Result<i32> {
     let mut session = common.simple_attach()?;
 
     let mut file = match File::open(path) {
@@ -35,6 +80,7 @@ pub fn run(
             reset_halt: false,
             log: None,
             restore_unwritten: false,
+            skip_unchanged: false,
             flash_layout_output_path: None,
             elf: None,
             work_dir: None,
@@ -45,29 +91,238 @@ pub fn run(
         chip_erase,
     )?;
 
-    let rtt_config = rtt::RttConfig::default();
+    let rtt_config = rtt::RttConfig {
+        down_channel: rtt_options.down_channel,
+        block_if_full_ms: rtt_options.block_if_full_ms,
+        ..Default::default()
+    };
 
     let memory_map = session.target().memory_map.clone();
 
-    let mut core = session.core(0)?;
-    core.reset()?;
+    session.core(0)?.reset()?;
 
-    let mut rtta = match rtt::attach_to_rtt(&mut core, &memory_map, Path::new(path), &rtt_config) {
-        Ok(target_rtt) => Some(target_rtt),
-        Err(error) => {
-            log::error!("{:?} Continuing without RTT... ", error);
-            None
-        }
+    // Without RTT or semihosting, the program does not send anything to the host, so there is nothing to wait for.
+    let elf_data = std::fs::read(path).context("Failed to read binary file.")?;
+    let uses_rtt = rtt_options.server.is_some()
+        || rtt::RttActiveTarget::get_rtt_symbol(&mut Cursor::new(&elf_data)).is_some();
+    if !uses_rtt && !elf_uses_semihosting(&elf_data) {
+        println!("The program uses neither RTT nor semihosting, it keeps running on the target.");
+        return Ok(0);
+    }
+
+    // The RTT channels are streamed on a thread, which shares the session with the loop below.
+    let shared_session = Arc::new(Mutex::new(session));
+
+    // RTT is attached once the target initialized the control block, and again after the target was reset.
+    let (mut rtta, mut rtt_server) = match &rtt_options.server {
+        Some(address) => (
+            None,
+            Some(rtt::RttServerAutoAttach::new(
+                &memory_map,
+                Path::new(path),
+                address,
+            )),
+        ),
+        None => (
+            Some(rtt::RttAutoAttach::new(
+                &shared_session,
+                &memory_map,
+                Path::new(path),
+                &rtt_config,
+            )),
+            None,
+        ),
     };
 
-    if let Some(rtta) = &mut rtta {
-        let mut stdout = std::io::stdout();
-        loop {
+    // With raw input, a terminal is switched to raw mode, so its input is not buffered by lines.
+    let terminal_raw_mode =
+        rtta.is_some() && rtt_options.input == RttInputMode::Raw && std::io::stdin().is_tty();
+
+    let stdin = match (&rtta, rtt_options.input) {
+        (Some(_), RttInputMode::Off) | (None, _) => None,
+        (Some(_), input) => Some(read_stdin(input, terminal_raw_mode)),
+    };
+
+    // The terminal is restored when the guard is dropped, before anything else is printed.
+    let mut raw_terminal = if terminal_raw_mode {
+        Some(RawTerminal::enable()?)
+    } else {
+        None
+    };
+
+    // Semihosting requests halt the core, so they have to be serviced while polling RTT.
+    let mut semihosting = Semihosting::new();
+    let mut stdout = std::io::stdout();
+    // Once the program stopped, the loop runs once more, to print the data the stream read in the meantime.
+    let mut stopped = None;
+    loop {
+        let mut session = shared_session
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut core = session.core(0)?;
+        let mut received_rtt_data = false;
+        if let Some((rtta, attached)) = rtta.as_mut().and_then(|rtta| rtta.poll_attach(&mut core)) {
+            if attached && stdin.is_some() && rtta.down_channel(rtt_config.down_channel).is_none() {
+                log::warn!(
+                    "The target has no matching RTT down channel, the standard input is ignored"
+                );
+            }
+
+            for input in stdin.iter().flat_map(Receiver::try_iter) {
+                if let Err(error) = rtta.push_rtt(rtt_config.down_channel, &input) {
+                    log::error!("Unable to write to RTT: {:?}", error);
+                }
+            }
+
             for (_ch, data) in rtta.poll_rtt(&mut core) {
+                // A terminal in raw mode does not return the cursor to the start of a new line.
+                let data = match raw_terminal {
+                    Some(_) => data.replace('\n', "\r\n"),
+                    None => data,
+                };
                 stdout.write_all(data.as_bytes()).unwrap();
+                received_rtt_data = true;
+            }
+        }
+
+        if let Some(rtt_server) = &mut rtt_server {
+            received_rtt_data |= rtt_server.poll(&mut core)?;
+        }
+
+        if let Some(status) = stopped {
+            // Channels left in blocking mode would block the program, once it runs again without a host.
+            if let Some(rtta) = &mut rtta {
+                rtta.detach(&mut core);
+            }
+
+            return match status {
+                SemihostingStatus::Exit(exit_code) => Ok(exit_code),
+                _ => {
+                    drop(raw_terminal.take());
+                    report_halt(&mut core)
+                }
+            };
+        }
+
+        let delay = if core.core_halted()? {
+            match semihosting.handle(&mut core)? {
+                SemihostingStatus::Handled => None,
+                status => {
+                    stopped = Some(status);
+                    Some(RTT_DRAIN_TIME)
+                }
             }
+        } else if !received_rtt_data {
+            Some(Duration::from_millis(10))
+        } else {
+            None
+        };
+
+        // The session is unlocked while waiting, so the RTT stream can access the target.
+        drop(core);
+        drop(session);
+        if let Some(delay) = delay {
+            thread::sleep(delay);
+        }
+    }
+}
+
+/// Reports why the core halted, and returns the exit code for the halt.
+///
+/// Exceptions, e.g. faults caught with vector catch, are reported as a failure.
+fn report_halt(core: &mut Core) -> Result<i32> {
+    let reason = match core.status()? {
+        CoreStatus::Halted(reason) => reason,
+        status => {
+            println!("The core stopped running: {:?}", status);
+            return Ok(0);
+        }
+    };
+
+    let pc = core.read_core_reg(core.registers().program_counter())?;
+    let description = match reason {
+        HaltReason::Breakpoint => "a breakpoint",
+        HaltReason::Exception => "an exception",
+        HaltReason::Watchpoint(_) => "a watchpoint",
+        HaltReason::Step => "a single step",
+        HaltReason::Request | HaltReason::External => "a halt request",
+        HaltReason::Multiple | HaltReason::Unknown => "an unknown reason",
+    };
+    println!(
+        "The core halted at {:#010x} because of {}.",
+        pc, description
+    );
+
+    Ok(match reason {
+        HaltReason::Exception => 1,
+        _ => 0,
+    })
+}
+
+/// Switches the terminal to raw mode, until it is dropped.
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enable() -> Result<Self> {
+        terminal::enable_raw_mode().context("Failed to switch the terminal to raw mode")?;
+
+        Ok(Self)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        if let Err(error) = terminal::disable_raw_mode() {
+            log::error!("Failed to restore the terminal: {}", error);
         }
     }
+}
+
+/// Reads the standard input in a separate thread, as reading it blocks.
+///
+/// At most [`INPUT_QUEUE_SIZE`] inputs are queued. If the terminal is in raw mode, Ctrl+C restores it
+/// and exits the process, like it does when the terminal handles it.
+fn read_stdin(mode: RttInputMode, terminal_raw_mode: bool) -> Receiver<Vec<u8>> {
+    let (sender, receiver) = mpsc::sync_channel(INPUT_QUEUE_SIZE);
+
+    thread::spawn(move || {
+        let stdin = std::io::stdin();
+        let mut stdin = stdin.lock();
+
+        loop {
+            let mut input = Vec::new();
+            let result = match mode {
+                RttInputMode::Line => stdin.read_until(b'\n', &mut input).map(|_| {
+                    if input.ends_with(b"\r\n") {
+                        input.remove(input.len() - 2);
+                    }
+                }),
+                _ => {
+                    input.resize(1024, 0);
+                    stdin.read(&mut input).map(|count| input.truncate(count))
+                }
+            };
+
+            if terminal_raw_mode && input.contains(&CTRL_C) {
+                let _ = terminal::disable_raw_mode();
+                std::process::exit(130);
+            }
+
+            // Stop at the end of the input, or when the receiver is gone.
+            match result {
+                Ok(()) if !input.is_empty() => {
+                    if sender.send(input).is_err() {
+                        break;
+                    }
+                }
+                Ok(()) => break,
+                Err(error) => {
+                    log::error!("Unable to read the standard input: {}", error);
+                    break;
+                }
+            }
+        }
+    });
 
-    Ok(())
+    receiver
 }
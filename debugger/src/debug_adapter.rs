@@ -2,6 +2,7 @@ use crate::dap_types;
 use crate::debugger::BreakpointType;
 use crate::debugger::ConsoleLog;
 use crate::debugger::CoreData;
+use crate::debugger::DebugSession;
 use crate::DebuggerError;
 use anyhow::{anyhow, Result};
 use dap_types::*;
@@ -9,11 +10,13 @@ use num_traits::Zero;
 use parse_int::parse;
 use probe_rs::debug::Registers;
 use probe_rs::debug::SourceLocation;
+use probe_rs::debug::SteppingMode;
 use probe_rs::debug::VariableLocation;
 use probe_rs::debug::{VariableCache, VariableName};
-use probe_rs::{debug::ColumnType, CoreStatus, HaltReason, MemoryInterface};
+use probe_rs::{debug::ColumnType, CoreStatus, HaltReason, MemoryInterface, VectorCatchCondition};
 use probe_rs_cli_util::rtt;
 use serde::{de::DeserializeOwned, Serialize};
+use std::collections::{BTreeMap, VecDeque};
 use std::string::ToString;
 use std::{convert::TryInto, path::Path, str, thread, time::Duration};
 
@@ -22,13 +25,75 @@ use crate::protocol::ProtocolAdapter;
 /// Progress ID used for progress reporting when the debug adapter protocol is used.
 type ProgressId = i64;
 
+/// The exception filters offered to the DAP client, as `(filter id, label, description, vector catch condition)`.
+///
+/// The filters are offered before the target is known, so the description names the cores which support them.
+/// Filters which are not supported by a core are reported as unverified by `setExceptionBreakpoints`.
+/// ARMv7-A cores don't support any of them.
+pub(crate) const EXCEPTION_FILTERS: &[(&str, &str, &str, VectorCatchCondition)] = &[
+    (
+        "reset",
+        "Core reset",
+        "Halt when the core leaves reset. Supported on ARM Cortex-M, ARMv8-A and RISC-V cores.",
+        VectorCatchCondition::CoreReset,
+    ),
+    (
+        "hardfault",
+        "HardFault",
+        "Halt on entry of the HardFault handler. Supported on ARM Cortex-M cores.",
+        VectorCatchCondition::HardFault,
+    ),
+    (
+        "memmanage",
+        "MemManage fault",
+        "Halt on entry of the MemManage handler. Supported on ARMv7-M and ARMv8-M cores.",
+        VectorCatchCondition::MemManage,
+    ),
+    (
+        "busfault",
+        "BusFault",
+        "Halt on entry of the BusFault handler. Supported on ARMv7-M and ARMv8-M cores.",
+        VectorCatchCondition::BusFault,
+    ),
+    (
+        "usagefault",
+        "UsageFault",
+        "Halt on entry of the UsageFault handler. Supported on ARMv7-M and ARMv8-M cores.",
+        VectorCatchCondition::UsageFault,
+    ),
+    (
+        "securefault",
+        "SecureFault",
+        "Halt on entry of the SecureFault handler. Supported on ARMv8-M cores.",
+        VectorCatchCondition::SecureFault,
+    ),
+    (
+        "exception_entry_return",
+        "Exception entry or return error",
+        "Halt on a fault during exception entry or return. Supported on ARMv7-M and ARMv8-M cores.",
+        VectorCatchCondition::ExceptionEntryOrReturn,
+    ),
+    (
+        "ebreak",
+        "RISC-V ebreak",
+        "Halt on ebreak instructions. Supported on RISC-V cores.",
+        VectorCatchCondition::Ebreak,
+    ),
+    (
+        "exception",
+        "Exception",
+        "Halt when an exception is taken. Supported on ARMv8-A and RISC-V cores.",
+        VectorCatchCondition::Exception,
+    ),
+];
+
 pub struct DebugAdapter<P: ProtocolAdapter> {
-    /// Track the last_known_status of the probe.
-    /// The debug client needs to be notified when the probe changes state,
-    /// and the only way is to poll the probe status periodically.
-    /// For instance, when the client sets the probe running,
-    /// and the probe halts because of a breakpoint, we need to notify the client.
-    pub(crate) last_known_status: CoreStatus,
+    /// Track the last known status of each core that is debugged, indexed by the core index, which is also the DAP thread id.
+    /// The debug client needs to be notified when a core changes state,
+    /// and the only way is to poll the core status periodically.
+    /// For instance, when the client sets the core running,
+    /// and the core halts because of a breakpoint, we need to notify the client.
+    core_statuses: BTreeMap<usize, CoreStatus>,
     pub(crate) halt_after_reset: bool,
     progress_id: ProgressId,
     /// Flag to indicate if the connected client supports progress reporting.
@@ -38,26 +103,52 @@ pub struct DebugAdapter<P: ProtocolAdapter> {
     pub(crate) lines_start_at_1: bool,
     /// [DWARF] spec at Sect 2.14 uses 1 based numbering, with a 0 indicating not-specified. We will follow that standard, and translate incoming requests depending on the DAP Client treatment of 0 or 1 based numbering.
     pub(crate) columns_start_at_1: bool,
+    /// Requests which were received while a step was running, and which are processed after it.
+    deferred_requests: VecDeque<Request>,
     adapter: P,
 }
 
 impl<P: ProtocolAdapter> DebugAdapter<P> {
     pub fn new(adapter: P) -> DebugAdapter<P> {
         DebugAdapter {
-            last_known_status: CoreStatus::Unknown,
+            core_statuses: BTreeMap::new(),
             halt_after_reset: false,
             progress_id: 0,
             supports_progress_reporting: false,
             lines_start_at_1: true,
             columns_start_at_1: true,
+            deferred_requests: VecDeque::new(),
             adapter,
         }
     }
 
+    /// Start tracking the status of the core at `core_index`. The status is unknown until the client finished its configuration.
+    pub(crate) fn add_core(&mut self, core_index: usize) {
+        self.core_statuses.insert(core_index, CoreStatus::Unknown);
+    }
+
+    /// The last known status of the core at `core_index`.
+    pub(crate) fn last_known_status(&self, core_index: usize) -> CoreStatus {
+        self.core_statuses
+            .get(&core_index)
+            .copied()
+            .unwrap_or(CoreStatus::Unknown)
+    }
+
+    /// Update the last known status of the core at `core_index`.
+    pub(crate) fn set_last_known_status(&mut self, core_index: usize, status: CoreStatus) {
+        self.core_statuses.insert(core_index, status);
+    }
+
+    /// Check if all cores which are debugged are halted, which is reported as `allThreadsStopped` to the client.
+    pub(crate) fn all_cores_halted(&self) -> bool {
+        self.core_statuses.values().all(|status| status.is_halted())
+    }
+
     pub(crate) fn status(&mut self, core_data: &mut CoreData, request: Request) -> Result<()> {
         let status = match core_data.target_core.status() {
             Ok(status) => {
-                self.last_known_status = status;
+                self.set_last_known_status(core_data.target_core.id(), status);
                 status
             }
             Err(error) => {
@@ -92,8 +183,9 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         }
     }
 
+    /// Halts the core of the thread in the request. The other cores keep running.
     pub(crate) fn pause(&mut self, core_data: &mut CoreData, request: Request) -> Result<()> {
-        // let args: PauseArguments = get_arguments(&request)?;
+        let core_index = core_data.target_core.id();
 
         match core_data.target_core.halt(Duration::from_millis(500)) {
             Ok(cpu_info) => {
@@ -104,14 +196,14 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                         return Err(anyhow!("Failed to retrieve core status"));
                     }
                 };
-                self.last_known_status = new_status;
+                self.set_last_known_status(core_index, new_status);
                 let event_body = Some(StoppedEventBody {
                     reason: "pause".to_owned(),
-                    description: Some(self.last_known_status.short_long_status().1.to_owned()),
-                    thread_id: Some(core_data.target_core.id() as i64),
+                    description: Some(new_status.short_long_status().1.to_owned()),
+                    thread_id: Some(core_index as i64),
                     preserve_focus_hint: Some(false),
                     text: None,
-                    all_threads_stopped: Some(false), // TODO: Implement multi-core logic here
+                    all_threads_stopped: Some(self.all_cores_halted()),
                     hit_breakpoint_ids: None,
                 });
                 self.send_event("stopped", event_body)?;
@@ -122,7 +214,7 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                         cpu_info.pc
                     ))),
                 )?;
-                self.last_known_status = CoreStatus::Halted(HaltReason::Request);
+                self.set_last_known_status(core_index, CoreStatus::Halted(HaltReason::Request));
 
                 Ok(())
             }
@@ -272,6 +364,37 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
             Err(error) => return self.send_response::<()>(request, Err(error)),
         };
 
+        // Text entered in the debug console with a `>` prefix is sent to the RTT down channel, e.g. to a shell running on the target.
+        if arguments.context.as_deref() == Some("repl") {
+            if let Some(input) = arguments.expression.strip_prefix('>') {
+                let input = input.strip_prefix(' ').unwrap_or(input);
+                let result = match core_data.rtt_connection.as_mut() {
+                    Some(rtt_connection) => {
+                        rtt_connection.send_input(format!("{}\n", input).as_bytes())
+                    }
+                    None => Err(anyhow!("RTT is not active, the input can not be sent")),
+                };
+
+                return match result {
+                    Ok(()) => self.send_response(
+                        request,
+                        Ok(Some(EvaluateResponseBody {
+                            indexed_variables: None,
+                            memory_reference: None,
+                            named_variables: None,
+                            presentation_hint: None,
+                            result: String::new(),
+                            type_: None,
+                            variables_reference: 0_i64,
+                        })),
+                    ),
+                    Err(error) => {
+                        self.send_response::<()>(request, Err(DebuggerError::Other(error)))
+                    }
+                };
+            }
+        }
+
         // Various fields in the response_body will be updated before we return.
         let mut response_body = EvaluateResponseBody {
             indexed_variables: None,
@@ -512,9 +635,9 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         if let Some(request) = request {
             match core_data.target_core.reset() {
                 Ok(_) => {
-                    self.last_known_status = CoreStatus::Running;
+                    self.set_last_known_status(core_data.target_core.id(), CoreStatus::Running);
                     let event_body = Some(ContinuedEventBody {
-                        all_threads_continued: Some(false), // TODO: Implement multi-core logic here
+                        all_threads_continued: Some(false),
                         thread_id: core_data.target_core.id() as i64,
                     });
 
@@ -539,7 +662,11 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                         return self.send_response::<()>(request, Ok(None));
                     }
                     // Only notify the DAP client if we are NOT in initialization stage (`CoreStatus::Unknown`).
-                    if self.last_known_status != CoreStatus::Unknown {
+                    if self.last_known_status(core_data.target_core.id()) != CoreStatus::Unknown {
+                        self.set_last_known_status(
+                            core_data.target_core.id(),
+                            CoreStatus::Halted(HaltReason::External),
+                        );
                         let event_body = Some(StoppedEventBody {
                             reason: "reset".to_owned(),
                             description: Some(
@@ -551,11 +678,10 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                             thread_id: Some(core_data.target_core.id() as i64),
                             preserve_focus_hint: None,
                             text: None,
-                            all_threads_stopped: Some(false), // TODO: Implement multi-core logic here
+                            all_threads_stopped: Some(self.all_cores_halted()),
                             hit_breakpoint_ids: None,
                         });
                         self.send_event("stopped", event_body)?;
-                        self.last_known_status = CoreStatus::Halted(HaltReason::External);
                     }
                     Ok(())
                 }
@@ -579,12 +705,12 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
     /// See [https://github.com/golang/vscode-go/issues/940] for more info.
     /// In order to avoid overhead and duplicate responses, we will implement the following logic.
     /// - `configuration_done` will ignore target status, and simply notify VSCode that we're done.
-    /// - `threads` will check for [DebugAdapter::last_known_status] and ...
+    /// - `threads` will check for [DebugAdapter::last_known_status] of each core and ...
     ///   - If it is `Unknown`, it will ...
-    ///     - send back a threads response, with `all_threds_stopped=Some(false)`
     ///     - check on actual core status, and update [DebugAdapter::last_known_status] as well as synch status with the VSCode client.
-    ///   - If it is `Halted`, it will respond with thread information as expected.
-    ///   - Any other status will send and error.
+    ///     - send back a threads response, and then resume the cores that should not stay halted.
+    ///   - If it is `Halted`, it will unwind the stack of the core, to serve up the following `stackTrace` requests.
+    ///   - Any other status will only report the thread.
     pub(crate) fn configuration_done(
         &mut self,
         _core_data: &mut CoreData,
@@ -593,9 +719,18 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         self.send_response::<()>(request, Ok(None))
     }
 
+    /// Sets the source breakpoints of the requested file on every core which has the source location in its binary.
     pub(crate) fn set_breakpoints(
         &mut self,
-        core_data: &mut CoreData,
+        session_data: &mut DebugSession,
+        request: Request,
+    ) -> Result<()> {
+        self.with_sleeping_cores_halted(session_data, request, Self::set_source_breakpoints)
+    }
+
+    fn set_source_breakpoints(
+        &mut self,
+        session_data: &mut DebugSession,
         request: Request,
     ) -> Result<()> {
         let args: SetBreakpointsArguments = match get_arguments(&request) {
@@ -615,17 +750,26 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
 
         let source_path = args.source.path.as_ref().map(Path::new);
 
+        let core_indices = session_data.core_indices();
+
         // Always clear existing breakpoints before setting new ones. The DAP Specification doesn't make allowances for deleting and setting individual breakpoints.
-        match core_data.clear_breakpoints(BreakpointType::SourceBreakpoint) {
-            Ok(_) => {}
-            Err(error) => {
-                return self.send_response::<()>(
-                    request,
-                    Err(DebuggerError::Other(anyhow!(
-                        "Failed to clear existing breakpoints before setting new ones : {}",
-                        error
-                    ))),
-                )
+        for core_index in core_indices.iter().copied() {
+            match session_data
+                .attach_core(core_index)
+                .map_err(anyhow::Error::from)
+                .and_then(|mut core_data| {
+                    core_data.clear_breakpoints(BreakpointType::SourceBreakpoint)
+                }) {
+                Ok(_) => {}
+                Err(error) => {
+                    return self.send_response::<()>(
+                        request,
+                        Err(DebuggerError::Other(anyhow!(
+                            "Failed to clear existing breakpoints before setting new ones : {}",
+                            error
+                        ))),
+                    )
+                }
             }
         }
 
@@ -649,47 +793,65 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                     Some(bp.column.unwrap_or(0) as u64 + 1)
                 };
 
-                // Try to find source code location
-                let source_location: Option<u64> = if let Some(source_path) = source_path {
-                    core_data
-                        .debug_info
-                        .get_breakpoint_location(source_path, breakpoint_line, breakpoint_column)
-                        .unwrap_or(None)
-                } else {
-                    None
-                };
+                // The same source file can be part of the binaries of multiple cores, so the breakpoint is set on each of them.
+                let mut first_location: Option<u64> = None;
+                let mut verified = false;
+                let mut messages: Vec<String> = Vec::new();
+                for core_index in core_indices.iter().copied() {
+                    let mut core_data = match session_data.attach_core(core_index) {
+                        Ok(core_data) => core_data,
+                        Err(error) => return self.send_response::<()>(request, Err(error)),
+                    };
 
-                if let Some(location) = source_location {
-                    let (verified, reason_msg) = match core_data
-                        .set_breakpoint(location as u32, BreakpointType::SourceBreakpoint)
-                    {
-                        Ok(_) => (
-                            true,
-                            Some(format!(
-                                "Source breakpoint at memory address: {:#010x}",
-                                location
-                            )),
-                        ),
-                        Err(err) => {
-                            let message = format!(
-                                "Warning: Could not set breakpoint at memory address: {:#010x}: {}",
-                                location, err
+                    // Try to find source code location
+                    let source_location: Option<u64> = if let Some(source_path) = source_path {
+                        core_data
+                            .debug_info
+                            .get_breakpoint_location(
+                                source_path,
+                                breakpoint_line,
+                                breakpoint_column,
                             )
-                            .to_string();
-                            // In addition to sending the error to the 'Hover' message, also write it to the Debug Console Log.
-                            self.log_to_console(format!("Warning: {}", message));
-                            self.show_message(MessageSeverity::Warning, message.clone());
-                            (false, Some(message))
-                        }
+                            .unwrap_or(None)
+                    } else {
+                        None
                     };
 
+                    if let Some(location) = source_location {
+                        first_location.get_or_insert(location);
+                        match core_data
+                            .set_breakpoint(location as u32, BreakpointType::SourceBreakpoint)
+                        {
+                            Ok(_) => {
+                                verified = true;
+                                messages.push(format!(
+                                    "Source breakpoint on core #{} at memory address: {:#010x}",
+                                    core_index, location
+                                ));
+                            }
+                            Err(err) => {
+                                let message = format!(
+                                    "Warning: Could not set breakpoint on core #{} at memory address: {:#010x}: {}",
+                                    core_index, location, err
+                                )
+                                .to_string();
+                                // In addition to sending the error to the 'Hover' message, also write it to the Debug Console Log.
+                                self.log_to_console(format!("Warning: {}", message));
+                                self.show_message(MessageSeverity::Warning, message.clone());
+                                messages.push(message);
+                            }
+                        };
+                    }
+                }
+
+                if let Some(location) = first_location {
                     created_breakpoints.push(Breakpoint {
                         column: breakpoint_column.map(|c| c as i64),
                         end_column: None,
                         end_line: None,
                         id: None,
                         line: Some(breakpoint_line as i64),
-                        message: reason_msg,
+                        message: Some(messages.join("\n")),
                         source: None,
                         instruction_reference: Some(location.to_string()),
                         offset: None,
@@ -722,6 +884,128 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         self.send_response(request, Ok(Some(breakpoint_body)))
     }
 
+    /// Enables vector catch on every core, for the exception filters selected in the client.
+    /// A filter is verified if at least one core supports it.
+    pub(crate) fn set_exception_breakpoints(
+        &mut self,
+        session_data: &mut DebugSession,
+        request: Request,
+    ) -> Result<()> {
+        let args: SetExceptionBreakpointsArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => {
+                return self.send_response::<()>(
+                    request,
+                    Err(DebuggerError::Other(anyhow!(
+                        "Could not read arguments : {}",
+                        error
+                    ))),
+                )
+            }
+        };
+
+        // The response lists the `filters` first, followed by the `filterOptions`.
+        let filter_ids: Vec<String> = args
+            .filters
+            .iter()
+            .cloned()
+            .chain(
+                args.filter_options
+                    .iter()
+                    .flatten()
+                    .map(|filter_option| filter_option.filter_id.clone()),
+            )
+            .collect();
+
+        let conditions: Vec<Option<VectorCatchCondition>> = filter_ids
+            .iter()
+            .map(|filter_id| {
+                EXCEPTION_FILTERS
+                    .iter()
+                    .find(|(id, _, _, _)| id == filter_id)
+                    .map(|(_, _, _, condition)| *condition)
+            })
+            .collect();
+
+        let mut verified = vec![false; filter_ids.len()];
+        let mut messages: Vec<Vec<String>> = vec![Vec::new(); filter_ids.len()];
+
+        for core_index in session_data.core_indices() {
+            let mut core_data = match session_data.attach_core(core_index) {
+                Ok(core_data) => core_data,
+                Err(error) => return self.send_response::<()>(request, Err(error)),
+            };
+
+            // Find the conditions which this core supports, so that the unsupported ones don't disable the rest.
+            let mut core_conditions = Vec::new();
+            for (index, condition) in conditions.iter().enumerate() {
+                let condition = match condition {
+                    Some(condition) => *condition,
+                    None => continue,
+                };
+
+                if core_conditions.contains(&condition) {
+                    verified[index] = true;
+                    continue;
+                }
+
+                match core_data.target_core.set_vector_catch(&[condition]) {
+                    Ok(()) => {
+                        verified[index] = true;
+                        core_conditions.push(condition);
+                    }
+                    Err(error) => messages[index].push(format!("Core #{}: {}", core_index, error)),
+                }
+            }
+
+            if let Err(error) = core_data.target_core.set_vector_catch(&core_conditions) {
+                return self.send_response::<()>(
+                    request,
+                    Err(DebuggerError::Other(anyhow!(
+                        "Failed to set exception breakpoints on core #{} : {}",
+                        core_index,
+                        error
+                    ))),
+                );
+            }
+        }
+
+        let breakpoints = filter_ids
+            .iter()
+            .zip(conditions)
+            .zip(verified.into_iter().zip(messages))
+            .map(|((filter_id, condition), (verified, messages))| {
+                let message = if condition.is_none() {
+                    Some(format!("Unknown exception filter '{}'", filter_id))
+                } else if verified || messages.is_empty() {
+                    None
+                } else {
+                    Some(messages.join("\n"))
+                };
+
+                Breakpoint {
+                    column: None,
+                    end_column: None,
+                    end_line: None,
+                    id: None,
+                    line: None,
+                    message,
+                    source: None,
+                    instruction_reference: None,
+                    offset: None,
+                    verified,
+                }
+            })
+            .collect();
+
+        self.send_response(
+            request,
+            Ok(Some(SetExceptionBreakpointsResponseBody {
+                breakpoints: Some(breakpoints),
+            })),
+        )
+    }
+
     pub(crate) fn set_instruction_breakpoints(
         &mut self,
         core_data: &mut CoreData,
@@ -831,15 +1115,29 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         self.send_response(request, Ok(Some(instruction_breakpoint_body)))
     }
 
-    pub(crate) fn threads(&mut self, core_data: &mut CoreData, request: Request) -> Result<()> {
-        // TODO: Implement actual thread resolution. For now, we just use the core id as the thread id.
+    /// Reports every core which is debugged as a thread, using the core index as the thread id.
+    pub(crate) fn threads(
+        &mut self,
+        session_data: &mut DebugSession,
+        request: Request,
+    ) -> Result<()> {
+        self.with_sleeping_cores_halted(session_data, request, Self::report_threads)
+    }
+
+    fn report_threads(&mut self, session_data: &mut DebugSession, request: Request) -> Result<()> {
         let mut threads: Vec<Thread> = vec![];
-        match self.last_known_status {
-            CoreStatus::Unknown => {
+        let mut cores_to_resume: Vec<usize> = vec![];
+        for core_index in session_data.core_indices() {
+            let mut core_data = match session_data.attach_core(core_index) {
+                Ok(core_data) => core_data,
+                Err(error) => return self.send_response::<()>(request, Err(error)),
+            };
+
+            if self.last_known_status(core_index) == CoreStatus::Unknown {
                 // We are probably here because the `configuration_done` request just happened, so we can make sure the client and debugger are in synch.
                 match core_data.target_core.status() {
                     Ok(core_status) => {
-                        self.last_known_status = core_status;
+                        self.set_last_known_status(core_index, core_status);
                         // Make sure the DAP Client and the DAP Server are in sync with the status of the core.
                         if core_status.is_halted() {
                             if self.halt_after_reset
@@ -847,27 +1145,16 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                             {
                                 let event_body = Some(StoppedEventBody {
                                     reason: core_status.short_long_status().0.to_owned(),
-                                    description: Some(
-                                        core_status.short_long_status().1.to_string(),
-                                    ),
-                                    thread_id: Some(core_data.target_core.id() as i64),
+                                    description: Some(core_data.halted_description(core_status)),
+                                    thread_id: Some(core_index as i64),
                                     preserve_focus_hint: None,
                                     text: None,
-                                    all_threads_stopped: Some(false), // TODO: Implement multi-core logic here
+                                    all_threads_stopped: Some(self.all_cores_halted()),
                                     hit_breakpoint_ids: None,
                                 });
                                 self.send_event("stopped", event_body)?;
                             } else {
-                                let single_thread = Thread {
-                                    id: core_data.target_core.id() as i64,
-                                    name: core_data.target_name.clone(),
-                                };
-                                threads.push(single_thread);
-                                self.send_response(
-                                    request.clone(),
-                                    Ok(Some(ThreadsResponseBody { threads })),
-                                )?;
-                                return self.r#continue(core_data, request);
+                                cores_to_resume.push(core_index);
                             }
                         }
                     }
@@ -875,19 +1162,14 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                         return self.send_response::<()>(
                             request,
                             Err(DebuggerError::Other(anyhow!(
-                                "Could not read core status to synchronize the client and the probe. {:?}",
+                                "Could not read core status of core #{} to synchronize the client and the probe. {:?}",
+                                core_index,
                                 error
                             ))),
                         );
                     }
                 }
-            }
-            CoreStatus::Halted(_) => {
-                let single_thread = Thread {
-                    id: core_data.target_core.id() as i64,
-                    name: core_data.target_name.clone(),
-                };
-                threads.push(single_thread);
+            } else if self.last_known_status(core_index).is_halted() {
                 // We do the actual stack trace here, because VSCode sometimes sends multiple StackTrace requests, which lead to unnecessary unwind processing.
                 // By doing it here, we do it once, and serve up the results when we get the StackTrace requests.
                 let regs = core_data.target_core.registers();
@@ -898,25 +1180,25 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                             .send_response::<()>(request, Err(DebuggerError::ProbeRs(error)))
                     }
                 };
-                log::debug!(
-                    "Updating the stack frame data for core #{}",
-                    core_data.target_core.id()
-                );
+                log::debug!("Updating the stack frame data for core #{}", core_index);
                 *core_data.stack_frames = core_data
                     .debug_info
                     .unwind(&mut core_data.target_core, u64::from(pc))?;
             }
-            CoreStatus::Running | CoreStatus::LockedUp | CoreStatus::Sleeping => {
-                return self.send_response::<()>(
-                    request,
-                    Err(DebuggerError::Other(anyhow!(
-                        "Received request for `threads`, while last known core status was {:?}",
-                        self.last_known_status
-                    ))),
-                );
-            }
+
+            threads.push(Thread {
+                id: core_index as i64,
+                name: core_data.target_name.clone(),
+            });
+        }
+        self.send_response(request, Ok(Some(ThreadsResponseBody { threads })))?;
+
+        // Cores which were halted during initialization, and should not stay halted, are resumed after the client knows about their threads.
+        if !cores_to_resume.is_empty() {
+            self.resume_cores(session_data, &cores_to_resume)?;
+            self.report_halted_cores(session_data, &cores_to_resume)?;
         }
-        self.send_response(request, Ok(Some(ThreadsResponseBody { threads })))
+        Ok(())
     }
 
     pub(crate) fn stack_trace(&mut self, core_data: &mut CoreData, request: Request) -> Result<()> {
@@ -1520,88 +1802,279 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         self.send_response(request, response)
     }
 
-    pub(crate) fn r#continue(&mut self, core_data: &mut CoreData, request: Request) -> Result<()> {
-        match core_data.target_core.run() {
-            Ok(_) => {
-                self.last_known_status = core_data
-                    .target_core
-                    .status()
-                    .unwrap_or(CoreStatus::Unknown);
-                if request.command.as_str() == "continue" {
-                    // If this continue was initiated as part of some other request, then do not respond.
-                    self.send_response(
-                        request,
-                        Ok(Some(ContinueResponseBody {
-                            all_threads_continued: Some(false), // TODO: Implement multi-core logic here
-                        })),
-                    )?;
+    /// Resumes all cores, or only the core of the requested thread if `singleThread` is set.
+    pub(crate) fn r#continue(
+        &mut self,
+        session_data: &mut DebugSession,
+        request: Request,
+    ) -> Result<()> {
+        let arguments: ContinueArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+
+        let all_core_indices = session_data.core_indices();
+        let core_indices = if arguments.single_thread.unwrap_or(false) {
+            vec![arguments.thread_id as usize]
+        } else {
+            all_core_indices.clone()
+        };
+
+        if let Err(error) = self.resume_cores(session_data, &core_indices) {
+            self.send_response::<()>(request, Err(DebuggerError::Other(anyhow!("{}", error))))?;
+            return Err(error);
+        }
+        self.send_response(
+            request,
+            Ok(Some(ContinueResponseBody {
+                all_threads_continued: Some(core_indices == all_core_indices),
+            })),
+        )?;
+
+        self.report_halted_cores(session_data, &core_indices)
+    }
+
+    /// Resumes the cores in `core_indices` which are halted.
+    /// Handles `request` with `handler`, while the sleeping cores of the session are halted.
+    ///
+    /// This is the equivalent of the `unhalt_me` handling in `Debugger::process_core_request`, for requests which access every core.
+    /// NOTE: The cores will exit sleep mode as a result of this.
+    fn with_sleeping_cores_halted(
+        &mut self,
+        session_data: &mut DebugSession,
+        request: Request,
+        handler: impl FnOnce(&mut Self, &mut DebugSession, Request) -> Result<()>,
+    ) -> Result<()> {
+        let sleeping_cores = match self.halt_sleeping_cores(session_data) {
+            Ok(sleeping_cores) => sleeping_cores,
+            Err(error) => {
+                return self.send_response::<()>(request, Err(DebuggerError::Other(error)))
+            }
+        };
+
+        let result = handler(self, session_data, request);
+
+        // The cores are resumed even if the request failed, so they don't stay halted.
+        let unhalt_result = self.unhalt_cores(session_data, &sleeping_cores);
+        result.and(unhalt_result)
+    }
+
+    /// Halts the cores of the session which are sleeping, and returns their indices.
+    ///
+    /// If a core can't be halted, the cores which were already halted are resumed.
+    fn halt_sleeping_cores(&mut self, session_data: &mut DebugSession) -> Result<Vec<usize>> {
+        let mut sleeping_cores = Vec::new();
+        for core_index in session_data.core_indices() {
+            let halted = session_data
+                .attach_core(core_index)
+                .map_err(anyhow::Error::from)
+                .and_then(|mut core_data| {
+                    if core_data.target_core.status()? != CoreStatus::Sleeping {
+                        return Ok(false);
+                    }
+                    core_data.target_core.halt(Duration::from_millis(100))?;
+                    Ok(true)
+                });
+
+            match halted {
+                Ok(true) => {
+                    self.set_last_known_status(core_index, CoreStatus::Halted(HaltReason::Request));
+                    sleeping_cores.push(core_index);
+                }
+                Ok(false) => {}
+                Err(error) => {
+                    if let Err(unhalt_error) = self.unhalt_cores(session_data, &sleeping_cores) {
+                        log::warn!("Could not resume the sleeping cores: {}", unhalt_error);
+                    }
+                    return Err(error);
                 }
-                // We have to consider the fact that sometimes the `run()` is successfull,
-                // but "immediately" afterwards, the MCU hits a breakpoint or exception.
-                // So we have to check the status again to be sure.
-                thread::sleep(Duration::from_millis(100)); // Small delay to make sure the MCU hits user breakpoints early in `main()`.
-                let core_status = match core_data.target_core.status() {
-                    Ok(new_status) => match new_status {
-                        CoreStatus::Halted(_) => {
-                            let event_body = Some(StoppedEventBody {
-                                reason: new_status.short_long_status().0.to_owned(),
-                                description: Some(new_status.short_long_status().1.to_string()),
-                                thread_id: Some(core_data.target_core.id() as i64),
-                                preserve_focus_hint: None,
-                                text: None,
-                                all_threads_stopped: Some(false), // TODO: Implement multi-core logic here
-                                hit_breakpoint_ids: None,
-                            });
-                            self.send_event("stopped", event_body)?;
-                            new_status
-                        }
-                        other => other,
-                    },
-                    Err(_) => CoreStatus::Unknown,
-                };
-                self.last_known_status = core_status;
-                Ok(())
             }
-            Err(error) => {
-                self.last_known_status = CoreStatus::Halted(HaltReason::Unknown);
-                self.send_response::<()>(request, Err(DebuggerError::Other(anyhow!("{}", error))))?;
-                Err(error.into())
+        }
+        Ok(sleeping_cores)
+    }
+
+    /// Resumes the cores which were halted by [`DebugAdapter::halt_sleeping_cores`].
+    fn unhalt_cores(
+        &mut self,
+        session_data: &mut DebugSession,
+        core_indices: &[usize],
+    ) -> Result<()> {
+        for core_index in core_indices.iter().copied() {
+            session_data.attach_core(core_index)?.target_core.run()?;
+            self.set_last_known_status(core_index, CoreStatus::Running);
+        }
+        Ok(())
+    }
+
+    fn resume_cores(
+        &mut self,
+        session_data: &mut DebugSession,
+        core_indices: &[usize],
+    ) -> Result<()> {
+        for core_index in core_indices.iter().copied() {
+            let mut core_data = session_data.attach_core(core_index)?;
+            if !core_data.target_core.core_halted()? {
+                continue;
+            }
+
+            if let Err(error) = core_data.target_core.run() {
+                self.set_last_known_status(core_index, CoreStatus::Halted(HaltReason::Unknown));
+                return Err(error.into());
             }
+            let core_status = core_data
+                .target_core
+                .status()
+                .unwrap_or(CoreStatus::Unknown);
+            self.set_last_known_status(core_index, core_status);
         }
+        Ok(())
     }
 
-    /// Steps at 'instruction' granularity ONLY.
+    /// Notifies the client about the cores in `core_indices` which halted directly after they were resumed.
+    fn report_halted_cores(
+        &mut self,
+        session_data: &mut DebugSession,
+        core_indices: &[usize],
+    ) -> Result<()> {
+        // We have to consider the fact that sometimes the `run()` is successfull,
+        // but "immediately" afterwards, the MCU hits a breakpoint or exception.
+        // So we have to check the status again to be sure.
+        thread::sleep(Duration::from_millis(100)); // Small delay to make sure the MCU hits user breakpoints early in `main()`.
+        for core_index in core_indices.iter().copied() {
+            let mut core_data = session_data.attach_core(core_index)?;
+            let core_status = core_data
+                .target_core
+                .status()
+                .unwrap_or(CoreStatus::Unknown);
+            self.set_last_known_status(core_index, core_status);
+            if core_status.is_halted() {
+                let event_body = Some(StoppedEventBody {
+                    reason: core_status.short_long_status().0.to_owned(),
+                    description: Some(core_data.halted_description(core_status)),
+                    thread_id: Some(core_index as i64),
+                    preserve_focus_hint: None,
+                    text: None,
+                    all_threads_stopped: Some(self.all_cores_halted()),
+                    hit_breakpoint_ids: None,
+                });
+                self.send_event("stopped", event_body)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Steps to the next statement in the current function, or a single instruction for 'instruction' granularity.
     pub(crate) fn next(&mut self, core_data: &mut CoreData, request: Request) -> Result<()> {
-        // TODO: Implement 'statement' granularity, then update DAP `Capabilities` and read `NextArguments`.
-        // let args: NextArguments = get_arguments(&request)?;
+        let arguments: NextArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
 
-        match core_data.target_core.step() {
-            Ok(cpu_info) => {
-                let new_status = match core_data.target_core.status() {
-                    Ok(new_status) => new_status,
-                    Err(error) => {
-                        self.send_response::<()>(request, Err(DebuggerError::ProbeRs(error)))?;
-                        return Err(anyhow!("Failed to retrieve core status"));
-                    }
-                };
-                self.last_known_status = new_status;
+        let stepping_mode = match arguments.granularity.as_deref() {
+            Some("instruction") => SteppingMode::StepInstruction,
+            _ => SteppingMode::OverStatement,
+        };
+
+        self.debug_step(stepping_mode, core_data, request)
+    }
+
+    /// Steps to the next statement, following function calls, or a single instruction for 'instruction' granularity.
+    pub(crate) fn step_in(&mut self, core_data: &mut CoreData, request: Request) -> Result<()> {
+        let arguments: StepInArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+
+        let stepping_mode = match arguments.granularity.as_deref() {
+            Some("instruction") => SteppingMode::StepInstruction,
+            _ => SteppingMode::IntoStatement,
+        };
+
+        self.debug_step(stepping_mode, core_data, request)
+    }
+
+    /// Runs until the current function returns to its caller. The granularity has no effect on this.
+    pub(crate) fn step_out(&mut self, core_data: &mut CoreData, request: Request) -> Result<()> {
+        let _arguments: StepOutArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+
+        self.debug_step(SteppingMode::OutOfStatement, core_data, request)
+    }
+
+    /// Common implementation of the `next`, `stepIn` and `stepOut` requests.
+    ///
+    /// Only the core of the requested thread is stepped, the other cores are not resumed.
+    /// While the core runs a called function, a `pause` request for the core interrupts the step,
+    /// other requests are processed after the step.
+    fn debug_step(
+        &mut self,
+        stepping_mode: SteppingMode,
+        core_data: &mut CoreData,
+        request: Request,
+    ) -> Result<()> {
+        let core_index = core_data.target_core.id();
+        let mut pause_request = None;
+
+        let adapter = &mut self.adapter;
+        let deferred_requests = &mut self.deferred_requests;
+        let result = stepping_mode.step_interruptible(
+            &mut core_data.target_core,
+            core_data.debug_info,
+            &mut || match adapter.listen_for_request() {
+                Ok(Some(request))
+                    if request.command == "pause"
+                        && requested_thread_id(&request).map_or(true, |id| id == core_index) =>
+                {
+                    pause_request = Some(request);
+                    true
+                }
+                Ok(Some(request)) => {
+                    deferred_requests.push_back(request);
+                    false
+                }
+                Ok(None) => false,
+                Err(error) => {
+                    log::warn!(
+                        "Error while listening for requests during a step: {}",
+                        error
+                    );
+                    false
+                }
+            },
+        );
+
+        if let Some(pause_request) = pause_request {
+            self.send_response::<()>(pause_request, Ok(None))?;
+        }
+
+        match result {
+            Ok((new_status, program_counter)) => {
+                self.set_last_known_status(core_data.target_core.id(), new_status);
                 self.send_response::<()>(request, Ok(None))?;
+                // If the core halted for another reason than the step, e.g. a breakpoint, report that instead.
                 let event_body = Some(StoppedEventBody {
-                    reason: "step".to_owned(),
+                    reason: new_status.short_long_status().0.to_owned(),
                     description: Some(format!(
                         "{} at address {:#010x}",
                         new_status.short_long_status().1,
-                        cpu_info.pc
+                        program_counter
                     )),
                     thread_id: Some(core_data.target_core.id() as i64),
                     preserve_focus_hint: None,
                     text: None,
-                    all_threads_stopped: Some(false), // TODO: Implement multi-core logic here
+                    all_threads_stopped: Some(self.all_cores_halted()),
                     hit_breakpoint_ids: None,
                 });
                 self.send_event("stopped", event_body)
             }
             Err(error) => {
+                let core_status = core_data
+                    .target_core
+                    .status()
+                    .unwrap_or(CoreStatus::Unknown);
+                self.set_last_known_status(core_data.target_core.id(), core_status);
                 self.send_response::<()>(request, Err(DebuggerError::Other(anyhow!("{}", error))))
             }
         }
@@ -1651,6 +2124,10 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
     /// Returns one of the standard DAP Requests if all goes well, or a "error" request, which should indicate that the calling function should return.
     /// When preparing to return an "error" request, we will send a Response containing the DebuggerError encountered.
     pub fn listen_for_request(&mut self) -> anyhow::Result<Option<Request>> {
+        if let Some(request) = self.deferred_requests.pop_front() {
+            return Ok(Some(request));
+        }
+
         self.adapter.listen_for_request()
     }
 
@@ -1731,6 +2208,26 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
             .is_ok()
     }
 
+    /// Send console output of the target application, written using semihosting, to the MS DAP Client.
+    pub fn semihosting_output(&mut self, output: &[u8]) -> bool {
+        let event_body = match serde_json::to_value(OutputEventBody {
+            output: String::from_utf8_lossy(output).into_owned(),
+            category: Some("stdout".to_owned()),
+            variables_reference: None,
+            source: None,
+            line: None,
+            column: None,
+            data: None,
+            group: None,
+        }) {
+            Ok(event_body) => event_body,
+            Err(_) => {
+                return false;
+            }
+        };
+        self.send_event("output", Some(event_body)).is_ok()
+    }
+
     fn new_progress_id(&mut self) -> ProgressId {
         let id = self.progress_id;
 
@@ -1854,6 +2351,16 @@ fn get_dap_source(source_location: &SourceLocation) -> Option<Source> {
     })
 }
 
+/// Returns the thread id in the arguments of `request`, if any.
+fn requested_thread_id(request: &Request) -> Option<usize> {
+    request
+        .arguments
+        .as_ref()
+        .and_then(|arguments| arguments.get("threadId"))
+        .and_then(|thread_id| thread_id.as_u64())
+        .map(|thread_id| thread_id as usize)
+}
+
 /// Provides halt functionality that is re-used elsewhere, in context of multiple DAP Requests
 pub(crate) fn halt_core(
     target_core: &mut probe_rs::Core,
@@ -1895,7 +2402,7 @@ impl DapStatus for CoreStatus {
                     "exception",
                     "Core halted due to an exception, e.g. interupt handler",
                 ),
-                HaltReason::Watchpoint => (
+                HaltReason::Watchpoint(_) => (
                     "data breakpoint",
                     "Core halted due to a watchpoint or data breakpoint",
                 ),
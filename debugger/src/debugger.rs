@@ -8,6 +8,7 @@ use capstone::{
     arch::arm::ArchMode as armArchMode, arch::riscv::ArchMode as riscvArchMode, prelude::*,
     Capstone, Endian,
 };
+use probe_rs::architecture::arm::fault;
 use probe_rs::config::TargetSelector;
 use probe_rs::debug::DebugInfo;
 
@@ -15,14 +16,16 @@ use probe_rs::flashing::download_file_with_options;
 use probe_rs::flashing::DownloadOptions;
 use probe_rs::flashing::FlashProgress;
 use probe_rs::flashing::Format;
+use probe_rs::semihosting::{Semihosting, SemihostingStatus};
 use probe_rs::ProbeCreationError;
 use probe_rs::{
-    Core, CoreStatus, DebugProbeError, DebugProbeSelector, Permissions, Probe, Session,
+    Core, CoreStatus, CoreType, DebugProbeError, DebugProbeSelector, Permissions, Probe, Session,
     WireProtocol,
 };
 use probe_rs_cli_util::rtt;
 use serde::Deserialize;
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::net::Ipv4Addr;
 use std::net::TcpListener;
 use std::ops::Mul;
@@ -31,6 +34,7 @@ use std::{
     env::{current_dir, set_current_dir},
     path::PathBuf,
     str::FromStr,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
     thread,
     time::Duration,
 };
@@ -122,6 +126,12 @@ pub struct DebuggerOptions {
     #[serde(default)]
     pub(crate) core_index: usize,
 
+    /// The cores to debug, each with its own binary. Each core is shown as a separate thread in the DAP client.
+    /// If this is empty, only the core at `core_index` is debugged, using `program_binary`.
+    #[clap(skip)]
+    #[serde(default)]
+    pub(crate) core_configs: Vec<CoreConfig>,
+
     /// The target to be selected.
     #[clap(short, long)]
     pub(crate) chip: Option<String>,
@@ -174,6 +184,11 @@ pub struct DebuggerOptions {
     #[serde(default)]
     pub(crate) restore_unwritten_bytes: bool,
 
+    /// Compare the flash with the new contents first, and skip erasing and programming the sectors which are unchanged
+    #[clap(long)]
+    #[serde(default)]
+    pub(crate) skip_unchanged_sectors: bool,
+
     /// Level of information to be logged to the debugger console (Error, Info or Debug )
     #[clap(long)]
     #[serde(default = "default_console_log")]
@@ -215,25 +230,65 @@ impl DebuggerOptions {
         new_program_binary: Option<PathBuf>,
     ) -> Result<(), DebuggerError> {
         self.program_binary = match new_program_binary {
-            Some(temp_path) => {
-                let mut new_path = PathBuf::new();
-                if temp_path.is_relative() {
-                    if let Some(cwd_path) = self.cwd.clone() {
-                        new_path.push(cwd_path);
-                    } else {
-                        return Err(DebuggerError::Other(anyhow!(
-                            "Invalid value {:?} for `cwd`",
-                            self.cwd
-                        )));
-                    }
-                }
-                new_path.push(temp_path);
-                Some(new_path)
-            }
+            Some(temp_path) => Some(self.qualify_path(temp_path)?),
             None => None,
         };
         Ok(())
     }
+
+    /// Make sure there is a [`CoreConfig`] for every core to debug, and qualify the paths of their binaries.
+    /// If no `core_configs` were specified, the `core_index` and `program_binary` options are used.
+    pub(crate) fn qualify_and_update_core_configs(&mut self) -> Result<(), DebuggerError> {
+        if self.core_configs.is_empty() {
+            self.core_configs.push(CoreConfig {
+                core_index: self.core_index,
+                program_binary: self.program_binary.clone(),
+            });
+        }
+
+        let mut core_configs = std::mem::take(&mut self.core_configs);
+        for core_config in core_configs.iter_mut() {
+            if let Some(program_binary) = core_config.program_binary.take() {
+                core_config.program_binary = Some(self.qualify_path(program_binary)?);
+            }
+        }
+        self.core_configs = core_configs;
+        Ok(())
+    }
+
+    /// The core which is used for RTT. This is the first core in `core_configs`.
+    pub(crate) fn rtt_core_config(&self) -> Option<&CoreConfig> {
+        self.core_configs.first()
+    }
+
+    /// Join a relative path with the cwd.
+    fn qualify_path(&self, path: PathBuf) -> Result<PathBuf, DebuggerError> {
+        let mut new_path = PathBuf::new();
+        if path.is_relative() {
+            if let Some(cwd_path) = self.cwd.clone() {
+                new_path.push(cwd_path);
+            } else {
+                return Err(DebuggerError::Other(anyhow!(
+                    "Invalid value {:?} for `cwd`",
+                    self.cwd
+                )));
+            }
+        }
+        new_path.push(path);
+        Ok(new_path)
+    }
+}
+
+/// The configuration of a single core in a debug session.
+#[derive(Clone, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CoreConfig {
+    /// The index of the core, as listed in the target description.
+    #[serde(default)]
+    pub(crate) core_index: usize,
+
+    /// Binary which runs on this core, as a path. Relative to `cwd`, or fully qualified.
+    pub(crate) program_binary: Option<PathBuf>,
 }
 
 /// The supported breakpoint types
@@ -250,24 +305,40 @@ pub struct ActiveBreakpoint {
     breakpoint_address: u32,
 }
 
-/// DebugSession is designed to be similar to [probe_rs::Session], in as much that it provides handles to the [CoreData] instances for each of the available [probe_rs::Core] involved in the debug session.
-/// To get access to the [CoreData] for a specific [Core], the
-/// TODO: Adjust [DebuggerOptions] to allow multiple cores (and if appropriate, their binaries) to be specified.
-pub struct DebugSession {
-    pub(crate) session: Session,
+/// [SessionData] stores the state of the debug session between the requests of the DAP client.
+/// The [Session] is shared with the thread which streams RTT, so it is only locked while a request is processed, see [SessionData::lock].
+pub struct SessionData {
+    pub(crate) session: Arc<Mutex<Session>>,
     /// Provides ability to disassemble binary code.
     pub(crate) capstone: Capstone,
-    /// [DebugSession] will manage one [DebugInfo] per [DebuggerOptions::program_binary]
-    pub(crate) debug_infos: Vec<DebugInfo>,
-    /// [DebugSession] will manage a `Vec<StackFrame>` per [Core]. Each core's collection of StackFrames will be recreated whenever a stacktrace is performed, using the results of [DebugInfo::unwind]
+    /// [SessionData] will manage one [DebugInfo] per [CoreConfig::program_binary], indexed by the core it runs on.
+    pub(crate) debug_infos: BTreeMap<usize, DebugInfo>,
+    /// [SessionData] will manage a `Vec<StackFrame>` per [Core]. Each core's collection of StackFrames will be recreated whenever a stacktrace is performed, using the results of [DebugInfo::unwind]
     pub(crate) stack_frames: Vec<Vec<probe_rs::debug::StackFrame>>,
-    /// [DebugSession] will manage a `Vec<ActiveBreakpoint>` per [Core]. Each core's collection of ActiveBreakpoint's will be managed on demand.
+    /// [SessionData] will manage a `Vec<ActiveBreakpoint>` per [Core]. Each core's collection of ActiveBreakpoint's will be managed on demand.
     pub(crate) breakpoints: Vec<Vec<ActiveBreakpoint>>,
     /// The control structures for handling RTT in this Core of the DebugSession.
     pub(crate) rtt_connection: Option<RttConnection>,
+    /// Services semihosting requests of the target application.
+    pub(crate) semihosting: Semihosting,
 }
 
-impl DebugSession {
+/// DebugSession is designed to be similar to [probe_rs::Session], in as much that it provides handles to the [CoreData] instances for each of the available [probe_rs::Core] involved in the debug session.
+/// To get access to the [CoreData] for a specific [Core], use [DebugSession::attach_core].
+/// Only the cores specified in [DebuggerOptions::core_configs] can be attached to.
+///
+/// It is created by [SessionData::lock], and keeps the [Session] locked until it is dropped. The other fields refer to the fields of [SessionData].
+pub struct DebugSession<'s> {
+    pub(crate) session: MutexGuard<'s, Session>,
+    pub(crate) capstone: &'s Capstone,
+    pub(crate) debug_infos: &'s BTreeMap<usize, DebugInfo>,
+    pub(crate) stack_frames: &'s mut Vec<Vec<probe_rs::debug::StackFrame>>,
+    pub(crate) breakpoints: &'s mut Vec<Vec<ActiveBreakpoint>>,
+    pub(crate) rtt_connection: &'s mut Option<RttConnection>,
+    pub(crate) semihosting: &'s mut Semihosting,
+}
+
+impl SessionData {
     pub(crate) fn new(debugger_options: &DebuggerOptions) -> Result<Self, DebuggerError> {
         let mut target_probe = match debugger_options.probe_selector.clone() {
             Some(selector) => Probe::open(selector.clone()).map_err(|e| match e {
@@ -369,19 +440,39 @@ impl DebugSession {
             })?;
         };
 
-        // TODO: We currently only allow a single core & binary to be specified in [DebuggerOptions]. When this is extended to support multicore, the following should initialize [DebugInfo] and [VariableCache] for each available core.
-        // Configure the [DebugInfo].
-        let debug_infos = vec![
-            if let Some(binary_path) = &debugger_options.program_binary {
+        // Configure the [DebugInfo] for each of the cores to debug.
+        let mut debug_infos = BTreeMap::new();
+        for core_config in &debugger_options.core_configs {
+            if core_config.core_index >= target_session.list_cores().len() {
+                return Err(anyhow!(
+                    "Core #{} is not available on this target",
+                    core_config.core_index
+                )
+                .into());
+            }
+
+            let debug_info = if let Some(binary_path) = &core_config.program_binary {
                 DebugInfo::from_file(binary_path)
                     .map_err(|error| DebuggerError::Other(anyhow!(error)))?
             } else {
                 return Err(anyhow!(
-                    "Please provide a valid `program_binary` for this debug session"
+                    "Please provide a valid `program_binary` for core #{} in this debug session",
+                    core_config.core_index
                 )
                 .into());
-            },
-        ];
+            };
+
+            if debug_infos
+                .insert(core_config.core_index, debug_info)
+                .is_some()
+            {
+                return Err(anyhow!(
+                    "Core #{} was specified more than once",
+                    core_config.core_index
+                )
+                .into());
+            }
+        }
 
         // Configure the [VariableCache].
         let stack_frames = target_session.list_cores()
@@ -407,24 +498,62 @@ impl DebugSession {
                 Vec::<ActiveBreakpoint>::new()
             }).collect();
 
-        Ok(DebugSession {
-            session: target_session,
+        Ok(SessionData {
+            session: Arc::new(Mutex::new(target_session)),
             capstone,
             debug_infos,
             stack_frames,
             breakpoints,
             rtt_connection: None,
+            semihosting: Semihosting::captured(),
         })
     }
 
+    /// Locks the [Session], to process a request. Don't keep the [DebugSession] while waiting, so RTT can be streamed in the meantime.
+    pub(crate) fn lock(&mut self) -> DebugSession<'_> {
+        DebugSession {
+            // The stream only accesses the channels of the target, so the session is still usable if its thread panicked.
+            session: self.session.lock().unwrap_or_else(PoisonError::into_inner),
+            capstone: &self.capstone,
+            debug_infos: &self.debug_infos,
+            stack_frames: &mut self.stack_frames,
+            breakpoints: &mut self.breakpoints,
+            rtt_connection: &mut self.rtt_connection,
+            semihosting: &mut self.semihosting,
+        }
+    }
+}
+
+impl DebugSession<'_> {
+    /// The indices of all the cores which are debugged in this session. Each of them is reported as a thread to the DAP client.
+    pub(crate) fn core_indices(&self) -> Vec<usize> {
+        self.debug_infos.keys().copied().collect()
+    }
+
+    /// Find the core which owns the stack frame with the given `frame_id`.
+    pub(crate) fn core_index_for_frame(&self, frame_id: i64) -> Option<usize> {
+        self.stack_frames
+            .iter()
+            .position(|stack_frames| stack_frames.iter().any(|frame| frame.id == frame_id))
+    }
+
     pub fn attach_core(&mut self, core_index: usize) -> Result<CoreData, DebuggerError> {
         let target_name = self.session.target().name.clone();
+        let core_type = self
+            .session
+            .list_cores()
+            .get(core_index)
+            .map(|(_, core_type)| *core_type)
+            .ok_or(DebuggerError::UnableToOpenProbe(Some(
+                "No core at the specified index.",
+            )))?;
         // Do a 'light weight'(just get references to existing data structures) attach to the core and return relevant debug data.
         match self.session.core(core_index) {
             Ok(target_core) => Ok(CoreData {
                 target_core,
                 target_name: format!("{}-{}", core_index, target_name),
-                debug_info: self.debug_infos.get(core_index).ok_or_else(|| {
+                core_type,
+                debug_info: self.debug_infos.get(&core_index).ok_or_else(|| {
                     DebuggerError::Other(anyhow!(
                         "No available `DebugInfo` for core # {}",
                         core_index
@@ -436,14 +565,15 @@ impl DebugSession {
                         core_index
                     ))
                 })?,
-                capstone: &self.capstone,
+                capstone: self.capstone,
                 breakpoints: self.breakpoints.get_mut(core_index).ok_or_else(|| {
                     DebuggerError::Other(anyhow!(
                         "ActiveBreakpoint cache was not correctly configured for core # {}",
                         core_index
                     ))
                 })?,
-                rtt_connection: &mut self.rtt_connection,
+                rtt_connection: &mut *self.rtt_connection,
+                semihosting: &mut *self.semihosting,
             }),
             Err(_) => Err(DebuggerError::UnableToOpenProbe(Some(
                 "No core at the specified index.",
@@ -458,6 +588,8 @@ pub(crate) struct RttConnection {
     target_rtt: rtt::RttActiveTarget,
     /// Some status fields and methods to ensure continuity in flow of data from target to debugger to client.
     debugger_rtt_channels: Vec<DebuggerRttChannel>,
+    /// The down channel which receives input from the client, as configured with `rttDownChannel`.
+    down_channel: Option<usize>,
 }
 
 impl RttConnection {
@@ -468,29 +600,45 @@ impl RttConnection {
         debug_adapter: &mut DebugAdapter<P>,
         target_core: &mut Core,
     ) -> bool {
+        // The channels are streamed on a thread, only the modes of the channels are restored after the target booted.
+        self.target_rtt.restore_channel_modes(target_core);
+
         let mut at_least_one_channel_had_data = false;
         for debugger_rtt_channel in self.debugger_rtt_channels.iter_mut() {
-            if debugger_rtt_channel.poll_rtt_data(target_core, debug_adapter, &mut self.target_rtt)
-            {
+            if debugger_rtt_channel.poll_rtt_data(debug_adapter, &mut self.target_rtt) {
                 at_least_one_channel_had_data = true;
             }
         }
         at_least_one_channel_had_data
     }
+
+    /// Returns `true` if the RTT control block is gone, e.g. because the target was reset, and RTT has to be attached again.
+    pub(crate) fn control_block_lost(&mut self, target_core: &mut Core) -> bool {
+        self.target_rtt.control_block_lost(target_core)
+    }
+
+    /// Sends input from the client to the target, using the configured down channel.
+    pub(crate) fn send_input(&mut self, data: &[u8]) -> Result<()> {
+        self.target_rtt.push_rtt(self.down_channel, data)
+    }
+
+    /// Calls `operation` while RTT is not streamed, e.g. while the flash is written.
+    pub(crate) fn paused<R>(&self, operation: impl FnOnce() -> R) -> R {
+        self.target_rtt.paused(operation)
+    }
 }
 
 pub(crate) struct DebuggerRttChannel {
     pub(crate) channel_number: usize,
-    // We will not poll target RTT channels until we have confirmation from the client that the output window has been opened.
+    // We will not send data of the RTT channels until we have confirmation from the client that the output window has been opened.
     pub(crate) has_client_window: bool,
 }
 impl DebuggerRttChannel {
-    /// Poll and retrieve data from the target, and send it to the client, depending on the state of `hasClientWindow`.
-    /// Doing this selectively ensures that we don't pull data from target buffers until we have an output window, and also helps us drain buffers after the target has entered a `is_halted` state.
+    /// Retrieve the data which the RTT stream read from the target, and send it to the client, depending on the state of `hasClientWindow`.
+    /// Until we have an output window, the data is kept by the stream, and the stream also drains the buffers after the target has entered a `is_halted` state.
     /// Errors will be reported back to the `debug_adapter`, and the return `bool` value indicates whether there was available data that was processed.
     pub(crate) fn poll_rtt_data<P: ProtocolAdapter>(
         &mut self,
-        core: &mut Core,
         debug_adapter: &mut DebugAdapter<P>,
         rtt_target: &mut rtt::RttActiveTarget,
     ) -> bool {
@@ -505,9 +653,7 @@ impl DebuggerRttChannel {
                         false
                     }
                 })
-                .and_then(|rtt_channel| {
-                    rtt_channel.get_rtt_data(core, rtt_target.defmt_state.as_ref())
-                })
+                .and_then(|rtt_channel| rtt_channel.get_rtt_data(rtt_target.defmt_state.as_ref()))
                 .and_then(|(channel_number, channel_data)| {
                     if debug_adapter
                         .rtt_output(channel_number.parse::<usize>().unwrap_or(0), channel_data)
@@ -530,11 +676,13 @@ impl DebuggerRttChannel {
 pub struct CoreData<'p> {
     pub(crate) target_core: Core<'p>,
     pub(crate) target_name: String,
+    pub(crate) core_type: CoreType,
     pub(crate) debug_info: &'p DebugInfo,
     pub(crate) stack_frames: &'p mut Vec<probe_rs::debug::StackFrame>,
     pub(crate) capstone: &'p Capstone,
     pub(crate) breakpoints: &'p mut Vec<ActiveBreakpoint>,
     pub(crate) rtt_connection: &'p mut Option<RttConnection>,
+    pub(crate) semihosting: &'p mut Semihosting,
 }
 
 impl<'p> CoreData<'p> {
@@ -545,16 +693,32 @@ impl<'p> CoreData<'p> {
             .find(|stack_frame| stack_frame.id == id)
     }
 
+    /// Describe why the core halted. If the core is halted in a fault handler, the analysis of the fault is included.
+    pub(crate) fn halted_description(&mut self, status: CoreStatus) -> String {
+        let description = status.short_long_status().1;
+
+        match fault::analyze_fault(&mut self.target_core, self.core_type) {
+            Ok(Some(report)) => format!("{} - {}", description, report.summary()),
+            Ok(None) => description.to_string(),
+            Err(error) => {
+                log::debug!("Unable to analyze the fault status: {}", error);
+                description.to_string()
+            }
+        }
+    }
+
     /// Confirm RTT initialization on the target, and use the RTT channel configurations to initialize the output windows on the DAP Client.
     pub fn attach_to_rtt<P: ProtocolAdapter>(
         &mut self,
         debug_adapter: &mut DebugAdapter<P>,
+        session: &Arc<Mutex<Session>>,
         target_memory_map: &[probe_rs::config::MemoryRegion],
         program_binary: &std::path::Path,
         rtt_config: &rtt::RttConfig,
     ) -> Result<()> {
         let mut debugger_rtt_channels: Vec<DebuggerRttChannel> = vec![];
         match rtt::attach_to_rtt(
+            session,
             &mut self.target_core,
             target_memory_map,
             program_binary,
@@ -578,10 +742,14 @@ impl<'p> CoreData<'p> {
                 *self.rtt_connection = Some(RttConnection {
                     target_rtt,
                     debugger_rtt_channels,
+                    down_channel: rtt_config.down_channel,
                 });
             }
-            Err(_error) => {
-                log::warn!("Failed to initalize RTT. Will try again on the next request... ");
+            Err(error) => {
+                log::debug!(
+                    "Failed to initalize RTT. Will try again later... {:?}",
+                    error
+                );
             }
         };
         Ok(())
@@ -643,6 +811,16 @@ pub(crate) enum DebuggerStatus {
     TerminateSession,
 }
 
+/// The result of polling a single core, while there are no requests from the DAP Client.
+enum CorePollStatus {
+    /// Nothing changed, so there is no need to poll again right away.
+    Idle,
+    /// The core status changed, or there was data to process, so we should poll again right away.
+    Busy,
+    /// The target application exited, and the debug session should end.
+    Exited,
+}
+
 /// #Debugger Overview
 /// The DAP Server will usually be managed automatically by the VSCode client.
 /// The DAP Server can optionally be run from the command line as a "server" process.
@@ -650,78 +828,178 @@ pub(crate) enum DebuggerStatus {
 ///   - `probe-rs-debug --debug --port <IP port number> <other options>` : Uses TCP Sockets to the defined IP port number to service DAP requests.
 pub struct Debugger {
     debugger_options: DebuggerOptions,
+    /// The core which requests without a thread or stack frame apply to, e.g. `variables` or `readMemory`.
+    /// This is the core of the last request which specified a thread or stack frame.
+    focused_core_index: usize,
 }
 
 impl Debugger {
     pub fn new(debugger_options: DebuggerOptions) -> Self {
         // Define all the commands supported by the debugger.
         // TODO: There is a lot of repetitive code here, and a great opportunity for macros.
-        Self { debugger_options }
+        let focused_core_index = debugger_options.core_index;
+        Self {
+            debugger_options,
+            focused_core_index,
+        }
     }
 
     pub(crate) fn process_next_request<P: ProtocolAdapter>(
         &mut self,
-        session_data: &mut DebugSession,
+        session_data: &mut SessionData,
         debug_adapter: &mut DebugAdapter<P>,
     ) -> Result<DebuggerStatus, DebuggerError> {
         let request = debug_adapter.listen_for_request()?;
         match request {
             None => {
-                /*
-                The logic of this command is as follows:
-                - While we are waiting for DAP-Client (TCP or STDIO), we have to continuously check in on the status of the probe.
-                - Initally, while `LAST_KNOWN_STATUS` probe-rs::CoreStatus::Unknown, we do nothing. Wait until latter part of `debug_session` sets it to something known.
-                - If the `LAST_KNOWN_STATUS` is `Halted`, then we stop polling the Probe until the next DAP-Client request attempts an action
-                - If the `new_status` is an Err, then the probe is no longer available, and we  end the debugging session
-                - If the `new_status` is different from the `LAST_KNOWN_STATUS`, then we have to tell the DAP-Client by way of an `Event`
-                - If the `new_status` is `Running`, then we have to poll on a regular basis, until the Probe stops for good reasons like breakpoints, or bad reasons like panics. Then tell the DAP-Client.
-                */
-                match debug_adapter.last_known_status {
-                    CoreStatus::Unknown => Ok(DebuggerStatus::ContinueSession), // Don't do anything until we know VSCode's startup sequence is complete, and changes this to either Halted or Running.
-                    CoreStatus::Halted(_) => {
-                        // Make sure the RTT buffers are drained.
-                        match session_data.attach_core(self.debugger_options.core_index) {
-                            Ok(mut core_data) => {
-                                if let Some(rtt_active_target) = &mut core_data.rtt_connection {
-                                    rtt_active_target.process_rtt_data(
-                                        debug_adapter,
-                                        &mut core_data.target_core,
-                                    );
-                                };
-                            }
-                            Err(error) => {
-                                let _ = debug_adapter.send_error_response(&error)?;
-                                return Err(error);
+                // While we are waiting for DAP-Client (TCP or STDIO), we have to continuously check in on the status of each core.
+                let mut poll_again = false;
+                let mut session_data = session_data.lock();
+                for core_index in session_data.core_indices() {
+                    match self.poll_core(core_index, &mut session_data, debug_adapter)? {
+                        CorePollStatus::Idle => {}
+                        CorePollStatus::Busy => poll_again = true,
+                        CorePollStatus::Exited => return Ok(DebuggerStatus::TerminateSession),
+                    }
+                }
+                // The session is unlocked while sleeping, so RTT can be streamed.
+                drop(session_data);
+
+                // Only sleep (nap for a short duration) IF none of the core statuses changed AND there was no RTT data in the last poll.
+                // Otherwise loop again to keep things flowing as fast as possible.
+                // The justification is that any client side CPU used to keep polling is a small price to pay for maximum throughput of debug requests and RTT from the probe.
+                if !poll_again {
+                    thread::sleep(Duration::from_millis(50)); // Small delay to reduce fast looping costs.
+                }
+                Ok(DebuggerStatus::ContinueSession)
+            }
+            Some(request) => {
+                let session_data = &mut session_data.lock();
+                // Some requests apply to all the cores in the session, so they are handled without attaching to a single core.
+                let result = match request.command.clone().as_ref() {
+                    "threads" => debug_adapter.threads(session_data, request),
+                    "continue" => debug_adapter.r#continue(session_data, request),
+                    "setBreakpoints" => debug_adapter.set_breakpoints(session_data, request),
+                    "setExceptionBreakpoints" => {
+                        debug_adapter.set_exception_breakpoints(session_data, request)
+                    }
+                    _ => return self.process_core_request(request, session_data, debug_adapter),
+                };
+
+                match result {
+                    Ok(_) => Ok(DebuggerStatus::ContinueSession),
+                    Err(e) => Err(DebuggerError::Other(e.context("Error executing request."))),
+                }
+            }
+        }
+    }
+
+    /// Find the core a request applies to. This is the thread in the request, or the core which owns the stack frame in the request.
+    /// Other requests apply to the core which was used last, e.g. a `variables` request after a `scopes` request.
+    fn requested_core_index(&mut self, session_data: &DebugSession, request: &Request) -> usize {
+        let arguments = request.arguments.as_ref();
+
+        let thread_core_index = arguments
+            .and_then(|arguments| arguments.get("threadId"))
+            .and_then(|thread_id| thread_id.as_u64())
+            .map(|thread_id| thread_id as usize);
+        let frame_core_index = arguments
+            .and_then(|arguments| arguments.get("frameId"))
+            .and_then(|frame_id| frame_id.as_i64())
+            .and_then(|frame_id| session_data.core_index_for_frame(frame_id));
+
+        if let Some(core_index) = thread_core_index.or(frame_core_index) {
+            self.focused_core_index = core_index;
+        }
+        self.focused_core_index
+    }
+
+    /// Polls the core at `core_index` for status changes, semihosting requests and RTT data, and notifies the DAP Client.
+    fn poll_core<P: ProtocolAdapter>(
+        &self,
+        core_index: usize,
+        session_data: &mut DebugSession,
+        debug_adapter: &mut DebugAdapter<P>,
+    ) -> Result<CorePollStatus, DebuggerError> {
+        /*
+        The logic of this command is as follows:
+        - Initally, while `last_known_status` is probe-rs::CoreStatus::Unknown, we do nothing. Wait until latter part of `debug_session` sets it to something known.
+        - If the `last_known_status` is `Halted`, then we stop polling the core until the next DAP-Client request attempts an action
+        - If the `new_status` is an Err, then the probe is no longer available, and we  end the debugging session
+        - If the `new_status` is different from the `last_known_status`, then we have to tell the DAP-Client by way of an `Event`
+        - If the `new_status` is `Running`, then we have to poll on a regular basis, until the core stops for good reasons like breakpoints, or bad reasons like panics. Then tell the DAP-Client.
+        */
+        // RTT is only polled through the core which was used to attach to it.
+        let is_rtt_core = self
+            .debugger_options
+            .rtt_core_config()
+            .map(|core_config| core_config.core_index)
+            == Some(core_index);
+
+        let last_known_status = debug_adapter.last_known_status(core_index);
+        match last_known_status {
+            CoreStatus::Unknown => Ok(CorePollStatus::Idle), // Don't do anything until we know VSCode's startup sequence is complete, and changes this to either Halted or Running.
+            CoreStatus::Halted(_) => {
+                // Make sure the RTT buffers are drained.
+                if is_rtt_core {
+                    match session_data.attach_core(core_index) {
+                        Ok(mut core_data) => {
+                            if let Some(rtt_active_target) = &mut core_data.rtt_connection {
+                                rtt_active_target
+                                    .process_rtt_data(debug_adapter, &mut core_data.target_core);
+                            };
+                        }
+                        Err(error) => {
+                            let _ = debug_adapter.send_error_response(&error)?;
+                            return Err(error);
+                        }
+                    };
+                }
+
+                // No need to poll the target status if we know it is halted and waiting for us to do something.
+                Ok(CorePollStatus::Idle)
+            }
+            _other => {
+                let mut received_rtt_data = false;
+                let mut core_data = match session_data.attach_core(core_index) {
+                    Ok(mut core_data) => {
+                        // Use every opportunity to poll the RTT channels for data
+                        if is_rtt_core {
+                            if let Some(rtt_active_target) = &mut core_data.rtt_connection {
+                                received_rtt_data = rtt_active_target
+                                    .process_rtt_data(debug_adapter, &mut core_data.target_core);
+
+                                // The main loop attaches again, once the target initialized RTT after the reset.
+                                if rtt_active_target.control_block_lost(&mut core_data.target_core)
+                                {
+                                    log::info!("The RTT control block was lost, probably because the target was reset.");
+                                    *core_data.rtt_connection = None;
+                                }
                             }
-                        };
+                        }
+                        core_data
+                    }
+                    Err(error) => {
+                        let _ = debug_adapter.send_error_response(&error)?;
+                        return Err(error);
+                    }
+                };
 
-                        // No need to poll the target status if we know it is halted and waiting for us to do something.
-                        thread::sleep(Duration::from_millis(50)); // Small delay to reduce fast looping costs on the client
-                        Ok(DebuggerStatus::ContinueSession)
+                // Check and update the core status.
+                let new_status = match core_data.target_core.status() {
+                    Ok(new_status) => new_status,
+                    Err(error) => {
+                        let error = DebuggerError::ProbeRs(error);
+                        let _ = debug_adapter.send_error_response(&error);
+                        return Err(error);
                     }
-                    _other => {
-                        let mut received_rtt_data = false;
-                        let mut core_data =
-                            match session_data.attach_core(self.debugger_options.core_index) {
-                                Ok(mut core_data) => {
-                                    // Use every opportunity to poll the RTT channels for data
-                                    if let Some(rtt_active_target) = &mut core_data.rtt_connection {
-                                        received_rtt_data = rtt_active_target.process_rtt_data(
-                                            debug_adapter,
-                                            &mut core_data.target_core,
-                                        );
-                                    }
-                                    core_data
-                                }
-                                Err(error) => {
-                                    let _ = debug_adapter.send_error_response(&error)?;
-                                    return Err(error);
-                                }
-                            };
+                };
 
-                        // Check and update the core status.
-                        let new_status = match core_data.target_core.status() {
-                            Ok(new_status) => new_status,
+                // Semihosting requests halt the core, so service them before the halt is reported to the client.
+                if new_status.is_halted() {
+                    let semihosting_status =
+                        match core_data.semihosting.handle(&mut core_data.target_core) {
+                            Ok(semihosting_status) => semihosting_status,
                             Err(error) => {
                                 let error = DebuggerError::ProbeRs(error);
                                 let _ = debug_adapter.send_error_response(&error);
@@ -729,248 +1007,275 @@ impl Debugger {
                             }
                         };
 
-                        // Only sleep (nap for a short duration) IF the probe's status hasn't changed AND there was no RTT data in the last poll.
-                        // Otherwise loop again to keep things flowing as fast as possible.
-                        // The justification is that any client side CPU used to keep polling is a small price to pay for maximum throughput of debug requests and RTT from the probe.
-                        if received_rtt_data && new_status == debug_adapter.last_known_status {
-                            return Ok(DebuggerStatus::ContinueSession);
-                        } else if new_status == debug_adapter.last_known_status {
-                            thread::sleep(Duration::from_millis(50)); // Small delay to reduce fast looping costs.
-                            return Ok(DebuggerStatus::ContinueSession);
-                        } else {
-                            debug_adapter.last_known_status = new_status;
-                        };
-
-                        match new_status {
-                            CoreStatus::Running | CoreStatus::Sleeping => {
-                                let event_body = Some(ContinuedEventBody {
-                                    all_threads_continued: Some(true),
-                                    thread_id: core_data.target_core.id() as i64,
-                                });
-                                debug_adapter.send_event("continued", event_body)?;
-                            }
-                            CoreStatus::Halted(_) => {
-                                let event_body = Some(StoppedEventBody {
-                                    reason: new_status.short_long_status().0.to_owned(),
-                                    description: Some(new_status.short_long_status().1.to_owned()),
-                                    thread_id: Some(core_data.target_core.id() as i64),
-                                    preserve_focus_hint: Some(false),
-                                    text: None,
-                                    all_threads_stopped: Some(true),
-                                    hit_breakpoint_ids: None,
-                                });
-                                debug_adapter.send_event("stopped", event_body)?;
-                            }
-                            CoreStatus::LockedUp => {
-                                debug_adapter.show_message(
-                                    MessageSeverity::Error,
-                                    new_status.short_long_status().1.to_owned(),
-                                );
-                                return Err(DebuggerError::Other(anyhow!(new_status
-                                    .short_long_status()
-                                    .1
-                                    .to_owned())));
-                            }
-                            CoreStatus::Unknown => {
-                                debug_adapter.send_error_response(&DebuggerError::Other(
-                                    anyhow!("Unknown Device status reveived from Probe-rs"),
-                                ))?;
+                    let output = core_data.semihosting.take_console_output();
+                    if !output.is_empty() {
+                        debug_adapter.semihosting_output(&output);
+                    }
 
-                                return Err(DebuggerError::Other(anyhow!(
-                                    "Unknown Device status reveived from Probe-rs"
-                                )));
-                            }
-                        };
-                        Ok(DebuggerStatus::ContinueSession)
+                    match semihosting_status {
+                        SemihostingStatus::Handled => return Ok(CorePollStatus::Busy),
+                        SemihostingStatus::Exit(exit_code) => {
+                            debug_adapter.send_event(
+                                "exited",
+                                Some(ExitedEventBody {
+                                    exit_code: exit_code as i64,
+                                }),
+                            )?;
+                            debug_adapter.send_event(
+                                "terminated",
+                                Some(TerminatedEventBody { restart: None }),
+                            )?;
+                            return Ok(CorePollStatus::Exited);
+                        }
+                        SemihostingStatus::NoRequest => {}
                     }
                 }
-            }
-            Some(request) => {
-                // First, attach to the core.
-                let mut core_data = match session_data.attach_core(self.debugger_options.core_index)
-                {
-                    Ok(core_data) => core_data,
-                    Err(error) => {
-                        let failed_command = request.command.clone();
-                        debug_adapter.send_response::<()>(request, Err(error))?;
+
+                if new_status == last_known_status {
+                    return Ok(if received_rtt_data {
+                        CorePollStatus::Busy
+                    } else {
+                        CorePollStatus::Idle
+                    });
+                }
+                debug_adapter.set_last_known_status(core_index, new_status);
+
+                match new_status {
+                    CoreStatus::Running | CoreStatus::Sleeping => {
+                        let event_body = Some(ContinuedEventBody {
+                            all_threads_continued: Some(false),
+                            thread_id: core_index as i64,
+                        });
+                        debug_adapter.send_event("continued", event_body)?;
+                    }
+                    CoreStatus::Halted(_) => {
+                        let event_body = Some(StoppedEventBody {
+                            reason: new_status.short_long_status().0.to_owned(),
+                            description: Some(core_data.halted_description(new_status)),
+                            thread_id: Some(core_index as i64),
+                            preserve_focus_hint: Some(false),
+                            text: None,
+                            all_threads_stopped: Some(debug_adapter.all_cores_halted()),
+                            hit_breakpoint_ids: None,
+                        });
+                        debug_adapter.send_event("stopped", event_body)?;
+                    }
+                    CoreStatus::LockedUp => {
+                        debug_adapter.show_message(
+                            MessageSeverity::Error,
+                            new_status.short_long_status().1.to_owned(),
+                        );
+                        return Err(DebuggerError::Other(anyhow!(new_status
+                            .short_long_status()
+                            .1
+                            .to_owned())));
+                    }
+                    CoreStatus::Unknown => {
+                        debug_adapter.send_error_response(&DebuggerError::Other(anyhow!(
+                            "Unknown Device status reveived from Probe-rs"
+                        )))?;
+
                         return Err(DebuggerError::Other(anyhow!(
-                            "Error while attaching to core. Could not complete command {}",
-                            failed_command
+                            "Unknown Device status reveived from Probe-rs"
                         )));
                     }
                 };
+                Ok(CorePollStatus::Busy)
+            }
+        }
+    }
 
-                // For some operations, we need to make sure the core isn't sleeping, by calling `Core::halt()`.
-                // When we do this, we need to flag it (`unhalt_me = true`), and later call `Core::run()` again.
-                // NOTE: The target will exit sleep mode as a result of this command.
-                let mut unhalt_me = false;
-                match request.command.as_ref() {
-                    "configurationDone"
-                    | "setBreakpoint"
-                    | "setBreakpoints"
-                    | "setInstructionBreakpoints"
-                    | "clearBreakpoint"
-                    | "stackTrace"
-                    | "threads"
-                    | "scopes"
-                    | "variables"
-                    | "readMemory"
-                    | "writeMemory"
-                    | "disassemble" => {
-                        match core_data.target_core.status() {
-                            Ok(current_status) => {
-                                if current_status == CoreStatus::Sleeping {
-                                    match core_data.target_core.halt(Duration::from_millis(100)) {
-                                        Ok(_) => {
-                                            debug_adapter.last_known_status =
-                                                CoreStatus::Halted(probe_rs::HaltReason::Request);
-                                            unhalt_me = true;
-                                        }
-                                        Err(error) => {
-                                            debug_adapter.send_response::<()>(
-                                                request,
-                                                Err(DebuggerError::Other(anyhow!("{}", error))),
-                                            )?;
-                                            return Err(error.into());
-                                        }
-                                    }
-                                }
-                            }
-                            Err(error) => {
-                                let failed_command = request.command.clone();
-                                let wrapped_err = DebuggerError::ProbeRs(error);
-                                debug_adapter.send_response::<()>(request, Err(wrapped_err))?;
-
-                                // TODO: Nicer response here
-                                return Err(DebuggerError::Other(anyhow!(
-                                    "Failed to get core status. Could not complete command: {:?}",
-                                    failed_command
-                                )));
-                            }
-                        }
-                    }
-                    _ => {}
-                }
+    /// Process a request which applies to a single core.
+    fn process_core_request<P: ProtocolAdapter>(
+        &mut self,
+        request: Request,
+        session_data: &mut DebugSession,
+        debug_adapter: &mut DebugAdapter<P>,
+    ) -> Result<DebuggerStatus, DebuggerError> {
+        let core_index = self.requested_core_index(session_data, &request);
+        // First, attach to the core.
+        let mut core_data = match session_data.attach_core(core_index) {
+            Ok(core_data) => core_data,
+            Err(error) => {
+                let failed_command = request.command.clone();
+                debug_adapter.send_response::<()>(request, Err(error))?;
+                return Err(DebuggerError::Other(anyhow!(
+                    "Error while attaching to core. Could not complete command {}",
+                    failed_command
+                )));
+            }
+        };
 
-                // Now we are ready to execute supported commands, or return an error if it isn't supported.
-                match match request.command.clone().as_ref() {
-                    "rttWindowOpened" => {
-                        if let Some(debugger_rtt_target) = core_data.rtt_connection {
-                            match get_arguments::<RttWindowOpenedArguments>(&request) {
-                                Ok(arguments) => {
-                                    debugger_rtt_target
-                                        .debugger_rtt_channels
-                                        .iter_mut()
-                                        .find(|debugger_rtt_channel| {
-                                            debugger_rtt_channel.channel_number
-                                                == arguments.channel_number
-                                        })
-                                        .map_or(false, |rtt_channel| {
-                                            rtt_channel.has_client_window =
-                                                arguments.window_is_open;
-                                            arguments.window_is_open
-                                        });
-                                    debug_adapter.send_response::<()>(request, Ok(None))?;
+        // For some operations, we need to make sure the core isn't sleeping, by calling `Core::halt()`.
+        // When we do this, we need to flag it (`unhalt_me = true`), and later call `Core::run()` again.
+        // NOTE: The target will exit sleep mode as a result of this command.
+        let mut unhalt_me = false;
+        match request.command.as_ref() {
+            "configurationDone"
+            | "setBreakpoint"
+            | "setInstructionBreakpoints"
+            | "clearBreakpoint"
+            | "stackTrace"
+            | "scopes"
+            | "variables"
+            | "readMemory"
+            | "writeMemory"
+            | "disassemble" => {
+                match core_data.target_core.status() {
+                    Ok(current_status) => {
+                        if current_status == CoreStatus::Sleeping {
+                            match core_data.target_core.halt(Duration::from_millis(100)) {
+                                Ok(_) => {
+                                    debug_adapter.set_last_known_status(
+                                        core_index,
+                                        CoreStatus::Halted(probe_rs::HaltReason::Request),
+                                    );
+                                    unhalt_me = true;
                                 }
                                 Err(error) => {
                                     debug_adapter.send_response::<()>(
                                         request,
-                                        Err(DebuggerError::Other(anyhow!(
-                                    "Could not deserialize arguments for RttWindowOpened : {:?}.",
-                                    error
-                                ))),
+                                        Err(DebuggerError::Other(anyhow!("{}", error))),
                                     )?;
+                                    return Err(error.into());
                                 }
                             }
                         }
-                        Ok(DebuggerStatus::ContinueSession)
                     }
-                    "disconnect" => debug_adapter
-                        .send_response::<()>(request, Ok(None))
-                        .and(Ok(DebuggerStatus::TerminateSession)),
-                    "terminate" => debug_adapter
-                        .pause(&mut core_data, request)
-                        .and(Ok(DebuggerStatus::TerminateSession)),
-                    "status" => debug_adapter
-                        .status(&mut core_data, request)
-                        .and(Ok(DebuggerStatus::ContinueSession)),
-                    "next" => debug_adapter
-                        .next(&mut core_data, request)
-                        .and(Ok(DebuggerStatus::ContinueSession)),
-                    "pause" => debug_adapter
-                        .pause(&mut core_data, request)
-                        .and(Ok(DebuggerStatus::ContinueSession)),
-                    "readMemory" => debug_adapter
-                        .read_memory(&mut core_data, request)
-                        .and(Ok(DebuggerStatus::ContinueSession)),
-                    "writeMemory" => debug_adapter
-                        .write_memory(&mut core_data, request)
-                        .and(Ok(DebuggerStatus::ContinueSession)),
-                    "setVariable" => debug_adapter
-                        .set_variable(&mut core_data, request)
-                        .and(Ok(DebuggerStatus::ContinueSession)),
-                    "configurationDone" => debug_adapter
-                        .configuration_done(&mut core_data, request)
-                        .and(Ok(DebuggerStatus::ContinueSession)),
-                    "threads" => debug_adapter
-                        .threads(&mut core_data, request)
-                        .and(Ok(DebuggerStatus::ContinueSession)),
-                    "restart" => {
-                        // Reset RTT so that the link can be re-established
-                        *core_data.rtt_connection = None;
-                        debug_adapter
-                            .restart(&mut core_data, Some(request))
-                            .and(Ok(DebuggerStatus::ContinueSession))
+                    Err(error) => {
+                        let failed_command = request.command.clone();
+                        let wrapped_err = DebuggerError::ProbeRs(error);
+                        debug_adapter.send_response::<()>(request, Err(wrapped_err))?;
+
+                        // TODO: Nicer response here
+                        return Err(DebuggerError::Other(anyhow!(
+                            "Failed to get core status. Could not complete command: {:?}",
+                            failed_command
+                        )));
                     }
-                    "setBreakpoints" => debug_adapter
-                        .set_breakpoints(&mut core_data, request)
-                        .and(Ok(DebuggerStatus::ContinueSession)),
-                    "setInstructionBreakpoints" => debug_adapter
-                        .set_instruction_breakpoints(&mut core_data, request)
-                        .and(Ok(DebuggerStatus::ContinueSession)),
-                    "stackTrace" => debug_adapter
-                        .stack_trace(&mut core_data, request)
-                        .and(Ok(DebuggerStatus::ContinueSession)),
-                    "scopes" => debug_adapter
-                        .scopes(&mut core_data, request)
-                        .and(Ok(DebuggerStatus::ContinueSession)),
-                    "disassemble" => debug_adapter
-                        .disassemble(&mut core_data, request)
-                        .and(Ok(DebuggerStatus::ContinueSession)),
-                    "variables" => debug_adapter
-                        .variables(&mut core_data, request)
-                        .and(Ok(DebuggerStatus::ContinueSession)),
-                    "continue" => debug_adapter
-                        .r#continue(&mut core_data, request)
-                        .and(Ok(DebuggerStatus::ContinueSession)),
-                    "evaluate" => debug_adapter
-                        .evaluate(&mut core_data, request)
-                        .and(Ok(DebuggerStatus::ContinueSession)),
-                    other_command => {
-                        // Unimplemented command.
-                        debug_adapter.send_response::<()>(
-                            request,
-                            Err(DebuggerError::Other(anyhow!("Received request '{}', which is not supported or not implemented yet", other_command))),)
-                            .and(Ok(DebuggerStatus::ContinueSession))
+                }
+            }
+            _ => {}
+        }
+
+        // Now we are ready to execute supported commands, or return an error if it isn't supported.
+        match match request.command.clone().as_ref() {
+            "rttWindowOpened" => {
+                if let Some(debugger_rtt_target) = core_data.rtt_connection {
+                    match get_arguments::<RttWindowOpenedArguments>(&request) {
+                        Ok(arguments) => {
+                            debugger_rtt_target
+                                .debugger_rtt_channels
+                                .iter_mut()
+                                .find(|debugger_rtt_channel| {
+                                    debugger_rtt_channel.channel_number == arguments.channel_number
+                                })
+                                .map_or(false, |rtt_channel| {
+                                    rtt_channel.has_client_window = arguments.window_is_open;
+                                    arguments.window_is_open
+                                });
+                            debug_adapter.send_response::<()>(request, Ok(None))?;
+                        }
+                        Err(error) => {
+                            debug_adapter.send_response::<()>(
+                                request,
+                                Err(DebuggerError::Other(anyhow!(
+                                    "Could not deserialize arguments for RttWindowOpened : {:?}.",
+                                    error
+                                ))),
+                            )?;
+                        }
                     }
-                } {
-                    Ok(debugger_status) => {
-                        if unhalt_me {
-                            match core_data.target_core.run() {
-                                Ok(_) => debug_adapter.last_known_status = CoreStatus::Running,
-                                Err(error) => {
-                                    debug_adapter.send_error_response(&DebuggerError::Other(
-                                        anyhow!("{}", error),
-                                    ))?;
-                                    return Err(error.into());
-                                }
-                            }
+                }
+                Ok(DebuggerStatus::ContinueSession)
+            }
+            "disconnect" => debug_adapter
+                .send_response::<()>(request, Ok(None))
+                .and(Ok(DebuggerStatus::TerminateSession)),
+            "terminate" => debug_adapter
+                .pause(&mut core_data, request)
+                .and(Ok(DebuggerStatus::TerminateSession)),
+            "status" => debug_adapter
+                .status(&mut core_data, request)
+                .and(Ok(DebuggerStatus::ContinueSession)),
+            "next" => debug_adapter
+                .next(&mut core_data, request)
+                .and(Ok(DebuggerStatus::ContinueSession)),
+            "stepIn" => debug_adapter
+                .step_in(&mut core_data, request)
+                .and(Ok(DebuggerStatus::ContinueSession)),
+            "stepOut" => debug_adapter
+                .step_out(&mut core_data, request)
+                .and(Ok(DebuggerStatus::ContinueSession)),
+            "pause" => debug_adapter
+                .pause(&mut core_data, request)
+                .and(Ok(DebuggerStatus::ContinueSession)),
+            "readMemory" => debug_adapter
+                .read_memory(&mut core_data, request)
+                .and(Ok(DebuggerStatus::ContinueSession)),
+            "writeMemory" => debug_adapter
+                .write_memory(&mut core_data, request)
+                .and(Ok(DebuggerStatus::ContinueSession)),
+            "setVariable" => debug_adapter
+                .set_variable(&mut core_data, request)
+                .and(Ok(DebuggerStatus::ContinueSession)),
+            "configurationDone" => debug_adapter
+                .configuration_done(&mut core_data, request)
+                .and(Ok(DebuggerStatus::ContinueSession)),
+            "restart" => {
+                // Reset RTT so that the link can be re-established
+                *core_data.rtt_connection = None;
+                debug_adapter
+                    .restart(&mut core_data, Some(request))
+                    .and(Ok(DebuggerStatus::ContinueSession))
+            }
+            "setInstructionBreakpoints" => debug_adapter
+                .set_instruction_breakpoints(&mut core_data, request)
+                .and(Ok(DebuggerStatus::ContinueSession)),
+            "stackTrace" => debug_adapter
+                .stack_trace(&mut core_data, request)
+                .and(Ok(DebuggerStatus::ContinueSession)),
+            "scopes" => debug_adapter
+                .scopes(&mut core_data, request)
+                .and(Ok(DebuggerStatus::ContinueSession)),
+            "disassemble" => debug_adapter
+                .disassemble(&mut core_data, request)
+                .and(Ok(DebuggerStatus::ContinueSession)),
+            "variables" => debug_adapter
+                .variables(&mut core_data, request)
+                .and(Ok(DebuggerStatus::ContinueSession)),
+            "evaluate" => debug_adapter
+                .evaluate(&mut core_data, request)
+                .and(Ok(DebuggerStatus::ContinueSession)),
+            other_command => {
+                // Unimplemented command.
+                debug_adapter
+                    .send_response::<()>(
+                        request,
+                        Err(DebuggerError::Other(anyhow!(
+                            "Received request '{}', which is not supported or not implemented yet",
+                            other_command
+                        ))),
+                    )
+                    .and(Ok(DebuggerStatus::ContinueSession))
+            }
+        } {
+            Ok(debugger_status) => {
+                if unhalt_me {
+                    match core_data.target_core.run() {
+                        Ok(_) => {
+                            debug_adapter.set_last_known_status(core_index, CoreStatus::Running)
+                        }
+                        Err(error) => {
+                            debug_adapter
+                                .send_error_response(&DebuggerError::Other(anyhow!("{}", error)))?;
+                            return Err(error.into());
                         }
-                        Ok(debugger_status)
                     }
-                    Err(e) => Err(DebuggerError::Other(e.context("Error executing request."))),
                 }
+                Ok(debugger_status)
             }
+            Err(e) => Err(DebuggerError::Other(e.context("Error executing request."))),
         }
     }
 
@@ -1059,11 +1364,24 @@ impl Debugger {
             supports_clipboard_context: Some(true),
             supports_disassemble_request: Some(true),
             supports_instruction_breakpoints: Some(true),
+            supports_stepping_granularity: Some(true),
+            supports_single_thread_execution_requests: Some(true),
             // supports_value_formatting_options: Some(true),
             // supports_function_breakpoints: Some(true),
-            // TODO: Use DEMCR register to implement exception breakpoints
-            // supports_exception_options: Some(true),
-            // supports_exception_filter_options: Some (true),
+            supports_exception_filter_options: Some(true),
+            exception_breakpoint_filters: Some(
+                EXCEPTION_FILTERS
+                    .iter()
+                    .map(
+                        |(filter, label, description, _)| ExceptionBreakpointsFilter {
+                            filter: filter.to_string(),
+                            label: label.to_string(),
+                            description: Some(description.to_string()),
+                            ..Default::default()
+                        },
+                    )
+                    .collect(),
+            ),
             ..Default::default()
         };
         debug_adapter.send_response(initialize_request, Ok(Some(capabilities)))?;
@@ -1150,34 +1468,50 @@ impl Debugger {
                         return Err(err);
                     }
                 }
-                match self.debugger_options.program_binary.clone() {
-                    Some(program_binary) => {
-                        if !program_binary.is_file() {
+                match self.debugger_options.qualify_and_update_core_configs() {
+                    Ok(_) => {}
+                    Err(error) => {
+                        let err = DebuggerError::Other(anyhow!(
+                            "Unable to validate the program_binary path of the core_configs '{:?}'",
+                            error
+                        ));
+
+                        debug_adapter.send_error_response(&err)?;
+                        return Err(err);
+                    }
+                }
+                for core_config in &self.debugger_options.core_configs {
+                    match core_config.program_binary.clone() {
+                        Some(program_binary) => {
+                            if !program_binary.is_file() {
+                                debug_adapter.send_response::<()>(
+                                    la_request,
+                                    Err(DebuggerError::Other(anyhow!(
+                                        "Invalid program binary file specified '{:?}'",
+                                        program_binary
+                                    ))),
+                                )?;
+                                return Err(DebuggerError::Other(anyhow!(
+                                    "Invalid program binary file specified '{:?}'",
+                                    program_binary
+                                )));
+                            }
+                        }
+                        None => {
                             debug_adapter.send_response::<()>(
                                 la_request,
                                 Err(DebuggerError::Other(anyhow!(
-                                    "Invalid program binary file specified '{:?}'",
-                                    program_binary
+                                    "Please use the --program-binary option to specify an executable for core #{}",
+                                    core_config.core_index
                                 ))),
                             )?;
+
                             return Err(DebuggerError::Other(anyhow!(
-                                "Invalid program binary file specified '{:?}'",
-                                program_binary
+                                "Please use the --program-binary option to specify an executable for core #{}",
+                                core_config.core_index
                             )));
                         }
                     }
-                    None => {
-                        debug_adapter.send_response::<()>(
-                            la_request,
-                            Err(DebuggerError::Other(anyhow!(
-                                "Please use the --program-binary option to specify an executable"
-                            ))),
-                        )?;
-
-                        return Err(DebuggerError::Other(anyhow!(
-                            "Please use the --program-binary option to specify an executable"
-                        )));
-                    }
                 }
                 debug_adapter.send_response::<()>(la_request, Ok(None))?;
             }
@@ -1199,7 +1533,7 @@ impl Debugger {
             }
         };
 
-        let mut session_data = match DebugSession::new(&self.debugger_options) {
+        let mut session_data = match SessionData::new(&self.debugger_options) {
             Ok(session_data) => session_data,
             Err(error) => {
                 debug_adapter.send_error_response(&error)?;
@@ -1207,20 +1541,27 @@ impl Debugger {
             }
         };
         debug_adapter.halt_after_reset = self.debugger_options.halt_after_reset;
+        let core_indices = session_data.lock().core_indices();
+        for core_index in core_indices.iter().copied() {
+            debug_adapter.add_core(core_index);
+        }
+        // Requests without a thread apply to the first core, until the client selects another one.
+        if let Some(core_config) = self.debugger_options.core_configs.first() {
+            self.focused_core_index = core_config.core_index;
+        }
 
-        // Do the flashing.
-        {
-            if self.debugger_options.flashing_enabled {
-                let path_to_elf = match self.debugger_options.program_binary.clone() {
-                    Some(program_binary) => program_binary,
-                    None => {
-                        let err = DebuggerError::Other(anyhow!(
-                            "Please use the --program-binary option to specify an executable"
-                        ));
-                        debug_adapter.send_error_response(&err)?;
-                        return Err(err);
+        // Do the flashing. The binaries of all the cores are written one after the other.
+        if self.debugger_options.flashing_enabled {
+            let mut program_binaries: Vec<PathBuf> = vec![];
+            for core_config in &self.debugger_options.core_configs {
+                if let Some(program_binary) = &core_config.program_binary {
+                    if !program_binaries.contains(program_binary) {
+                        program_binaries.push(program_binary.clone());
                     }
-                };
+                }
+            }
+
+            for path_to_elf in program_binaries {
                 debug_adapter.log_to_console(format!(
                     "INFO: FLASHING: Starting write of {:?} to device memory",
                     &path_to_elf
@@ -1232,6 +1573,8 @@ impl Debugger {
                 download_options.keep_unwritten_bytes =
                     self.debugger_options.restore_unwritten_bytes;
                 download_options.do_chip_erase = self.debugger_options.full_chip_erase;
+                download_options.skip_unchanged_sectors =
+                    self.debugger_options.skip_unchanged_sectors;
                 let flash_result = {
                     let rc_debug_adapter = Rc::new(RefCell::new(debug_adapter));
                     let rc_debug_adapter_clone = rc_debug_adapter.clone();
@@ -1325,7 +1668,8 @@ impl Debugger {
                                     probe_rs::flashing::ProgressEvent::SectorErased {
                                         size,
                                         ..
-                                    } => {
+                                    }
+                                    | probe_rs::flashing::ProgressEvent::SectorSkipped { size } => {
                                         flash_progress.sector_size_done += size as usize;
                                         let progress = flash_progress.sector_size_done as f64
                                             / flash_progress.total_sector_size as f64;
@@ -1363,7 +1707,8 @@ impl Debugger {
                                     probe_rs::flashing::ProgressEvent::PageProgrammed {
                                         size,
                                         ..
-                                    } => {
+                                    }
+                                    | probe_rs::flashing::ProgressEvent::PageSkipped { size } => {
                                         flash_progress.page_size_done += size as usize;
                                         let progress = flash_progress.page_size_done as f64
                                             / flash_progress.total_page_size as f64;
@@ -1402,12 +1747,25 @@ impl Debugger {
                             FlashProgress::new(|_event| {})
                         };
                         download_options.progress = Some(&flash_progress);
-                        download_file_with_options(
-                            &mut session_data.session,
-                            &path_to_elf,
-                            Format::Elf,
-                            download_options,
-                        )
+                        let mut session_data = session_data.lock();
+                        let DebugSession {
+                            session,
+                            rtt_connection,
+                            ..
+                        } = &mut session_data;
+                        let download = || {
+                            download_file_with_options(
+                                session,
+                                &path_to_elf,
+                                Format::Elf,
+                                download_options,
+                            )
+                        };
+                        // RTT must not access the target while the flash is written.
+                        match rtt_connection {
+                            Some(rtt_connection) => rtt_connection.paused(download),
+                            None => download(),
+                        }
                     };
                     debug_adapter = match Rc::try_unwrap(rc_debug_adapter) {
                         Ok(debug_adapter) => debug_adapter.into_inner(),
@@ -1439,10 +1797,11 @@ impl Debugger {
             }
         }
 
-        // This is the first attach to the requested core. If this one works, all subsequent ones will be no-op requests for a Core reference. Do NOT hold onto this reference for the duration of the session ... that is why this code is in a block of its own.
-        {
+        // This is the first attach to the requested cores. If this one works, all subsequent ones will be no-op requests for a Core reference. Do NOT hold onto this reference for the duration of the session ... that is why this code is in a loop of its own.
+        for core_index in core_indices {
+            let mut session_data = session_data.lock();
             // First, attach to the core
-            let mut core_data = match session_data.attach_core(self.debugger_options.core_index) {
+            let mut core_data = match session_data.attach_core(core_index) {
                 Ok(mut core_data) => {
                     // Immediately after attaching, halt the core, so that we can finish initalization without bumping into user code.
                     // Depending on supplied `debugger_options`, the core will be restarted at the end of initialization in the `configuration_done` request.
@@ -1483,20 +1842,30 @@ impl Debugger {
             return Err(error);
         }
 
+        // The target may initialize RTT late, so attaching is retried, with an increasing delay between the attempts.
+        let mut rtt_backoff = rtt::RttBackoff::default();
+
         // Loop through remaining (user generated) requests and send to the [processs_request] method until either the client or some unexpected behaviour termintates the process.
         loop {
             match self.process_next_request(&mut session_data, &mut debug_adapter) {
                 Ok(DebuggerStatus::ContinueSession) => {
-                    // Validate and if necessary, initialize the RTT structure.
+                    // Validate and if necessary, initialize the RTT structure, using the binary of the first core.
+                    // We can safely unwrap() the core config and its program_binary here, because they are validated to exist at startup of the debugger
+                    #[allow(clippy::unwrap_used)]
+                    let rtt_core_config = self.debugger_options.rtt_core_config().unwrap().clone();
+                    let rtt_core_status =
+                        debug_adapter.last_known_status(rtt_core_config.core_index);
                     if self.debugger_options.rtt.enabled
                         && session_data.rtt_connection.is_none()
-                        && !(debug_adapter.last_known_status == CoreStatus::Unknown
-                            || debug_adapter.last_known_status.is_halted())
+                        && !(rtt_core_status == CoreStatus::Unknown || rtt_core_status.is_halted())
+                        && rtt_backoff.attempt_due()
                     // Do not attempt this until we have processed the MSDAP request for "configurationDone" ...
                     {
+                        let shared_session = session_data.session.clone();
+                        let mut session_data = session_data.lock();
                         let target_memory_map = session_data.session.target().memory_map.clone();
                         let mut core_data =
-                            match session_data.attach_core(self.debugger_options.core_index) {
+                            match session_data.attach_core(rtt_core_config.core_index) {
                                 Ok(core_data) => core_data,
                                 Err(error) => {
                                     debug_adapter.send_error_response(&error)?;
@@ -1507,14 +1876,20 @@ impl Debugger {
                         // RTT can only be initialized if the target application has been allowed to run to the point where it does the RTT initialization.
                         // If the target halts before it processes this code, then this RTT intialization silently fails, and will try again later ...
                         // See `probe-rs-rtt::Rtt` for more information.
-                        // We can safely unwrap() program_binary here, because it is validated to exist at startup of the debugger
                         #[allow(clippy::unwrap_used)]
                         core_data.attach_to_rtt(
                             &mut debug_adapter,
+                            &shared_session,
                             &target_memory_map,
-                            self.debugger_options.program_binary.as_ref().unwrap(),
+                            rtt_core_config.program_binary.as_ref().unwrap(),
                             &self.debugger_options.rtt,
                         )?;
+
+                        if core_data.rtt_connection.is_some() {
+                            rtt_backoff.reset();
+                        } else {
+                            rtt_backoff.failed();
+                        }
                     }
                 }
                 Ok(DebuggerStatus::TerminateSession) => {
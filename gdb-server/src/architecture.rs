@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use probe_rs::{
     config::{CoreType, MemoryRegion},
     Core, CoreRegisterAddress,
@@ -80,101 +82,147 @@ impl<'probe> GdbArchitectureExt for Core<'probe> {
     }
 }
 
-/// Extension trait for probe_rs::Target, to get XML-based target description and
-/// memory map.
-pub trait GdbTargetExt {
-    /// Memory map in GDB XML format.
-    ///
-    /// See https://sourceware.org/gdb/onlinedocs/gdb/Memory-Map-Format.html#Memory-Map-Format
-    fn gdb_memory_map(&self) -> String;
-
-    /// Target description in GDB XML Format.
-    ///
-    /// See https://sourceware.org/gdb/onlinedocs/gdb/Target-Descriptions.html#Target-Descriptions
-    fn target_description(&self) -> String;
-}
-
-impl GdbTargetExt for probe_rs::Target {
-    fn gdb_memory_map(&self) -> String {
-        let mut xml_map = r#"<?xml version="1.0"?>
+/// Memory map in GDB XML format.
+///
+/// NVM regions are reported as flash with the erase sector size as block size, if they are covered
+/// by `flash_sectors`, so GDB programs them with the `vFlash` packets. Otherwise they are reported as ROM.
+///
+/// See https://sourceware.org/gdb/onlinedocs/gdb/Memory-Map-Format.html#Memory-Map-Format
+pub(crate) fn gdb_memory_map(
+    memory_map: &[MemoryRegion],
+    flash_sectors: &[(Range<u32>, u32)],
+) -> String {
+    let mut xml_map = r#"<?xml version="1.0"?>
 <!DOCTYPE memory-map PUBLIC "+//IDN gnu.org//DTD GDB Memory Map V1.0//EN" "http://sourceware.org/gdb/gdb-memory-map.dtd">
 <memory-map>
 "#.to_owned();
 
-        for region in &self.memory_map {
-            let region_entry = match region {
-                MemoryRegion::Ram(ram) => format!(
-                    r#"<memory type="ram" start="{:#x}" length="{:#x}"/>\n"#,
-                    ram.range.start,
-                    ram.range.end - ram.range.start
-                ),
-                MemoryRegion::Generic(region) => format!(
-                    r#"<memory type="rom" start="{:#x}" length="{:#x}"/>\n"#,
-                    region.range.start,
-                    region.range.end - region.range.start
-                ),
-                MemoryRegion::Nvm(region) => {
-                    // TODO: Use flash with block size
+    for region in memory_map {
+        let region_entry = match region {
+            MemoryRegion::Ram(ram) => format!(
+                r#"<memory type="ram" start="{:#x}" length="{:#x}"/>\n"#,
+                ram.range.start,
+                ram.range.end - ram.range.start
+            ),
+            MemoryRegion::Generic(region) => format!(
+                r#"<memory type="rom" start="{:#x}" length="{:#x}"/>\n"#,
+                region.range.start,
+                region.range.end - region.range.start
+            ),
+            MemoryRegion::Nvm(region) => {
+                let flash_entries: String = flash_sectors
+                    .iter()
+                    .filter(|(range, _)| {
+                        region.range.start <= range.start && range.end <= region.range.end
+                    })
+                    .map(|(range, sector_size)| {
+                        format!(
+                            r#"<memory type="flash" start="{:#x}" length="{:#x}"><property name="blocksize">{:#x}</property></memory>\n"#,
+                            range.start,
+                            range.end - range.start,
+                            sector_size
+                        )
+                    })
+                    .collect();
+
+                if flash_entries.is_empty() {
                     format!(
                         r#"<memory type="rom" start="{:#x}" length="{:#x}"/>\n"#,
                         region.range.start,
                         region.range.end - region.range.start
                     )
+                } else {
+                    flash_entries
                 }
-            };
-
-            xml_map.push_str(&region_entry);
-        }
-
-        xml_map.push_str(r#"</memory-map>"#);
+            }
+        };
 
-        xml_map
+        xml_map.push_str(&region_entry);
     }
 
-    fn target_description(&self) -> String {
-        // GDB-architectures
-        //
-        // - armv6-m      -> Core-M0
-        // - armv7-m      -> Core-M3
-        // - armv7e-m      -> Core-M4, Core-M7
-        // - armv8-m.base -> Core-M23
-        // - armv8-m.main -> Core-M33
-        // - riscv:rv32   -> RISCV
-
-        // TODO: what if they're not all equal?
-        let architecture = match self.cores[0].core_type {
-            CoreType::Armv6m => "armv6-m",
-            CoreType::Armv7m => "armv7",
-            CoreType::Armv7em => "armv7e-m",
-            CoreType::Armv8m => "armv8-m.main",
-            CoreType::Riscv => "riscv:rv32",
-        };
+    xml_map.push_str(r#"</memory-map>"#);
+
+    xml_map
+}
 
-        // Only target.xml is supported
-        let mut target_description = r#"<?xml version="1.0"?>
+/// Target description of a core with type `core_type` in GDB XML Format.
+///
+/// The cores of a chip can differ, so each core has its own description.
+///
+/// See https://sourceware.org/gdb/onlinedocs/gdb/Target-Descriptions.html#Target-Descriptions
+pub(crate) fn target_description(core_type: CoreType) -> String {
+    // GDB-architectures
+    //
+    // - armv6-m      -> Core-M0
+    // - armv7        -> Cortex-A7, Cortex-A9, Cortex-R5
+    // - armv7-m      -> Core-M3
+    // - armv7e-m      -> Core-M4, Core-M7
+    // - armv8-m.base -> Core-M23
+    // - armv8-m.main -> Core-M33
+    // - aarch64      -> Cortex-A53, Cortex-A72
+    // - riscv:rv32   -> RISCV
+
+    let architecture = match core_type {
+        CoreType::Armv6m => "armv6-m",
+        CoreType::Armv7a => "armv7",
+        CoreType::Armv7m => "armv7",
+        CoreType::Armv7em => "armv7e-m",
+        CoreType::Armv7r => "armv7",
+        CoreType::Armv8m => "armv8-m.main",
+        CoreType::Armv8a => "aarch64",
+        CoreType::Riscv => "riscv:rv32",
+    };
+
+    // Only target.xml is supported
+    let mut target_description = r#"<?xml version="1.0"?>
         <!DOCTYPE target SYSTEM "gdb-target.dtd">
         <target version="1.0">
         "#
-        .to_owned();
+    .to_owned();
 
-        target_description.push_str(&format!("<architecture>{}</architecture>", architecture));
+    target_description.push_str(&format!("<architecture>{}</architecture>", architecture));
 
-        target_description.push_str("</target>");
+    target_description.push_str("</target>");
 
-        target_description
-    }
+    target_description
 }
 
 #[cfg(test)]
 mod test {
-    use crate::architecture::GdbTargetExt;
+    use crate::architecture::{gdb_memory_map, target_description};
+    use probe_rs::config::{MemoryRegion, NvmRegion};
 
     #[test]
     fn test_target_description_microbit() {
         let target = probe_rs::config::get_target_by_name("nrf51822_xxAA").unwrap();
 
-        let description = target.target_description();
+        let description = target_description(target.cores[0].core_type);
 
         insta::assert_snapshot!(description);
     }
+
+    #[test]
+    fn test_memory_map_flash_block_size() {
+        let memory_map = [MemoryRegion::Nvm(NvmRegion {
+            name: None,
+            range: 0x0800_0000..0x0804_0000,
+            is_boot_memory: true,
+            cores: Vec::new(),
+        })];
+
+        let flash_sectors = [
+            (0x0800_0000..0x0801_0000, 0x4000),
+            (0x0801_0000..0x0804_0000, 0x10000),
+        ];
+
+        let memory_map = gdb_memory_map(&memory_map, &flash_sectors);
+
+        assert!(memory_map.contains(
+            r#"<memory type="flash" start="0x8000000" length="0x10000"><property name="blocksize">0x4000</property></memory>"#
+        ));
+        assert!(memory_map.contains(
+            r#"<memory type="flash" start="0x8010000" length="0x30000"><property name="blocksize">0x10000</property></memory>"#
+        ));
+        assert!(!memory_map.contains(r#"type="rom""#));
+    }
 }
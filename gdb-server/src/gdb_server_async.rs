@@ -7,7 +7,9 @@ use async_std::{
 };
 use futures::channel::mpsc;
 use gdb_protocol::packet::CheckedPacket;
-use probe_rs::Session;
+
+use crate::monitor::MonitorCommands;
+use crate::target::DebugTarget;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 type Sender<T> = mpsc::UnboundedSender<T>;
@@ -18,21 +20,41 @@ const CONNECTION_STRING: &str = "127.0.0.1:1337";
 /// This is the main entrypoint which we will call to start the GDB stub.
 /// This function is blocking. If you would like to use it concurently to other users of the session,
 /// please use a thread.
-pub fn run(connection_string: Option<impl Into<String>>, session: &Mutex<Session>) -> Result<()> {
+///
+/// The debugged system can be a [`probe_rs::Session`] with a connected target,
+/// or a [`probe_rs::CoreDump`] for post-mortem debugging without hardware.
+pub fn run(
+    connection_string: Option<impl Into<String>>,
+    session: &Mutex<impl DebugTarget>,
+) -> Result<()> {
+    run_with_monitor_commands(connection_string, session, &MonitorCommands::default())
+}
+
+/// Starts the GDB stub like [`run`], with the monitor commands of `monitor_commands`
+/// instead of the built-in ones.
+pub fn run_with_monitor_commands<T: DebugTarget>(
+    connection_string: Option<impl Into<String>>,
+    session: &Mutex<T>,
+    monitor_commands: &MonitorCommands<T>,
+) -> Result<()> {
     let connection_string = connection_string
         .map(|cs| cs.into())
         .unwrap_or_else(|| CONNECTION_STRING.to_owned());
     log::info!("GDB stub listening on {}", connection_string);
-    task::block_on(accept_loop(connection_string, session))
+    task::block_on(accept_loop(connection_string, session, monitor_commands))
 }
 
 /// This function accepts any incomming connection.
-async fn accept_loop(addr: impl ToSocketAddrs, session: &Mutex<Session>) -> Result<()> {
+async fn accept_loop<T: DebugTarget>(
+    addr: impl ToSocketAddrs,
+    session: &Mutex<T>,
+    monitor_commands: &MonitorCommands<T>,
+) -> Result<()> {
     let listener = TcpListener::bind(addr).await?;
 
     let mut incoming = listener.incoming();
     while let Some(stream) = incoming.next().await {
-        if let Err(e) = handle_connection(stream?, session).await {
+        if let Err(e) = handle_connection(stream?, session, monitor_commands).await {
             log::error!(
                 "An error with the current connection has been encountered. It has been closed."
             );
@@ -43,7 +65,11 @@ async fn accept_loop(addr: impl ToSocketAddrs, session: &Mutex<Session>) -> Resu
 }
 
 /// Handle a single connection of a client
-async fn handle_connection(stream: TcpStream, session: &Mutex<Session>) -> Result<()> {
+async fn handle_connection<T: DebugTarget>(
+    stream: TcpStream,
+    session: &Mutex<T>,
+    monitor_commands: &MonitorCommands<T>,
+) -> Result<()> {
     let (packet_stream_sender, packet_stream_receiver) = mpsc::unbounded();
     let (tbd_sender, tbd_receiver) = mpsc::unbounded();
 
@@ -55,7 +81,13 @@ async fn handle_connection(stream: TcpStream, session: &Mutex<Session>) -> Resul
         packet_stream_receiver,
     ));
 
-    super::worker::worker(tbd_receiver, packet_stream_sender, session).await?;
+    super::worker::worker(
+        tbd_receiver,
+        packet_stream_sender,
+        session,
+        monitor_commands,
+    )
+    .await?;
 
     inbound_broker_handle.await?;
 
@@ -1,14 +1,30 @@
-use crate::architecture::{GdbArchitectureExt, GdbTargetExt};
-use probe_rs::{Core, CoreStatus, MemoryInterface, Session};
-use std::time::Duration;
+use crate::architecture::{gdb_memory_map, target_description, GdbArchitectureExt};
+use crate::target::{flash_algorithm, DebugTarget};
+use probe_rs::flashing::{
+    crc32_update, DownloadOptions, FlashError, FlashLoader, MemoryImage, CRC32_INITIAL_VALUE,
+};
+use probe_rs::{Core, CoreStatus, MemoryInterface, Target, WatchpointKind};
+use std::{collections::BTreeSet, ops::Range, time::Duration};
 
 pub(crate) fn q_supported() -> Option<String> {
     Some(
-        "PacketSize=2048;swbreak-;hwbreak+;vContSupported+;qXfer:features:read+;qXfer:memory-map:read+"
+        "PacketSize=2048;swbreak-;hwbreak+;vContSupported+;qXfer:features:read+;qXfer:memory-map:read+;QNonStop+"
             .into(),
     )
 }
 
+/// Returns the GDB thread id of the core with index `core_index`.
+///
+/// The thread ids 0 and -1 have a special meaning in GDB, so thread ids start at 1.
+pub(crate) fn thread_id(core_index: usize) -> usize {
+    core_index + 1
+}
+
+/// Returns a stop reply for the core with index `core_index`, reporting `signal` to GDB.
+pub(crate) fn stop_reply(core_index: usize, signal: u8) -> String {
+    format!("T{:02x}thread:{:x};", signal, thread_id(core_index))
+}
+
 pub(crate) fn reply_empty() -> Option<String> {
     Some("".into())
 }
@@ -21,8 +37,40 @@ pub(crate) fn q_attached() -> Option<String> {
     Some("1".into())
 }
 
-pub(crate) fn halt_reason() -> Option<String> {
-    Some("S05".into())
+pub(crate) fn halt_reason(core_index: usize) -> Option<String> {
+    Some(stop_reply(core_index, 5))
+}
+
+pub(crate) fn current_thread(core_index: usize) -> Option<String> {
+    Some(format!("QC{:x}", thread_id(core_index)))
+}
+
+pub(crate) fn thread_info(num_cores: usize) -> Option<String> {
+    let thread_ids = (0..num_cores)
+        .map(|core_index| format!("{:x}", thread_id(core_index)))
+        .collect::<Vec<_>>();
+
+    Some(format!("m{}", thread_ids.join(",")))
+}
+
+pub(crate) fn thread_info_end() -> Option<String> {
+    Some("l".into())
+}
+
+pub(crate) fn unknown_thread() -> Option<String> {
+    // Tell GDB that the requested thread does not exist with a ENOENT response.
+    // Errno values can be found here: https://sourceware.org/gdb/current/onlinedocs/gdb/Errno-Values.html
+    Some("E02".to_string())
+}
+
+pub(crate) fn thread_extra_info(target: &impl DebugTarget, core_index: usize) -> Option<String> {
+    let (_, core_type) = target.list_cores()[core_index];
+
+    Some(hex::encode(format!(
+        "{} ({:?})",
+        target.core_name(core_index),
+        core_type
+    )))
 }
 
 pub(crate) fn read_general_registers(mut core: Core) -> Option<String> {
@@ -253,6 +301,32 @@ pub(crate) fn read_memory(address: u32, length: u32, mut core: Core) -> Option<S
     }
 }
 
+/// The size of the chunks in which the memory is read to calculate its CRC32.
+const CRC_CHUNK_SIZE: usize = 4096;
+
+pub(crate) fn crc(address: u32, length: u32, mut core: Core) -> Option<String> {
+    // The checksum is calculated on the host. The CRC32 routine of the flash algorithms would
+    // be faster, but loading it would overwrite the RAM of the program being debugged.
+    // The memory is read in chunks, so a large length does not allocate a buffer of that size.
+    let mut buffer = [0u8; CRC_CHUNK_SIZE];
+    let mut crc = CRC32_INITIAL_VALUE;
+
+    let end = u64::from(address) + u64::from(length);
+    let mut chunk_address = u64::from(address);
+    while chunk_address < end {
+        let chunk = &mut buffer[..(end - chunk_address).min(CRC_CHUNK_SIZE as u64) as usize];
+        if core.read(chunk_address as u32, chunk).is_err() {
+            // The same error code as for reading memory.
+            return Some("E79".to_string());
+        }
+
+        crc = crc32_update(crc, chunk);
+        chunk_address += chunk.len() as u64;
+    }
+
+    Some(format!("C{:08x}", crc))
+}
+
 pub(crate) fn vcont_supported() -> Option<String> {
     // It is important to announce support for both
     // the variants with and without signal support,
@@ -268,32 +342,107 @@ pub(crate) fn host_info() -> Option<String> {
     Some("cputype:12;cpusubtype:14;triple:armv6m--none-eabi;endian:litte;ptrsize:4".to_string())
 }
 
-pub(crate) fn run(mut core: Core, awaits_halt: &mut bool) -> Option<String> {
+pub(crate) fn run(mut core: Core, running_cores: &mut BTreeSet<usize>) -> Option<String> {
     core.run().unwrap();
-    *awaits_halt = true;
+    running_cores.insert(core.id());
     None
 }
 
-pub(crate) fn stop(mut core: Core, awaits_halt: &mut bool) -> Option<String> {
+pub(crate) fn stop(mut core: Core, running_cores: &mut BTreeSet<usize>) -> Option<String> {
     core.halt(Duration::from_millis(100)).unwrap();
-    *awaits_halt = false;
+    running_cores.remove(&core.id());
     Some("OK".into())
 }
 
-pub(crate) fn step(mut core: Core, awaits_halt: &mut bool) -> Option<String> {
+pub(crate) fn step(mut core: Core, running_cores: &mut BTreeSet<usize>) -> Option<String> {
     core.step().unwrap();
-    *awaits_halt = false;
-    Some("S05".into())
+    running_cores.remove(&core.id());
+    Some(stop_reply(core.id(), 5))
 }
 
 pub(crate) fn insert_hardware_break(address: u32, _kind: u32, mut core: Core) -> Option<String> {
-    core.set_hw_breakpoint(address).unwrap();
-    Some("OK".into())
+    match core.set_hw_breakpoint(address) {
+        Ok(()) => reply_ok(),
+        Err(e) => {
+            log::warn!("Unable to set breakpoint at {:#010x}: {}", address, e);
+            Some("E22".to_string())
+        }
+    }
 }
 
 pub(crate) fn remove_hardware_break(address: u32, _kind: u32, mut core: Core) -> Option<String> {
-    core.clear_hw_breakpoint(address).unwrap();
-    Some("OK".into())
+    match core.clear_hw_breakpoint(address) {
+        Ok(()) => reply_ok(),
+        Err(e) => {
+            log::warn!("Unable to remove breakpoint at {:#010x}: {}", address, e);
+            Some("E22".to_string())
+        }
+    }
+}
+
+/// Returns the start and size of the smallest aligned power-of-two region which covers
+/// `length` bytes at `address`.
+///
+/// GDB watches variables with their natural size and address, but the watchpoint units of
+/// the cores only compare aligned power-of-two regions.
+pub(crate) fn watchpoint_region(address: u32, length: u32) -> (u32, u32) {
+    let end = u64::from(address) + u64::from(length.max(1));
+
+    let mut size = u64::from(length.max(1).next_power_of_two());
+    loop {
+        let start = u64::from(address) & !(size - 1);
+        if start + size >= end || size > u64::from(u32::MAX) {
+            return (start as u32, size.min(u64::from(u32::MAX)) as u32);
+        }
+        size *= 2;
+    }
+}
+
+/// Returns the `watch`, `rwatch` or `awatch` stop reason for a triggered watchpoint of `kind`.
+pub(crate) fn watchpoint_stop_reason(kind: WatchpointKind, address: u32) -> String {
+    let name = match kind {
+        WatchpointKind::Write => "watch",
+        WatchpointKind::Read => "rwatch",
+        WatchpointKind::ReadWrite => "awatch",
+    };
+
+    format!("{}:{:x};", name, address)
+}
+
+pub(crate) fn insert_watchpoint(
+    address: u32,
+    length: u32,
+    kind: WatchpointKind,
+    mut core: Core,
+) -> Option<String> {
+    let (region_address, region_size) = watchpoint_region(address, length);
+
+    match core.set_hw_watchpoint(region_address, region_size, kind) {
+        Ok(()) => reply_ok(),
+        Err(e) => {
+            log::warn!(
+                "Unable to set watchpoint at {:#010x} with length {}: {}",
+                address,
+                length,
+                e
+            );
+            // Tell GDB that the watchpoint could not be inserted with an EINVAL response.
+            // Errno values can be found here: https://sourceware.org/gdb/current/onlinedocs/gdb/Errno-Values.html
+            Some("E22".to_string())
+        }
+    }
+}
+
+pub(crate) fn remove_watchpoint(address: u32, length: u32, mut core: Core) -> Option<String> {
+    let (region_address, _) = watchpoint_region(address, length);
+
+    match core.clear_hw_watchpoint(region_address) {
+        Ok(()) => reply_ok(),
+        Err(e) => {
+            log::warn!("Unable to remove watchpoint at {:#010x}: {}", address, e);
+            Some("E22".to_string())
+        }
+    }
 }
 
 pub(crate) fn write_memory(address: u32, data: &[u8], mut core: Core) -> Option<String> {
@@ -302,16 +451,216 @@ pub(crate) fn write_memory(address: u32, data: &[u8], mut core: Core) -> Option<
     Some("OK".into())
 }
 
-pub(crate) fn get_memory_map(session: &Session) -> Option<String> {
-    let memory_map = session.target().gdb_memory_map();
+pub(crate) fn get_memory_map(
+    target: &impl DebugTarget,
+    offset: u32,
+    length: u32,
+) -> Option<String> {
+    let memory_map = gdb_memory_map(&target.memory_map(), &target.flash_sectors());
+
+    Some(String::from_utf8(gdb_sanitize_file(memory_map.as_bytes(), offset, length)).unwrap())
+}
+
+/// Returns `true` if the range `start..end` is flash, which can be programmed with a flash algorithm.
+fn is_flash(target: &impl DebugTarget, start: u32, end: u32) -> bool {
+    let flash_sectors = target.flash_sectors();
 
-    Some(String::from_utf8(gdb_sanitize_file(memory_map.as_bytes(), 0, 1000)).unwrap())
+    // The range can span several entries of `flash_sectors`, as long as they are contiguous.
+    let mut address = start;
+    while address < end {
+        match flash_sectors
+            .iter()
+            .find(|(range, _)| range.contains(&address))
+        {
+            Some((range, _)) => address = range.end,
+            None => return false,
+        }
+    }
+
+    true
 }
 
-pub(crate) fn user_halt(mut core: Core, awaits_halt: &mut bool) -> Option<String> {
+/// Handles the `vFlashErase` packet.
+///
+/// The flash is not erased right away, the range is added to `erased_flash` instead. The range is
+/// erased on `vFlashDone`, together with programming the data of `vFlashWrite`.
+pub(crate) fn flash_erase(
+    target: &mut impl DebugTarget,
+    flash_loader: &mut Option<FlashLoader>,
+    erased_flash: &mut Vec<Range<u32>>,
+    address: u32,
+    length: u32,
+) -> Option<String> {
+    let end = match address.checked_add(length) {
+        Some(end) if is_flash(target, address, end) => end,
+        _ => {
+            log::warn!(
+                "Unable to erase {:#010x} with length {:#x}, which is not flash",
+                address,
+                length
+            );
+            return Some("E01".to_string());
+        }
+    };
+
+    match target.session_mut() {
+        Some(session) => {
+            log::debug!("Erasing flash at {:#010x}..{:#010x}", address, end);
+            flash_loader.get_or_insert_with(|| session.target().flash_loader());
+            erased_flash.push(address..end);
+            reply_ok()
+        }
+        None => {
+            log::warn!("Flash programming is only possible with a connected target");
+            Some("E01".to_string())
+        }
+    }
+}
+
+/// Handles the `vFlashWrite` packet, by buffering the data until `vFlashDone`.
+pub(crate) fn flash_write(
+    target: &mut impl DebugTarget,
+    flash_loader: &mut Option<FlashLoader>,
+    address: u32,
+    data: &[u8],
+) -> Option<String> {
+    let in_flash = address
+        .checked_add(data.len() as u32)
+        .map(|end| is_flash(target, address, end))
+        .unwrap_or(false);
+
+    if !in_flash {
+        log::warn!(
+            "Unable to write {} bytes to {:#010x}, which is not flash",
+            data.len(),
+            address
+        );
+        return Some("E.memtype".to_string());
+    }
+
+    let session = match target.session_mut() {
+        Some(session) => session,
+        None => {
+            log::warn!("Flash programming is only possible with a connected target");
+            return Some("E01".to_string());
+        }
+    };
+
+    let flash_loader = flash_loader.get_or_insert_with(|| session.target().flash_loader());
+
+    match flash_loader.add_data(address, data) {
+        Ok(()) => reply_ok(),
+        Err(e) => {
+            log::warn!(
+                "Unable to write {} bytes to flash at {:#010x}: {}",
+                data.len(),
+                address,
+                e
+            );
+            Some("E01".to_string())
+        }
+    }
+}
+
+/// Handles the `vFlashDone` packet, by erasing the ranges of `vFlashErase`, and programming the data
+/// buffered by `vFlashWrite`.
+pub(crate) fn flash_done(
+    target: &mut impl DebugTarget,
+    flash_loader: &mut Option<FlashLoader>,
+    erased_flash: &mut Vec<Range<u32>>,
+) -> Option<String> {
+    let erased_flash = std::mem::take(erased_flash);
+    let mut flash_loader = match flash_loader.take() {
+        Some(flash_loader) => flash_loader,
+        None => return reply_ok(),
+    };
+
+    let session = match target.session_mut() {
+        Some(session) => session,
+        None => {
+            log::warn!("Flash programming is only possible with a connected target");
+            return Some("E01".to_string());
+        }
+    };
+
+    if let Err(e) = fill_erased_flash(session.target(), &mut flash_loader, &erased_flash) {
+        log::warn!("Unable to erase the flash: {}", e);
+        return Some("E01".to_string());
+    }
+
+    match flash_loader.commit(session, DownloadOptions::default()) {
+        Ok(()) => reply_ok(),
+        Err(e) => {
+            log::warn!("Unable to program the flash: {}", e);
+            Some("E01".to_string())
+        }
+    }
+}
+
+/// Returns the parts of `erased_ranges` which are not covered by `written_ranges`.
+///
+/// `written_ranges` must be sorted, and must not overlap.
+fn unwritten_ranges(
+    erased_ranges: &[Range<u32>],
+    written_ranges: &[Range<u32>],
+) -> Vec<Range<u32>> {
+    let mut unwritten_ranges = Vec::new();
+
+    for erased_range in MemoryImage::merge_ranges(erased_ranges) {
+        let mut start = erased_range.start;
+
+        for written_range in written_ranges {
+            if written_range.end <= start || written_range.start >= erased_range.end {
+                continue;
+            }
+
+            if start < written_range.start {
+                unwritten_ranges.push(start..written_range.start);
+            }
+            start = written_range.end;
+        }
+
+        if start < erased_range.end {
+            unwritten_ranges.push(start..erased_range.end);
+        }
+    }
+
+    unwritten_ranges
+}
+
+/// Adds erased data to `flash_loader` for the parts of `erased_ranges` which no data was written
+/// to.
+///
+/// The flash loader only erases the sectors which contain data, so the erased data makes sure that
+/// every sector erased by GDB is erased, even if GDB does not write to it.
+fn fill_erased_flash(
+    target: &Target,
+    flash_loader: &mut FlashLoader,
+    erased_ranges: &[Range<u32>],
+) -> Result<(), FlashError> {
+    let written_ranges: Vec<_> = flash_loader
+        .data()
+        .map(|(address, data)| address..address + data.len() as u32)
+        .collect();
+
+    for range in unwritten_ranges(erased_ranges, &written_ranges) {
+        let erased_byte_value = flash_algorithm(target, &range)
+            .map(|algorithm| algorithm.flash_properties.erased_byte_value)
+            .unwrap_or(0xFF);
+
+        flash_loader.add_data(
+            range.start,
+            &vec![erased_byte_value; (range.end - range.start) as usize],
+        )?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn user_halt(mut core: Core, running_cores: &mut BTreeSet<usize>) -> Option<String> {
     let _ = core.halt(Duration::from_millis(100));
-    *awaits_halt = false;
-    Some("T02".into())
+    running_cores.remove(&core.id());
+    Some(stop_reply(core.id(), 2))
 }
 
 pub(crate) fn detach(break_due: &mut bool) -> Option<String> {
@@ -319,11 +668,6 @@ pub(crate) fn detach(break_due: &mut bool) -> Option<String> {
     Some("OK".into())
 }
 
-pub(crate) fn reset_halt(mut core: Core) -> Option<String> {
-    let _cpu_info = core.reset_and_halt(Duration::from_millis(400));
-    Some("OK".into())
-}
-
 fn gdb_sanitize_file(data: &[u8], offset: u32, len: u32) -> Vec<u8> {
     let offset = offset as usize;
     let len = len as usize;
@@ -345,13 +689,92 @@ fn gdb_sanitize_file(data: &[u8], offset: u32, len: u32) -> Vec<u8> {
     }
 }
 
-pub(crate) fn read_target_description(session: &Session, annex: &[u8]) -> Option<String> {
-    // Only target.xml is supported
+pub(crate) fn read_target_description(
+    target: &impl DebugTarget,
+    core_index: usize,
+    annex: &[u8],
+) -> Option<String> {
+    // Only target.xml is supported, which describes the currently selected core.
     if annex == b"target.xml" {
-        let description = session.target().target_description();
+        let (_, core_type) = target.list_cores()[core_index];
+        let description = target_description(core_type);
 
         Some(String::from_utf8(gdb_sanitize_file(description.as_bytes(), 0, 1000)).unwrap())
     } else {
         None
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{fill_erased_flash, unwritten_ranges, watchpoint_region, watchpoint_stop_reason};
+    use probe_rs::WatchpointKind;
+
+    #[test]
+    fn aligned_watchpoint_region() {
+        assert_eq!(watchpoint_region(0x2000_0000, 4), (0x2000_0000, 4));
+        assert_eq!(watchpoint_region(0x2000_0004, 1), (0x2000_0004, 1));
+    }
+
+    #[test]
+    fn unaligned_watchpoint_region() {
+        // A 3 byte struct is covered by a 4 byte region.
+        assert_eq!(watchpoint_region(0x2000_0000, 3), (0x2000_0000, 4));
+        // A word crossing an alignment boundary needs a larger region.
+        assert_eq!(watchpoint_region(0x2000_0002, 4), (0x2000_0000, 8));
+        assert_eq!(watchpoint_region(0x2000_0006, 4), (0x2000_0000, 16));
+        assert_eq!(watchpoint_region(0x2000_0005, 12), (0x2000_0000, 32));
+    }
+
+    #[test]
+    fn watchpoint_region_at_end_of_address_space() {
+        assert_eq!(watchpoint_region(0xFFFF_FFFE, 2), (0xFFFF_FFFE, 2));
+    }
+
+    #[test]
+    fn stop_reason_matches_kind() {
+        assert_eq!(
+            watchpoint_stop_reason(WatchpointKind::Write, 0x2000_0000),
+            "watch:20000000;"
+        );
+        assert_eq!(
+            watchpoint_stop_reason(WatchpointKind::Read, 0x2000_0000),
+            "rwatch:20000000;"
+        );
+        assert_eq!(
+            watchpoint_stop_reason(WatchpointKind::ReadWrite, 0x2000_0000),
+            "awatch:20000000;"
+        );
+    }
+
+    #[test]
+    fn unwritten_parts_of_erased_ranges() {
+        assert_eq!(
+            unwritten_ranges(
+                &[0x1000..0x2000, 0x0..0x1000, 0x4000..0x5000],
+                &[0x800..0x900, 0x1000..0x1800, 0x4000..0x5000]
+            ),
+            vec![0x0..0x800, 0x900..0x1000, 0x1800..0x2000]
+        );
+    }
+
+    #[test]
+    fn erased_but_unwritten_sector_is_erased() {
+        let target = probe_rs::config::get_target_by_name("nrf51822_xxAA").unwrap();
+        let mut flash_loader = target.flash_loader();
+
+        // GDB erases two sectors, but only writes to the second one.
+        flash_loader.add_data(0x400, &[1, 2, 3, 4]).unwrap();
+        fill_erased_flash(&target, &mut flash_loader, &[0x0..0x800]).unwrap();
+
+        let mut flash = vec![0; 0x800];
+        for (address, data) in flash_loader.data() {
+            let address = address as usize;
+            flash[address..address + data.len()].copy_from_slice(data);
+        }
+
+        assert!(flash[..0x400].iter().all(|byte| *byte == 0xFF));
+        assert_eq!(&flash[0x400..0x404], &[1, 2, 3, 4]);
+        assert!(flash[0x404..].iter().all(|byte| *byte == 0xFF));
+    }
+}
@@ -3,9 +3,13 @@
 mod architecture;
 mod gdb_server_async;
 mod handlers;
+mod monitor;
 mod parser;
 mod reader;
+mod target;
 mod worker;
 mod writer;
 
-pub use gdb_server_async::run;
+pub use gdb_server_async::{run, run_with_monitor_commands};
+pub use monitor::{MonitorCommand, MonitorCommands, MonitorContext};
+pub use target::DebugTarget;
@@ -0,0 +1,871 @@
+//! Monitor commands, which are sent by GDB with `monitor <command> <arguments>`.
+//!
+//! The commands are kept in a [`MonitorCommands`] registry. It contains the built-in commands
+//! of probe-rs, and can be extended with custom commands implementing [`MonitorCommand`].
+
+use std::{fmt::Write, fs::File, io::Write as _, path::Path, time::Duration};
+
+use anyhow::{anyhow, bail, Context, Result};
+use itm_decode::{Decoder, DecoderOptions, TracePacket};
+use probe_rs::{
+    architecture::arm::{fault, swo::SwoConfig},
+    flashing::{self, BinOptions, Format, Uf2Options},
+    MemoryInterface, Session,
+};
+use probe_rs_rtt::{Rtt, RttServer, RttServerAddress, ScanRegion};
+
+use crate::target::DebugTarget;
+
+/// A command which can be executed with `monitor <name>` in GDB.
+pub trait MonitorCommand<T> {
+    /// The name of the command, which is the first word of the command line.
+    fn name(&self) -> &'static str;
+
+    /// The arguments of the command, as shown by `monitor help`.
+    fn usage(&self) -> &'static str {
+        ""
+    }
+
+    /// A short description of the command, as shown by `monitor help`.
+    fn description(&self) -> &'static str;
+
+    /// Executes the command with the words following the name of the command.
+    ///
+    /// Text written to [`MonitorContext::output`] is shown in the GDB console.
+    fn execute(&self, context: &mut MonitorContext<'_, T>, args: &[&str]) -> Result<()>;
+}
+
+/// The state a monitor command is executed with.
+pub struct MonitorContext<'a, T> {
+    /// The debugged system.
+    pub target: &'a mut T,
+    /// The index of the core selected by GDB.
+    pub core_index: usize,
+    /// The output of the command, which is shown in the GDB console.
+    pub output: String,
+    target_output: &'a mut TargetOutput,
+}
+
+impl<T: DebugTarget> MonitorContext<'_, T> {
+    /// Returns the session, or an error for targets without a connected probe.
+    fn session(&mut self) -> Result<&mut Session> {
+        self.target
+            .session_mut()
+            .ok_or_else(|| anyhow!("This command requires a connected target"))
+    }
+
+    /// Returns the core with index `argument`, or the core selected by GDB if there is no argument.
+    fn core_index_argument(&self, argument: Option<&&str>) -> Result<usize> {
+        let core_index = match argument {
+            Some(argument) => argument
+                .parse()
+                .with_context(|| format!("Invalid core index '{}'", argument))?,
+            None => self.core_index,
+        };
+
+        let num_cores = self.target.list_cores().len();
+        if core_index >= num_cores {
+            bail!(
+                "Core {} does not exist, the target has {} cores",
+                core_index,
+                num_cores
+            );
+        }
+
+        Ok(core_index)
+    }
+}
+
+/// A registry of monitor commands.
+pub struct MonitorCommands<T> {
+    commands: Vec<Box<dyn MonitorCommand<T>>>,
+}
+
+impl<T: DebugTarget> MonitorCommands<T> {
+    /// Creates a registry without any commands.
+    pub fn new() -> Self {
+        Self {
+            commands: Vec::new(),
+        }
+    }
+
+    /// Adds a command to the registry, replacing any command with the same name.
+    pub fn register(&mut self, command: impl MonitorCommand<T> + 'static) {
+        self.commands
+            .retain(|existing| existing.name() != command.name());
+        self.commands.push(Box::new(command));
+    }
+
+    /// Executes a command line received with the `qRcmd` packet, and returns its output.
+    pub(crate) fn execute(
+        &self,
+        target: &mut T,
+        core_index: usize,
+        target_output: &mut TargetOutput,
+        command_line: &str,
+    ) -> String {
+        let words: Vec<&str> = command_line.split_whitespace().collect();
+
+        let (name, args) = match words.split_first() {
+            Some((&"help", _)) | None => return self.help(),
+            Some((name, args)) => (*name, args),
+        };
+
+        let command = match self.commands.iter().find(|command| command.name() == name) {
+            Some(command) => command,
+            None => {
+                log::debug!("Unknown monitor command: '{}'", command_line);
+                return format!(
+                    "Unknown monitor command '{}', use 'monitor help' to list the commands\n",
+                    name
+                );
+            }
+        };
+
+        let mut context = MonitorContext {
+            target,
+            core_index,
+            output: String::new(),
+            target_output,
+        };
+
+        if let Err(e) = command.execute(&mut context, args) {
+            let _ = writeln!(context.output, "Error: {:#}", e);
+        }
+
+        if !context.output.is_empty() && !context.output.ends_with('\n') {
+            context.output.push('\n');
+        }
+
+        context.output
+    }
+
+    /// Lists the available commands.
+    fn help(&self) -> String {
+        let mut output = String::from("Available monitor commands:\n");
+
+        let mut commands: Vec<_> = self
+            .commands
+            .iter()
+            .map(|command| {
+                (
+                    format!("{} {}", command.name(), command.usage()),
+                    command.description(),
+                )
+            })
+            .collect();
+        commands.push(("help".to_string(), "Show this list"));
+
+        let width = commands
+            .iter()
+            .map(|(usage, _)| usage.trim_end().len())
+            .max()
+            .unwrap_or(0);
+
+        for (usage, description) in commands {
+            let _ = writeln!(
+                output,
+                "  {:width$}  {}",
+                usage.trim_end(),
+                description,
+                width = width
+            );
+        }
+
+        output
+    }
+}
+
+/// The registry contains the built-in commands of probe-rs.
+impl<T: DebugTarget> Default for MonitorCommands<T> {
+    fn default() -> Self {
+        let mut commands = Self::new();
+
+        commands.register(Reset);
+        commands.register(Halt);
+        commands.register(Resume);
+        commands.register(Fault);
+        commands.register(Erase);
+        commands.register(Flash);
+        commands.register(Dump);
+        commands.register(Swo);
+        commands.register(RttCommand);
+        commands.register(Speed);
+        commands.register(Info);
+
+        commands
+    }
+}
+
+/// Output of the target, which is shown in the GDB console while the target runs.
+#[derive(Default)]
+pub(crate) struct TargetOutput {
+    /// The RTT control block, and the index of the core used to read it.
+    rtt: Option<(Rtt, usize)>,
+    /// The server for the RTT channels, and the index of the core used to access them.
+    rtt_server: Option<(RttServer, usize)>,
+    /// The decoder for ITM packets received over SWO, if SWO is enabled.
+    swo: Option<Decoder>,
+}
+
+impl TargetOutput {
+    /// Reads the available data of the RTT up channels and the ITM stimulus ports.
+    pub(crate) fn poll(&mut self, target: &mut impl DebugTarget) -> Vec<u8> {
+        let mut output = Vec::new();
+
+        if let Some((rtt, core_index)) = &mut self.rtt {
+            match target.core(*core_index) {
+                Ok(mut core) => {
+                    let mut buffer = [0u8; 1024];
+                    for channel in rtt.up_channels().iter() {
+                        match channel.read(&mut core, &mut buffer) {
+                            Ok(count) => output.extend_from_slice(&buffer[..count]),
+                            Err(e) => log::warn!("Unable to read RTT channel: {}", e),
+                        }
+                    }
+                }
+                Err(e) => log::warn!("Unable to attach to core {}: {}", core_index, e),
+            }
+        }
+
+        if let Some((server, core_index)) = &mut self.rtt_server {
+            match target.core(*core_index) {
+                Ok(mut core) => {
+                    if let Err(e) = server.poll(&mut core) {
+                        log::warn!("Unable to serve RTT channels: {}", e);
+                    }
+                }
+                Err(e) => log::warn!("Unable to attach to core {}: {}", core_index, e),
+            }
+        }
+
+        if let Some(decoder) = &mut self.swo {
+            let data = match target.session_mut().map(Session::read_swo) {
+                Some(Ok(data)) => data,
+                Some(Err(e)) => {
+                    log::warn!("Unable to read SWO data: {}", e);
+                    Vec::new()
+                }
+                None => Vec::new(),
+            };
+
+            decoder.push(&data);
+            while let Ok(Some(packet)) = decoder.pull() {
+                if let TracePacket::Instrumentation { payload, .. } = packet {
+                    output.extend_from_slice(&payload);
+                }
+            }
+        }
+
+        output
+    }
+}
+
+/// `reset [halt|run|hardware]`
+struct Reset;
+
+impl<T: DebugTarget> MonitorCommand<T> for Reset {
+    fn name(&self) -> &'static str {
+        "reset"
+    }
+
+    fn usage(&self) -> &'static str {
+        "[halt|run|hardware]"
+    }
+
+    fn description(&self) -> &'static str {
+        "Reset the selected core, and halt it (default) or let it run. 'hardware' uses the reset pin of the probe"
+    }
+
+    fn execute(&self, context: &mut MonitorContext<'_, T>, args: &[&str]) -> Result<()> {
+        match args.first().copied().unwrap_or("halt") {
+            "halt" => {
+                let mut core = context.target.core(context.core_index)?;
+                core.reset_and_halt(Duration::from_millis(400))?;
+                writeln!(
+                    context.output,
+                    "Core {} reset and halted",
+                    context.core_index
+                )?;
+            }
+            "run" => {
+                context.target.core(context.core_index)?.reset()?;
+                writeln!(
+                    context.output,
+                    "Core {} reset, and running",
+                    context.core_index
+                )?;
+            }
+            "hardware" => {
+                context.session()?.probe_mut().target_reset()?;
+
+                // GDB expects the cores to be halted.
+                for core_index in 0..context.target.list_cores().len() {
+                    context
+                        .target
+                        .core(core_index)?
+                        .halt(Duration::from_millis(100))?;
+                }
+                writeln!(
+                    context.output,
+                    "Target reset with the reset pin, and halted"
+                )?;
+            }
+            other => bail!("Unknown reset type '{}'", other),
+        }
+
+        Ok(())
+    }
+}
+
+/// `halt [core]`
+struct Halt;
+
+impl<T: DebugTarget> MonitorCommand<T> for Halt {
+    fn name(&self) -> &'static str {
+        "halt"
+    }
+
+    fn usage(&self) -> &'static str {
+        "[core]"
+    }
+
+    fn description(&self) -> &'static str {
+        "Halt a core, by default the selected one"
+    }
+
+    fn execute(&self, context: &mut MonitorContext<'_, T>, args: &[&str]) -> Result<()> {
+        let core_index = context.core_index_argument(args.first())?;
+
+        let info = context
+            .target
+            .core(core_index)?
+            .halt(Duration::from_millis(100))?;
+        writeln!(
+            context.output,
+            "Core {} halted at {:#010x}",
+            core_index, info.pc
+        )?;
+
+        Ok(())
+    }
+}
+
+/// `resume [core]`
+struct Resume;
+
+impl<T: DebugTarget> MonitorCommand<T> for Resume {
+    fn name(&self) -> &'static str {
+        "resume"
+    }
+
+    fn usage(&self) -> &'static str {
+        "[core]"
+    }
+
+    fn description(&self) -> &'static str {
+        "Resume a core without notifying GDB, by default the selected one"
+    }
+
+    fn execute(&self, context: &mut MonitorContext<'_, T>, args: &[&str]) -> Result<()> {
+        let core_index = context.core_index_argument(args.first())?;
+
+        context.target.core(core_index)?.run()?;
+        writeln!(context.output, "Core {} resumed", core_index)?;
+
+        Ok(())
+    }
+}
+
+/// `fault`
+struct Fault;
+
+impl<T: DebugTarget> MonitorCommand<T> for Fault {
+    fn name(&self) -> &'static str {
+        "fault"
+    }
+
+    fn description(&self) -> &'static str {
+        "Analyze the fault the selected core is handling"
+    }
+
+    fn execute(&self, context: &mut MonitorContext<'_, T>, _args: &[&str]) -> Result<()> {
+        let (_, core_type) = context.target.list_cores()[context.core_index];
+        let mut core = context.target.core(context.core_index)?;
+
+        match fault::analyze_fault(&mut core, core_type)? {
+            Some(report) => write!(context.output, "{}", report)?,
+            None => writeln!(context.output, "The core is not handling a fault")?,
+        }
+
+        Ok(())
+    }
+}
+
+/// `erase`
+struct Erase;
+
+impl<T: DebugTarget> MonitorCommand<T> for Erase {
+    fn name(&self) -> &'static str {
+        "erase"
+    }
+
+    fn description(&self) -> &'static str {
+        "Erase the complete flash"
+    }
+
+    fn execute(&self, context: &mut MonitorContext<'_, T>, _args: &[&str]) -> Result<()> {
+        flashing::erase_all(context.session()?)?;
+        writeln!(context.output, "Flash erased")?;
+
+        Ok(())
+    }
+}
+
+/// `flash <file> [address]`
+struct Flash;
+
+impl<T: DebugTarget> MonitorCommand<T> for Flash {
+    fn name(&self) -> &'static str {
+        "flash"
+    }
+
+    fn usage(&self) -> &'static str {
+        "<file> [address]"
+    }
+
+    fn description(&self) -> &'static str {
+        "Program an ELF, Intel HEX, UF2 or S-record file, or a binary file to the given address"
+    }
+
+    fn execute(&self, context: &mut MonitorContext<'_, T>, args: &[&str]) -> Result<()> {
+        let path = Path::new(args.first().ok_or_else(|| anyhow!("No file given"))?);
+
+        let format = match (args.get(1), path.extension().and_then(|e| e.to_str())) {
+            (Some(address), _) => Format::Bin(BinOptions {
+                base_address: Some(parse_u32(address)?),
+                skip: 0,
+            }),
+            (None, Some("hex")) => Format::Hex,
+            (None, Some("uf2")) => Format::Uf2(Uf2Options::default()),
+            (None, Some("srec" | "s19" | "s28" | "s37" | "mot")) => Format::Srec,
+            (None, Some("bin")) => bail!("Binary files require the address to program them to"),
+            (None, _) => Format::Elf,
+        };
+
+        flashing::download_file(context.session()?, path, format)?;
+        writeln!(context.output, "Programmed {}", path.display())?;
+
+        Ok(())
+    }
+}
+
+/// `dump <address> <length> <file>`
+struct Dump;
+
+impl<T: DebugTarget> MonitorCommand<T> for Dump {
+    fn name(&self) -> &'static str {
+        "dump"
+    }
+
+    fn usage(&self) -> &'static str {
+        "<address> <length> <file>"
+    }
+
+    fn description(&self) -> &'static str {
+        "Write the memory contents to a binary file"
+    }
+
+    fn execute(&self, context: &mut MonitorContext<'_, T>, args: &[&str]) -> Result<()> {
+        let (address, length, path) = match args {
+            [address, length, path] => (parse_u32(address)?, parse_u32(length)?, Path::new(path)),
+            _ => bail!("Usage: dump <address> <length> <file>"),
+        };
+
+        let mut data = vec![0u8; length as usize];
+        context
+            .target
+            .core(context.core_index)?
+            .read_8(address, &mut data)?;
+
+        File::create(path)
+            .and_then(|mut file| file.write_all(&data))
+            .with_context(|| format!("Unable to write {}", path.display()))?;
+
+        writeln!(
+            context.output,
+            "Wrote {} bytes from {:#010x} to {}",
+            length,
+            address,
+            path.display()
+        )?;
+
+        Ok(())
+    }
+}
+
+/// `swo start <tpiu clock> [baud] | swo stop`
+struct Swo;
+
+impl<T: DebugTarget> MonitorCommand<T> for Swo {
+    fn name(&self) -> &'static str {
+        "swo"
+    }
+
+    fn usage(&self) -> &'static str {
+        "start <tpiu clock> [baud] | stop"
+    }
+
+    fn description(&self) -> &'static str {
+        "Show the ITM stimulus ports received over SWO in the console while the target runs"
+    }
+
+    fn execute(&self, context: &mut MonitorContext<'_, T>, args: &[&str]) -> Result<()> {
+        let core_index = context.core_index;
+
+        match args {
+            ["start", tpiu_clock, rest @ ..] => {
+                let baud = match rest.first() {
+                    Some(baud) => parse_u32(baud)?,
+                    None => 1_000_000,
+                };
+                let config = SwoConfig::new(parse_u32(tpiu_clock)?)
+                    .set_baud(baud)
+                    .set_continuous_formatting(false);
+
+                context.session()?.setup_swv(core_index, &config)?;
+                context.target_output.swo = Some(Decoder::new(DecoderOptions::default()));
+                writeln!(context.output, "SWO started with {} baud", baud)?;
+            }
+            ["stop"] => {
+                context.target_output.swo = None;
+                context.session()?.disable_swv(core_index)?;
+                writeln!(context.output, "SWO stopped")?;
+            }
+            _ => bail!("Usage: swo start <tpiu clock> [baud] | swo stop"),
+        }
+
+        Ok(())
+    }
+}
+
+/// `rtt start [address] | rtt server <address> [address] | rtt stop`
+struct RttCommand;
+
+impl RttCommand {
+    /// Attaches to the RTT control block at `address`, or searches the RAM for it.
+    fn attach<T: DebugTarget>(
+        context: &mut MonitorContext<'_, T>,
+        address: Option<&&str>,
+    ) -> Result<Rtt> {
+        let scan_region = match address {
+            Some(address) => ScanRegion::Exact(parse_u32(address)?),
+            None => ScanRegion::Ram,
+        };
+
+        let memory_map = context.target.memory_map();
+        let mut core = context.target.core(context.core_index)?;
+        let rtt = Rtt::attach_region(&mut core, &memory_map, &scan_region)?;
+
+        writeln!(
+            context.output,
+            "RTT control block found at {:#010x}",
+            rtt.ptr()
+        )?;
+
+        Ok(rtt)
+    }
+}
+
+impl<T: DebugTarget> MonitorCommand<T> for RttCommand {
+    fn name(&self) -> &'static str {
+        "rtt"
+    }
+
+    fn usage(&self) -> &'static str {
+        "start [control block address] | server <port|ip:port|unix:directory> [control block address] | stop"
+    }
+
+    fn description(&self) -> &'static str {
+        "Show the RTT up channels in the console while the target runs, or serve each channel on a socket"
+    }
+
+    fn execute(&self, context: &mut MonitorContext<'_, T>, args: &[&str]) -> Result<()> {
+        match args {
+            ["start", rest @ ..] => {
+                context.target_output.rtt_server = None;
+                let mut rtt = Self::attach(context, rest.first())?;
+
+                for channel in rtt.up_channels().iter() {
+                    writeln!(
+                        context.output,
+                        "  Up channel {}: {}",
+                        channel.number(),
+                        channel.name().unwrap_or("(unnamed)")
+                    )?;
+                }
+
+                context.target_output.rtt = Some((rtt, context.core_index));
+            }
+            ["server", address, rest @ ..] => {
+                let address: RttServerAddress = address.parse().map_err(|e: String| anyhow!(e))?;
+
+                context.target_output.rtt = None;
+                context.target_output.rtt_server = None;
+                let mut rtt = Self::attach(context, rest.first())?;
+                let server = RttServer::new(&mut rtt, &address)?;
+
+                for (number, name, address) in server.channels() {
+                    writeln!(
+                        context.output,
+                        "  Channel {}: {} on {}",
+                        number,
+                        name.unwrap_or("(unnamed)"),
+                        address
+                    )?;
+                }
+
+                context.target_output.rtt_server = Some((server, context.core_index));
+            }
+            ["stop"] => {
+                context.target_output.rtt = None;
+                context.target_output.rtt_server = None;
+                writeln!(context.output, "RTT stopped")?;
+            }
+            _ => bail!("Usage: rtt start [control block address] | rtt server <address> [control block address] | rtt stop"),
+        }
+
+        Ok(())
+    }
+}
+
+/// `speed [kHz]`
+struct Speed;
+
+impl<T: DebugTarget> MonitorCommand<T> for Speed {
+    fn name(&self) -> &'static str {
+        "speed"
+    }
+
+    fn usage(&self) -> &'static str {
+        "[kHz]"
+    }
+
+    fn description(&self) -> &'static str {
+        "Show or set the speed of the probe"
+    }
+
+    fn execute(&self, context: &mut MonitorContext<'_, T>, args: &[&str]) -> Result<()> {
+        let probe = context.session()?.probe_mut();
+
+        let speed_khz = match args.first() {
+            Some(speed_khz) => probe.set_speed(parse_u32(speed_khz)?)?,
+            None => probe.speed_khz(),
+        };
+
+        writeln!(context.output, "Probe speed: {} kHz", speed_khz)?;
+
+        Ok(())
+    }
+}
+
+/// `info probe|target`
+struct Info;
+
+impl<T: DebugTarget> MonitorCommand<T> for Info {
+    fn name(&self) -> &'static str {
+        "info"
+    }
+
+    fn usage(&self) -> &'static str {
+        "probe|target"
+    }
+
+    fn description(&self) -> &'static str {
+        "Show information about the probe or the target"
+    }
+
+    fn execute(&self, context: &mut MonitorContext<'_, T>, args: &[&str]) -> Result<()> {
+        match args {
+            ["probe"] => {
+                let probe = context.session()?.probe();
+                let protocol = probe
+                    .active_protocol()
+                    .map(|protocol| protocol.to_string())
+                    .unwrap_or_else(|| "unknown".to_string());
+                let output = format!(
+                    "Probe: {}\nProtocol: {}\nSpeed: {} kHz\n",
+                    probe.get_name(),
+                    protocol,
+                    probe.speed_khz()
+                );
+
+                context.output.push_str(&output);
+            }
+            ["target"] => {
+                let cores = context.target.list_cores();
+                if let Some(session) = context.target.session_mut() {
+                    writeln!(context.output, "Target: {}", session.target().name)?;
+                }
+
+                for (core_index, core_type) in cores {
+                    let name = context.target.core_name(core_index);
+                    let status = context.target.core(core_index)?.status()?;
+                    writeln!(
+                        context.output,
+                        "Core {}: {} ({:?}), {:?}",
+                        core_index, name, core_type, status
+                    )?;
+                }
+
+                for region in context.target.memory_map() {
+                    writeln!(context.output, "{:x?}", region)?;
+                }
+            }
+            _ => bail!("Usage: info probe|target"),
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a decimal number, or a hexadecimal, octal or binary number with a `0x`, `0o` or `0b` prefix.
+fn parse_u32(value: &str) -> Result<u32> {
+    parse_int::parse(value).with_context(|| format!("Invalid number '{}'", value))
+}
+
+#[cfg(test)]
+mod test {
+    use std::ops::Range;
+
+    use probe_rs::{config::MemoryRegion, Core, CoreType, Error};
+
+    use super::*;
+
+    /// A target without cores, for testing the dispatch of the commands.
+    struct NoTarget;
+
+    impl DebugTarget for NoTarget {
+        fn list_cores(&self) -> Vec<(usize, CoreType)> {
+            vec![(0, CoreType::Armv7m)]
+        }
+
+        fn core(&mut self, _n: usize) -> Result<Core<'_>, Error> {
+            Err(Error::Other(anyhow!("No core in test")))
+        }
+
+        fn core_name(&self, _n: usize) -> String {
+            "core0".to_string()
+        }
+
+        fn memory_map(&self) -> Vec<MemoryRegion> {
+            Vec::new()
+        }
+
+        fn flash_sectors(&self) -> Vec<(Range<u32>, u32)> {
+            Vec::new()
+        }
+
+        fn session_mut(&mut self) -> Option<&mut Session> {
+            None
+        }
+    }
+
+    /// A command which echoes its arguments, prefixed with `prefix`.
+    struct Echo {
+        prefix: &'static str,
+    }
+
+    impl MonitorCommand<NoTarget> for Echo {
+        fn name(&self) -> &'static str {
+            "echo"
+        }
+
+        fn usage(&self) -> &'static str {
+            "<words>"
+        }
+
+        fn description(&self) -> &'static str {
+            "Print the arguments"
+        }
+
+        fn execute(&self, context: &mut MonitorContext<'_, NoTarget>, args: &[&str]) -> Result<()> {
+            if args.is_empty() {
+                bail!("Nothing to echo");
+            }
+
+            write!(context.output, "{}{}", self.prefix, args.join(" "))?;
+
+            Ok(())
+        }
+    }
+
+    fn execute(commands: &MonitorCommands<NoTarget>, command_line: &str) -> String {
+        commands.execute(&mut NoTarget, 0, &mut TargetOutput::default(), command_line)
+    }
+
+    #[test]
+    fn parse_numbers() {
+        assert_eq!(parse_u32("1234").unwrap(), 1234);
+        assert_eq!(parse_u32("0x20000000").unwrap(), 0x2000_0000);
+        assert!(parse_u32("0xg").is_err());
+    }
+
+    #[test]
+    fn dispatch_to_command() {
+        let mut commands = MonitorCommands::new();
+        commands.register(Echo { prefix: "" });
+
+        assert_eq!(execute(&commands, "echo hello  world"), "hello world\n");
+        assert_eq!(execute(&commands, "echo"), "Error: Nothing to echo\n");
+    }
+
+    #[test]
+    fn register_replaces_command_with_same_name() {
+        let mut commands = MonitorCommands::new();
+        commands.register(Echo { prefix: "first: " });
+        commands.register(Echo { prefix: "second: " });
+
+        assert_eq!(execute(&commands, "echo a"), "second: a\n");
+        assert_eq!(execute(&commands, "help").matches("echo").count(), 1);
+    }
+
+    #[test]
+    fn unknown_command() {
+        let commands = MonitorCommands::<NoTarget>::new();
+
+        assert_eq!(
+            execute(&commands, "frobnicate 1 2"),
+            "Unknown monitor command 'frobnicate', use 'monitor help' to list the commands\n"
+        );
+    }
+
+    #[test]
+    fn help_lists_commands() {
+        let mut commands = MonitorCommands::new();
+        commands.register(Echo { prefix: "" });
+
+        let help = "Available monitor commands:\n  echo <words>  Print the arguments\n  help          Show this list\n";
+        assert_eq!(execute(&commands, "help"), help);
+        assert_eq!(execute(&commands, ""), help);
+    }
+
+    #[test]
+    fn builtin_commands_report_errors() {
+        let commands = MonitorCommands::default();
+
+        assert_eq!(
+            execute(&commands, "erase"),
+            "Error: This command requires a connected target\n"
+        );
+        assert_eq!(
+            execute(&commands, "halt 3"),
+            "Error: Core 3 does not exist, the target has 1 cores\n"
+        );
+        assert_eq!(
+            execute(&commands, "dump 0x1000"),
+            "Error: Usage: dump <address> <length> <file>\n"
+        );
+        assert!(execute(&commands, "help").contains("  reset [halt|run|hardware]"));
+    }
+}
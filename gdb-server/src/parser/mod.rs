@@ -16,11 +16,13 @@ use nom::{
 };
 
 use anyhow::{anyhow, Result};
-use query::query_packet;
+use query::{query_packet, query_set_packet};
+use util::thread_id;
 use v_packet::v_packet;
 
-pub use query::{Pid, QueryPacket};
+pub use query::{Pid, QueryPacket, QuerySetPacket};
 use util::hex_u64;
+pub use util::ThreadId;
 pub use v_packet::VPacket;
 
 #[allow(dead_code)]
@@ -61,7 +63,10 @@ pub enum Packet {
         reg_values: String,
     },
     /// Packet `H`
-    SelectThread,
+    SelectThread {
+        operation: ThreadOperation,
+        thread_id: ThreadId,
+    },
     /// Packet `i`
     StepClockCycle,
     /// Packet `I`
@@ -85,7 +90,7 @@ pub enum Packet {
     // Packet 'q'
     Query(QueryPacket),
     // Packet 'Q'
-    QuerySet,
+    QuerySet(QuerySetPacket),
     // Packet 'r'
     Reset,
     // Packet 'R'
@@ -97,7 +102,7 @@ pub enum Packet {
     // Packet 't'
     SearchBackwards,
     // Packet 'T'
-    ThreadInfo,
+    ThreadAlive(ThreadId),
     // Packet 'v'
     V(VPacket),
     // Packet 'X'
@@ -121,6 +126,15 @@ pub enum Packet {
     Interrupt,
 }
 
+/// The operations a thread can be selected for with the `H` packet.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ThreadOperation {
+    /// `Hg`, used for register and memory accesses.
+    General,
+    /// `Hc`, used for continue and step packets.
+    Continue,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum BreakpointType {
     Software,
@@ -139,6 +153,9 @@ pub fn parse_packet(input: &[u8]) -> Result<Packet> {
         read_register_hex,
         read_memory,
         query,
+        query_set,
+        select_thread,
+        thread_alive,
         v,
         insert_breakpoint,
         remove_breakpoint,
@@ -225,6 +242,39 @@ fn query(input: &[u8]) -> IResult<&[u8], Packet> {
     Ok((input, Packet::Query(packet)))
 }
 
+fn query_set(input: &[u8]) -> IResult<&[u8], Packet> {
+    let (input, _) = char('Q')(input)?;
+    let (input, packet) = query_set_packet(input)?;
+
+    Ok((input, Packet::QuerySet(packet)))
+}
+
+fn select_thread(input: &[u8]) -> IResult<&[u8], Packet> {
+    let (input, _) = char('H')(input)?;
+
+    let (input, operation) = alt((
+        value(ThreadOperation::General, char('g')),
+        value(ThreadOperation::Continue, char('c')),
+    ))(input)?;
+
+    let (input, thread_id) = thread_id(input)?;
+
+    Ok((
+        input,
+        Packet::SelectThread {
+            operation,
+            thread_id,
+        },
+    ))
+}
+
+fn thread_alive(input: &[u8]) -> IResult<&[u8], Packet> {
+    let (input, _) = char('T')(input)?;
+    let (input, thread_id) = thread_id(input)?;
+
+    Ok((input, Packet::ThreadAlive(thread_id)))
+}
+
 fn v(input: &[u8]) -> IResult<&[u8], Packet> {
     let (input, _) = char('v')(input)?;
 
@@ -441,6 +491,18 @@ mod test {
         );
     }
 
+    #[test]
+    fn parse_insert_watchpoint() {
+        assert_eq!(
+            parse_packet(b"Z2,20000010,4").unwrap(),
+            Packet::InsertBreakpoint {
+                breakpoint_type: BreakpointType::WriteWatchpoint,
+                address: 0x20000010,
+                kind: 4,
+            }
+        );
+    }
+
     #[test]
     fn parse_write_memory_binary() {
         assert_eq!(
@@ -472,6 +534,40 @@ mod test {
         );
     }
 
+    #[test]
+    fn parse_select_thread() {
+        assert_eq!(
+            parse_packet(b"Hg2").unwrap(),
+            Packet::SelectThread {
+                operation: ThreadOperation::General,
+                thread_id: ThreadId::Id(2),
+            }
+        );
+        assert_eq!(
+            parse_packet(b"Hc-1").unwrap(),
+            Packet::SelectThread {
+                operation: ThreadOperation::Continue,
+                thread_id: ThreadId::All,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_thread_alive() {
+        assert_eq!(
+            parse_packet(b"T1").unwrap(),
+            Packet::ThreadAlive(ThreadId::Id(1))
+        );
+    }
+
+    #[test]
+    fn parse_query_set_non_stop() {
+        assert_eq!(
+            parse_packet(b"QNonStop:1").unwrap(),
+            Packet::QuerySet(QuerySetPacket::NonStop(true))
+        );
+    }
+
     #[test]
     fn parse_query_crc_packet() {
         assert_eq!(
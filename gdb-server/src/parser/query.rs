@@ -1,9 +1,9 @@
-use super::util::hex_bytes;
+use super::util::{hex_bytes, thread_id, ThreadId};
 use nom::{
     branch::alt,
     bytes::complete::{tag, take_until, take_while},
     character::complete::char,
-    combinator::{all_consuming, opt, peek},
+    combinator::{all_consuming, opt, peek, value},
     error::ErrorKind,
     multi::separated_list1,
     number::complete::hex_u32,
@@ -14,6 +14,12 @@ use nom::{
 #[derive(Debug, PartialEq, Clone)]
 pub enum QueryPacket {
     ThreadId,
+    /// `qfThreadInfo`, requests the first part of the thread list.
+    FirstThreadInfo,
+    /// `qsThreadInfo`, requests the remaining part of the thread list.
+    SubsequentThreadInfo,
+    /// `qThreadExtraInfo`, requests a description of a thread.
+    ThreadExtraInfo(ThreadId),
     Attached(Option<Pid>),
     Command(Vec<u8>),
     Supported(Vec<String>),
@@ -43,6 +49,13 @@ pub enum TransferOperation {
     },
 }
 
+/// Packets starting with `Q`, which set a value.
+#[derive(Debug, PartialEq, Clone)]
+pub enum QuerySetPacket {
+    /// `QNonStop`, enables or disables non-stop mode.
+    NonStop(bool),
+}
+
 pub type Pid = u32;
 
 /// Parse PID
@@ -53,6 +66,8 @@ pub fn pid(input: &[u8]) -> IResult<&[u8], Pid> {
 pub fn query_packet(input: &[u8]) -> IResult<&[u8], QueryPacket> {
     let (input, query_packet) = alt((
         query_thread_id,
+        query_thread_info,
+        query_thread_extra_info,
         query_attached,
         query_command,
         query_crc,
@@ -73,6 +88,21 @@ fn query_thread_id(input: &[u8]) -> IResult<&[u8], QueryPacket> {
     Ok((input, QueryPacket::ThreadId))
 }
 
+fn query_thread_info(input: &[u8]) -> IResult<&[u8], QueryPacket> {
+    alt((
+        value(QueryPacket::FirstThreadInfo, tag("fThreadInfo")),
+        value(QueryPacket::SubsequentThreadInfo, tag("sThreadInfo")),
+    ))(input)
+}
+
+fn query_thread_extra_info(input: &[u8]) -> IResult<&[u8], QueryPacket> {
+    let (input, _) = tag("ThreadExtraInfo,")(input)?;
+
+    let (input, thread_id) = thread_id(input)?;
+
+    Ok((input, QueryPacket::ThreadExtraInfo(thread_id)))
+}
+
 fn query_command(input: &[u8]) -> IResult<&[u8], QueryPacket> {
     let (input, _) = tag("Rcmd,")(input)?;
 
@@ -199,6 +229,18 @@ fn query_crc(input: &[u8]) -> IResult<&[u8], QueryPacket> {
     Ok((input, QueryPacket::Crc { address, length }))
 }
 
+pub fn query_set_packet(input: &[u8]) -> IResult<&[u8], QuerySetPacket> {
+    query_set_non_stop(input)
+}
+
+fn query_set_non_stop(input: &[u8]) -> IResult<&[u8], QuerySetPacket> {
+    let (input, _) = tag("NonStop:")(input)?;
+
+    let (input, enabled) = alt((value(false, char('0')), value(true, char('1'))))(input)?;
+
+    Ok((input, QuerySetPacket::NonStop(enabled)))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -253,4 +295,36 @@ mod test {
             )
         );
     }
+
+    #[test]
+    fn parse_thread_info() {
+        assert_eq!(
+            query_packet(b"fThreadInfo").unwrap(),
+            (EMPTY, QueryPacket::FirstThreadInfo)
+        );
+        assert_eq!(
+            query_packet(b"sThreadInfo").unwrap(),
+            (EMPTY, QueryPacket::SubsequentThreadInfo)
+        );
+    }
+
+    #[test]
+    fn parse_thread_extra_info() {
+        assert_eq!(
+            query_packet(b"ThreadExtraInfo,2").unwrap(),
+            (EMPTY, QueryPacket::ThreadExtraInfo(ThreadId::Id(2)))
+        );
+    }
+
+    #[test]
+    fn parse_query_set_non_stop() {
+        assert_eq!(
+            query_set_packet(b"NonStop:1").unwrap(),
+            (EMPTY, QuerySetPacket::NonStop(true))
+        );
+        assert_eq!(
+            query_set_packet(b"NonStop:0").unwrap(),
+            (EMPTY, QuerySetPacket::NonStop(false))
+        );
+    }
 }
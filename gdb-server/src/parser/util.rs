@@ -1,4 +1,41 @@
-use nom::{bytes::complete::take_while_m_n, character::is_hex_digit, multi::many1, IResult};
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while_m_n},
+    character::is_hex_digit,
+    combinator::value,
+    multi::many1,
+    number::complete::hex_u32,
+    IResult,
+};
+
+/// Thread id, as used in packets like `H`, `T` or `vCont`.
+///
+/// Each core of the target is reported as a thread to GDB.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ThreadId {
+    /// All threads, encoded as `-1`.
+    All,
+    /// An arbitrary thread, encoded as `0`.
+    Any,
+    /// A specific thread.
+    Id(u32),
+}
+
+/// Parse a thread id.
+pub fn thread_id(input: &[u8]) -> IResult<&[u8], ThreadId> {
+    alt((value(ThreadId::All, tag("-1")), specific_thread_id))(input)
+}
+
+fn specific_thread_id(input: &[u8]) -> IResult<&[u8], ThreadId> {
+    let (input, id) = hex_u32(input)?;
+
+    let thread_id = match id {
+        0 => ThreadId::Any,
+        id => ThreadId::Id(id),
+    };
+
+    Ok((input, thread_id))
+}
 
 /// Parse bytes encoded as a ASCII hex string.
 ///
@@ -62,6 +99,16 @@ mod test {
         assert_eq!(hex_byte(b"853").unwrap(), ("3".as_bytes(), 0x85));
     }
 
+    #[test]
+    fn parse_thread_id() {
+        assert_eq!(thread_id(b"-1").unwrap(), (EMPTY, ThreadId::All));
+        assert_eq!(thread_id(b"0").unwrap(), (EMPTY, ThreadId::Any));
+        assert_eq!(
+            thread_id(b"2;c").unwrap(),
+            (";c".as_bytes(), ThreadId::Id(2))
+        );
+    }
+
     #[test]
     fn parse_hex_u64() {
         assert_eq!(hex_u64(b"0").unwrap(), (EMPTY, 0x0));
@@ -1,14 +1,39 @@
-use super::{query::pid, Pid};
+use super::{
+    query::pid,
+    util::{thread_id, ThreadId},
+    Pid,
+};
 use nom::{
-    branch::alt, bytes::complete::tag, character::complete::char, combinator::value, IResult,
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::char,
+    combinator::{opt, rest, value},
+    multi::many1,
+    number::complete::hex_u32,
+    sequence::preceded,
+    IResult,
 };
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum VPacket {
     Attach(Pid),
-    Continue(Action),
+    Continue(Vec<ThreadAction>),
     Unknown(Vec<u8>),
     QueryContSupport,
+    /// `vStopped`, acknowledges a stop notification in non-stop mode.
+    Stopped,
+    /// `vFlashErase`, erases the flash in the given range.
+    FlashErase {
+        address: u32,
+        length: u32,
+    },
+    /// `vFlashWrite`, writes data to flash.
+    FlashWrite {
+        address: u32,
+        data: Vec<u8>,
+    },
+    /// `vFlashDone`, finishes a sequence of flash operations.
+    FlashDone,
 }
 
 #[allow(dead_code)]
@@ -22,8 +47,25 @@ pub enum Action {
     RangeStep { start: u32, end: u32 },
 }
 
+/// An action of a `vCont` packet, and the thread it applies to.
+///
+/// Actions without a thread id apply to all threads.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ThreadAction {
+    pub action: Action,
+    pub thread_id: ThreadId,
+}
+
 pub fn v_packet(input: &[u8]) -> IResult<&[u8], VPacket> {
-    let parse_result = alt((v_attach, v_cont_support, v_cont))(input);
+    let parse_result = alt((
+        v_attach,
+        v_cont_support,
+        v_cont,
+        v_stopped,
+        v_flash_erase,
+        v_flash_write,
+        v_flash_done,
+    ))(input);
 
     match parse_result {
         Ok((input, packet)) => Ok((input, packet)),
@@ -51,11 +93,25 @@ fn v_cont_support(input: &[u8]) -> IResult<&[u8], VPacket> {
 }
 
 fn v_cont(input: &[u8]) -> IResult<&[u8], VPacket> {
-    let (input, _) = tag("Cont;")(input)?;
+    let (input, _) = tag("Cont")(input)?;
+
+    let (input, actions) = many1(preceded(char(';'), v_cont_thread_action))(input)?;
 
+    Ok((input, VPacket::Continue(actions)))
+}
+
+fn v_cont_thread_action(input: &[u8]) -> IResult<&[u8], ThreadAction> {
     let (input, action) = v_cont_action(input)?;
 
-    Ok((input, VPacket::Continue(action)))
+    let (input, thread_id) = opt(preceded(char(':'), thread_id))(input)?;
+
+    Ok((
+        input,
+        ThreadAction {
+            action,
+            thread_id: thread_id.unwrap_or(ThreadId::All),
+        },
+    ))
 }
 
 fn v_cont_action(input: &[u8]) -> IResult<&[u8], Action> {
@@ -66,6 +122,42 @@ fn v_cont_action(input: &[u8]) -> IResult<&[u8], Action> {
     ))(input)
 }
 
+fn v_stopped(input: &[u8]) -> IResult<&[u8], VPacket> {
+    value(VPacket::Stopped, tag("Stopped"))(input)
+}
+
+fn v_flash_erase(input: &[u8]) -> IResult<&[u8], VPacket> {
+    let (input, _) = tag("FlashErase:")(input)?;
+
+    let (input, address) = hex_u32(input)?;
+    let (input, _) = char(',')(input)?;
+    let (input, length) = hex_u32(input)?;
+
+    Ok((input, VPacket::FlashErase { address, length }))
+}
+
+fn v_flash_write(input: &[u8]) -> IResult<&[u8], VPacket> {
+    let (input, _) = tag("FlashWrite:")(input)?;
+
+    let (input, address) = hex_u32(input)?;
+    let (input, _) = char(':')(input)?;
+
+    // The data is binary, and extends to the end of the packet.
+    let (input, data) = rest(input)?;
+
+    Ok((
+        input,
+        VPacket::FlashWrite {
+            address,
+            data: data.to_owned(),
+        },
+    ))
+}
+
+fn v_flash_done(input: &[u8]) -> IResult<&[u8], VPacket> {
+    value(VPacket::FlashDone, tag("FlashDone"))(input)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -89,7 +181,13 @@ mod test {
     fn parse_v_cont_cont() {
         assert_eq!(
             v_packet(b"Cont;c").unwrap(),
-            (EMPTY, VPacket::Continue(Action::Continue))
+            (
+                EMPTY,
+                VPacket::Continue(vec![ThreadAction {
+                    action: Action::Continue,
+                    thread_id: ThreadId::All,
+                }])
+            )
         );
     }
 
@@ -97,7 +195,13 @@ mod test {
     fn parse_v_cont_step() {
         assert_eq!(
             v_packet(b"Cont;s").unwrap(),
-            (EMPTY, VPacket::Continue(Action::Step))
+            (
+                EMPTY,
+                VPacket::Continue(vec![ThreadAction {
+                    action: Action::Step,
+                    thread_id: ThreadId::All,
+                }])
+            )
         );
     }
 
@@ -105,7 +209,71 @@ mod test {
     fn parse_v_cont_stop() {
         assert_eq!(
             v_packet(b"Cont;t").unwrap(),
-            (EMPTY, VPacket::Continue(Action::Stop))
+            (
+                EMPTY,
+                VPacket::Continue(vec![ThreadAction {
+                    action: Action::Stop,
+                    thread_id: ThreadId::All,
+                }])
+            )
         );
     }
+
+    #[test]
+    fn parse_v_cont_thread_actions() {
+        assert_eq!(
+            v_packet(b"Cont;s:2;c").unwrap(),
+            (
+                EMPTY,
+                VPacket::Continue(vec![
+                    ThreadAction {
+                        action: Action::Step,
+                        thread_id: ThreadId::Id(2),
+                    },
+                    ThreadAction {
+                        action: Action::Continue,
+                        thread_id: ThreadId::All,
+                    }
+                ])
+            )
+        );
+    }
+
+    #[test]
+    fn parse_v_stopped() {
+        assert_eq!(v_packet(b"Stopped").unwrap(), (EMPTY, VPacket::Stopped));
+    }
+
+    #[test]
+    fn parse_v_flash_erase() {
+        assert_eq!(
+            v_packet(b"FlashErase:08000000,4000").unwrap(),
+            (
+                EMPTY,
+                VPacket::FlashErase {
+                    address: 0x0800_0000,
+                    length: 0x4000,
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn parse_v_flash_write() {
+        assert_eq!(
+            v_packet(b"FlashWrite:08000000:\x00\x01:#").unwrap(),
+            (
+                EMPTY,
+                VPacket::FlashWrite {
+                    address: 0x0800_0000,
+                    data: b"\x00\x01:#".to_vec(),
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn parse_v_flash_done() {
+        assert_eq!(v_packet(b"FlashDone").unwrap(), (EMPTY, VPacket::FlashDone));
+    }
 }
@@ -0,0 +1,173 @@
+use std::ops::Range;
+
+use probe_rs::{
+    config::{MemoryRegion, RamRegion, RawFlashAlgorithm},
+    Core, CoreDump, CoreType, Error, Session, Target,
+};
+
+/// The system debugged with GDB.
+///
+/// This is implemented for a [`Session`] with a connected target, and for a [`CoreDump`],
+/// which allows post-mortem debugging without hardware.
+pub trait DebugTarget {
+    /// Returns the index and type of each core.
+    fn list_cores(&self) -> Vec<(usize, CoreType)>;
+
+    /// Attaches to the core with index `n`.
+    fn core(&mut self, n: usize) -> Result<Core<'_>, Error>;
+
+    /// Returns the name of the core with index `n`.
+    fn core_name(&self, n: usize) -> String;
+
+    /// Returns the memory regions of the system.
+    fn memory_map(&self) -> Vec<MemoryRegion>;
+
+    /// Returns the flash memory of the system, as ranges of equally sized erase sectors,
+    /// together with the sector size.
+    ///
+    /// Only flash which can be programmed with a flash algorithm is returned.
+    fn flash_sectors(&self) -> Vec<(Range<u32>, u32)>;
+
+    /// Returns the session, if the target is connected to a probe.
+    ///
+    /// Flash programming is only possible with a session.
+    fn session_mut(&mut self) -> Option<&mut Session>;
+}
+
+impl DebugTarget for Session {
+    fn list_cores(&self) -> Vec<(usize, CoreType)> {
+        Session::list_cores(self)
+    }
+
+    fn core(&mut self, n: usize) -> Result<Core<'_>, Error> {
+        Session::core(self, n)
+    }
+
+    fn core_name(&self, n: usize) -> String {
+        self.target().cores[n].name.clone()
+    }
+
+    fn memory_map(&self) -> Vec<MemoryRegion> {
+        self.target().memory_map.clone()
+    }
+
+    fn flash_sectors(&self) -> Vec<(Range<u32>, u32)> {
+        flash_sectors(self.target())
+    }
+
+    fn session_mut(&mut self) -> Option<&mut Session> {
+        Some(self)
+    }
+}
+
+/// Returns the flash algorithm which programs `range`.
+///
+/// The default algorithm is preferred, if several algorithms can program the range.
+pub(crate) fn flash_algorithm<'a>(
+    target: &'a Target,
+    range: &Range<u32>,
+) -> Option<&'a RawFlashAlgorithm> {
+    target
+        .flash_algorithms
+        .iter()
+        .filter(|algorithm| {
+            let algorithm_range = &algorithm.flash_properties.address_range;
+            algorithm_range.start <= range.start && range.end <= algorithm_range.end
+        })
+        .max_by_key(|algorithm| algorithm.default)
+}
+
+/// Returns the flash of `target` which can be programmed with a flash algorithm, as ranges of
+/// equally sized erase sectors, together with the sector size.
+fn flash_sectors(target: &Target) -> Vec<(Range<u32>, u32)> {
+    let mut flash_sectors = Vec::new();
+
+    for region in &target.memory_map {
+        let region = match region {
+            MemoryRegion::Nvm(region) => region,
+            _ => continue,
+        };
+
+        let properties = match flash_algorithm(target, &region.range) {
+            Some(algorithm) => &algorithm.flash_properties,
+            None => continue,
+        };
+
+        // Each sector description applies up to the start of the next one.
+        for (index, sector) in properties.sectors.iter().enumerate() {
+            let start = properties.address_range.start + sector.address;
+            let end = properties
+                .sectors
+                .get(index + 1)
+                .map(|next_sector| properties.address_range.start + next_sector.address)
+                .unwrap_or(properties.address_range.end);
+
+            let start = start.max(region.range.start);
+            let end = end.min(region.range.end);
+
+            if start < end {
+                flash_sectors.push((start..end, sector.size));
+            }
+        }
+    }
+
+    flash_sectors
+}
+
+/// A core dump contains a single core, and the captured memory ranges.
+impl DebugTarget for CoreDump {
+    fn list_cores(&self) -> Vec<(usize, CoreType)> {
+        vec![(0, self.core_type())]
+    }
+
+    fn core(&mut self, n: usize) -> Result<Core<'_>, Error> {
+        match n {
+            0 => Ok(CoreDump::core(self)),
+            n => Err(Error::CoreNotFound(n)),
+        }
+    }
+
+    fn core_name(&self, _n: usize) -> String {
+        "core dump".to_string()
+    }
+
+    fn memory_map(&self) -> Vec<MemoryRegion> {
+        self.memory_ranges()
+            .map(|range| {
+                MemoryRegion::Ram(RamRegion {
+                    name: None,
+                    range,
+                    is_boot_memory: false,
+                    cores: Vec::new(),
+                })
+            })
+            .collect()
+    }
+
+    fn flash_sectors(&self) -> Vec<(Range<u32>, u32)> {
+        Vec::new()
+    }
+
+    fn session_mut(&mut self) -> Option<&mut Session> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::flash_sectors;
+
+    #[test]
+    fn flash_sectors_have_erase_sector_size() {
+        let target = probe_rs::config::get_target_by_name("STM32F411RETx").unwrap();
+
+        assert_eq!(
+            flash_sectors(&target),
+            vec![
+                (0x0800_0000..0x0801_0000, 0x4000),
+                (0x0801_0000..0x0802_0000, 0x10000),
+                (0x0802_0000..0x0808_0000, 0x20000),
+            ]
+        );
+    }
+}
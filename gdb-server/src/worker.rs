@@ -4,64 +4,166 @@ use futures::channel::mpsc;
 use futures::future::FutureExt;
 use futures::select;
 use gdb_protocol::packet::{CheckedPacket, Kind as PacketKind};
-use probe_rs::Session;
+use probe_rs::flashing::FlashLoader;
+use probe_rs::semihosting::{Semihosting, SemihostingStatus};
+use probe_rs::{Core, CoreStatus, HaltReason, WatchpointKind};
+use std::collections::{BTreeSet, HashMap, VecDeque};
 use std::convert::TryFrom;
+use std::ops::Range;
 use std::{sync::Mutex, time::Duration};
 
-use crate::parser::parse_packet;
+use crate::parser::v_packet::{Action, ThreadAction};
+use crate::parser::{parse_packet, BreakpointType, ThreadId};
 
 use crate::handlers;
+use crate::monitor::{MonitorCommands, TargetOutput};
+use crate::target::DebugTarget;
 
 type ServerResult<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 type Sender<T> = mpsc::UnboundedSender<T>;
 type Receiver<T> = mpsc::UnboundedReceiver<T>;
 
-pub async fn worker(
+/// State of a GDB connection, which is kept between packets.
+///
+/// Each core of the target is exposed to GDB as a separate thread.
+struct ConnectionState {
+    /// The number of cores of the target.
+    num_cores: usize,
+    /// The core selected with `Hg`, used for register and memory accesses.
+    general_core: usize,
+    /// The core selected with `Hc`, used by the `c` packet. `None` selects all cores.
+    continue_core: Option<usize>,
+    /// Cores which were resumed, and whose halt has not been reported to GDB yet.
+    running_cores: BTreeSet<usize>,
+    /// Whether GDB enabled non-stop mode using `QNonStop:1`.
+    non_stop: bool,
+    /// Whether a `Stop` notification was sent, which GDB has not acknowledged with `vStopped` yet.
+    notification_pending: bool,
+    /// Stop replies which are sent in response to `vStopped`.
+    queued_stop_replies: VecDeque<String>,
+    /// Data of `vFlashWrite` packets, which is programmed on `vFlashDone`.
+    flash_loader: Option<FlashLoader>,
+    /// Ranges erased with `vFlashErase`, which are erased on `vFlashDone`.
+    erased_flash: Vec<Range<u32>>,
+    /// RTT and SWO output, which is shown in the GDB console while the target runs.
+    target_output: TargetOutput,
+    /// Watchpoints inserted by GDB, by the address of the region they were rounded to.
+    /// Contains the address GDB watches, and the kind of the watchpoint.
+    watchpoints: HashMap<u32, (u32, WatchpointKind)>,
+}
+
+impl ConnectionState {
+    fn new(num_cores: usize) -> Self {
+        Self {
+            num_cores,
+            general_core: 0,
+            continue_core: None,
+            running_cores: BTreeSet::new(),
+            non_stop: false,
+            notification_pending: false,
+            queued_stop_replies: VecDeque::new(),
+            flash_loader: None,
+            erased_flash: Vec::new(),
+            target_output: TargetOutput::default(),
+            watchpoints: HashMap::new(),
+        }
+    }
+
+    /// Returns the index of the core for `thread_id`, or `None` if no such core exists.
+    ///
+    /// `ThreadId::Any` and `ThreadId::All` refer to the core selected for general operations.
+    fn core_index(&self, thread_id: ThreadId) -> Option<usize> {
+        match thread_id {
+            ThreadId::Id(id) => (id as usize)
+                .checked_sub(1)
+                .filter(|core_index| *core_index < self.num_cores),
+            ThreadId::Any | ThreadId::All => Some(self.general_core),
+        }
+    }
+
+    /// Returns the indices of all cores for `thread_id`.
+    fn core_indices(&self, thread_id: ThreadId) -> Vec<usize> {
+        match thread_id {
+            ThreadId::All => (0..self.num_cores).collect(),
+            thread_id => self.core_index(thread_id).into_iter().collect(),
+        }
+    }
+
+    /// Reports the stop of a core to GDB.
+    ///
+    /// In non-stop mode, only one `Stop` notification can be pending,
+    /// further stops are queued until GDB requests them with `vStopped`.
+    fn report_stop(&mut self, output_stream: &Sender<CheckedPacket>, stop_reply: String) {
+        if !self.non_stop {
+            send_packet(output_stream, PacketKind::Packet, stop_reply);
+        } else if self.notification_pending {
+            self.queued_stop_replies.push_back(stop_reply);
+        } else {
+            self.notification_pending = true;
+            send_packet(
+                output_stream,
+                PacketKind::Notification,
+                format!("Stop:{}", stop_reply),
+            );
+        }
+    }
+}
+
+pub async fn worker<T: DebugTarget>(
     mut input_stream: Receiver<CheckedPacket>,
     output_stream: Sender<CheckedPacket>,
-    session: &Mutex<Session>,
+    session: &Mutex<T>,
+    monitor_commands: &MonitorCommands<T>,
 ) -> ServerResult<()> {
-    // When we first attach to the core, GDB expects us to halt the core, so we do this here when a new client connects.
-    // If the core is already halted, nothing happens if we issue a halt command again, so we always do this no matter of core state.
-    session
-        .lock()
-        .unwrap()
-        .core(0)?
-        .halt(Duration::from_millis(100))?;
+    let mut state = {
+        let mut session = session.lock().unwrap();
+        let num_cores = session.list_cores().len();
+
+        // When we first attach to the target, GDB expects us to halt all cores, so we do this here when a new client connects.
+        // If a core is already halted, nothing happens if we issue a halt command again, so we always do this no matter of core state.
+        for core_index in 0..num_cores {
+            session.core(core_index)?.halt(Duration::from_millis(100))?;
+        }
 
-    let mut awaits_halt = false;
+        ConnectionState::new(num_cores)
+    };
+
+    let mut semihosting = Semihosting::captured();
 
     loop {
         select! {
             potential_packet = input_stream.next().fuse() => {
                 if let Some(packet) = potential_packet {
                     log::warn!("WORKING {}", String::from_utf8_lossy(&packet.data));
-                    if handler(session, &output_stream, &mut awaits_halt, packet).await? {
+                    let break_due =
+                        handler(session, monitor_commands, &output_stream, &mut state, packet).await?;
+                    if break_due {
                         break;
                     }
                 } else {
                     break
                 }
             },
-            _ = await_halt(session, &output_stream, &mut awaits_halt).fuse() => {}
+            _ = await_halt(session, &output_stream, &mut state, &mut semihosting).fuse() => {}
         }
     }
     Ok(())
 }
 
-pub async fn handler(
-    session: &Mutex<Session>,
+async fn handler<T: DebugTarget>(
+    session: &Mutex<T>,
+    monitor_commands: &MonitorCommands<T>,
     output_stream: &Sender<CheckedPacket>,
-    awaits_halt: &mut bool,
+    state: &mut ConnectionState,
     packet: CheckedPacket,
 ) -> ServerResult<bool> {
     let parsed_packet = parse_packet(&packet.data);
     let mut break_due = false;
 
-    use crate::parser::v_packet::Action;
-    use crate::parser::BreakpointType;
     use crate::parser::Packet::*;
     use crate::parser::QueryPacket;
+    use crate::parser::QuerySetPacket;
+    use crate::parser::ThreadOperation;
     use crate::parser::VPacket;
 
     let response: Option<String> = match parsed_packet {
@@ -69,80 +171,201 @@ pub async fn handler(
             log::debug!("Parsed packet: {:?}", parsed_packet);
             let mut session = session.lock().expect("Poisoned Mutex");
             match parsed_packet {
-                HaltReason => handlers::halt_reason(),
-                Continue => handlers::run(session.core(0)?, awaits_halt),
+                HaltReason => halt_reason(&mut *session, state)?,
+                Continue => {
+                    let core_indices = match state.continue_core {
+                        Some(core_index) => vec![core_index],
+                        None => (0..state.num_cores).collect(),
+                    };
+
+                    for core_index in core_indices {
+                        handlers::run(session.core(core_index)?, &mut state.running_cores);
+                    }
+
+                    None
+                }
                 V(VPacket::QueryContSupport) => handlers::vcont_supported(),
                 Query(QueryPacket::Supported { .. }) => handlers::q_supported(),
                 Query(QueryPacket::Attached { .. }) => handlers::q_attached(),
-                Query(QueryPacket::Command(cmd)) => {
-                    if cmd == b"reset" {
-                        handlers::reset_halt(session.core(0)?)
-                    } else {
-                        log::debug!("Unknown monitor command: '{:?}'", cmd);
-                        Some(hex::encode(
-                            "Unknown monitor command\nOnly 'reset' is currently supported\n"
-                                .as_bytes(),
-                        ))
+                Query(QueryPacket::ThreadId) => handlers::current_thread(state.general_core),
+                Query(QueryPacket::FirstThreadInfo) => handlers::thread_info(state.num_cores),
+                Query(QueryPacket::SubsequentThreadInfo) => handlers::thread_info_end(),
+                Query(QueryPacket::ThreadExtraInfo(thread_id)) => {
+                    match state.core_index(thread_id) {
+                        Some(core_index) => handlers::thread_extra_info(&*session, core_index),
+                        None => handlers::unknown_thread(),
                     }
                 }
+                Query(QueryPacket::Command(cmd)) => {
+                    let output = monitor_commands.execute(
+                        &mut *session,
+                        state.general_core,
+                        &mut state.target_output,
+                        &String::from_utf8_lossy(&cmd),
+                    );
+
+                    // The output is sent with `O` packets, so it is shown in the GDB console as it is.
+                    send_console_output(output_stream, output.as_bytes());
+
+                    handlers::reply_ok()
+                }
                 Query(QueryPacket::HostInfo) => handlers::host_info(),
-                ReadGeneralRegister => handlers::read_general_registers(session.core(0)?),
-                ReadRegisterHex(register) => handlers::read_register(register, session.core(0)?),
-                WriteGeneralRegister { reg_values } => {
-                    handlers::write_general_registers(&reg_values, session.core(0)?)
+                Query(QueryPacket::Crc { address, length }) => {
+                    handlers::crc(address, length, session.core(state.general_core)?)
+                }
+                QuerySet(QuerySetPacket::NonStop(enabled)) => {
+                    state.non_stop = enabled;
+                    handlers::reply_ok()
                 }
+                SelectThread {
+                    operation,
+                    thread_id,
+                } => match (operation, thread_id) {
+                    (ThreadOperation::Continue, ThreadId::All | ThreadId::Any) => {
+                        state.continue_core = None;
+                        handlers::reply_ok()
+                    }
+                    (operation, thread_id) => match state.core_index(thread_id) {
+                        Some(core_index) => {
+                            match operation {
+                                ThreadOperation::General => state.general_core = core_index,
+                                ThreadOperation::Continue => state.continue_core = Some(core_index),
+                            }
+                            handlers::reply_ok()
+                        }
+                        None => handlers::unknown_thread(),
+                    },
+                },
+                ThreadAlive(thread_id) => match state.core_index(thread_id) {
+                    Some(_) => handlers::reply_ok(),
+                    None => handlers::unknown_thread(),
+                },
+                ReadGeneralRegister => {
+                    handlers::read_general_registers(session.core(state.general_core)?)
+                }
+                ReadRegisterHex(register) => {
+                    handlers::read_register(register, session.core(state.general_core)?)
+                }
+                WriteGeneralRegister { reg_values } => handlers::write_general_registers(
+                    &reg_values,
+                    session.core(state.general_core)?,
+                ),
                 WriteRegisterHex { address, value } => {
-                    handlers::write_register(address, &value, session.core(0)?)
+                    handlers::write_register(address, &value, session.core(state.general_core)?)
                 }
                 ReadMemory { address, length } => {
                     // LLDB will send 64 bit addresses, which are not supported by probe-rs
                     // yet.
 
                     if let Ok(address) = u32::try_from(address) {
-                        handlers::read_memory(address, length, session.core(0)?)
+                        handlers::read_memory(address, length, session.core(state.general_core)?)
                     } else {
                         //
                         handlers::reply_empty()
                     }
                 }
                 Detach => handlers::detach(&mut break_due),
-                V(VPacket::Continue(action)) => match action {
-                    Action::Continue => handlers::run(session.core(0)?, awaits_halt),
-                    Action::Stop => handlers::stop(session.core(0)?, awaits_halt),
-                    Action::Step => handlers::step(session.core(0)?, awaits_halt),
-                    other => {
-                        log::warn!("vCont with action {:?} not supported", other);
-                        handlers::reply_empty()
+                V(VPacket::Continue(actions)) => {
+                    v_cont(&mut *session, state, output_stream, actions)?
+                }
+                V(VPacket::Stopped) => match state.queued_stop_replies.pop_front() {
+                    Some(stop_reply) => Some(stop_reply),
+                    None => {
+                        state.notification_pending = false;
+                        handlers::reply_ok()
                     }
                 },
+                V(VPacket::FlashErase { address, length }) => handlers::flash_erase(
+                    &mut *session,
+                    &mut state.flash_loader,
+                    &mut state.erased_flash,
+                    address,
+                    length,
+                ),
+                V(VPacket::FlashWrite { address, data }) => {
+                    handlers::flash_write(&mut *session, &mut state.flash_loader, address, &data)
+                }
+                V(VPacket::FlashDone) => handlers::flash_done(
+                    &mut *session,
+                    &mut state.flash_loader,
+                    &mut state.erased_flash,
+                ),
                 InsertBreakpoint {
                     breakpoint_type,
                     address,
                     kind,
-                } => match breakpoint_type {
-                    BreakpointType::Hardware => {
-                        handlers::insert_hardware_break(address, kind, session.core(0)?)
+                } => {
+                    // Breakpoints apply to all threads in GDB, so they are set on every core.
+                    let mut response = handlers::reply_ok();
+                    let mut inserted_cores = 0;
+                    for core_index in 0..state.num_cores {
+                        response = insert_breakpoint(
+                            &breakpoint_type,
+                            address,
+                            kind,
+                            session.core(core_index)?,
+                        );
+
+                        if response != handlers::reply_ok() {
+                            break;
+                        }
+                        inserted_cores += 1;
                     }
-                    other => {
-                        log::warn!("Breakpoint type {:?} is not supported.", other);
-                        handlers::reply_empty()
+
+                    if response == handlers::reply_ok() {
+                        if let Some(watchpoint_kind) = watchpoint_kind(&breakpoint_type) {
+                            let (region_address, _) = handlers::watchpoint_region(address, kind);
+                            state
+                                .watchpoints
+                                .insert(region_address, (address, watchpoint_kind));
+                        }
+                    } else {
+                        // GDB considers the breakpoint not inserted, so it must not stay on the cores it was set on before.
+                        for core_index in 0..inserted_cores {
+                            match session.core(core_index) {
+                                Ok(core) => {
+                                    remove_breakpoint(&breakpoint_type, address, kind, core);
+                                }
+                                Err(error) => log::warn!(
+                                    "Unable to remove the breakpoint at {:#010x} from core {}: {}",
+                                    address,
+                                    core_index,
+                                    error
+                                ),
+                            }
+                        }
                     }
-                },
+
+                    response
+                }
                 RemoveBreakpoint {
                     breakpoint_type,
                     address,
                     kind,
-                } => match breakpoint_type {
-                    BreakpointType::Hardware => {
-                        handlers::remove_hardware_break(address, kind, session.core(0)?)
+                } => {
+                    let mut response = handlers::reply_ok();
+                    for core_index in 0..state.num_cores {
+                        response = remove_breakpoint(
+                            &breakpoint_type,
+                            address,
+                            kind,
+                            session.core(core_index)?,
+                        );
+
+                        if response != handlers::reply_ok() {
+                            break;
+                        }
                     }
-                    other => {
-                        log::warn!("Breakpoint type {:?} is not supported.", other);
-                        handlers::reply_empty()
+
+                    if watchpoint_kind(&breakpoint_type).is_some() {
+                        let (region_address, _) = handlers::watchpoint_region(address, kind);
+                        state.watchpoints.remove(&region_address);
                     }
-                },
+
+                    response
+                }
                 WriteMemoryBinary { address, data } => {
-                    handlers::write_memory(address, &data, session.core(0)?)
+                    handlers::write_memory(address, &data, session.core(state.general_core)?)
                 }
                 Query(QueryPacket::Transfer { object, operation }) => {
                     use crate::parser::query::TransferOperation;
@@ -150,8 +373,8 @@ pub async fn handler(
                     match object.as_slice() {
                         b"memory-map" => {
                             match operation {
-                                TransferOperation::Read { .. } => {
-                                    handlers::get_memory_map(&session)
+                                TransferOperation::Read { offset, length, .. } => {
+                                    handlers::get_memory_map(&*session, offset, length)
                                 }
                                 TransferOperation::Write { .. } => {
                                     // not supported
@@ -162,7 +385,11 @@ pub async fn handler(
                         b"features" => {
                             match operation {
                                 TransferOperation::Read { annex, .. } => {
-                                    handlers::read_target_description(&session, &annex)
+                                    handlers::read_target_description(
+                                        &*session,
+                                        state.general_core,
+                                        &annex,
+                                    )
                                 }
                                 TransferOperation::Write { .. } => {
                                     // not supported
@@ -176,7 +403,21 @@ pub async fn handler(
                         }
                     }
                 }
-                Interrupt => handlers::user_halt(session.core(0)?, awaits_halt),
+                Interrupt => {
+                    // GDB expects all cores to stop, and the stop to be reported for the selected core.
+                    let mut response = None;
+                    for core_index in 0..state.num_cores {
+                        let stop_reply = handlers::user_halt(
+                            session.core(core_index)?,
+                            &mut state.running_cores,
+                        );
+
+                        if core_index == state.general_core {
+                            response = stop_reply;
+                        }
+                    }
+                    response
+                }
                 other => {
                     log::warn!("Unknown command: '{:?}'", other);
 
@@ -211,24 +452,254 @@ pub async fn handler(
     Ok(break_due)
 }
 
-pub async fn await_halt(
-    session: &Mutex<Session>,
+/// Answers the `?` packet.
+///
+/// In non-stop mode, a stop reply is sent for every halted core.
+/// The first one is the response, the others are requested by GDB with `vStopped`.
+fn halt_reason<T: DebugTarget>(
+    session: &mut T,
+    state: &mut ConnectionState,
+) -> ServerResult<Option<String>> {
+    if !state.non_stop {
+        return Ok(handlers::halt_reason(state.general_core));
+    }
+
+    state.queued_stop_replies.clear();
+
+    for core_index in 0..state.num_cores {
+        if session.core(core_index)?.core_halted()? {
+            state
+                .queued_stop_replies
+                .push_back(handlers::stop_reply(core_index, 5));
+        }
+    }
+
+    Ok(match state.queued_stop_replies.pop_front() {
+        Some(stop_reply) => Some(stop_reply),
+        None => handlers::reply_ok(),
+    })
+}
+
+/// Executes the actions of a `vCont` packet.
+///
+/// Each core is handled by the first action which applies to it.
+fn v_cont<T: DebugTarget>(
+    session: &mut T,
+    state: &mut ConnectionState,
     output_stream: &Sender<CheckedPacket>,
-    await_halt: &mut bool,
+    actions: Vec<ThreadAction>,
+) -> ServerResult<Option<String>> {
+    let mut handled_cores = BTreeSet::new();
+    let mut resumed_cores = Vec::new();
+    let mut stop_replies = Vec::new();
+
+    for ThreadAction { action, thread_id } in actions {
+        for core_index in state.core_indices(thread_id) {
+            if !handled_cores.insert(core_index) {
+                continue;
+            }
+
+            match &action {
+                Action::Continue => resumed_cores.push(core_index),
+                Action::Step => {
+                    if let Some(stop_reply) =
+                        handlers::step(session.core(core_index)?, &mut state.running_cores)
+                    {
+                        stop_replies.push(stop_reply);
+                    }
+                }
+                Action::Stop => {
+                    // Only cores which are running report a stop.
+                    if state.running_cores.contains(&core_index) {
+                        handlers::stop(session.core(core_index)?, &mut state.running_cores);
+                        stop_replies.push(handlers::stop_reply(core_index, 0));
+                    }
+                }
+                other => {
+                    log::warn!("vCont with action {:?} not supported", other);
+                }
+            }
+        }
+    }
+
+    if !state.non_stop {
+        // In all-stop mode, all cores stop together. A step is reported right away,
+        // so the other cores are not resumed in that case.
+        if let Some(stop_reply) = stop_replies.into_iter().next() {
+            return Ok(Some(stop_reply));
+        }
+
+        for core_index in resumed_cores {
+            handlers::run(session.core(core_index)?, &mut state.running_cores);
+        }
+
+        return Ok(None);
+    }
+
+    for core_index in resumed_cores {
+        handlers::run(session.core(core_index)?, &mut state.running_cores);
+    }
+
+    // In non-stop mode, the `OK` reply has to be sent before any stop is reported.
+    send_packet(output_stream, PacketKind::Packet, "OK".into());
+
+    for stop_reply in stop_replies {
+        state.report_stop(output_stream, stop_reply);
+    }
+
+    Ok(None)
+}
+
+fn insert_breakpoint(
+    breakpoint_type: &BreakpointType,
+    address: u32,
+    kind: u32,
+    core: Core,
+) -> Option<String> {
+    if let Some(watchpoint_kind) = watchpoint_kind(breakpoint_type) {
+        return handlers::insert_watchpoint(address, kind, watchpoint_kind, core);
+    }
+
+    match breakpoint_type {
+        BreakpointType::Hardware => handlers::insert_hardware_break(address, kind, core),
+        other => {
+            log::warn!("Breakpoint type {:?} is not supported.", other);
+            handlers::reply_empty()
+        }
+    }
+}
+
+/// Returns the kind of watchpoint for `breakpoint_type`, or `None` if it is a breakpoint.
+fn watchpoint_kind(breakpoint_type: &BreakpointType) -> Option<WatchpointKind> {
+    match breakpoint_type {
+        BreakpointType::WriteWatchpoint => Some(WatchpointKind::Write),
+        BreakpointType::ReadWatchpoint => Some(WatchpointKind::Read),
+        BreakpointType::AccessWatchpoint => Some(WatchpointKind::ReadWrite),
+        _ => None,
+    }
+}
+
+fn remove_breakpoint(
+    breakpoint_type: &BreakpointType,
+    address: u32,
+    kind: u32,
+    core: Core,
+) -> Option<String> {
+    match breakpoint_type {
+        BreakpointType::Hardware => handlers::remove_hardware_break(address, kind, core),
+        BreakpointType::WriteWatchpoint
+        | BreakpointType::ReadWatchpoint
+        | BreakpointType::AccessWatchpoint => handlers::remove_watchpoint(address, kind, core),
+        other => {
+            log::warn!("Breakpoint type {:?} is not supported.", other);
+            handlers::reply_empty()
+        }
+    }
+}
+
+async fn await_halt<T: DebugTarget>(
+    session: &Mutex<T>,
+    output_stream: &Sender<CheckedPacket>,
+    state: &mut ConnectionState,
+    semihosting: &mut Semihosting,
 ) -> ServerResult<()> {
     task::sleep(Duration::from_millis(10)).await;
-    if *await_halt {
-        let mut session = session.lock().expect("Poisoned Mutex");
-        if session.core(0)?.core_halted().unwrap() {
-            let response = CheckedPacket::from_data(PacketKind::Packet, b"T05hwbreak:;".to_vec());
 
-            let mut bytes = Vec::new();
-            response.encode(&mut bytes).unwrap();
-            *await_halt = false;
+    if state.running_cores.is_empty() {
+        return Ok(());
+    }
+
+    let mut session = session.lock().expect("Poisoned Mutex");
+
+    let target_output = state.target_output.poll(&mut *session);
+    if state.non_stop {
+        if !target_output.is_empty() {
+            log::info!("{}", String::from_utf8_lossy(&target_output));
+        }
+    } else {
+        send_console_output(output_stream, &target_output);
+    }
+
+    for core_index in state.running_cores.clone() {
+        let mut core = session.core(core_index)?;
+
+        if !core.core_halted()? {
+            continue;
+        }
+
+        // Semihosting requests are serviced without telling GDB about the halt.
+        let semihosting_status = semihosting.handle(&mut core)?;
+
+        let console_output = semihosting.take_console_output();
+        if state.non_stop {
+            // `O` packets are not allowed in non-stop mode, so the output is logged instead.
+            if !console_output.is_empty() {
+                log::info!("{}", String::from_utf8_lossy(&console_output));
+            }
+        } else {
+            send_console_output(output_stream, &console_output);
+        }
+
+        let stop_reply = match semihosting_status {
+            SemihostingStatus::Handled => continue,
+            SemihostingStatus::Exit(exit_code) => format!("W{:02x}", exit_code as u8),
+            SemihostingStatus::NoRequest => {
+                // Report the address GDB watches and the kind of a triggered watchpoint,
+                // so GDB can show the accessed variable.
+                let reason = match core.status()? {
+                    CoreStatus::Halted(HaltReason::Watchpoint(Some(address))) => {
+                        match state.watchpoints.get(&address) {
+                            Some((gdb_address, kind)) => {
+                                handlers::watchpoint_stop_reason(*kind, *gdb_address)
+                            }
+                            None => {
+                                handlers::watchpoint_stop_reason(WatchpointKind::Write, address)
+                            }
+                        }
+                    }
+                    _ => "hwbreak:;".to_string(),
+                };
+
+                format!("{}{}", handlers::stop_reply(core_index, 5), reason)
+            }
+        };
+
+        state.running_cores.remove(&core_index);
+
+        if state.non_stop {
+            state.report_stop(output_stream, stop_reply);
+        } else {
+            // In all-stop mode, the other cores are halted as well before the stop is reported.
+            for other_core_index in std::mem::take(&mut state.running_cores) {
+                session
+                    .core(other_core_index)?
+                    .halt(Duration::from_millis(100))?;
+            }
+
+            state.report_stop(output_stream, stop_reply);
 
-            let _ = output_stream.unbounded_send(response);
+            break;
         }
     }
 
     Ok(())
 }
+
+fn send_packet(output_stream: &Sender<CheckedPacket>, kind: PacketKind, data: String) {
+    let _ = output_stream.unbounded_send(CheckedPacket::from_data(kind, data.into_bytes()));
+}
+
+/// Sends output of the target to the GDB console, using `O` packets.
+///
+/// These packets are only allowed while GDB waits for the target to stop.
+fn send_console_output(output_stream: &Sender<CheckedPacket>, output: &[u8]) {
+    // Each byte is hex encoded, so this keeps the packets below the announced packet size.
+    for chunk in output.chunks(1000) {
+        let response = CheckedPacket::from_data(
+            PacketKind::Packet,
+            format!("O{}", hex::encode(chunk)).into_bytes(),
+        );
+
+        let _ = output_stream.unbounded_send(response);
+    }
+}
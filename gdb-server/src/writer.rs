@@ -17,6 +17,11 @@ pub async fn writer(
     encode(&packet, stream).await?;
     stream.flush().await?;
 
+    // Notifications are not acknowledged by GDB.
+    if let PacketKind::Notification = packet.kind {
+        return Ok(());
+    }
+
     log::debug!("Request ACK for {}", String::from_utf8_lossy(&packet.data));
     'ack: loop {
         log::debug!("Reading");
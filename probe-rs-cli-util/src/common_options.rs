@@ -85,6 +85,11 @@ pub struct FlashOptions {
         help = "Enable this flag to restore all bytes erased in the sector erase but not overwritten by any page."
     )]
     pub restore_unwritten: bool,
+    #[structopt(
+        long = "skip-unchanged",
+        help = "Enable this flag to compare the flash with the new contents first, and skip the sectors which are unchanged."
+    )]
+    pub skip_unchanged: bool,
     #[structopt(
         name = "filename",
         long = "flash-layout",
@@ -29,6 +29,7 @@ pub fn run_flash_download(
     download_option.dry_run = opt.probe_options.dry_run;
     download_option.do_chip_erase = do_chip_erase;
     download_option.disable_double_buffering = opt.disable_double_buffering;
+    download_option.skip_unchanged_sectors = opt.skip_unchanged;
 
     if !opt.disable_progressbars {
         // Create progress bars.
@@ -100,10 +101,10 @@ pub fn run_flash_download(
                         fp.reset_elapsed()
                     };
                 }
-                PageProgrammed { size, .. } => {
+                PageProgrammed { size, .. } | PageSkipped { size } => {
                     program_progress.inc(size as u64);
                 }
-                SectorErased { size, .. } => {
+                SectorErased { size, .. } | SectorSkipped { size } => {
                     erase_progress.inc(size as u64);
                 }
                 PageFilled { size, .. } => {
@@ -1,48 +1,326 @@
 use crate::*;
 use anyhow::{anyhow, Result};
-use chrono::Local;
-use num_traits::Zero;
+use chrono::{DateTime, Local};
 use probe_rs::config::MemoryRegion;
-use probe_rs::Core;
-use probe_rs_rtt::{DownChannel, Rtt, ScanRegion, UpChannel};
+use probe_rs::{Core, Session};
+use probe_rs_rtt::{
+    ChannelMode, DownChannel, Rtt, RttData, RttStream, RttStreamBuilder, ScanRegion, UpChannel,
+};
+pub use probe_rs_rtt::{RttServer, RttServerAddress};
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs::File;
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{Arc, Mutex, TryLockError};
 use std::{
-    fmt,
     fmt::Write,
     fs,
     io::{Read, Seek},
     str::FromStr,
+    thread,
+    time::{Duration, Instant},
 };
 
+/// Attaches to RTT on `core`, and starts streaming its channels.
+///
+/// The channels are streamed on a thread, which accesses the core through `session`, so `core` has to belong to it.
 pub fn attach_to_rtt(
+    session: &Arc<Mutex<Session>>,
     core: &mut Core,
     memory_map: &[MemoryRegion],
     elf_file: &Path,
     rtt_config: &RttConfig,
 ) -> Result<crate::rtt::RttActiveTarget, anyhow::Error> {
     log::info!("Initializing RTT");
-    let rtt_header_address = if let Ok(mut file) = File::open(elf_file) {
-        if let Some(address) = RttActiveTarget::get_rtt_symbol(&mut file) {
-            ScanRegion::Exact(address as u32)
-        } else {
-            ScanRegion::Ram
-        }
-    } else {
-        ScanRegion::Ram
-    };
+    let rtt_header_address = rtt_scan_region(elf_file);
+
+    attach_to_rtt_region(
+        session,
+        core,
+        memory_map,
+        &rtt_header_address,
+        elf_file,
+        rtt_config,
+    )
+}
 
-    match Rtt::attach_region(core, memory_map, &rtt_header_address) {
+fn attach_to_rtt_region(
+    session: &Arc<Mutex<Session>>,
+    core: &mut Core,
+    memory_map: &[MemoryRegion],
+    rtt_header_address: &ScanRegion,
+    elf_file: &Path,
+    rtt_config: &RttConfig,
+) -> Result<RttActiveTarget, anyhow::Error> {
+    match Rtt::attach_region(core, memory_map, rtt_header_address) {
         Ok(rtt) => {
             log::info!("RTT initialized.");
-            let app = RttActiveTarget::new(rtt, elf_file, rtt_config)?;
+            let mut app = RttActiveTarget::new(rtt, session, core.id(), elf_file, rtt_config)?;
+            if let Some(block_if_full_ms) = rtt_config.block_if_full_ms {
+                app.block_if_full(core, Duration::from_millis(block_if_full_ms));
+            }
             Ok(app)
         }
         Err(err) => Err(anyhow!("Error attempting to attach to RTT: {}", err)),
     }
 }
 
+/// Attaches to RTT as soon as the target has initialized the control block, and attaches again when the control block is lost,
+/// e.g. because the target was reset. Failed attempts are repeated with an increasing delay, see [`RttBackoff`].
+#[derive(Debug)]
+pub struct RttAutoAttach {
+    session: Arc<Mutex<Session>>,
+    memory_map: Vec<MemoryRegion>,
+    rtt_header_address: ScanRegion,
+    elf_file: PathBuf,
+    rtt_config: RttConfig,
+    target: Option<RttActiveTarget>,
+    backoff: RttBackoff,
+}
+
+impl RttAutoAttach {
+    /// The channels are streamed through `session`, which the cores passed to [`RttAutoAttach::poll`] have to belong to.
+    pub fn new(
+        session: &Arc<Mutex<Session>>,
+        memory_map: &[MemoryRegion],
+        elf_file: &Path,
+        rtt_config: &RttConfig,
+    ) -> Self {
+        Self {
+            session: session.clone(),
+            memory_map: memory_map.to_vec(),
+            rtt_header_address: rtt_scan_region(elf_file),
+            elf_file: elf_file.to_path_buf(),
+            rtt_config: rtt_config.clone(),
+            target: None,
+            backoff: RttBackoff::default(),
+        }
+    }
+
+    /// Attaches to RTT if necessary, and returns the attached target, if any.
+    pub fn poll(&mut self, core: &mut Core) -> Option<&mut RttActiveTarget> {
+        self.poll_attach(core).map(|(target, _)| target)
+    }
+
+    /// Like [`RttAutoAttach::poll`], but also returns `true` if the target was attached in this call,
+    /// e.g. to check the channels of a new control block.
+    pub fn poll_attach(&mut self, core: &mut Core) -> Option<(&mut RttActiveTarget, bool)> {
+        let lost = self
+            .target
+            .as_mut()
+            .map_or(false, |target| target.control_block_lost(core));
+        if lost {
+            log::info!("The RTT control block was lost, probably because the target was reset. Attaching again...");
+            self.target = None;
+            self.backoff.reset();
+        }
+
+        let mut attached = false;
+        if self.target.is_none() && self.backoff.attempt_due() {
+            match attach_to_rtt_region(
+                &self.session,
+                core,
+                &self.memory_map,
+                &self.rtt_header_address,
+                &self.elf_file,
+                &self.rtt_config,
+            ) {
+                Ok(target) => {
+                    self.target = Some(target);
+                    self.backoff.reset();
+                    attached = true;
+                }
+                Err(error) => {
+                    log::debug!("{:?} Trying again in {:?}...", error, self.backoff.delay);
+                    self.backoff.failed();
+                }
+            }
+        }
+
+        self.target.as_mut().map(|target| (target, attached))
+    }
+
+    /// Detaches from RTT, see [`RttActiveTarget::detach`].
+    pub fn detach(&mut self, core: &mut Core) {
+        if let Some(target) = self.target.take() {
+            target.detach(core);
+        }
+    }
+}
+
+/// The delay between attempts to attach to RTT, which doubles after each failed attempt, up to [`RttBackoff::MAX_DELAY`].
+#[derive(Debug)]
+pub struct RttBackoff {
+    delay: Duration,
+    next_attempt: Instant,
+}
+
+impl RttBackoff {
+    const INITIAL_DELAY: Duration = Duration::from_millis(10);
+    pub const MAX_DELAY: Duration = Duration::from_secs(1);
+
+    /// Returns `true` if the next attempt is due.
+    pub fn attempt_due(&self) -> bool {
+        Instant::now() >= self.next_attempt
+    }
+
+    /// Delays the next attempt after a failed one.
+    pub fn failed(&mut self) {
+        self.next_attempt = Instant::now() + self.delay;
+        self.delay = (self.delay * 2).min(Self::MAX_DELAY);
+    }
+
+    /// Allows an immediate attempt, e.g. after the target was reset.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Default for RttBackoff {
+    fn default() -> Self {
+        Self {
+            delay: Self::INITIAL_DELAY,
+            next_attempt: Instant::now(),
+        }
+    }
+}
+
+/// Serves all RTT channels on sockets at `address`, instead of processing their data.
+///
+/// Like [`RttAutoAttach`], it attaches as soon as the target has initialized the control block, and attaches again when
+/// the control block is lost. The sockets are kept, so clients stay connected while the target is reset.
+#[derive(Debug)]
+pub struct RttServerAutoAttach {
+    memory_map: Vec<MemoryRegion>,
+    rtt_header_address: ScanRegion,
+    address: RttServerAddress,
+    server: Option<RttServer>,
+    /// The control block whose channels are served, if it is still present.
+    control_block: Option<ControlBlockWatch>,
+    backoff: RttBackoff,
+}
+
+impl RttServerAutoAttach {
+    pub fn new(memory_map: &[MemoryRegion], elf_file: &Path, address: &RttServerAddress) -> Self {
+        Self {
+            memory_map: memory_map.to_vec(),
+            rtt_header_address: rtt_scan_region(elf_file),
+            address: address.clone(),
+            server: None,
+            control_block: None,
+            backoff: RttBackoff::default(),
+        }
+    }
+
+    /// Attaches to RTT if necessary, and transfers the available data between the channels and the clients.
+    ///
+    /// Returns `true` if any data was transferred. Errors while accessing the channels are logged, only failing to
+    /// create the sockets is returned as an error.
+    pub fn poll(&mut self, core: &mut Core) -> Result<bool> {
+        let lost = self
+            .control_block
+            .as_mut()
+            .map_or(false, |control_block| control_block.lost(core));
+        if lost {
+            log::info!("The RTT control block was lost, probably because the target was reset. Attaching again...");
+            self.control_block = None;
+            self.backoff.reset();
+        }
+
+        if self.control_block.is_none() && self.backoff.attempt_due() {
+            match Rtt::attach_region(core, &self.memory_map, &self.rtt_header_address) {
+                Ok(mut rtt) => {
+                    log::info!("RTT initialized.");
+                    match &mut self.server {
+                        Some(server) => server.replace_channels(&mut rtt)?,
+                        None => self.server = Some(RttServer::new(&mut rtt, &self.address)?),
+                    }
+
+                    if let Some(server) = &self.server {
+                        for (number, name, address) in server.channels() {
+                            log::info!(
+                                "RTT channel {} ({}) is served on {}",
+                                number,
+                                name.unwrap_or("no name"),
+                                address
+                            );
+                        }
+                    }
+
+                    self.control_block = Some(ControlBlockWatch::new(rtt));
+                    self.backoff.reset();
+                }
+                Err(error) => {
+                    log::debug!(
+                        "Error attempting to attach to RTT: {} Trying again in {:?}...",
+                        error,
+                        self.backoff.delay
+                    );
+                    self.backoff.failed();
+                }
+            }
+        }
+
+        // The channels of a lost control block must not be accessed.
+        match (&mut self.server, &self.control_block) {
+            (Some(server), Some(_)) => match server.poll(core) {
+                Ok(transferred) => Ok(transferred),
+                Err(error) => {
+                    log::error!("Error serving RTT: {}", error);
+                    Ok(false)
+                }
+            },
+            _ => Ok(false),
+        }
+    }
+}
+
+/// Detects when an RTT control block is lost, e.g. because the target was reset.
+#[derive(Debug)]
+struct ControlBlockWatch {
+    /// The control block, whose channels may have been moved elsewhere.
+    rtt: Rtt,
+    last_validation: Instant,
+}
+
+impl ControlBlockWatch {
+    fn new(rtt: Rtt) -> Self {
+        Self {
+            rtt,
+            last_validation: Instant::now(),
+        }
+    }
+
+    /// Returns `true` if the control block was lost.
+    ///
+    /// To limit the load on the probe, the control block is only checked every [`RttActiveTarget::VALIDATION_INTERVAL`].
+    fn lost(&mut self, core: &mut Core) -> bool {
+        if self.last_validation.elapsed() < RttActiveTarget::VALIDATION_INTERVAL {
+            return false;
+        }
+        self.last_validation = Instant::now();
+
+        match self.rtt.is_control_block_present(core) {
+            Ok(present) => !present,
+            Err(error) => {
+                log::debug!("Unable to check the RTT control block: {}", error);
+                false
+            }
+        }
+    }
+}
+
+/// Returns the location of the RTT control block, using the `_SEGGER_RTT` symbol of the ELF file if possible.
+fn rtt_scan_region(elf_file: &Path) -> ScanRegion {
+    if let Ok(mut file) = File::open(elf_file) {
+        if let Some(address) = RttActiveTarget::get_rtt_symbol(&mut file) {
+            return ScanRegion::Exact(address as u32);
+        }
+    }
+
+    ScanRegion::Ram
+}
+
 /// Used by serde to provide defaults for `RttConfig`
 fn default_channel_formats() -> Vec<RttChannelConfig> {
     vec![]
@@ -84,6 +362,14 @@ pub struct RttConfig {
     #[structopt(skip)]
     #[serde(default = "default_channel_formats", rename = "rttChannelFormats")]
     pub channels: Vec<RttChannelConfig>,
+    /// Switch the up channels to `BlockIfFull` mode for this many milliseconds after attaching, so the logs of the target are not dropped while it boots.
+    #[structopt(skip)]
+    #[serde(default, rename = "rttBlockIfFullMs")]
+    pub block_if_full_ms: Option<u64>,
+    /// The down channel which receives input from the host. If not specified, down channel 0 is used.
+    #[structopt(skip)]
+    #[serde(default, rename = "rttDownChannel")]
+    pub down_channel: Option<usize>,
 }
 
 /// The User specified configuration for each active RTT Channel. The configuration is passed via a DAP Client configuration (`launch.json`). If no configuration is specified, the defaults will be `Dataformat::String` and `show_timestamps=false`.
@@ -99,16 +385,17 @@ pub struct RttChannelConfig {
     pub show_timestamps: bool,
 }
 
-/// This is the primary interface through which RTT channel data is read and written. Every actual RTT channel has a configuration and buffer that is used for this purpose.
+/// This is the primary interface through which RTT channel data is read and written. Every actual RTT channel has a configuration and a connection to the stream of its `RttActiveTarget`, which transfers the data.
 #[derive(Debug)]
 pub struct RttActiveChannel {
     pub up_channel: Option<UpChannel>,
     pub down_channel: Option<DownChannel>,
     pub channel_name: String,
     pub data_format: DataFormat,
-    /// Data that will be written to the down_channel (host to target)
-    _input_data: String,
-    rtt_buffer: RttBuffer,
+    /// The data read from the `UpChannel` by the stream.
+    up_data: Option<Receiver<RttData>>,
+    /// Data that will be written to the `DownChannel` by the stream.
+    down_data: Option<Sender<Vec<u8>>>,
     show_timestamps: bool,
 }
 
@@ -135,11 +422,6 @@ impl RttActiveChannel {
             })
             .or_else(|| full_config.clone().channel_name)
             .unwrap_or_else(|| "Unnamed RTT channel".to_string());
-        let buffer_size: usize = up_channel
-            .as_ref()
-            .map(|up| up.buffer_size())
-            .or_else(|| down_channel.as_ref().map(|down| down.buffer_size()))
-            .unwrap_or(1024); // If no explicit config is requested, assign a default
         let defmt_enabled: bool = up_channel
             .as_ref()
             .map(|up| up.name() == Some("defmt"))
@@ -159,8 +441,8 @@ impl RttActiveChannel {
             down_channel,
             channel_name: name,
             data_format,
-            _input_data: String::new(),
-            rtt_buffer: RttBuffer::new(buffer_size),
+            up_data: None,
+            down_data: None,
             show_timestamps: full_config.show_timestamps,
         }
     }
@@ -170,114 +452,128 @@ impl RttActiveChannel {
         self.up_channel.as_ref().map(|uc| uc.number())
     }
 
-    /// Polls the RTT target for new data on the channel represented by `self`.
-    /// Processes all the new data into the channel internal buffer and returns the number of bytes that was read.
-    pub fn poll_rtt(&mut self, core: &mut Core) -> Option<usize> {
-        if let Some(channel) = self.up_channel.as_mut() {
-            // Retry loop, in case the probe is temporarily unavailable, e.g. user pressed the `reset` button.
-            for _loop_count in 0..10 {
-                match channel.read(core, self.rtt_buffer.0.as_mut()) {
-                    Ok(count) => {
-                        if count.is_zero() {
-                            return None;
-                        } else {
-                            return Some(count);
-                        }
-                    }
-                    Err(err) => {
-                        if matches!(err, probe_rs_rtt::Error::Probe(_)) {
-                            std::thread::sleep(std::time::Duration::from_millis(50));
-                        } else {
-                            log::error!("\nError reading from RTT: {}", err);
-                            return None;
-                        }
-                    }
-                }
-            }
-        }
-        None
+    /// Returns the number of the `DownChannel`.
+    pub fn down_channel_number(&self) -> Option<usize> {
+        self.down_channel.as_ref().map(|dc| dc.number())
+    }
+
+    /// Connects the channel to `stream`, which transfers its data.
+    fn stream(&mut self, stream: &mut RttStreamBuilder) {
+        self.up_data = self
+            .up_channel
+            .clone()
+            .map(|up_channel| stream.up_channel(up_channel));
+        self.down_data = self
+            .down_channel
+            .clone()
+            .map(|down_channel| stream.down_channel(down_channel));
     }
 
-    /// Retrieves available data from the channel and if available, returns `Some(channel_number:String, formatted_data:String)`.
+    /// Retrieves the data which the stream read from the channel since the last call, and if available, returns `Some(channel_number:String, formatted_data:String)`.
+    /// The data is read by the thread of the [`RttStream`].
     pub fn get_rtt_data(
         &mut self,
-        core: &mut Core,
         defmt_state: Option<&(defmt_decoder::Table, Option<defmt_decoder::Locations>)>,
     ) -> Option<(String, String)> {
-        self
-            .poll_rtt(core)
-            .map(|bytes_read| {
-                (
-                    self.number().unwrap_or(0).to_string(), // If the Channel doesn't have a number, then send the output to channel 0
-                    {
-                        let mut formatted_data = String::new();
-                        match self.data_format {
-                            DataFormat::String => {
-                                let incoming = String::from_utf8_lossy(&self.rtt_buffer.0[..bytes_read]).to_string();
-                                for (_i, line) in incoming.split_terminator('\n').enumerate() {
-                                    if self.show_timestamps {
-                                        write!(formatted_data, "{} :", Local::now())
-                                            .map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                    }
-                                    writeln!(formatted_data, "{}", line).map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                }
-                            }
-                            DataFormat::BinaryLE => {
-                                for element in &self.rtt_buffer.0[..bytes_read] {
-                                    // Width of 4 allows 0xFF to be printed.
-                                    write!(formatted_data, "{:#04x}", element).map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                }
-                            }
-                            DataFormat::Defmt => {
-                                match defmt_state {
-                                    Some((table, locs)) => {
-
-                                        let mut stream_decoder = table.new_stream_decoder();
-                                        stream_decoder.received(&self.rtt_buffer.0[..bytes_read]);
-                                        while let Ok(frame) = stream_decoder.decode()
-                                        {
-                                            // NOTE(`[]` indexing) all indices in `table` have already been
-                                            // verified to exist in the `locs` map.
-                                            let loc = locs.as_ref().map(|locs| &locs[&frame.index()]);
-                                            writeln!(formatted_data, "{}", frame.display(false)).map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                            if let Some(loc) = loc {
-                                                let relpath = if let Ok(relpath) =
-                                                    loc.file.strip_prefix(&std::env::current_dir().unwrap())
-                                                {
-                                                    relpath
-                                                } else {
-                                                    // not relative; use full path
-                                                    &loc.file
-                                                };
-                                                writeln!(formatted_data,
-                                                    "└─ {}:{}",
-                                                    relpath.display(),
-                                                    loc.line
-                                                ).map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                            }
-                                        }
-                                    }
-                                    None => {
-                                        write!(formatted_data, "Running rtt in defmt mode but table or locations could not be loaded.")
-                                            .map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                    }
-                                }
+        let received: Vec<RttData> = self.up_data.as_ref()?.try_iter().collect();
+        if received.is_empty() {
+            return None;
+        }
+
+        let mut formatted_data = String::new();
+        match self.data_format {
+            DataFormat::String => {
+                for data in &received {
+                    let incoming = String::from_utf8_lossy(&data.data);
+                    for line in incoming.split_terminator('\n') {
+                        if self.show_timestamps {
+                            write!(
+                                formatted_data,
+                                "{} :",
+                                DateTime::<Local>::from(data.timestamp)
+                            )
+                            .map_or_else(
+                                |err| log::error!("Failed to format RTT data - {:?}", err),
+                                |r| r,
+                            );
+                        }
+                        writeln!(formatted_data, "{}", line).map_or_else(
+                            |err| log::error!("Failed to format RTT data - {:?}", err),
+                            |r| r,
+                        );
+                    }
+                }
+            }
+            DataFormat::BinaryLE => {
+                for element in received.iter().flat_map(|data| &data.data) {
+                    // Width of 4 allows 0xFF to be printed.
+                    write!(formatted_data, "{:#04x}", element).map_or_else(
+                        |err| log::error!("Failed to format RTT data - {:?}", err),
+                        |r| r,
+                    );
+                }
+            }
+            DataFormat::Defmt => {
+                match defmt_state {
+                    Some((table, locs)) => {
+                        let mut stream_decoder = table.new_stream_decoder();
+                        for data in &received {
+                            stream_decoder.received(&data.data);
+                        }
+                        while let Ok(frame) = stream_decoder.decode() {
+                            // NOTE(`[]` indexing) all indices in `table` have already been
+                            // verified to exist in the `locs` map.
+                            let loc = locs.as_ref().map(|locs| &locs[&frame.index()]);
+                            writeln!(formatted_data, "{}", frame.display(false)).map_or_else(
+                                |err| log::error!("Failed to format RTT data - {:?}", err),
+                                |r| r,
+                            );
+                            if let Some(loc) = loc {
+                                let relpath = if let Ok(relpath) =
+                                    loc.file.strip_prefix(&std::env::current_dir().unwrap())
+                                {
+                                    relpath
+                                } else {
+                                    // not relative; use full path
+                                    &loc.file
+                                };
+                                writeln!(formatted_data, "└─ {}:{}", relpath.display(), loc.line)
+                                    .map_or_else(
+                                        |err| log::error!("Failed to format RTT data - {:?}", err),
+                                        |r| r,
+                                    );
                             }
-                        };
-                        formatted_data
+                        }
                     }
-                )
-            })
+                    None => {
+                        write!(
+                            formatted_data,
+                            "Running rtt in defmt mode but table or locations could not be loaded."
+                        )
+                        .map_or_else(
+                            |err| log::error!("Failed to format RTT data - {:?}", err),
+                            |r| r,
+                        );
+                    }
+                }
+            }
+        };
+
+        // If the Channel doesn't have a number, then send the output to channel 0
+        Some((self.number().unwrap_or(0).to_string(), formatted_data))
     }
 
-    pub fn _push_rtt(&mut self, core: &mut Core) {
-        if let Some(down_channel) = self.down_channel.as_mut() {
-            self._input_data += "\n";
-            down_channel
-                .write(core, self._input_data.as_bytes())
-                .unwrap();
-            self._input_data.clear();
-        }
+    /// Sends `data` to the target on the `DownChannel`.
+    /// Data that does not fit into the channel buffer is kept by the stream, and written once the target consumed the previous data.
+    pub fn push_rtt(&mut self, data: &[u8]) -> Result<()> {
+        let down_data = self
+            .down_data
+            .as_ref()
+            .ok_or_else(|| anyhow!("RTT channel '{}' is not a down channel", self.channel_name))?;
+
+        down_data
+            .send(data.to_vec())
+            .map_err(|_| anyhow!("RTT channel '{}' is no longer streamed", self.channel_name))
     }
 }
 
@@ -286,12 +582,31 @@ impl RttActiveChannel {
 pub struct RttActiveTarget {
     pub active_channels: Vec<RttActiveChannel>,
     pub defmt_state: Option<(defmt_decoder::Table, Option<defmt_decoder::Locations>)>,
+    /// The control block, whose channels were moved to `active_channels`. It is used to detect when the control block is lost.
+    control_block: ControlBlockWatch,
+    /// Transfers the data of `active_channels`.
+    stream: RttStream,
+    /// The modes of the up channels, which are restored at the given time after [`RttActiveTarget::block_if_full`].
+    channel_modes: Option<(Instant, Vec<(usize, ChannelMode)>)>,
+    /// The session which the stream accesses. It is used to restore the modes of the up channels when the target is dropped.
+    session: Arc<Mutex<Session>>,
+    core_index: usize,
 }
 
 impl RttActiveTarget {
+    /// The interval in which [`RttActiveTarget::control_block_lost`] checks the control block.
+    pub const VALIDATION_INTERVAL: Duration = Duration::from_millis(100);
+
+    /// How long dropping the target waits for the session, to restore the modes of the up channels.
+    const DROP_LOCK_TIMEOUT: Duration = Duration::from_millis(100);
+
     /// RttActiveTarget collects references to all the `RttActiveChannel`s, for latter polling/pushing of data.
+    ///
+    /// The channels are streamed on a thread, which accesses core `core_index` of `session`.
     pub fn new(
         mut rtt: probe_rs_rtt::Rtt,
+        session: &Arc<Mutex<Session>>,
+        core_index: usize,
         elf_file: &Path,
         rtt_config: &RttConfig,
     ) -> Result<Self> {
@@ -326,6 +641,11 @@ impl RttActiveTarget {
             ));
         }
 
+        let mut stream = RttStream::builder();
+        for active_channel in active_channels.iter_mut() {
+            active_channel.stream(&mut stream);
+        }
+
         let defmt_enabled = active_channels
             .iter()
             .any(|elem| elem.data_format == DataFormat::Defmt);
@@ -364,9 +684,99 @@ impl RttActiveTarget {
         Ok(Self {
             active_channels,
             defmt_state,
+            control_block: ControlBlockWatch::new(rtt),
+            stream: stream.start(session.clone(), core_index)?,
+            channel_modes: None,
+            session: session.clone(),
+            core_index,
         })
     }
 
+    /// Returns `true` if the control block was lost, e.g. because the target was reset.
+    ///
+    /// To limit the load on the probe, the control block is only checked every [`RttActiveTarget::VALIDATION_INTERVAL`].
+    pub fn control_block_lost(&mut self, core: &mut Core) -> bool {
+        let lost = self.control_block.lost(core);
+        if lost {
+            // The memory of the channels may be used for something else now.
+            self.channel_modes = None;
+        }
+        lost
+    }
+
+    /// Switches the up channels to [`ChannelMode::BlockIfFull`] for `duration`, e.g. while the target boots, so no data is dropped.
+    /// The previous modes are restored once `duration` has passed, so the target does not block for good if the host stops reading.
+    /// They are also restored earlier, if the target is detached with [`RttActiveTarget::detach`] or dropped.
+    pub fn block_if_full(&mut self, core: &mut Core, duration: Duration) {
+        let mut modes = Vec::new();
+        for up_channel in self
+            .active_channels
+            .iter()
+            .filter_map(|active_channel| active_channel.up_channel.as_ref())
+        {
+            let result = up_channel.mode(core).and_then(|mode| {
+                up_channel.set_mode(core, ChannelMode::BlockIfFull)?;
+                Ok(mode)
+            });
+
+            match result {
+                Ok(mode) => modes.push((up_channel.number(), mode)),
+                Err(error) => log::warn!(
+                    "Unable to switch RTT channel {} to blocking mode: {}",
+                    up_channel.number(),
+                    error
+                ),
+            }
+        }
+
+        self.channel_modes = Some((Instant::now() + duration, modes));
+    }
+
+    /// Restores the modes of the up channels, once the duration of [`RttActiveTarget::block_if_full`] has passed.
+    ///
+    /// This is called by [`RttActiveTarget::poll_rtt`]. Users of [`RttActiveChannel::get_rtt_data`] have to call it regularly.
+    pub fn restore_channel_modes(&mut self, core: &mut Core) {
+        match &self.channel_modes {
+            Some((restore_at, _)) if Instant::now() >= *restore_at => {}
+            _ => return,
+        }
+
+        self.reset_channel_modes(core);
+    }
+
+    /// Restores the modes of the up channels right away, if they were switched by [`RttActiveTarget::block_if_full`].
+    fn reset_channel_modes(&mut self, core: &mut Core) {
+        if let Some((_, modes)) = self.channel_modes.take() {
+            // The channels of a lost control block must not be written.
+            match self.control_block.rtt.is_control_block_present(core) {
+                Ok(true) => {}
+                Ok(false) => return,
+                Err(error) => {
+                    log::warn!("Unable to restore the modes of the RTT channels: {}", error);
+                    return;
+                }
+            }
+
+            for (number, mode) in modes {
+                let up_channel = self
+                    .active_channels
+                    .iter()
+                    .filter_map(|active_channel| active_channel.up_channel.as_ref())
+                    .find(|up_channel| up_channel.number() == number);
+
+                if let Some(Err(error)) =
+                    up_channel.map(|up_channel| up_channel.set_mode(core, mode))
+                {
+                    log::warn!(
+                        "Unable to restore the mode of RTT channel {}: {}",
+                        number,
+                        error
+                    );
+                }
+            }
+        }
+    }
+
     pub fn get_rtt_symbol<T: Read + Seek>(file: &mut T) -> Option<u64> {
         let mut buffer = Vec::new();
         if file.read_to_end(&mut buffer).is_ok() {
@@ -387,32 +797,95 @@ impl RttActiveTarget {
 
     /// Polls the RTT target on all channels and returns available data.
     pub fn poll_rtt(&mut self, core: &mut Core) -> HashMap<String, String> {
+        self.restore_channel_modes(core);
+
         let defmt_state = self.defmt_state.as_ref();
         self.active_channels
             .iter_mut()
-            .filter_map(|active_channel| active_channel.get_rtt_data(core, defmt_state))
+            .filter_map(|active_channel| active_channel.get_rtt_data(defmt_state))
             .collect::<HashMap<_, _>>()
     }
 
-    // pub fn push_rtt(&mut self) {
-    //     self.tabs[self.current_tab].push_rtt();
-    // }
-}
+    /// Stops streaming the channels, and restores the modes of the up channels if they are still switched by
+    /// [`RttActiveTarget::block_if_full`], so the target does not block once the host stops reading.
+    ///
+    /// Dropping the target does the same, but it can't restore the modes while the session is locked, e.g. by the caller.
+    pub fn detach(mut self, core: &mut Core) {
+        self.reset_channel_modes(core);
+    }
 
-struct RttBuffer(Vec<u8>);
-impl RttBuffer {
-    /// Initialize the buffer and ensure it has enough capacity to match the size of the RTT channel on the target at the time of instantiation. Doing this now prevents later performance impact if the buffer capacity has to be grown dynamically.
-    pub fn new(mut buffer_size: usize) -> RttBuffer {
-        let mut rtt_buffer = vec![0u8; 1];
-        while buffer_size > 0 {
-            buffer_size -= 1;
-            rtt_buffer.push(0u8);
-        }
-        RttBuffer(rtt_buffer)
+    /// Calls `operation` while the channels are not streamed, e.g. while the flash is written.
+    ///
+    /// See [`RttStream::paused`].
+    pub fn paused<R>(&self, operation: impl FnOnce() -> R) -> R {
+        self.stream.paused(operation)
+    }
+
+    /// Returns the active channel for down channel `channel_number`.
+    /// Without a channel number, down channel 0 is used, or the first available down channel.
+    pub fn down_channel(&mut self, channel_number: Option<usize>) -> Option<&mut RttActiveChannel> {
+        let channel_number = channel_number.or_else(|| {
+            let mut numbers = self
+                .active_channels
+                .iter()
+                .filter_map(|active_channel| active_channel.down_channel_number());
+            numbers
+                .clone()
+                .find(|number| *number == 0)
+                .or_else(|| numbers.next())
+        })?;
+
+        self.active_channels
+            .iter_mut()
+            .find(|active_channel| active_channel.down_channel_number() == Some(channel_number))
+    }
+
+    /// Sends `data` to the target on down channel `channel_number`, see [`RttActiveTarget::down_channel`].
+    /// The data is written by the thread of the [`RttStream`], once it fits into the channel buffer.
+    pub fn push_rtt(&mut self, channel_number: Option<usize>, data: &[u8]) -> Result<()> {
+        let channel = self
+            .down_channel(channel_number)
+            .ok_or_else(|| match channel_number {
+                Some(channel_number) => anyhow!("There is no RTT down channel {}", channel_number),
+                None => anyhow!("The target has no RTT down channels"),
+            })?;
+
+        channel.push_rtt(data)
     }
 }
-impl fmt::Debug for RttBuffer {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.0.fmt(f)
+
+impl Drop for RttActiveTarget {
+    fn drop(&mut self) {
+        if self.channel_modes.is_none() {
+            return;
+        }
+
+        // The session is not waited for, as the caller may hold the lock. The paused stream only
+        // holds the lock until it finished the current poll.
+        self.stream.pause();
+        let deadline = Instant::now() + Self::DROP_LOCK_TIMEOUT;
+        let shared_session = self.session.clone();
+        let mut session = loop {
+            match shared_session.try_lock() {
+                Ok(session) => break session,
+                Err(TryLockError::Poisoned(error)) => break error.into_inner(),
+                Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                    thread::sleep(Duration::from_millis(1));
+                }
+                Err(TryLockError::WouldBlock) => {
+                    log::warn!(
+                        "Unable to restore the modes of the RTT channels, the session is in use."
+                    );
+                    return;
+                }
+            }
+        };
+
+        match session.core(self.core_index) {
+            Ok(mut core) => self.reset_channel_modes(&mut core),
+            Err(error) => {
+                log::warn!("Unable to restore the modes of the RTT channels: {}", error)
+            }
+        }
     }
 }
@@ -82,6 +82,22 @@ pub struct ArmCoreAccessOptions {
     pub ap: u8,
     /// The port select number to access the core
     pub psel: u32,
+    /// The base address of the external debug registers of the core.
+    ///
+    /// Only used for cores which are not Cortex-M cores. If it is not specified, it is looked up in the ROM table.
+    #[cfg_attr(
+        not(feature = "bincode"),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub debug_base: Option<u64>,
+    /// The base address of the cross trigger interface (CTI) of the core.
+    ///
+    /// Only used for cores which are not Cortex-M cores. If it is not specified, it is looked up in the ROM table.
+    #[cfg_attr(
+        not(feature = "bincode"),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub cti_base: Option<u64>,
 }
 
 /// The data required to access a Risc-V core
@@ -28,12 +28,18 @@ pub enum TargetDescriptionSource {
 pub enum CoreType {
     /// ARMv6-M: Cortex M0, M0+, M1
     Armv6m,
+    /// ARMv7-A: Cortex A5, A7, A9
+    Armv7a,
     /// ARMv7-M: Cortex M3
     Armv7m,
     /// ARMv7e-M: Cortex M4, M7
     Armv7em,
+    /// ARMv7-R: Cortex R4, R5, R7
+    Armv7r,
     /// ARMv8-M: Cortex M23, M33
     Armv8m,
+    /// ARMv8-A: Cortex A53, A72, running in AArch64 state
+    Armv8a,
     /// RISC-V
     Riscv,
 }
@@ -41,7 +47,7 @@ pub enum CoreType {
 /// The architecture family of a specific [`CoreType`].
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Architecture {
-    /// An ARM core of one of the specific types [`CoreType::Armv6m`], [`CoreType::Armv7m`], [`CoreType::Armv7em`] or [`CoreType::Armv8m`]
+    /// An ARM core of one of the specific types [`CoreType::Armv6m`], [`CoreType::Armv7a`], [`CoreType::Armv7m`], [`CoreType::Armv7em`], [`CoreType::Armv7r`], [`CoreType::Armv8m`] or [`CoreType::Armv8a`]
     Arm,
     /// A RISC-V core.
     Riscv,
@@ -55,6 +61,14 @@ impl CoreType {
             _ => Architecture::Arm,
         }
     }
+
+    /// Returns true if the core is a Cortex-M core, which is debugged through the memory mapped debug registers of the M-profile.
+    pub fn is_cortex_m(&self) -> bool {
+        matches!(
+            self,
+            CoreType::Armv6m | CoreType::Armv7m | CoreType::Armv7em | CoreType::Armv8m
+        )
+    }
 }
 
 /// This describes a chip family with all its variants.
@@ -9,8 +9,8 @@ use super::{
     ApAddress, DapAccess, DpAddress, PortType, RawDapAccess, SwoAccess, SwoConfig,
 };
 use crate::{
-    architecture::arm::ap::DataSize, CommunicationInterface, DebugProbe, DebugProbeError,
-    Error as ProbeRsError, Memory, Probe,
+    architecture::arm::ap::DataSize, probe::AsDebugProbe, CommunicationInterface, DebugProbe,
+    DebugProbeError, Error as ProbeRsError, Memory, Probe,
 };
 use anyhow::anyhow;
 use jep106::JEP106Code;
@@ -76,6 +76,12 @@ pub trait ArmProbeInterface: DapAccess + SwdSequence + SwoAccess + Send {
         dp: DpAddress,
     ) -> Result<Option<ArmChipInfo>, ProbeRsError>;
 
+    /// Returns the probe driver which is used by the interface.
+    fn probe(&self) -> &dyn DebugProbe;
+
+    /// Returns the probe driver which is used by the interface, e.g. to change its speed.
+    fn probe_mut(&mut self) -> &mut dyn DebugProbe;
+
     /// Closes the interface and returns back the generic probe it consumed.
     fn close(self: Box<Self>) -> Probe;
 }
@@ -277,7 +283,7 @@ pub struct ArmCommunicationInterface<S: ArmDebugState> {
 ///
 /// This is used to combine the traits, because it cannot be done in the ArmCommunicationInterface
 /// struct itself.
-pub trait DapProbe: RawDapAccess + DebugProbe {}
+pub trait DapProbe: RawDapAccess + DebugProbe + AsDebugProbe {}
 
 impl ArmProbeInterface for ArmCommunicationInterface<Initialized> {
     fn memory_interface(&mut self, access_port: MemoryAp) -> Result<Memory<'_>, ProbeRsError> {
@@ -299,6 +305,14 @@ impl ArmProbeInterface for ArmCommunicationInterface<Initialized> {
         ArmCommunicationInterface::num_access_ports(self, dp)
     }
 
+    fn probe(&self) -> &dyn DebugProbe {
+        self.probe.as_debug_probe()
+    }
+
+    fn probe_mut(&mut self) -> &mut dyn DebugProbe {
+        self.probe.as_debug_probe_mut()
+    }
+
     fn close(self: Box<Self>) -> Probe {
         Probe::from_attached_probe(RawDapAccess::into_probe(self.probe))
     }
@@ -1,14 +1,15 @@
 //! Register types and the core interface for armv6-M
 
+use super::dwt::{self, DwtVersion};
 use super::{Dfsr, State, ARM_REGISTER_FILE};
 
 use crate::architecture::arm::sequences::ArmDebugSequence;
-use crate::core::{RegisterDescription, RegisterFile, RegisterKind};
+use crate::core::{RegisterDescription, RegisterFile, RegisterKind, Watchpoint};
 use crate::error::Error;
 use crate::memory::Memory;
 use crate::{
     Architecture, CoreInformation, CoreInterface, CoreRegister, CoreRegisterAddress, CoreStatus,
-    DebugProbeError, HaltReason, MemoryInterface,
+    DebugProbeError, HaltReason, MemoryInterface, VectorCatchCondition,
 };
 use anyhow::Result;
 use bitfield::bitfield;
@@ -433,12 +434,14 @@ const PC: RegisterDescription = RegisterDescription {
     name: "PC",
     _kind: RegisterKind::PC,
     address: CoreRegisterAddress(0b0_1111),
+    size_in_bits: 32,
 };
 
 const XPSR: RegisterDescription = RegisterDescription {
     name: "XPSR",
     _kind: RegisterKind::General,
     address: CoreRegisterAddress(0b1_0000),
+    size_in_bits: 32,
 };
 
 /// The state of a core that can be used to persist core state across calls to multiple different cores.
@@ -538,7 +541,9 @@ impl<'probe> CoreInterface for Armv6m<'probe> {
         let pc_value = self.read_core_reg(PC.address)?;
 
         // get pc
-        Ok(CoreInformation { pc: pc_value })
+        Ok(CoreInformation {
+            pc: pc_value.into(),
+        })
     }
 
     fn run(&mut self) -> Result<(), Error> {
@@ -591,7 +596,9 @@ impl<'probe> CoreInterface for Armv6m<'probe> {
         let pc_value = self.read_core_reg(PC.address)?;
 
         // get pc
-        Ok(CoreInformation { pc: pc_value })
+        Ok(CoreInformation {
+            pc: pc_value.into(),
+        })
     }
 
     fn reset(&mut self) -> Result<(), Error> {
@@ -599,6 +606,10 @@ impl<'probe> CoreInterface for Armv6m<'probe> {
     }
 
     fn reset_and_halt(&mut self, _timeout: Duration) -> Result<CoreInformation, Error> {
+        // A vector catch on core reset, which the user configured, has to survive the reset catch sequence.
+        let vector_catch_core_reset =
+            Demcr::from(self.memory.read_word_32(Demcr::ADDRESS)?).vc_corereset();
+
         self.sequence.reset_catch_set(&mut self.memory)?;
         self.sequence.reset_system(&mut self.memory)?;
 
@@ -613,11 +624,19 @@ impl<'probe> CoreInterface for Armv6m<'probe> {
 
         self.sequence.reset_catch_clear(&mut self.memory)?;
 
+        if vector_catch_core_reset {
+            let mut demcr = Demcr::from(self.memory.read_word_32(Demcr::ADDRESS)?);
+            demcr.set_vc_corereset(true);
+            self.memory.write_word_32(Demcr::ADDRESS, demcr.into())?;
+        }
+
         // try to read the program counter
         let pc_value = self.read_core_reg(PC.address)?;
 
         // get pc
-        Ok(CoreInformation { pc: pc_value })
+        Ok(CoreInformation {
+            pc: pc_value.into(),
+        })
     }
 
     fn available_breakpoint_units(&mut self) -> Result<u32, Error> {
@@ -684,6 +703,30 @@ impl<'probe> CoreInterface for Armv6m<'probe> {
         Ok(())
     }
 
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        dwt::available_watchpoint_units(&mut self.memory)
+    }
+
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        dwt::hw_watchpoints(&mut self.memory, DwtVersion::V7)
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        unit_index: usize,
+        watchpoint: Watchpoint,
+    ) -> Result<(), Error> {
+        dwt::set_hw_watchpoint(&mut self.memory, DwtVersion::V7, unit_index, watchpoint)
+    }
+
+    fn clear_hw_watchpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        dwt::clear_hw_watchpoint(&mut self.memory, unit_index)
+    }
+
+    fn watchpoints_share_breakpoint_units(&self) -> bool {
+        false
+    }
+
     fn hw_breakpoints_enabled(&self) -> bool {
         self.state.hw_breakpoints_enabled
     }
@@ -692,6 +735,30 @@ impl<'probe> CoreInterface for Armv6m<'probe> {
         Architecture::Arm
     }
 
+    fn set_vector_catch(&mut self, conditions: &[VectorCatchCondition]) -> Result<(), Error> {
+        let mut demcr = Demcr::from(self.memory.read_word_32(Demcr::ADDRESS)?);
+
+        demcr.set_vc_corereset(false);
+        demcr.set_vc_harderr(false);
+
+        for condition in conditions {
+            match condition {
+                VectorCatchCondition::CoreReset => demcr.set_vc_corereset(true),
+                VectorCatchCondition::HardFault => demcr.set_vc_harderr(true),
+                other => {
+                    return Err(Error::Other(anyhow::anyhow!(
+                        "Vector catch condition {:?} is not supported by ARMv6-M cores",
+                        other
+                    )))
+                }
+            }
+        }
+
+        self.memory.write_word_32(Demcr::ADDRESS, demcr.into())?;
+
+        Ok(())
+    }
+
     fn status(&mut self) -> Result<crate::core::CoreStatus, Error> {
         let dhcsr = Dhcsr(self.memory.read_word_32(Dhcsr::ADDRESS)?);
 
@@ -719,7 +786,13 @@ impl<'probe> CoreInterface for Armv6m<'probe> {
         if dhcsr.s_halt() {
             let dfsr = Dfsr(self.memory.read_word_32(Dfsr::ADDRESS)?);
 
-            let reason = dfsr.halt_reason();
+            let reason = match dfsr.halt_reason() {
+                HaltReason::Watchpoint(_) => HaltReason::Watchpoint(dwt::triggered_watchpoint(
+                    &mut self.memory,
+                    DwtVersion::V7,
+                )?),
+                reason => reason,
+            };
 
             // Clear bits from Dfsr register
             self.memory
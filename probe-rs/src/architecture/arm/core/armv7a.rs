@@ -0,0 +1,1183 @@
+//! Register types and the core interface for ARMv7-A and ARMv7-R cores, e.g. Cortex-A7 or Cortex-R5.
+//!
+//! The core is controlled through its memory mapped debug registers, and halted using its cross
+//! trigger interface (CTI) if it has one. While the core is halted, registers and memory are accessed
+//! by executing ARM instructions written to DBGITR, which exchange data with the debugger through
+//! the debug communications channel (DCC).
+//!
+//! See ARM architecture reference manual ARMv7-A and ARMv7-R edition, part C "Debug Architecture".
+
+use super::{cti, register, CortexAState};
+use crate::architecture::arm::component::DebugRegister;
+use crate::core::{
+    CoreInformation, CoreInterface, CoreRegisterAddress, RegisterDescription, RegisterFile,
+    RegisterKind, RegisterValue, Watchpoint,
+};
+use crate::error::Error;
+use crate::memory::Memory;
+use crate::{
+    Architecture, CoreStatus, DebugProbeError, HaltReason, MemoryInterface, VectorCatchCondition,
+};
+use anyhow::{anyhow, Result};
+use bitfield::bitfield;
+use std::time::{Duration, Instant};
+
+/// The number of registers in [`ARMV7A_REGISTER_FILE`].
+const NUM_REGISTERS: usize = 17;
+
+/// How long to wait for an instruction written to DBGITR to complete.
+const INSTRUCTION_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// The register indices used by [`ARMV7A_REGISTER_FILE`].
+mod register_index {
+    /// The link register, R14. The registers R0-R14 of the current mode use the indices 0-14.
+    pub const LR: u16 = 14;
+    pub const PC: u16 = 15;
+    pub const CPSR: u16 = 16;
+}
+
+/// An ARMv7-A or ARMv7-R core, e.g. a Cortex-A7, Cortex-A9 or Cortex-R5.
+pub struct Armv7a<'probe> {
+    memory: Memory<'probe>,
+
+    state: &'probe mut CortexAState,
+
+    /// The base address of the debug registers of the core.
+    base_address: u32,
+
+    /// The base address of the CTI of the core.
+    ///
+    /// Without a CTI, the core is halted using DBGDRCR.
+    cti_address: Option<u32>,
+}
+
+impl<'probe> Armv7a<'probe> {
+    pub(crate) fn new(
+        memory: Memory<'probe>,
+        state: &'probe mut CortexAState,
+        base_address: u32,
+        cti_address: Option<u32>,
+    ) -> Result<Self, Error> {
+        let mut core = Self {
+            memory,
+            state,
+            base_address,
+            cti_address,
+        };
+
+        if !core.state.initialized() {
+            core.initialize()?;
+        }
+
+        Ok(core)
+    }
+
+    fn initialize(&mut self) -> Result<(), Error> {
+        // The software lock is optional, and writes to DBGLAR are ignored if it is not implemented.
+        self.memory
+            .write_word_32(self.base_address + DBGLAR, UNLOCK_KEY)?;
+
+        // The OS lock is set on a cold reset of ARMv7.1 cores, and blocks access to most of the debug registers.
+        self.memory.write_word_32(self.base_address + DBGOSLAR, 0)?;
+
+        let mut dbgdscr: Dbgdscr = self.read_debug_register()?;
+        dbgdscr.set_hdbgen(true);
+        self.write_debug_register(dbgdscr)?;
+
+        if let Some(cti_address) = self.cti_address {
+            cti::initialize(&mut self.memory, cti_address)?;
+        }
+
+        self.state.invalidate_register_cache(NUM_REGISTERS);
+
+        let dbgdscr: Dbgdscr = self.read_debug_register()?;
+        self.state.current_state = if dbgdscr.halted() {
+            self.enable_instruction_execution()?;
+
+            CoreStatus::Halted(dbgdscr.halt_reason())
+        } else {
+            CoreStatus::Running
+        };
+
+        log::debug!("State when connecting: {:?}", self.state.current_state);
+
+        self.state.initialize();
+
+        Ok(())
+    }
+
+    fn read_debug_register<R: DebugRegister>(&mut self) -> Result<R, Error> {
+        Ok(R::from(
+            self.memory.read_word_32(self.base_address + R::ADDRESS)?,
+        ))
+    }
+
+    fn write_debug_register<R: DebugRegister>(&mut self, value: R) -> Result<(), Error> {
+        self.memory
+            .write_word_32(self.base_address + R::ADDRESS, value.into())
+    }
+
+    /// Allows the debugger to execute instructions on the core, which has just been halted.
+    fn enable_instruction_execution(&mut self) -> Result<(), Error> {
+        let mut dbgdscr: Dbgdscr = self.read_debug_register()?;
+        dbgdscr.set_itren(true);
+
+        self.write_debug_register(dbgdscr)
+    }
+
+    /// Clears the sticky exception flags of DBGDSCR, which block the execution of further instructions.
+    fn clear_sticky_exceptions(&mut self) -> Result<(), Error> {
+        let mut dbgdrcr = Dbgdrcr(0);
+        dbgdrcr.set_cse(true);
+
+        self.write_debug_register(dbgdrcr)
+    }
+
+    /// Executes the ARM `instruction` on the halted core, and waits until it has completed.
+    fn execute_instruction(&mut self, instruction: u32) -> Result<(), Error> {
+        self.memory
+            .write_word_32(self.base_address + DBGITR, instruction)?;
+
+        let start = Instant::now();
+        loop {
+            let dbgdscr: Dbgdscr = self.read_debug_register()?;
+
+            if dbgdscr.und_l() || dbgdscr.sdabort_l() || dbgdscr.adabort_l() {
+                self.clear_sticky_exceptions()?;
+
+                return Err(Error::Other(anyhow!(
+                    "Instruction {:#010x} caused an exception while the core was halted",
+                    instruction
+                )));
+            }
+
+            if dbgdscr.instrcompl_l() {
+                return Ok(());
+            }
+
+            if start.elapsed() > INSTRUCTION_TIMEOUT {
+                return Err(Error::Probe(DebugProbeError::Timeout));
+            }
+        }
+    }
+
+    /// Reads the value which the core wrote to DBGDTRTX.
+    fn read_dcc(&mut self) -> Result<u32, Error> {
+        let dbgdscr: Dbgdscr = self.read_debug_register()?;
+        if !dbgdscr.txfull_l() {
+            return Err(Error::Other(anyhow!(
+                "The core did not write any data to the debug communications channel"
+            )));
+        }
+
+        self.memory.read_word_32(self.base_address + DBGDTRTX)
+    }
+
+    /// Writes a value to DBGDTRRX, which the core can read.
+    fn write_dcc(&mut self, value: u32) -> Result<(), Error> {
+        self.memory
+            .write_word_32(self.base_address + DBGDTRRX, value)
+    }
+
+    /// Makes sure that the core is halted, so that registers and memory can be accessed.
+    fn check_halted(&mut self) -> Result<(), Error> {
+        if !self.state.current_state.is_halted() {
+            self.status()?;
+        }
+
+        if !self.state.current_state.is_halted() {
+            return Err(Error::Other(anyhow!(
+                "The core needs to be halted to access its registers and memory"
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn register_description(index: u16) -> Result<&'static RegisterDescription, Error> {
+        ARMV7A_REGISTER_FILE
+            .get_platform_register(index as usize)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Register {} does not exist on ARMv7-A and ARMv7-R cores",
+                    index
+                )
+                .into()
+            })
+    }
+
+    /// Reads a register, using the cached value if it was read before.
+    fn read_register(&mut self, index: u16) -> Result<u32, Error> {
+        Self::register_description(index)?;
+        self.check_halted()?;
+
+        if let Some((value, _)) = self.state.register_cache[index as usize] {
+            return u32::try_from(value);
+        }
+
+        let value = self.read_register_uncached(index)?;
+        self.state.register_cache[index as usize] = Some((RegisterValue::U32(value), false));
+
+        Ok(value)
+    }
+
+    /// Changes the value of a register, which is written to the core before it is restarted.
+    fn write_register(&mut self, index: u16, value: u32) -> Result<(), Error> {
+        Self::register_description(index)?;
+        self.check_halted()?;
+
+        self.state.register_cache[index as usize] = Some((RegisterValue::U32(value), true));
+
+        Ok(())
+    }
+
+    /// Caches the value of the general purpose register `index`, before it is used by an instruction.
+    ///
+    /// The register is marked as modified, so its original value is restored before the core is restarted.
+    fn save_scratch_register(&mut self, index: u16) -> Result<(), Error> {
+        let value = self.read_register(index)?;
+        self.state.register_cache[index as usize] = Some((RegisterValue::U32(value), true));
+
+        Ok(())
+    }
+
+    fn read_register_uncached(&mut self, index: u16) -> Result<u32, Error> {
+        use register_index::*;
+
+        match index {
+            0..=LR => {
+                self.execute_instruction(mcr_dbgdtrtx(index.into()))?;
+                self.read_dcc()
+            }
+            PC => {
+                // Reads of the PC return the address of the current instruction plus an offset,
+                // which depends on the instruction set state.
+                let offset = if self.is_thumb_state()? { 4 } else { 8 };
+                let value = self.read_through_r0(mov(0, 15))?;
+
+                Ok(value.wrapping_sub(offset))
+            }
+            CPSR => self.read_through_r0(mrs_cpsr(0)),
+            _ => unreachable!("Register {} was checked to exist", index),
+        }
+    }
+
+    fn write_register_uncached(&mut self, index: u16, value: u32) -> Result<(), Error> {
+        use register_index::*;
+
+        match index {
+            0..=LR => {
+                self.write_dcc(value)?;
+                self.execute_instruction(mrc_dbgdtrrx(index.into()))
+            }
+            PC => self.write_through_r0(value, mov(15, 0)),
+            CPSR => {
+                self.write_through_r0(value, msr_cpsr(0))?;
+                // Changes of the CPSR only take effect after the pipeline has been flushed.
+                self.execute_instruction(PREFETCH_FLUSH)
+            }
+            _ => unreachable!("Register {} was checked to exist", index),
+        }
+    }
+
+    /// Executes `instruction`, which stores its result in R0, and reads the result.
+    fn read_through_r0(&mut self, instruction: u32) -> Result<u32, Error> {
+        self.save_scratch_register(0)?;
+
+        self.execute_instruction(instruction)?;
+        self.execute_instruction(mcr_dbgdtrtx(0))?;
+
+        self.read_dcc()
+    }
+
+    /// Loads `value` into R0, and executes `instruction`, which reads its operand from R0.
+    fn write_through_r0(&mut self, value: u32, instruction: u32) -> Result<(), Error> {
+        self.load_r0(value)?;
+
+        self.execute_instruction(instruction)
+    }
+
+    /// Loads `value` into R0, which is restored before the core is restarted.
+    fn load_r0(&mut self, value: u32) -> Result<(), Error> {
+        self.save_scratch_register(0)?;
+
+        self.write_dcc(value)?;
+        self.execute_instruction(mrc_dbgdtrrx(0))
+    }
+
+    fn is_thumb_state(&mut self) -> Result<bool, Error> {
+        let cpsr = self.read_register(register_index::CPSR)?;
+
+        Ok(cpsr & CPSR_THUMB != 0)
+    }
+
+    /// Writes all modified registers back to the core.
+    fn write_back_registers(&mut self) -> Result<(), Error> {
+        use register_index::*;
+
+        // The PC and the CPSR are written using R0, so R0 is restored last. The CPSR can change
+        // the mode of the core, so it is written after the banked registers of the current mode.
+        let indices = std::iter::once(PC)
+            .chain(1..=LR)
+            .chain(std::iter::once(CPSR))
+            .chain(std::iter::once(0));
+
+        for index in indices {
+            if let Some((value, true)) = self.state.register_cache[index as usize] {
+                self.write_register_uncached(index, u32::try_from(value)?)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Restarts the halted core.
+    fn restart(&mut self) -> Result<(), Error> {
+        self.write_back_registers()?;
+
+        // Instruction execution has to be disabled before the core leaves debug state.
+        let mut dbgdscr: Dbgdscr = self.read_debug_register()?;
+        dbgdscr.set_itren(false);
+        self.write_debug_register(dbgdscr)?;
+
+        if let Some(cti_address) = self.cti_address {
+            cti::acknowledge_halt(&mut self.memory, cti_address)?;
+        }
+
+        let mut dbgdrcr = Dbgdrcr(0);
+        dbgdrcr.set_cse(true);
+        dbgdrcr.set_rrq(true);
+        self.write_debug_register(dbgdrcr)?;
+
+        let start = Instant::now();
+        loop {
+            let dbgdscr: Dbgdscr = self.read_debug_register()?;
+            if dbgdscr.restarted() {
+                break;
+            }
+
+            if start.elapsed() > INSTRUCTION_TIMEOUT {
+                return Err(Error::Probe(DebugProbeError::Timeout));
+            }
+        }
+
+        self.state.invalidate_register_cache(NUM_REGISTERS);
+        self.state.current_state = CoreStatus::Running;
+
+        Ok(())
+    }
+
+    /// Reads `count` values of `size` bytes from memory, using the load instructions of the core.
+    ///
+    /// The address is a virtual address, which is translated by the MMU or checked by the MPU of the core.
+    fn read_memory(&mut self, address: u32, size: u32, count: usize) -> Result<Vec<u32>, Error> {
+        self.prepare_memory_access(address, size)?;
+
+        let mut values = Vec::with_capacity(count);
+        for _ in 0..count {
+            self.execute_instruction(load_post_increment(size, 1, 0))?;
+            self.execute_instruction(mcr_dbgdtrtx(1))?;
+            values.push(self.read_dcc()?);
+        }
+
+        Ok(values)
+    }
+
+    /// Writes `values` of `size` bytes to memory, using the store instructions of the core.
+    ///
+    /// The address is a virtual address, which is translated by the MMU or checked by the MPU of the core.
+    fn write_memory(
+        &mut self,
+        address: u32,
+        size: u32,
+        values: impl Iterator<Item = u32>,
+    ) -> Result<(), Error> {
+        self.prepare_memory_access(address, size)?;
+
+        for value in values {
+            self.write_dcc(value)?;
+            self.execute_instruction(mrc_dbgdtrrx(1))?;
+            self.execute_instruction(store_post_increment(size, 1, 0))?;
+        }
+
+        Ok(())
+    }
+
+    /// Loads `address` into R0, which is used as address register by the memory accesses.
+    ///
+    /// R1 is used to transfer the data.
+    fn prepare_memory_access(&mut self, address: u32, size: u32) -> Result<(), Error> {
+        if address % size != 0 {
+            return Err(Error::Other(anyhow!(
+                "The address {:#010x} is not aligned to the access size of {} bytes",
+                address,
+                size
+            )));
+        }
+
+        self.check_halted()?;
+        self.save_scratch_register(1)?;
+
+        self.load_r0(address)
+    }
+
+    fn breakpoint_register(&self, register: u32, unit_index: usize) -> u32 {
+        self.base_address + register + 4 * unit_index as u32
+    }
+
+    fn set_breakpoint_enabled(&mut self, unit_index: usize, enabled: bool) -> Result<(), Error> {
+        let address = self.breakpoint_register(DBGBCR0, unit_index);
+
+        let mut control = Dbgbcr(self.memory.read_word_32(address)?);
+        control.set_e(enabled);
+
+        self.memory.write_word_32(address, control.into())
+    }
+
+    /// Executes a single instruction, using a breakpoint which matches all addresses except the current instruction.
+    ///
+    /// The last breakpoint unit is used for this, its previous configuration is restored afterwards.
+    fn step_with_mismatch_breakpoint(&mut self, program_counter: u32) -> Result<(), Error> {
+        let unit_index = self.available_breakpoint_units()? as usize - 1;
+
+        let value_address = self.breakpoint_register(DBGBVR0, unit_index);
+        let control_address = self.breakpoint_register(DBGBCR0, unit_index);
+        let previous_value = self.memory.read_word_32(value_address)?;
+        let previous_control = self.memory.read_word_32(control_address)?;
+
+        let byte_address_select = if self.is_thumb_state()? {
+            0b0011 << (program_counter & 0b10)
+        } else {
+            0b1111
+        };
+
+        let mut control = Dbgbcr(0);
+        control.set_bt(BREAKPOINT_TYPE_MISMATCH);
+        control.set_bas(byte_address_select);
+        control.set_pmc(0b11);
+        control.set_e(true);
+
+        self.memory
+            .write_word_32(value_address, program_counter & !0b11)?;
+        self.memory.write_word_32(control_address, control.into())?;
+
+        let result = self
+            .restart()
+            .and_then(|_| self.wait_for_core_halted(Duration::from_millis(100)));
+
+        if result.is_err() {
+            // The instruction never completes if it jumps to itself.
+            log::debug!("Step did not complete, halting the core");
+            self.halt(Duration::from_millis(100))?;
+        }
+
+        self.memory
+            .write_word_32(control_address, previous_control)?;
+        self.memory.write_word_32(value_address, previous_value)?;
+
+        Ok(())
+    }
+}
+
+impl<'probe> CoreInterface for Armv7a<'probe> {
+    fn wait_for_core_halted(&mut self, timeout: Duration) -> Result<(), Error> {
+        let start = Instant::now();
+
+        while start.elapsed() < timeout {
+            if self.core_halted()? {
+                return Ok(());
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        Err(Error::Probe(DebugProbeError::Timeout))
+    }
+
+    fn core_halted(&mut self) -> Result<bool, Error> {
+        let dbgdscr: Dbgdscr = self.read_debug_register()?;
+
+        Ok(dbgdscr.halted())
+    }
+
+    fn halt(&mut self, timeout: Duration) -> Result<CoreInformation, Error> {
+        if !self.core_halted()? {
+            match self.cti_address {
+                Some(cti_address) => cti::request_halt(&mut self.memory, cti_address)?,
+                None => {
+                    let mut dbgdrcr = Dbgdrcr(0);
+                    dbgdrcr.set_hrq(true);
+                    self.write_debug_register(dbgdrcr)?;
+                }
+            }
+
+            self.wait_for_core_halted(timeout)?;
+
+            if let Some(cti_address) = self.cti_address {
+                cti::acknowledge_halt(&mut self.memory, cti_address)?;
+            }
+        }
+
+        // Update core status
+        let _ = self.status()?;
+
+        Ok(CoreInformation {
+            pc: self.read_register(register_index::PC)?.into(),
+        })
+    }
+
+    fn run(&mut self) -> Result<(), Error> {
+        // Before we run, we always perform a single instruction step, to account for possible breakpoints that might get us stuck on the current instruction.
+        self.step()?;
+
+        self.restart()
+    }
+
+    fn reset(&mut self) -> Result<(), Error> {
+        let mut dbgprcr = Dbgprcr(0);
+        dbgprcr.set_cwrr(true);
+        self.write_debug_register(dbgprcr)?;
+        self.memory.flush()?;
+
+        self.state.invalidate_register_cache(NUM_REGISTERS);
+        self.state.current_state = CoreStatus::Unknown;
+
+        Ok(())
+    }
+
+    fn reset_and_halt(&mut self, timeout: Duration) -> Result<CoreInformation, Error> {
+        // Halt the core on the first instruction of the reset handler.
+        let mut dbgvcr: Dbgvcr = self.read_debug_register()?;
+        dbgvcr.set_reset(true);
+        self.write_debug_register(dbgvcr)?;
+
+        self.reset()?;
+        let result = self.wait_for_core_halted(timeout);
+
+        dbgvcr.set_reset(false);
+        self.write_debug_register(dbgvcr)?;
+        result?;
+
+        // Update core status
+        let _ = self.status()?;
+
+        Ok(CoreInformation {
+            pc: self.read_register(register_index::PC)?.into(),
+        })
+    }
+
+    fn step(&mut self) -> Result<CoreInformation, Error> {
+        let program_counter = self.read_register(register_index::PC)?;
+
+        // A breakpoint on the current instruction would halt the core again before the instruction is executed.
+        let breakpoint_units: Vec<usize> = self
+            .hw_breakpoints()?
+            .iter()
+            .enumerate()
+            .filter(|(_, address)| **address == Some(program_counter))
+            .map(|(unit_index, _)| unit_index)
+            .collect();
+
+        for unit_index in &breakpoint_units {
+            log::debug!("Disabling breakpoint #{} for the step", unit_index);
+            self.set_breakpoint_enabled(*unit_index, false)?;
+        }
+
+        let result = self.step_with_mismatch_breakpoint(program_counter);
+
+        for unit_index in &breakpoint_units {
+            self.set_breakpoint_enabled(*unit_index, true)?;
+        }
+
+        result?;
+
+        // The core reports the mismatch breakpoint as reason for the halt.
+        if self.status()? == CoreStatus::Halted(HaltReason::Breakpoint) {
+            self.state.current_state = CoreStatus::Halted(HaltReason::Step);
+        }
+
+        Ok(CoreInformation {
+            pc: self.read_register(register_index::PC)?.into(),
+        })
+    }
+
+    fn read_core_reg(&mut self, address: CoreRegisterAddress) -> Result<u32, Error> {
+        self.read_register(address.0)
+    }
+
+    fn write_core_reg(&mut self, address: CoreRegisterAddress, value: u32) -> Result<()> {
+        self.write_register(address.0, value)?;
+
+        Ok(())
+    }
+
+    fn read_core_reg_value(
+        &mut self,
+        address: CoreRegisterAddress,
+    ) -> Result<RegisterValue, Error> {
+        self.read_register(address.0).map(RegisterValue::U32)
+    }
+
+    fn write_core_reg_value(
+        &mut self,
+        address: CoreRegisterAddress,
+        value: RegisterValue,
+    ) -> Result<(), Error> {
+        self.write_register(address.0, u32::try_from(value)?)
+    }
+
+    fn available_breakpoint_units(&mut self) -> Result<u32, Error> {
+        let dbgdidr: Dbgdidr = self.read_debug_register()?;
+
+        Ok(dbgdidr.brps() + 1)
+    }
+
+    /// See docs on the [`CoreInterface::hw_breakpoints`] trait
+    fn hw_breakpoints(&mut self) -> Result<Vec<Option<u32>>, Error> {
+        let num_hw_breakpoints = self.available_breakpoint_units()? as usize;
+        let mut breakpoints = Vec::with_capacity(num_hw_breakpoints);
+
+        for unit_index in 0..num_hw_breakpoints {
+            let control = Dbgbcr(
+                self.memory
+                    .read_word_32(self.breakpoint_register(DBGBCR0, unit_index))?,
+            );
+
+            if control.e() && control.bt() == BREAKPOINT_TYPE_ADDRESS_MATCH {
+                let address = self
+                    .memory
+                    .read_word_32(self.breakpoint_register(DBGBVR0, unit_index))?;
+
+                // Breakpoints on the upper halfword of a word only select its upper two bytes.
+                let offset = if control.bas() == 0b1100 { 2 } else { 0 };
+
+                breakpoints.push(Some(address + offset));
+            } else {
+                breakpoints.push(None);
+            }
+        }
+
+        Ok(breakpoints)
+    }
+
+    fn enable_breakpoints(&mut self, state: bool) -> Result<(), Error> {
+        // The breakpoint units are enabled individually, there is no global enable.
+        self.state.hw_breakpoints_enabled = state;
+
+        Ok(())
+    }
+
+    fn set_hw_breakpoint(&mut self, unit_index: usize, addr: u32) -> Result<(), Error> {
+        self.memory
+            .write_word_32(self.breakpoint_register(DBGBVR0, unit_index), addr & !0b11)?;
+
+        let mut control = Dbgbcr(0);
+        // Match the halfword at the address, which is the start of both ARM and Thumb
+        // instructions, in all processor modes.
+        control.set_bas(0b0011 << (addr & 0b10));
+        control.set_pmc(0b11);
+        control.set_e(true);
+
+        self.memory.write_word_32(
+            self.breakpoint_register(DBGBCR0, unit_index),
+            control.into(),
+        )?;
+
+        self.memory.flush()
+    }
+
+    fn clear_hw_breakpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        self.memory
+            .write_word_32(self.breakpoint_register(DBGBCR0, unit_index), 0)?;
+
+        self.memory.flush()
+    }
+
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        Ok(0)
+    }
+
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        Ok(Vec::new())
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        _unit_index: usize,
+        _watchpoint: Watchpoint,
+    ) -> Result<(), Error> {
+        Err(Error::Other(anyhow!(
+            "Watchpoints are not supported on ARMv7-A and ARMv7-R cores yet"
+        )))
+    }
+
+    fn clear_hw_watchpoint(&mut self, _unit_index: usize) -> Result<(), Error> {
+        Err(Error::Other(anyhow!(
+            "Watchpoints are not supported on ARMv7-A and ARMv7-R cores yet"
+        )))
+    }
+
+    fn watchpoints_share_breakpoint_units(&self) -> bool {
+        false
+    }
+
+    fn registers(&self) -> &'static RegisterFile {
+        &ARMV7A_REGISTER_FILE
+    }
+
+    fn hw_breakpoints_enabled(&self) -> bool {
+        self.state.hw_breakpoints_enabled
+    }
+
+    fn architecture(&self) -> Architecture {
+        Architecture::Arm
+    }
+
+    fn set_vector_catch(&mut self, conditions: &[VectorCatchCondition]) -> Result<(), Error> {
+        if conditions.is_empty() {
+            return Ok(());
+        }
+
+        Err(anyhow!("Vector catch is not supported for ARMv7-A cores").into())
+    }
+
+    fn status(&mut self) -> Result<CoreStatus, Error> {
+        let dbgdscr: Dbgdscr = self.read_debug_register()?;
+
+        if dbgdscr.halted() {
+            // The method of entry doesn't change while the core is halted, so the reason
+            // which was determined when the core halted is kept.
+            if self.state.current_state.is_halted() {
+                return Ok(self.state.current_state);
+            }
+
+            let reason = dbgdscr.halt_reason();
+            log::debug!("Core halted, reason: {:?}", reason);
+
+            self.enable_instruction_execution()?;
+            self.state.invalidate_register_cache(NUM_REGISTERS);
+            self.state.current_state = CoreStatus::Halted(reason);
+
+            return Ok(CoreStatus::Halted(reason));
+        }
+
+        if self.state.current_state.is_halted() {
+            log::warn!("Core is running, but we expected it to be halted");
+            self.state.invalidate_register_cache(NUM_REGISTERS);
+        }
+
+        self.state.current_state = CoreStatus::Running;
+
+        Ok(CoreStatus::Running)
+    }
+}
+
+impl<'probe> MemoryInterface for Armv7a<'probe> {
+    fn read_word_32(&mut self, address: u32) -> Result<u32, Error> {
+        Ok(self.read_memory(address, 4, 1)?[0])
+    }
+    fn read_word_8(&mut self, address: u32) -> Result<u8, Error> {
+        Ok(self.read_memory(address, 1, 1)?[0] as u8)
+    }
+    fn read_32(&mut self, address: u32, data: &mut [u32]) -> Result<(), Error> {
+        let values = self.read_memory(address, 4, data.len())?;
+        data.copy_from_slice(&values);
+        Ok(())
+    }
+    fn read_8(&mut self, address: u32, data: &mut [u8]) -> Result<(), Error> {
+        let values = self.read_memory(address, 1, data.len())?;
+        for (data, value) in data.iter_mut().zip(values) {
+            *data = value as u8;
+        }
+        Ok(())
+    }
+    fn write_word_32(&mut self, address: u32, data: u32) -> Result<(), Error> {
+        self.write_memory(address, 4, std::iter::once(data))
+    }
+    fn write_word_8(&mut self, address: u32, data: u8) -> Result<(), Error> {
+        self.write_memory(address, 1, std::iter::once(data.into()))
+    }
+    fn write_32(&mut self, address: u32, data: &[u32]) -> Result<(), Error> {
+        self.write_memory(address, 4, data.iter().copied())
+    }
+    fn write_8(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
+        self.write_memory(address, 1, data.iter().map(|&value| value.into()))
+    }
+    fn flush(&mut self) -> Result<(), Error> {
+        self.memory.flush()
+    }
+}
+
+/// `MCR p14, 0, <Rt>, c0, c5, 0`, which writes `Rt` to DBGDTRTX.
+const fn mcr_dbgdtrtx(rt: u32) -> u32 {
+    0xEE00_0E15 | (rt << 12)
+}
+
+/// `MRC p14, 0, <Rt>, c0, c5, 0`, which reads DBGDTRRX into `Rt`.
+const fn mrc_dbgdtrrx(rt: u32) -> u32 {
+    0xEE10_0E15 | (rt << 12)
+}
+
+/// `MOV <Rd>, <Rm>`
+const fn mov(rd: u32, rm: u32) -> u32 {
+    0xE1A0_0000 | (rd << 12) | rm
+}
+
+/// `MRS <Rd>, CPSR`
+const fn mrs_cpsr(rd: u32) -> u32 {
+    0xE10F_0000 | (rd << 12)
+}
+
+/// `MSR CPSR_fsxc, <Rn>`
+const fn msr_cpsr(rn: u32) -> u32 {
+    0xE12F_F000 | rn
+}
+
+/// `MCR p15, 0, R0, c7, c5, 4`, which flushes the prefetch buffer.
+const PREFETCH_FLUSH: u32 = 0xEE07_0F95;
+
+/// `LDRB <Rt>, [<Rn>], #1` or `LDR <Rt>, [<Rn>], #4`, depending on `size`.
+const fn load_post_increment(size: u32, rt: u32, rn: u32) -> u32 {
+    0xE490_0000 | (((size == 1) as u32) << 22) | (rn << 16) | (rt << 12) | size
+}
+
+/// `STRB <Rt>, [<Rn>], #1` or `STR <Rt>, [<Rn>], #4`, depending on `size`.
+const fn store_post_increment(size: u32, rt: u32, rn: u32) -> u32 {
+    0xE480_0000 | (((size == 1) as u32) << 22) | (rn << 16) | (rt << 12) | size
+}
+
+/// The Thumb execution state bit of the CPSR.
+const CPSR_THUMB: u32 = 1 << 5;
+
+/// Debug data transfer register, receive, DBGDTRRX
+const DBGDTRRX: u32 = 0x080;
+/// Debug instruction transfer register, DBGITR
+const DBGITR: u32 = 0x084;
+/// Debug data transfer register, transmit, DBGDTRTX
+const DBGDTRTX: u32 = 0x08C;
+/// Breakpoint value register 0, DBGBVR0. DBGBVRn is at DBGBVR0 + 4 * n.
+const DBGBVR0: u32 = 0x100;
+/// Breakpoint control register 0, DBGBCR0. DBGBCRn is at DBGBCR0 + 4 * n.
+const DBGBCR0: u32 = 0x140;
+/// OS lock access register, DBGOSLAR
+const DBGOSLAR: u32 = 0x300;
+/// Lock access register, DBGLAR
+const DBGLAR: u32 = 0xFB0;
+
+/// The key which has to be written to DBGLAR to allow writes to the debug registers.
+const UNLOCK_KEY: u32 = 0xC5AC_CE55;
+
+/// Breakpoint type of DBGBCR, which matches the instruction at the address.
+const BREAKPOINT_TYPE_ADDRESS_MATCH: u32 = 0b000;
+/// Breakpoint type of DBGBCR, which matches all instructions except the one at the address.
+const BREAKPOINT_TYPE_MISMATCH: u32 = 0b100;
+
+bitfield! {
+    /// Debug ID Register, DBGDIDR
+    #[derive(Copy, Clone)]
+    pub struct Dbgdidr(u32);
+    impl Debug;
+    /// The number of watchpoint units, minus one.
+    pub wrps, _: 31, 28;
+    /// The number of breakpoint units, minus one.
+    pub brps, _: 27, 24;
+    /// The version of the debug architecture.
+    pub version, _: 19, 16;
+}
+
+debug_register!(Dbgdidr, 0x000, "DBGDIDR");
+
+bitfield! {
+    /// Debug Status and Control Register, DBGDSCR
+    #[derive(Copy, Clone)]
+    pub struct Dbgdscr(u32);
+    impl Debug;
+    /// DBGDTRRX full.
+    pub rxfull_l, _: 30;
+    /// DBGDTRTX full.
+    pub txfull_l, _: 29;
+    /// Set when the last instruction written to DBGITR has completed.
+    pub instrcompl_l, _: 24;
+    /// Halting debug mode enable. Breakpoints only halt the core if this is set.
+    pub hdbgen, set_hdbgen: 14;
+    /// Execute instruction enable. Instructions written to DBGITR are only executed if this is set.
+    pub itren, set_itren: 13;
+    /// Sticky undefined instruction, set if an instruction written to DBGITR was undefined.
+    pub und_l, _: 8;
+    /// Sticky asynchronous abort.
+    pub adabort_l, _: 7;
+    /// Sticky synchronous data abort, set if a memory access of an instruction written to DBGITR failed.
+    pub sdabort_l, _: 6;
+    /// Method of debug entry, the reason why the core was halted.
+    pub moe, _: 5, 2;
+    /// Set when the core left debug state after a restart request.
+    pub restarted, _: 1;
+    /// Set while the core is halted.
+    pub halted, _: 0;
+}
+
+debug_register!(Dbgdscr, 0x088, "DBGDSCR");
+
+impl Dbgdscr {
+    fn halt_reason(&self) -> HaltReason {
+        match self.moe() {
+            // Halt request through DBGDRCR, or external debug request through the CTI
+            0b0000 | 0b0100 => HaltReason::Request,
+            // Breakpoint, or BKPT instruction
+            0b0001 | 0b0011 => HaltReason::Breakpoint,
+            // Asynchronous or synchronous watchpoint
+            0b0010 | 0b1010 => HaltReason::Watchpoint(None),
+            // Vector catch, or OS unlock catch
+            0b0101 | 0b1000 => HaltReason::Exception,
+            _ => HaltReason::Unknown,
+        }
+    }
+}
+
+bitfield! {
+    /// Debug Run Control Register, DBGDRCR
+    #[derive(Copy, Clone)]
+    pub struct Dbgdrcr(u32);
+    impl Debug;
+    /// Clear the sticky exception flags of DBGDSCR.
+    pub cse, set_cse: 2;
+    /// Restart request.
+    pub rrq, set_rrq: 1;
+    /// Halt request.
+    pub hrq, set_hrq: 0;
+}
+
+debug_register!(Dbgdrcr, 0x090, "DBGDRCR");
+
+bitfield! {
+    /// Vector Catch Register, DBGVCR
+    #[derive(Copy, Clone)]
+    pub struct Dbgvcr(u32);
+    impl Debug;
+    /// Halt the core when it fetches the reset vector.
+    pub reset, set_reset: 0;
+}
+
+debug_register!(Dbgvcr, 0x01C, "DBGVCR");
+
+bitfield! {
+    /// Device Power-down and Reset Control Register, DBGPRCR
+    #[derive(Copy, Clone)]
+    pub struct Dbgprcr(u32);
+    impl Debug;
+    /// Warm reset request.
+    pub cwrr, set_cwrr: 1;
+}
+
+debug_register!(Dbgprcr, 0x310, "DBGPRCR");
+
+bitfield! {
+    /// Breakpoint Control Register, DBGBCRn
+    #[derive(Copy, Clone)]
+    pub struct Dbgbcr(u32);
+    impl Debug;
+    /// Breakpoint type, `0b000` for an address match and `0b100` for an address mismatch.
+    pub bt, set_bt: 22, 20;
+    /// Byte address select, the halfwords of the word which are matched.
+    pub bas, set_bas: 8, 5;
+    /// Privileged mode control, `0b11` matches all modes.
+    pub pmc, set_pmc: 2, 1;
+    /// Enable the breakpoint.
+    pub e, set_e: 0;
+}
+
+impl From<Dbgbcr> for u32 {
+    fn from(value: Dbgbcr) -> Self {
+        value.0
+    }
+}
+
+/// The registers of an ARMv7-A or ARMv7-R core.
+///
+/// The registers R0-R15 of the current mode use the addresses 0-15, followed by the CPSR.
+pub(crate) static ARMV7A_REGISTER_FILE: RegisterFile = RegisterFile {
+    platform_registers: &[
+        RegisterDescription {
+            name: "R0",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0),
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "R1",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(1),
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "R2",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(2),
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "R3",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(3),
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "R4",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(4),
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "R5",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(5),
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "R6",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(6),
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "R7",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(7),
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "R8",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(8),
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "R9",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(9),
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "R10",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(10),
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "R11",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(11),
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "R12",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(12),
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "R13",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(13),
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "R14",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(14),
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "R15",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(15),
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "CPSR",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(16),
+            size_in_bits: 32,
+        },
+    ],
+
+    program_counter: &register::PC,
+    stack_pointer: &register::SP,
+    return_address: &register::LR,
+    frame_pointer: &register::FP,
+
+    argument_registers: &[
+        RegisterDescription {
+            name: "a1",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0),
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a2",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(1),
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a3",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(2),
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a4",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(3),
+            size_in_bits: 32,
+        },
+    ],
+
+    result_registers: &[
+        RegisterDescription {
+            name: "a1",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0),
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "a2",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(1),
+            size_in_bits: 32,
+        },
+    ],
+
+    msp: None,
+    psp: None,
+    extra: None,
+};
+
+#[test]
+fn instruction_encoding() {
+    // Values from the GNU assembler.
+    assert_eq!(mcr_dbgdtrtx(0), 0xEE00_0E15);
+    assert_eq!(mrc_dbgdtrrx(1), 0xEE10_1E15);
+    assert_eq!(mov(0, 15), 0xE1A0_000F);
+    assert_eq!(mov(15, 0), 0xE1A0_F000);
+    assert_eq!(mrs_cpsr(0), 0xE10F_0000);
+    assert_eq!(msr_cpsr(0), 0xE12F_F000);
+    assert_eq!(load_post_increment(4, 1, 0), 0xE490_1004);
+    assert_eq!(load_post_increment(1, 1, 0), 0xE4D0_1001);
+    assert_eq!(store_post_increment(4, 1, 0), 0xE480_1004);
+    assert_eq!(store_post_increment(1, 1, 0), 0xE4C0_1001);
+}
+
+#[test]
+fn register_file_indices() {
+    use register_index::*;
+
+    assert_eq!(ARMV7A_REGISTER_FILE.platform_registers.len(), NUM_REGISTERS);
+
+    for (index, name) in [(LR, "R14"), (PC, "R15"), (CPSR, "CPSR")] {
+        assert_eq!(
+            ARMV7A_REGISTER_FILE
+                .platform_register(index as usize)
+                .name(),
+            name
+        );
+    }
+}
@@ -2,15 +2,16 @@
 
 use crate::architecture::arm::sequences::ArmDebugSequence;
 use crate::core::{
-    CoreInformation, CoreInterface, CoreRegister, CoreRegisterAddress, RegisterFile,
+    CoreInformation, CoreInterface, CoreRegister, CoreRegisterAddress, RegisterFile, Watchpoint,
 };
 use crate::error::Error;
 use crate::memory::Memory;
 use crate::DebugProbeError;
 
+use super::dwt::{self, DwtVersion};
 use super::{register, Dfsr, State, ARM_REGISTER_FILE};
 use crate::{
-    core::{Architecture, CoreStatus, HaltReason},
+    core::{Architecture, CoreStatus, HaltReason, VectorCatchCondition},
     MemoryInterface,
 };
 use anyhow::{anyhow, Result};
@@ -690,7 +691,13 @@ impl<'probe> CoreInterface for Armv7m<'probe> {
         if dhcsr.s_halt() {
             let dfsr = Dfsr(self.memory.read_word_32(Dfsr::ADDRESS)?);
 
-            let reason = dfsr.halt_reason();
+            let reason = match dfsr.halt_reason() {
+                HaltReason::Watchpoint(_) => HaltReason::Watchpoint(dwt::triggered_watchpoint(
+                    &mut self.memory,
+                    DwtVersion::V7,
+                )?),
+                reason => reason,
+            };
 
             // Clear bits from Dfsr register
             self.memory
@@ -758,7 +765,9 @@ impl<'probe> CoreInterface for Armv7m<'probe> {
         let pc_value = self.read_core_reg(register::PC.address)?;
 
         // get pc
-        Ok(CoreInformation { pc: pc_value })
+        Ok(CoreInformation {
+            pc: pc_value.into(),
+        })
     }
 
     fn run(&mut self) -> Result<(), Error> {
@@ -830,7 +839,9 @@ impl<'probe> CoreInterface for Armv7m<'probe> {
         let pc_value = self.read_core_reg(register::PC.address)?;
 
         // get pc
-        Ok(CoreInformation { pc: pc_value })
+        Ok(CoreInformation {
+            pc: pc_value.into(),
+        })
     }
 
     fn reset(&mut self) -> Result<(), Error> {
@@ -841,6 +852,10 @@ impl<'probe> CoreInterface for Armv7m<'probe> {
         // Set the vc_corereset bit in the DEMCR register.
         // This will halt the core after reset.
 
+        // A vector catch on core reset, which the user configured, has to survive the reset catch sequence.
+        let vector_catch_core_reset =
+            Demcr::from(self.memory.read_word_32(Demcr::ADDRESS)?).vc_corereset();
+
         self.sequence.reset_catch_set(&mut self.memory)?;
         self.sequence.reset_system(&mut self.memory)?;
 
@@ -855,11 +870,19 @@ impl<'probe> CoreInterface for Armv7m<'probe> {
 
         self.sequence.reset_catch_clear(&mut self.memory)?;
 
+        if vector_catch_core_reset {
+            let mut demcr = Demcr::from(self.memory.read_word_32(Demcr::ADDRESS)?);
+            demcr.set_vc_corereset(true);
+            self.memory.write_word_32(Demcr::ADDRESS, demcr.into())?;
+        }
+
         // try to read the program counter
         let pc_value = self.read_core_reg(register::PC.address)?;
 
         // get pc
-        Ok(CoreInformation { pc: pc_value })
+        Ok(CoreInformation {
+            pc: pc_value.into(),
+        })
     }
 
     fn available_breakpoint_units(&mut self) -> Result<u32, Error> {
@@ -937,6 +960,30 @@ impl<'probe> CoreInterface for Armv7m<'probe> {
         Ok(())
     }
 
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        dwt::available_watchpoint_units(&mut self.memory)
+    }
+
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        dwt::hw_watchpoints(&mut self.memory, DwtVersion::V7)
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        unit_index: usize,
+        watchpoint: Watchpoint,
+    ) -> Result<(), Error> {
+        dwt::set_hw_watchpoint(&mut self.memory, DwtVersion::V7, unit_index, watchpoint)
+    }
+
+    fn clear_hw_watchpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        dwt::clear_hw_watchpoint(&mut self.memory, unit_index)
+    }
+
+    fn watchpoints_share_breakpoint_units(&self) -> bool {
+        false
+    }
+
     fn hw_breakpoints_enabled(&self) -> bool {
         self.state.hw_breakpoints_enabled
     }
@@ -945,6 +992,44 @@ impl<'probe> CoreInterface for Armv7m<'probe> {
         Architecture::Arm
     }
 
+    fn set_vector_catch(&mut self, conditions: &[VectorCatchCondition]) -> Result<(), Error> {
+        let mut demcr = Demcr::from(self.memory.read_word_32(Demcr::ADDRESS)?);
+
+        demcr.set_vc_corereset(false);
+        demcr.set_vc_harderr(false);
+        demcr.set_vc_interr(false);
+        demcr.set_vc_mmerr(false);
+        demcr.set_vc_buserr(false);
+        demcr.set_vc_staterr(false);
+        demcr.set_vc_chkerr(false);
+        demcr.set_vc_nocperr(false);
+
+        for condition in conditions {
+            match condition {
+                VectorCatchCondition::CoreReset => demcr.set_vc_corereset(true),
+                VectorCatchCondition::HardFault => demcr.set_vc_harderr(true),
+                VectorCatchCondition::ExceptionEntryOrReturn => demcr.set_vc_interr(true),
+                VectorCatchCondition::MemManage => demcr.set_vc_mmerr(true),
+                VectorCatchCondition::BusFault => demcr.set_vc_buserr(true),
+                VectorCatchCondition::UsageFault => {
+                    demcr.set_vc_staterr(true);
+                    demcr.set_vc_chkerr(true);
+                    demcr.set_vc_nocperr(true);
+                }
+                other => {
+                    return Err(Error::Other(anyhow::anyhow!(
+                        "Vector catch condition {:?} is not supported by ARMv7-M cores",
+                        other
+                    )))
+                }
+            }
+        }
+
+        self.memory.write_word_32(Demcr::ADDRESS, demcr.into())?;
+
+        Ok(())
+    }
+
     /// See docs on the [`CoreInterface::hw_breakpoints`] trait.
     fn hw_breakpoints(&mut self) -> Result<Vec<Option<u32>>, Error> {
         let mut breakpoints = vec![];
@@ -0,0 +1,1734 @@
+//! Register types and the core interface for ARMv8-A cores, running in AArch64 state.
+//!
+//! The core is controlled through its external debug interface, and halted and restarted using its
+//! cross trigger interface (CTI). While the core is halted, registers and memory are accessed by
+//! executing instructions on the core, which exchange data with the debugger through the debug
+//! communications channel (DCC).
+//!
+//! Because memory is accessed through the core, it can only be accessed while the core is halted,
+//! and the addresses are virtual addresses, which are translated by the MMU of the core.
+//!
+//! See ARM architecture reference manual for A-profile, chapter H "External Debug".
+
+use super::{cti, CortexAState};
+use crate::architecture::arm::component::DebugRegister;
+use crate::core::{
+    CoreInformation, CoreInterface, CoreRegisterAddress, RegisterDescription, RegisterFile,
+    RegisterKind, RegisterValue, Watchpoint, WatchpointKind,
+};
+use crate::error::Error;
+use crate::memory::Memory;
+use crate::{
+    Architecture, CoreStatus, DebugProbeError, HaltReason, MemoryInterface, VectorCatchCondition,
+};
+use anyhow::{anyhow, Result};
+use bitfield::bitfield;
+use std::time::{Duration, Instant};
+
+/// The number of registers in [`AARCH64_REGISTER_FILE`].
+const NUM_REGISTERS: usize = 68;
+
+/// How long to wait for an instruction written to EDITR to complete.
+const INSTRUCTION_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// The register indices used by [`AARCH64_REGISTER_FILE`].
+mod register_index {
+    /// The last general purpose register, X30, which is used as link register.
+    pub const X30: u16 = 30;
+    pub const SP: u16 = 31;
+    pub const PC: u16 = 32;
+    pub const PSTATE: u16 = 33;
+    /// The first SIMD and floating-point register, V0.
+    pub const V0: u16 = 34;
+    /// The last SIMD and floating-point register, V31.
+    pub const V31: u16 = 65;
+    pub const FPSR: u16 = 66;
+    pub const FPCR: u16 = 67;
+}
+
+/// An ARMv8-A core, e.g. a Cortex-A53 or Cortex-A72.
+pub struct Armv8a<'probe> {
+    memory: Memory<'probe>,
+
+    state: &'probe mut CortexAState,
+
+    /// The base address of the external debug registers of the core.
+    base_address: u32,
+
+    /// The base address of the CTI of the core.
+    cti_address: u32,
+}
+
+impl<'probe> Armv8a<'probe> {
+    pub(crate) fn new(
+        memory: Memory<'probe>,
+        state: &'probe mut CortexAState,
+        base_address: u32,
+        cti_address: u32,
+    ) -> Result<Self, Error> {
+        let mut core = Self {
+            memory,
+            state,
+            base_address,
+            cti_address,
+        };
+
+        if !core.state.initialized() {
+            core.initialize()?;
+        }
+
+        Ok(core)
+    }
+
+    fn initialize(&mut self) -> Result<(), Error> {
+        // The software lock is optional, and writes to EDLAR are ignored if it is not implemented.
+        self.memory
+            .write_word_32(self.base_address + EDLAR, UNLOCK_KEY)?;
+
+        // The OS lock is set on a cold reset, and blocks access to most of the debug registers.
+        self.memory.write_word_32(self.base_address + OSLAR, 0)?;
+
+        let mut edscr: Edscr = self.read_debug_register()?;
+        edscr.set_hde(true);
+        self.write_debug_register(edscr)?;
+
+        cti::initialize(&mut self.memory, self.cti_address)?;
+
+        self.state.invalidate_register_cache(NUM_REGISTERS);
+
+        let edprsr: Edprsr = self.read_debug_register()?;
+        self.state.current_state = if edprsr.halted() {
+            let edscr: Edscr = self.read_debug_register()?;
+            self.state.is_64_bit = edscr.is_64_bit();
+
+            CoreStatus::Halted(edscr.halt_reason())
+        } else {
+            CoreStatus::Running
+        };
+
+        log::debug!("State when connecting: {:?}", self.state.current_state);
+
+        self.state.initialize();
+
+        Ok(())
+    }
+
+    fn read_debug_register<R: DebugRegister>(&mut self) -> Result<R, Error> {
+        Ok(R::from(
+            self.memory.read_word_32(self.base_address + R::ADDRESS)?,
+        ))
+    }
+
+    fn write_debug_register<R: DebugRegister>(&mut self, value: R) -> Result<(), Error> {
+        self.memory
+            .write_word_32(self.base_address + R::ADDRESS, value.into())
+    }
+
+    /// Clears the sticky error flags of EDSCR, which block the execution of further instructions.
+    fn clear_sticky_errors(&mut self) -> Result<(), Error> {
+        let mut edrcr = Edrcr(0);
+        edrcr.set_cse(true);
+
+        self.write_debug_register(edrcr)
+    }
+
+    /// Executes `instruction` on the halted core, and waits until it has completed.
+    fn execute_instruction(&mut self, instruction: u32) -> Result<(), Error> {
+        self.memory
+            .write_word_32(self.base_address + EDITR, instruction)?;
+
+        let start = Instant::now();
+        loop {
+            let edscr: Edscr = self.read_debug_register()?;
+
+            if edscr.err() {
+                self.clear_sticky_errors()?;
+
+                return Err(Error::Other(anyhow!(
+                    "Instruction {:#010x} caused an exception while the core was halted",
+                    instruction
+                )));
+            }
+
+            if edscr.ite() {
+                return Ok(());
+            }
+
+            if start.elapsed() > INSTRUCTION_TIMEOUT {
+                return Err(Error::Probe(DebugProbeError::Timeout));
+            }
+        }
+    }
+
+    /// Reads the 64 bit value which the core wrote to DBGDTR_EL0.
+    fn read_dcc(&mut self) -> Result<u64, Error> {
+        let edscr: Edscr = self.read_debug_register()?;
+        if !edscr.txfull() {
+            return Err(Error::Other(anyhow!(
+                "The core did not write any data to the debug communications channel"
+            )));
+        }
+
+        // Writes to DBGDTR_EL0 store the upper half in DTRRX, and the lower half in DTRTX.
+        // Reading DTRTX marks the channel as empty, so it is read last.
+        let high = self.memory.read_word_32(self.base_address + DBGDTRRX)?;
+        let low = self.memory.read_word_32(self.base_address + DBGDTRTX)?;
+
+        Ok(u64::from(low) | (u64::from(high) << 32))
+    }
+
+    /// Writes a 64 bit value, which the core can read from DBGDTR_EL0.
+    fn write_dcc(&mut self, value: u64) -> Result<(), Error> {
+        // Reads of DBGDTR_EL0 return DTRTX in the upper half, and DTRRX in the lower half.
+        // Writing DTRRX marks the channel as full, so it is written last.
+        self.memory
+            .write_word_32(self.base_address + DBGDTRTX, (value >> 32) as u32)?;
+        self.memory
+            .write_word_32(self.base_address + DBGDTRRX, value as u32)
+    }
+
+    /// Makes sure that the core is halted in AArch64 state, so that registers and memory can be accessed.
+    fn check_halted(&mut self) -> Result<(), Error> {
+        if !self.state.current_state.is_halted() {
+            self.status()?;
+        }
+
+        if !self.state.current_state.is_halted() {
+            return Err(Error::Other(anyhow!(
+                "The core needs to be halted to access its registers and memory"
+            )));
+        }
+
+        if !self.state.is_64_bit {
+            return Err(Error::Other(anyhow!(
+                "The core is executing in AArch32 state, which is not supported"
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn register_description(index: u16) -> Result<&'static RegisterDescription, Error> {
+        AARCH64_REGISTER_FILE
+            .get_platform_register(index as usize)
+            .ok_or_else(|| anyhow!("Register {} does not exist on ARMv8-A cores", index).into())
+    }
+
+    /// Reads a register, using the cached value if it was read before.
+    fn read_register(&mut self, index: u16) -> Result<RegisterValue, Error> {
+        Self::register_description(index)?;
+        self.check_halted()?;
+
+        if let Some((value, _)) = self.state.register_cache[index as usize] {
+            return Ok(value);
+        }
+
+        let value = self.read_register_uncached(index)?;
+        self.state.register_cache[index as usize] = Some((value, false));
+
+        Ok(value)
+    }
+
+    /// Changes the value of a register, which is written to the core before it is restarted.
+    fn write_register(&mut self, index: u16, value: RegisterValue) -> Result<(), Error> {
+        let description = Self::register_description(index)?;
+        self.check_halted()?;
+
+        let value = match description.size_in_bits {
+            32 => RegisterValue::U32(u32::try_from(value)?),
+            64 => RegisterValue::U64(u64::try_from(value)?),
+            _ => RegisterValue::U128(value.into()),
+        };
+
+        self.state.register_cache[index as usize] = Some((value, true));
+
+        Ok(())
+    }
+
+    /// Caches the value of the general purpose register `index`, before it is used by an instruction.
+    ///
+    /// The register is marked as modified, so its original value is restored before the core is restarted.
+    fn save_scratch_register(&mut self, index: u16) -> Result<(), Error> {
+        let value = self.read_register(index)?;
+        self.state.register_cache[index as usize] = Some((value, true));
+
+        Ok(())
+    }
+
+    fn read_register_uncached(&mut self, index: u16) -> Result<RegisterValue, Error> {
+        use register_index::*;
+
+        Ok(match index {
+            0..=X30 => {
+                self.execute_instruction(msr(DBGDTR_EL0, index.into()))?;
+                RegisterValue::U64(self.read_dcc()?)
+            }
+            SP => RegisterValue::U64(self.read_through_x0(mov_from_sp(0))?),
+            PC => RegisterValue::U64(self.read_through_x0(mrs(DLR_EL0, 0))?),
+            PSTATE => RegisterValue::U32(self.read_through_x0(mrs(DSPSR_EL0, 0))? as u32),
+            V0..=V31 => {
+                let n = u32::from(index - V0);
+                let low = self.read_through_x0(fmov_from_lower_half(0, n))?;
+                let high = self.read_through_x0(fmov_from_upper_half(0, n))?;
+                RegisterValue::U128(u128::from(low) | (u128::from(high) << 64))
+            }
+            FPSR => RegisterValue::U32(self.read_through_x0(mrs(FPSR_EL0, 0))? as u32),
+            FPCR => RegisterValue::U32(self.read_through_x0(mrs(FPCR_EL0, 0))? as u32),
+            _ => unreachable!("Register {} was checked to exist", index),
+        })
+    }
+
+    fn write_register_uncached(&mut self, index: u16, value: RegisterValue) -> Result<(), Error> {
+        use register_index::*;
+
+        match index {
+            0..=X30 => {
+                self.write_dcc(u64::try_from(value)?)?;
+                self.execute_instruction(mrs(DBGDTR_EL0, index.into()))
+            }
+            SP => self.write_through_x0(u64::try_from(value)?, mov_to_sp(0)),
+            PC => self.write_through_x0(u64::try_from(value)?, msr(DLR_EL0, 0)),
+            PSTATE => self.write_through_x0(u64::try_from(value)?, msr(DSPSR_EL0, 0)),
+            V0..=V31 => {
+                let n = u32::from(index - V0);
+                let value = u128::from(value);
+                // Writing the lower half clears the upper half, so it is written first.
+                self.write_through_x0(value as u64, fmov_to_lower_half(n, 0))?;
+                self.write_through_x0((value >> 64) as u64, fmov_to_upper_half(n, 0))
+            }
+            FPSR => self.write_through_x0(u64::try_from(value)?, msr(FPSR_EL0, 0)),
+            FPCR => self.write_through_x0(u64::try_from(value)?, msr(FPCR_EL0, 0)),
+            _ => unreachable!("Register {} was checked to exist", index),
+        }
+    }
+
+    /// Executes `instruction`, which stores its result in X0, and reads the result.
+    fn read_through_x0(&mut self, instruction: u32) -> Result<u64, Error> {
+        self.save_scratch_register(0)?;
+
+        self.execute_instruction(instruction)?;
+        self.execute_instruction(msr(DBGDTR_EL0, 0))?;
+
+        self.read_dcc()
+    }
+
+    /// Loads `value` into X0, and executes `instruction`, which reads its operand from X0.
+    fn write_through_x0(&mut self, value: u64, instruction: u32) -> Result<(), Error> {
+        self.save_scratch_register(0)?;
+
+        self.write_dcc(value)?;
+        self.execute_instruction(mrs(DBGDTR_EL0, 0))?;
+
+        self.execute_instruction(instruction)
+    }
+
+    /// Writes all modified registers back to the core.
+    fn write_back_registers(&mut self) -> Result<(), Error> {
+        // All registers except the general purpose registers are written using X0,
+        // so the general purpose registers are restored last.
+        let indices = (register_index::SP..NUM_REGISTERS as u16).chain(0..=register_index::X30);
+
+        for index in indices {
+            if let Some((value, true)) = self.state.register_cache[index as usize] {
+                self.write_register_uncached(index, value)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Restarts the halted core.
+    fn restart(&mut self) -> Result<(), Error> {
+        self.write_back_registers()?;
+        self.clear_sticky_errors()?;
+
+        cti::acknowledge_halt(&mut self.memory, self.cti_address)?;
+        cti::request_restart(&mut self.memory, self.cti_address)?;
+
+        let start = Instant::now();
+        loop {
+            let edprsr: Edprsr = self.read_debug_register()?;
+            if edprsr.sdr() {
+                break;
+            }
+
+            if start.elapsed() > INSTRUCTION_TIMEOUT {
+                return Err(Error::Probe(DebugProbeError::Timeout));
+            }
+        }
+
+        self.state.invalidate_register_cache(NUM_REGISTERS);
+        self.state.current_state = CoreStatus::Running;
+
+        Ok(())
+    }
+
+    fn read_program_counter(&mut self) -> Result<u64, Error> {
+        u64::try_from(self.read_register(register_index::PC)?)
+    }
+
+    /// Reads `count` values of `size` bytes from memory, using the load instructions of the core.
+    ///
+    /// The address is a virtual address, which is translated by the MMU of the core.
+    fn read_memory(&mut self, address: u64, size: u32, count: usize) -> Result<Vec<u64>, Error> {
+        self.prepare_memory_access(address, size)?;
+
+        let mut values = Vec::with_capacity(count);
+        for _ in 0..count {
+            self.execute_instruction(load_post_increment(size, 1, 0))?;
+            self.execute_instruction(msr(DBGDTR_EL0, 1))?;
+            values.push(self.read_dcc()?);
+        }
+
+        Ok(values)
+    }
+
+    /// Writes `values` of `size` bytes to memory, using the store instructions of the core.
+    ///
+    /// The address is a virtual address, which is translated by the MMU of the core.
+    fn write_memory(
+        &mut self,
+        address: u64,
+        size: u32,
+        values: impl Iterator<Item = u64>,
+    ) -> Result<(), Error> {
+        self.prepare_memory_access(address, size)?;
+
+        for value in values {
+            self.write_dcc(value)?;
+            self.execute_instruction(mrs(DBGDTR_EL0, 1))?;
+            self.execute_instruction(store_post_increment(size, 1, 0))?;
+        }
+
+        Ok(())
+    }
+
+    /// Loads `address` into X0, which is used as address register by the memory accesses.
+    ///
+    /// X1 is used to transfer the data.
+    fn prepare_memory_access(&mut self, address: u64, size: u32) -> Result<(), Error> {
+        if address % u64::from(size) != 0 {
+            return Err(Error::Other(anyhow!(
+                "The address {:#x} is not aligned to the access size of {} bytes",
+                address,
+                size
+            )));
+        }
+
+        self.check_halted()?;
+        self.save_scratch_register(1)?;
+
+        self.write_through_x0(address, NOP)
+    }
+}
+
+impl<'probe> CoreInterface for Armv8a<'probe> {
+    fn wait_for_core_halted(&mut self, timeout: Duration) -> Result<(), Error> {
+        let start = Instant::now();
+
+        while start.elapsed() < timeout {
+            if self.core_halted()? {
+                return Ok(());
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        Err(Error::Probe(DebugProbeError::Timeout))
+    }
+
+    fn core_halted(&mut self) -> Result<bool, Error> {
+        let edprsr: Edprsr = self.read_debug_register()?;
+
+        Ok(edprsr.halted())
+    }
+
+    fn halt(&mut self, timeout: Duration) -> Result<CoreInformation, Error> {
+        if !self.core_halted()? {
+            cti::request_halt(&mut self.memory, self.cti_address)?;
+            self.wait_for_core_halted(timeout)?;
+            cti::acknowledge_halt(&mut self.memory, self.cti_address)?;
+        }
+
+        // Update core status
+        let _ = self.status()?;
+
+        Ok(CoreInformation {
+            pc: self.read_program_counter()?,
+        })
+    }
+
+    fn run(&mut self) -> Result<(), Error> {
+        // Before we run, we always perform a single instruction step, to account for possible breakpoints that might get us stuck on the current instruction.
+        self.step()?;
+
+        self.restart()
+    }
+
+    fn reset(&mut self) -> Result<(), Error> {
+        let mut edprcr = Edprcr(0);
+        edprcr.set_cwrr(true);
+        self.write_debug_register(edprcr)?;
+        self.memory.flush()?;
+
+        self.state.invalidate_register_cache(NUM_REGISTERS);
+        self.state.current_state = CoreStatus::Unknown;
+
+        Ok(())
+    }
+
+    fn reset_and_halt(&mut self, timeout: Duration) -> Result<CoreInformation, Error> {
+        // Halt the core when it leaves the reset.
+        let mut edecr: Edecr = self.read_debug_register()?;
+        let reset_catch = edecr.rce();
+        edecr.set_rce(true);
+        self.write_debug_register(edecr)?;
+
+        self.reset()?;
+        let result = self.wait_for_core_halted(timeout);
+
+        // Keep reset catch enabled, if it was set with `set_vector_catch`.
+        edecr.set_rce(reset_catch);
+        self.write_debug_register(edecr)?;
+        result?;
+
+        // Update core status
+        let _ = self.status()?;
+
+        Ok(CoreInformation {
+            pc: self.read_program_counter()?,
+        })
+    }
+
+    fn step(&mut self) -> Result<CoreInformation, Error> {
+        let program_counter = self.read_program_counter()?;
+
+        // A breakpoint on the current instruction would halt the core again before the instruction is executed.
+        let breakpoint_units: Vec<usize> = self
+            .hw_breakpoints()?
+            .iter()
+            .enumerate()
+            .filter(|(_, address)| address.map(u64::from) == Some(program_counter))
+            .map(|(unit_index, _)| unit_index)
+            .collect();
+
+        for unit_index in &breakpoint_units {
+            log::debug!("Disabling breakpoint #{} for the step", unit_index);
+            self.set_breakpoint_enabled(*unit_index, false)?;
+        }
+
+        let mut edecr: Edecr = self.read_debug_register()?;
+        edecr.set_ss(true);
+        self.write_debug_register(edecr)?;
+
+        let result = self
+            .restart()
+            .and_then(|_| self.wait_for_core_halted(Duration::from_millis(100)));
+
+        edecr.set_ss(false);
+        self.write_debug_register(edecr)?;
+
+        for unit_index in &breakpoint_units {
+            self.set_breakpoint_enabled(*unit_index, true)?;
+        }
+
+        result?;
+
+        // Update core status
+        let _ = self.status()?;
+
+        Ok(CoreInformation {
+            pc: self.read_program_counter()?,
+        })
+    }
+
+    fn read_core_reg(&mut self, address: CoreRegisterAddress) -> Result<u32, Error> {
+        u32::try_from(self.read_register(address.0)?)
+    }
+
+    fn write_core_reg(&mut self, address: CoreRegisterAddress, value: u32) -> Result<()> {
+        self.write_register(address.0, RegisterValue::U32(value))?;
+
+        Ok(())
+    }
+
+    fn read_core_reg_value(
+        &mut self,
+        address: CoreRegisterAddress,
+    ) -> Result<RegisterValue, Error> {
+        self.read_register(address.0)
+    }
+
+    fn write_core_reg_value(
+        &mut self,
+        address: CoreRegisterAddress,
+        value: RegisterValue,
+    ) -> Result<(), Error> {
+        self.write_register(address.0, value)
+    }
+
+    fn available_breakpoint_units(&mut self) -> Result<u32, Error> {
+        let eddfr: Eddfr = self.read_debug_register()?;
+
+        Ok(eddfr.brps() + 1)
+    }
+
+    /// See docs on the [`CoreInterface::hw_breakpoints`] trait
+    fn hw_breakpoints(&mut self) -> Result<Vec<Option<u32>>, Error> {
+        let num_hw_breakpoints = self.available_breakpoint_units()? as usize;
+        let mut breakpoints = Vec::with_capacity(num_hw_breakpoints);
+
+        for unit_index in 0..num_hw_breakpoints {
+            let control = Dbgbcr(
+                self.memory
+                    .read_word_32(self.breakpoint_register(DBGBCR0, unit_index))?,
+            );
+
+            if control.e() {
+                let address = self
+                    .memory
+                    .read_word_32(self.breakpoint_register(DBGBVR0, unit_index))?;
+                breakpoints.push(Some(address));
+            } else {
+                breakpoints.push(None);
+            }
+        }
+
+        Ok(breakpoints)
+    }
+
+    fn enable_breakpoints(&mut self, state: bool) -> Result<(), Error> {
+        // The breakpoint units are enabled individually, there is no global enable.
+        self.state.hw_breakpoints_enabled = state;
+
+        Ok(())
+    }
+
+    fn set_hw_breakpoint(&mut self, unit_index: usize, addr: u32) -> Result<(), Error> {
+        // The upper half of the breakpoint value register is cleared, so only the lower 4 GiB are supported.
+        self.memory
+            .write_word_32(self.breakpoint_register(DBGBVR0, unit_index), addr & !0b11)?;
+        self.memory
+            .write_word_32(self.breakpoint_register(DBGBVR0 + 4, unit_index), 0)?;
+
+        let mut control = Dbgbcr(0);
+        // Match all instructions in the word, at EL0, EL1 and EL2.
+        control.set_bas(0b1111);
+        control.set_pmc(0b11);
+        control.set_hmc(true);
+        control.set_e(true);
+
+        self.memory.write_word_32(
+            self.breakpoint_register(DBGBCR0, unit_index),
+            control.into(),
+        )?;
+
+        self.memory.flush()
+    }
+
+    fn clear_hw_breakpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        self.memory
+            .write_word_32(self.breakpoint_register(DBGBCR0, unit_index), 0)?;
+
+        self.memory.flush()
+    }
+
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        let eddfr: Eddfr = self.read_debug_register()?;
+
+        Ok(eddfr.wrps() + 1)
+    }
+
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        let num_watchpoints = self.available_watchpoint_units()? as usize;
+        let mut watchpoints = Vec::with_capacity(num_watchpoints);
+
+        for unit_index in 0..num_watchpoints {
+            let control = Dbgwcr(
+                self.memory
+                    .read_word_32(self.breakpoint_register(DBGWCR0, unit_index))?,
+            );
+
+            if !control.e() {
+                watchpoints.push(None);
+                continue;
+            }
+
+            let value = self
+                .memory
+                .read_word_32(self.breakpoint_register(DBGWVR0, unit_index))?;
+
+            let kind = match control.lsc() {
+                0b01 => WatchpointKind::Read,
+                0b10 => WatchpointKind::Write,
+                _ => WatchpointKind::ReadWrite,
+            };
+
+            // Either a power-of-two sized region selected by the mask, or bytes of a doubleword.
+            let (address, size) = if control.mask() != 0 {
+                (value, 1 << control.mask())
+            } else {
+                (
+                    value + control.bas().trailing_zeros(),
+                    control.bas().count_ones(),
+                )
+            };
+
+            watchpoints.push(Some(Watchpoint {
+                address,
+                size,
+                kind,
+            }));
+        }
+
+        Ok(watchpoints)
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        unit_index: usize,
+        watchpoint: Watchpoint,
+    ) -> Result<(), Error> {
+        let Watchpoint {
+            address,
+            size,
+            kind,
+        } = watchpoint;
+
+        // Up to 8 bytes within a doubleword are selected with the byte address select field,
+        // larger regions with the address mask.
+        let (value, bas, mask) = if size <= 8 && address % 8 + size <= 8 {
+            let offset = address % 8;
+            (address - offset, ((1 << size) - 1) << offset, 0)
+        } else if size.is_power_of_two() && address % size == 0 {
+            (address, 0xFF, size.trailing_zeros())
+        } else {
+            return Err(Error::Other(anyhow!(
+                "A watchpoint of {} bytes at {:#010x} is not supported on ARMv8-A cores",
+                size,
+                address
+            )));
+        };
+
+        // The upper half of the watchpoint value register is cleared, so only the lower 4 GiB are supported.
+        self.memory
+            .write_word_32(self.breakpoint_register(DBGWVR0, unit_index), value)?;
+        self.memory
+            .write_word_32(self.breakpoint_register(DBGWVR0 + 4, unit_index), 0)?;
+
+        let mut control = Dbgwcr(0);
+        control.set_mask(mask);
+        control.set_bas(bas);
+        control.set_lsc(match kind {
+            WatchpointKind::Read => 0b01,
+            WatchpointKind::Write => 0b10,
+            WatchpointKind::ReadWrite => 0b11,
+        });
+        // Match accesses at EL0, EL1 and EL2.
+        control.set_pac(0b11);
+        control.set_hmc(true);
+        control.set_e(true);
+
+        self.memory.write_word_32(
+            self.breakpoint_register(DBGWCR0, unit_index),
+            control.into(),
+        )?;
+
+        self.memory.flush()
+    }
+
+    fn clear_hw_watchpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        self.memory
+            .write_word_32(self.breakpoint_register(DBGWCR0, unit_index), 0)?;
+
+        self.memory.flush()
+    }
+
+    fn watchpoints_share_breakpoint_units(&self) -> bool {
+        false
+    }
+
+    fn registers(&self) -> &'static RegisterFile {
+        &AARCH64_REGISTER_FILE
+    }
+
+    fn hw_breakpoints_enabled(&self) -> bool {
+        self.state.hw_breakpoints_enabled
+    }
+
+    fn architecture(&self) -> Architecture {
+        Architecture::Arm
+    }
+
+    fn set_vector_catch(&mut self, conditions: &[VectorCatchCondition]) -> Result<(), Error> {
+        if let Some(condition) = conditions.iter().find(|condition| {
+            !matches!(
+                condition,
+                VectorCatchCondition::CoreReset | VectorCatchCondition::Exception
+            )
+        }) {
+            return Err(anyhow!("{:?} can not be caught on ARMv8-A cores", condition).into());
+        }
+
+        let mut edecr: Edecr = self.read_debug_register()?;
+        edecr.set_rce(conditions.contains(&VectorCatchCondition::CoreReset));
+        self.write_debug_register(edecr)?;
+
+        let mut edeccr = Edeccr(0);
+        if conditions.contains(&VectorCatchCondition::Exception) {
+            // Exceptions taken to Non-secure EL1 and EL2, and to Secure EL1 and EL3.
+            edeccr.set_nse(0b0110);
+            edeccr.set_se(0b1010);
+        }
+        self.write_debug_register(edeccr)?;
+
+        self.memory.flush()
+    }
+
+    fn status(&mut self) -> Result<CoreStatus, Error> {
+        let edprsr: Edprsr = self.read_debug_register()?;
+
+        if edprsr.halted() {
+            // The reason doesn't change while the core is halted, so it is only determined once.
+            if self.state.current_state.is_halted() {
+                return Ok(self.state.current_state);
+            }
+
+            let edscr: Edscr = self.read_debug_register()?;
+            let reason = match edscr.halt_reason() {
+                HaltReason::Watchpoint(_) => HaltReason::Watchpoint(self.triggered_watchpoint()?),
+                reason => reason,
+            };
+
+            log::debug!("Core halted, reason: {:?}", reason);
+            self.state.invalidate_register_cache(NUM_REGISTERS);
+
+            self.state.is_64_bit = edscr.is_64_bit();
+            self.state.current_state = CoreStatus::Halted(reason);
+
+            return Ok(CoreStatus::Halted(reason));
+        }
+
+        if self.state.current_state.is_halted() {
+            log::warn!("Core is running, but we expected it to be halted");
+            self.state.invalidate_register_cache(NUM_REGISTERS);
+        }
+
+        self.state.current_state = CoreStatus::Running;
+
+        Ok(CoreStatus::Running)
+    }
+}
+
+impl<'probe> Armv8a<'probe> {
+    fn breakpoint_register(&self, register: u32, unit_index: usize) -> u32 {
+        self.base_address + register + BREAKPOINT_STRIDE * unit_index as u32
+    }
+
+    /// Returns the address of the watchpoint which contains the data address that halted the core.
+    fn triggered_watchpoint(&mut self) -> Result<Option<u32>, Error> {
+        let data_address = self.memory.read_word_32(self.base_address + EDWAR)?;
+
+        Ok(self
+            .hw_watchpoints()?
+            .into_iter()
+            .flatten()
+            .find(|watchpoint| {
+                watchpoint.address <= data_address
+                    && data_address - watchpoint.address < watchpoint.size
+            })
+            .map(|watchpoint| watchpoint.address))
+    }
+
+    fn set_breakpoint_enabled(&mut self, unit_index: usize, enabled: bool) -> Result<(), Error> {
+        let address = self.breakpoint_register(DBGBCR0, unit_index);
+
+        let mut control = Dbgbcr(self.memory.read_word_32(address)?);
+        control.set_e(enabled);
+
+        self.memory.write_word_32(address, control.into())
+    }
+}
+
+impl<'probe> MemoryInterface for Armv8a<'probe> {
+    fn read_word_32(&mut self, address: u32) -> Result<u32, Error> {
+        Ok(self.read_memory(address.into(), 4, 1)?[0] as u32)
+    }
+    fn read_word_8(&mut self, address: u32) -> Result<u8, Error> {
+        Ok(self.read_memory(address.into(), 1, 1)?[0] as u8)
+    }
+    fn read_32(&mut self, address: u32, data: &mut [u32]) -> Result<(), Error> {
+        let values = self.read_memory(address.into(), 4, data.len())?;
+        for (data, value) in data.iter_mut().zip(values) {
+            *data = value as u32;
+        }
+        Ok(())
+    }
+    fn read_8(&mut self, address: u32, data: &mut [u8]) -> Result<(), Error> {
+        let values = self.read_memory(address.into(), 1, data.len())?;
+        for (data, value) in data.iter_mut().zip(values) {
+            *data = value as u8;
+        }
+        Ok(())
+    }
+    fn write_word_32(&mut self, address: u32, data: u32) -> Result<(), Error> {
+        self.write_memory(address.into(), 4, std::iter::once(data.into()))
+    }
+    fn write_word_8(&mut self, address: u32, data: u8) -> Result<(), Error> {
+        self.write_memory(address.into(), 1, std::iter::once(data.into()))
+    }
+    fn write_32(&mut self, address: u32, data: &[u32]) -> Result<(), Error> {
+        self.write_memory(address.into(), 4, data.iter().map(|&value| value.into()))
+    }
+    fn write_8(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
+        self.write_memory(address.into(), 1, data.iter().map(|&value| value.into()))
+    }
+    fn supports_native_64bit_access(&mut self) -> bool {
+        true
+    }
+    fn read_word_64(&mut self, address: u64) -> Result<u64, Error> {
+        Ok(self.read_memory(address, 8, 1)?[0])
+    }
+    fn read_64(&mut self, address: u64, data: &mut [u64]) -> Result<(), Error> {
+        let values = self.read_memory(address, 8, data.len())?;
+        data.copy_from_slice(&values);
+        Ok(())
+    }
+    fn write_word_64(&mut self, address: u64, data: u64) -> Result<(), Error> {
+        self.write_memory(address, 8, std::iter::once(data))
+    }
+    fn write_64(&mut self, address: u64, data: &[u64]) -> Result<(), Error> {
+        self.write_memory(address, 8, data.iter().copied())
+    }
+    fn flush(&mut self) -> Result<(), Error> {
+        self.memory.flush()
+    }
+}
+
+/// Encodes the operands of a system register for the `MRS` and `MSR` instructions.
+const fn system_register(op0: u32, op1: u32, crn: u32, crm: u32, op2: u32) -> u32 {
+    (op0 << 19) | (op1 << 16) | (crn << 12) | (crm << 8) | (op2 << 5)
+}
+
+/// Debug data transfer register, DBGDTR_EL0
+const DBGDTR_EL0: u32 = system_register(0b10, 0b011, 0b0000, 0b0100, 0b000);
+/// Debug link register, DLR_EL0, which holds the program counter while the core is halted.
+const DLR_EL0: u32 = system_register(0b11, 0b011, 0b0100, 0b0101, 0b001);
+/// Debug saved program status register, DSPSR_EL0, which holds PSTATE while the core is halted.
+const DSPSR_EL0: u32 = system_register(0b11, 0b011, 0b0100, 0b0101, 0b000);
+/// Floating-point control register, FPCR
+const FPCR_EL0: u32 = system_register(0b11, 0b011, 0b0100, 0b0100, 0b000);
+/// Floating-point status register, FPSR
+const FPSR_EL0: u32 = system_register(0b11, 0b011, 0b0100, 0b0100, 0b001);
+
+/// `NOP`
+const NOP: u32 = 0xD503_201F;
+
+/// `MRS <Xt>, <system_register>`
+const fn mrs(system_register: u32, rt: u32) -> u32 {
+    0xD520_0000 | system_register | rt
+}
+
+/// `MSR <system_register>, <Xt>`
+const fn msr(system_register: u32, rt: u32) -> u32 {
+    0xD500_0000 | system_register | rt
+}
+
+/// `MOV <Xd>, SP`
+const fn mov_from_sp(rd: u32) -> u32 {
+    0x9100_03E0 | rd
+}
+
+/// `MOV SP, <Xn>`
+const fn mov_to_sp(rn: u32) -> u32 {
+    0x9100_001F | (rn << 5)
+}
+
+/// `FMOV <Xd>, <Dn>`
+const fn fmov_from_lower_half(rd: u32, vn: u32) -> u32 {
+    0x9E66_0000 | (vn << 5) | rd
+}
+
+/// `FMOV <Xd>, <Vn>.D[1]`
+const fn fmov_from_upper_half(rd: u32, vn: u32) -> u32 {
+    0x9EAE_0000 | (vn << 5) | rd
+}
+
+/// `FMOV <Dd>, <Xn>`
+const fn fmov_to_lower_half(vd: u32, rn: u32) -> u32 {
+    0x9E67_0000 | (rn << 5) | vd
+}
+
+/// `FMOV <Vd>.D[1], <Xn>`
+const fn fmov_to_upper_half(vd: u32, rn: u32) -> u32 {
+    0x9EAF_0000 | (rn << 5) | vd
+}
+
+/// `LDRB <Wt>, [<Xn>], #1`, `LDR <Wt>, [<Xn>], #4` or `LDR <Xt>, [<Xn>], #8`, depending on `size`.
+const fn load_post_increment(size: u32, rt: u32, rn: u32) -> u32 {
+    (size.trailing_zeros() << 30) | 0x3840_0400 | (size << 12) | (rn << 5) | rt
+}
+
+/// `STRB <Wt>, [<Xn>], #1`, `STR <Wt>, [<Xn>], #4` or `STR <Xt>, [<Xn>], #8`, depending on `size`.
+const fn store_post_increment(size: u32, rt: u32, rn: u32) -> u32 {
+    (size.trailing_zeros() << 30) | 0x3800_0400 | (size << 12) | (rn << 5) | rt
+}
+
+/// External debug watchpoint address register, EDWAR. The upper half of the address is at EDWAR + 4.
+const EDWAR: u32 = 0x030;
+/// Debug data transfer register, receive, DBGDTRRX_EL0
+const DBGDTRRX: u32 = 0x080;
+/// External debug instruction transfer register, EDITR
+const EDITR: u32 = 0x084;
+/// Debug data transfer register, transmit, DBGDTRTX_EL0
+const DBGDTRTX: u32 = 0x08C;
+/// OS lock access register, OSLAR_EL1
+const OSLAR: u32 = 0x300;
+/// External debug lock access register, EDLAR
+const EDLAR: u32 = 0xFB0;
+/// Breakpoint value register 0, DBGBVR0_EL1. The upper half of the address is at DBGBVR0 + 4.
+const DBGBVR0: u32 = 0x400;
+/// Breakpoint control register 0, DBGBCR0_EL1
+const DBGBCR0: u32 = 0x408;
+/// Watchpoint value register 0, DBGWVR0_EL1. The upper half of the address is at DBGWVR0 + 4.
+const DBGWVR0: u32 = 0x800;
+/// Watchpoint control register 0, DBGWCR0_EL1
+const DBGWCR0: u32 = 0x808;
+/// The distance between the registers of two consecutive breakpoint or watchpoint units.
+const BREAKPOINT_STRIDE: u32 = 16;
+
+/// The key which has to be written to EDLAR to allow writes to the debug registers.
+const UNLOCK_KEY: u32 = 0xC5AC_CE55;
+
+bitfield! {
+    /// External Debug Status and Control Register, EDSCR
+    #[derive(Copy, Clone)]
+    pub struct Edscr(u32);
+    impl Debug;
+    /// DTRRX full.
+    pub rxfull, _: 30;
+    /// DTRTX full.
+    pub txfull, _: 29;
+    /// EDITR empty, set when the last instruction written to EDITR has completed.
+    pub ite, _: 24;
+    /// Halting debug enable. Breakpoints only halt the core if this is set.
+    pub hde, set_hde: 14;
+    /// The execution state of the exception levels, bit `n` is set if ELn is using AArch64.
+    pub rw, _: 13, 10;
+    /// The current exception level.
+    pub el, _: 9, 8;
+    /// Cumulative error flag, set if an instruction written to EDITR caused an exception.
+    pub err, _: 6;
+    /// The reason why the core entered debug state, `0b000010` if the core is not halted.
+    pub status, _: 5, 0;
+}
+
+debug_register!(Edscr, 0x088, "EDSCR");
+
+impl Edscr {
+    fn halt_reason(&self) -> HaltReason {
+        match self.status() {
+            0b000111 => HaltReason::Breakpoint,
+            0b010011 => HaltReason::Request,
+            0b011011 | 0b011111 | 0b111011 => HaltReason::Step,
+            0b101011 => HaltReason::Watchpoint(None),
+            // HLT instruction
+            0b101111 => HaltReason::Breakpoint,
+            // OS unlock catch, reset catch and exception catch
+            0b100011 | 0b100111 | 0b110111 => HaltReason::Exception,
+            _ => HaltReason::Unknown,
+        }
+    }
+
+    fn is_64_bit(&self) -> bool {
+        (self.rw() >> self.el()) & 1 == 1
+    }
+}
+
+bitfield! {
+    /// External Debug Processor Status Register, EDPRSR
+    #[derive(Copy, Clone)]
+    pub struct Edprsr(u32);
+    impl Debug;
+    /// Sticky debug restart, set when the core left debug state. Cleared on read.
+    pub sdr, _: 11;
+    /// Set while the core is halted.
+    pub halted, _: 4;
+    /// Set while the core is powered up.
+    pub pu, _: 0;
+}
+
+debug_register!(Edprsr, 0x314, "EDPRSR");
+
+bitfield! {
+    /// External Debug Execution Control Register, EDECR
+    #[derive(Copy, Clone)]
+    pub struct Edecr(u32);
+    impl Debug;
+    /// Halting step enable. The core halts again after executing one instruction.
+    pub ss, set_ss: 2;
+    /// Reset catch enable. The core halts when it leaves reset.
+    pub rce, set_rce: 1;
+}
+
+debug_register!(Edecr, 0x024, "EDECR");
+
+bitfield! {
+    /// External Debug Exception Catch Control Register, EDECCR
+    #[derive(Copy, Clone)]
+    pub struct Edeccr(u32);
+    impl Debug;
+    /// Non-secure exception catch, bit `n` halts the core when it takes an exception to Non-secure ELn.
+    pub nse, set_nse: 7, 4;
+    /// Secure exception catch, bit `n` halts the core when it takes an exception to Secure ELn.
+    pub se, set_se: 3, 0;
+}
+
+debug_register!(Edeccr, 0x098, "EDECCR");
+
+bitfield! {
+    /// External Debug Reserve Control Register, EDRCR
+    #[derive(Copy, Clone)]
+    pub struct Edrcr(u32);
+    impl Debug;
+    /// Clear the sticky error flags of EDSCR.
+    pub cse, set_cse: 2;
+}
+
+debug_register!(Edrcr, 0x090, "EDRCR");
+
+bitfield! {
+    /// External Debug Power/Reset Control Register, EDPRCR
+    #[derive(Copy, Clone)]
+    pub struct Edprcr(u32);
+    impl Debug;
+    /// Warm reset request.
+    pub cwrr, set_cwrr: 1;
+}
+
+debug_register!(Edprcr, 0x310, "EDPRCR");
+
+bitfield! {
+    /// External Debug Feature Register, EDDFR
+    #[derive(Copy, Clone)]
+    pub struct Eddfr(u32);
+    impl Debug;
+    /// The number of watchpoint units, minus one.
+    pub wrps, _: 23, 20;
+    /// The number of breakpoint units, minus one.
+    pub brps, _: 15, 12;
+}
+
+debug_register!(Eddfr, 0xD28, "EDDFR");
+
+bitfield! {
+    /// Breakpoint Control Register, DBGBCRn_EL1
+    #[derive(Copy, Clone)]
+    pub struct Dbgbcr(u32);
+    impl Debug;
+    /// Higher mode control, matches EL2 together with `pmc`.
+    pub hmc, set_hmc: 13;
+    /// Byte address select, must be `0b1111` for AArch64 instructions.
+    pub bas, set_bas: 8, 5;
+    /// Privilege mode control, `0b11` matches EL0 and EL1.
+    pub pmc, set_pmc: 2, 1;
+    /// Enable the breakpoint.
+    pub e, set_e: 0;
+}
+
+impl From<Dbgbcr> for u32 {
+    fn from(value: Dbgbcr) -> Self {
+        value.0
+    }
+}
+
+bitfield! {
+    /// Watchpoint Control Register, DBGWCRn_EL1
+    #[derive(Copy, Clone)]
+    pub struct Dbgwcr(u32);
+    impl Debug;
+    /// Address mask, the number of low address bits which are ignored. Zero selects bytes with `bas`.
+    pub mask, set_mask: 28, 24;
+    /// Higher mode control, matches EL2 together with `pac`.
+    pub hmc, set_hmc: 13;
+    /// Byte address select, bit `n` watches the byte at the address plus `n`.
+    pub bas, set_bas: 12, 5;
+    /// Load/store control, `0b01` matches loads, `0b10` stores and `0b11` both.
+    pub lsc, set_lsc: 4, 3;
+    /// Privilege of access control, `0b11` matches EL0 and EL1.
+    pub pac, set_pac: 2, 1;
+    /// Enable the watchpoint.
+    pub e, set_e: 0;
+}
+
+impl From<Dbgwcr> for u32 {
+    fn from(value: Dbgwcr) -> Self {
+        value.0
+    }
+}
+
+mod register {
+    use crate::{
+        core::{RegisterDescription, RegisterKind},
+        CoreRegisterAddress,
+    };
+
+    pub const PC: RegisterDescription = RegisterDescription {
+        name: "PC",
+        _kind: RegisterKind::PC,
+        address: CoreRegisterAddress(32),
+        size_in_bits: 64,
+    };
+
+    pub const SP: RegisterDescription = RegisterDescription {
+        name: "SP",
+        _kind: RegisterKind::General,
+        address: CoreRegisterAddress(31),
+        size_in_bits: 64,
+    };
+
+    pub const LR: RegisterDescription = RegisterDescription {
+        name: "X30",
+        _kind: RegisterKind::General,
+        address: CoreRegisterAddress(30),
+        size_in_bits: 64,
+    };
+
+    pub const FP: RegisterDescription = RegisterDescription {
+        name: "X29",
+        _kind: RegisterKind::General,
+        address: CoreRegisterAddress(29),
+        size_in_bits: 64,
+    };
+}
+
+/// The registers of an ARMv8-A core in AArch64 state.
+///
+/// The general purpose registers X0-X30 use the addresses 0-30, followed by SP, PC, PSTATE,
+/// the SIMD and floating-point registers V0-V31, FPSR and FPCR.
+pub(crate) static AARCH64_REGISTER_FILE: RegisterFile = RegisterFile {
+    platform_registers: &[
+        RegisterDescription {
+            name: "X0",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X1",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(1),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X2",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(2),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X3",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(3),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X4",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(4),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X5",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(5),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X6",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(6),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X7",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(7),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X8",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(8),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X9",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(9),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X10",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(10),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X11",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(11),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X12",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(12),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X13",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(13),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X14",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(14),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X15",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(15),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X16",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(16),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X17",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(17),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X18",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(18),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X19",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(19),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X20",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(20),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X21",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(21),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X22",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(22),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X23",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(23),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X24",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(24),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X25",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(25),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X26",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(26),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X27",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(27),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X28",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(28),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X29",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(29),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X30",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(30),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "SP",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(31),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "PC",
+            _kind: RegisterKind::PC,
+            address: CoreRegisterAddress(32),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "PSTATE",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(33),
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "V0",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(34),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V1",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(35),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V2",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(36),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V3",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(37),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V4",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(38),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V5",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(39),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V6",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(40),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V7",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(41),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V8",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(42),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V9",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(43),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V10",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(44),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V11",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(45),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V12",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(46),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V13",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(47),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V14",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(48),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V15",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(49),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V16",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(50),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V17",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(51),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V18",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(52),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V19",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(53),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V20",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(54),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V21",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(55),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V22",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(56),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V23",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(57),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V24",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(58),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V25",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(59),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V26",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(60),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V27",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(61),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V28",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(62),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V29",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(63),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V30",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(64),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "V31",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(65),
+            size_in_bits: 128,
+        },
+        RegisterDescription {
+            name: "FPSR",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(66),
+            size_in_bits: 32,
+        },
+        RegisterDescription {
+            name: "FPCR",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(67),
+            size_in_bits: 32,
+        },
+    ],
+
+    program_counter: &register::PC,
+    stack_pointer: &register::SP,
+    return_address: &register::LR,
+    frame_pointer: &register::FP,
+
+    argument_registers: &[
+        RegisterDescription {
+            name: "X0",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X1",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(1),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X2",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(2),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X3",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(3),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X4",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(4),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X5",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(5),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X6",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(6),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X7",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(7),
+            size_in_bits: 64,
+        },
+    ],
+
+    result_registers: &[
+        RegisterDescription {
+            name: "X0",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(0),
+            size_in_bits: 64,
+        },
+        RegisterDescription {
+            name: "X1",
+            _kind: RegisterKind::General,
+            address: CoreRegisterAddress(1),
+            size_in_bits: 64,
+        },
+    ],
+
+    msp: None,
+    psp: None,
+    extra: None,
+};
+
+#[test]
+fn instruction_encoding() {
+    // Values from the GNU assembler.
+    assert_eq!(msr(DBGDTR_EL0, 0), 0xD513_0400);
+    assert_eq!(mrs(DBGDTR_EL0, 1), 0xD533_0401);
+    assert_eq!(mrs(DLR_EL0, 0), 0xD53B_4520);
+    assert_eq!(mrs(DSPSR_EL0, 0), 0xD53B_4500);
+    assert_eq!(load_post_increment(8, 1, 0), 0xF840_8401);
+    assert_eq!(load_post_increment(4, 1, 0), 0xB840_4401);
+    assert_eq!(load_post_increment(1, 1, 0), 0x3840_1401);
+    assert_eq!(store_post_increment(8, 1, 0), 0xF800_8401);
+}
+
+#[test]
+fn register_file_indices() {
+    use register_index::*;
+
+    assert_eq!(
+        AARCH64_REGISTER_FILE.platform_registers.len(),
+        NUM_REGISTERS
+    );
+
+    for (index, name) in [
+        (X30, "X30"),
+        (SP, "SP"),
+        (PC, "PC"),
+        (PSTATE, "PSTATE"),
+        (V0, "V0"),
+        (V31, "V31"),
+        (FPSR, "FPSR"),
+        (FPCR, "FPCR"),
+    ] {
+        assert_eq!(
+            AARCH64_REGISTER_FILE
+                .platform_register(index as usize)
+                .name(),
+            name
+        );
+    }
+}
@@ -1,7 +1,7 @@
 //! Register types and the core interface for armv8-M
 
 use crate::architecture::arm::sequences::ArmDebugSequence;
-use crate::core::RegisterFile;
+use crate::core::{RegisterFile, Watchpoint};
 use crate::error::Error;
 use crate::memory::Memory;
 use crate::CoreRegisterAddress;
@@ -9,11 +9,12 @@ use crate::{
     architecture::arm::core::register, CoreStatus, DebugProbeError, HaltReason, MemoryInterface,
 };
 use crate::{Architecture, CoreInformation};
-use crate::{CoreInterface, CoreRegister};
+use crate::{CoreInterface, CoreRegister, VectorCatchCondition};
 use anyhow::Result;
 
 use bitfield::bitfield;
 
+use super::dwt::{self, DwtVersion};
 use super::{Dfsr, State, ARM_REGISTER_FILE};
 use std::sync::Arc;
 use std::{
@@ -117,7 +118,9 @@ impl<'probe> CoreInterface for Armv8m<'probe> {
         let pc_value = self.read_core_reg(register::PC.address)?;
 
         // get pc
-        Ok(CoreInformation { pc: pc_value })
+        Ok(CoreInformation {
+            pc: pc_value.into(),
+        })
     }
 
     fn run(&mut self) -> Result<(), Error> {
@@ -146,6 +149,10 @@ impl<'probe> CoreInterface for Armv8m<'probe> {
         // Set the vc_corereset bit in the DEMCR register.
         // This will halt the core after reset.
 
+        // A vector catch on core reset, which the user configured, has to survive the reset catch sequence.
+        let vector_catch_core_reset =
+            Demcr::from(self.memory.read_word_32(Demcr::ADDRESS)?).vc_corereset();
+
         self.sequence.reset_catch_set(&mut self.memory)?;
         self.sequence.reset_system(&mut self.memory)?;
 
@@ -160,11 +167,19 @@ impl<'probe> CoreInterface for Armv8m<'probe> {
 
         self.sequence.reset_catch_clear(&mut self.memory)?;
 
+        if vector_catch_core_reset {
+            let mut demcr = Demcr::from(self.memory.read_word_32(Demcr::ADDRESS)?);
+            demcr.set_vc_corereset(true);
+            self.memory.write_word_32(Demcr::ADDRESS, demcr.into())?;
+        }
+
         // try to read the program counter
         let pc_value = self.read_core_reg(register::PC.address)?;
 
         // get pc
-        Ok(CoreInformation { pc: pc_value })
+        Ok(CoreInformation {
+            pc: pc_value.into(),
+        })
     }
 
     fn step(&mut self) -> Result<CoreInformation, Error> {
@@ -201,7 +216,9 @@ impl<'probe> CoreInterface for Armv8m<'probe> {
         let pc_value = self.read_core_reg(register::PC.address)?;
 
         // get pc
-        Ok(CoreInformation { pc: pc_value })
+        Ok(CoreInformation {
+            pc: pc_value.into(),
+        })
     }
 
     fn read_core_reg(&mut self, address: CoreRegisterAddress) -> Result<u32, Error> {
@@ -266,6 +283,30 @@ impl<'probe> CoreInterface for Armv8m<'probe> {
         Ok(())
     }
 
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        dwt::available_watchpoint_units(&mut self.memory)
+    }
+
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        dwt::hw_watchpoints(&mut self.memory, DwtVersion::V8)
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        unit_index: usize,
+        watchpoint: Watchpoint,
+    ) -> Result<(), Error> {
+        dwt::set_hw_watchpoint(&mut self.memory, DwtVersion::V8, unit_index, watchpoint)
+    }
+
+    fn clear_hw_watchpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        dwt::clear_hw_watchpoint(&mut self.memory, unit_index)
+    }
+
+    fn watchpoints_share_breakpoint_units(&self) -> bool {
+        false
+    }
+
     fn hw_breakpoints_enabled(&self) -> bool {
         self.state.hw_breakpoints_enabled
     }
@@ -274,6 +315,46 @@ impl<'probe> CoreInterface for Armv8m<'probe> {
         Architecture::Arm
     }
 
+    fn set_vector_catch(&mut self, conditions: &[VectorCatchCondition]) -> Result<(), Error> {
+        let mut demcr = Demcr::from(self.memory.read_word_32(Demcr::ADDRESS)?);
+
+        demcr.set_vc_corereset(false);
+        demcr.set_vc_harderr(false);
+        demcr.set_vc_interr(false);
+        demcr.set_vc_mmerr(false);
+        demcr.set_vc_buserr(false);
+        demcr.set_vc_staterr(false);
+        demcr.set_vc_chkerr(false);
+        demcr.set_vc_nocperr(false);
+        demcr.set_vc_sferr(false);
+
+        for condition in conditions {
+            match condition {
+                VectorCatchCondition::CoreReset => demcr.set_vc_corereset(true),
+                VectorCatchCondition::HardFault => demcr.set_vc_harderr(true),
+                VectorCatchCondition::ExceptionEntryOrReturn => demcr.set_vc_interr(true),
+                VectorCatchCondition::MemManage => demcr.set_vc_mmerr(true),
+                VectorCatchCondition::BusFault => demcr.set_vc_buserr(true),
+                VectorCatchCondition::UsageFault => {
+                    demcr.set_vc_staterr(true);
+                    demcr.set_vc_chkerr(true);
+                    demcr.set_vc_nocperr(true);
+                }
+                VectorCatchCondition::SecureFault => demcr.set_vc_sferr(true),
+                other => {
+                    return Err(Error::Other(anyhow::anyhow!(
+                        "Vector catch condition {:?} is not supported by ARMv8-M cores",
+                        other
+                    )))
+                }
+            }
+        }
+
+        self.memory.write_word_32(Demcr::ADDRESS, demcr.into())?;
+
+        Ok(())
+    }
+
     fn status(&mut self) -> Result<crate::core::CoreStatus, Error> {
         let dhcsr = Dhcsr(self.memory.read_word_32(Dhcsr::ADDRESS)?);
 
@@ -301,7 +382,13 @@ impl<'probe> CoreInterface for Armv8m<'probe> {
         if dhcsr.s_halt() {
             let dfsr = Dfsr(self.memory.read_word_32(Dfsr::ADDRESS)?);
 
-            let reason = dfsr.halt_reason();
+            let reason = match dfsr.halt_reason() {
+                HaltReason::Watchpoint(_) => HaltReason::Watchpoint(dwt::triggered_watchpoint(
+                    &mut self.memory,
+                    DwtVersion::V8,
+                )?),
+                reason => reason,
+            };
 
             // Clear bits from Dfsr register
             self.memory
@@ -0,0 +1,90 @@
+//! Halting and restarting cores using the cross trigger interface (CTI).
+//!
+//! Cortex-A and Cortex-R cores are halted and restarted with the triggers of the CTI which belongs to the core.
+//! Channel 0 is routed to the debug request trigger, and channel 1 to the restart trigger.
+//! The channels are not connected to the cross trigger matrix, so other cores are not affected.
+//!
+//! See ARM architecture reference manual for A-profile H5 and ARM CoreSight architecture specification B3.
+
+use crate::error::Error;
+use crate::memory::Memory;
+use crate::DebugProbeError;
+use std::time::{Duration, Instant};
+
+/// CTI Control Register, CTICONTROL
+const CTICONTROL: u32 = 0x000;
+/// CTI Interrupt Acknowledge Register, CTIINTACK
+const CTIINTACK: u32 = 0x010;
+/// CTI Application Pulse Register, CTIAPPPULSE
+const CTIAPPPULSE: u32 = 0x01C;
+/// CTI Trigger to Channel Enable Registers, CTIOUTEN0. CTIOUTENn is at CTIOUTEN0 + 4 * n.
+const CTIOUTEN0: u32 = 0x0A0;
+/// CTI Trigger Out Status Register, CTITRIGOUTSTATUS
+const CTITRIGOUTSTATUS: u32 = 0x134;
+/// CTI Channel Gate Enable Register, CTIGATE
+const CTIGATE: u32 = 0x140;
+/// CTI Lock Access Register, CTILAR
+const CTILAR: u32 = 0xFB0;
+
+/// The key which has to be written to the lock access register to allow writes to the other registers.
+const UNLOCK_KEY: u32 = 0xC5AC_CE55;
+
+/// The trigger output which halts the core.
+const DEBUG_REQUEST_TRIGGER: u32 = 0;
+/// The trigger output which restarts the core.
+const RESTART_TRIGGER: u32 = 1;
+
+/// The channel which is used to halt the core.
+const HALT_CHANNEL: u32 = 0;
+/// The channel which is used to restart the core.
+const RESTART_CHANNEL: u32 = 1;
+
+/// How long to wait for the acknowledgement of a trigger.
+const TRIGGER_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Enables the CTI at `base`, and routes the halt and restart channels to the triggers of the core.
+pub(crate) fn initialize(memory: &mut Memory, base: u32) -> Result<(), Error> {
+    memory.write_word_32(base + CTILAR, UNLOCK_KEY)?;
+    memory.write_word_32(base + CTICONTROL, 1)?;
+
+    // Don't pass the channel events to the cross trigger matrix, which would halt other cores as well.
+    memory.write_word_32(base + CTIGATE, 0)?;
+
+    memory.write_word_32(
+        base + CTIOUTEN0 + 4 * DEBUG_REQUEST_TRIGGER,
+        1 << HALT_CHANNEL,
+    )?;
+    memory.write_word_32(base + CTIOUTEN0 + 4 * RESTART_TRIGGER, 1 << RESTART_CHANNEL)?;
+
+    memory.flush()
+}
+
+/// Requests the core to halt.
+pub(crate) fn request_halt(memory: &mut Memory, base: u32) -> Result<(), Error> {
+    memory.write_word_32(base + CTIAPPPULSE, 1 << HALT_CHANNEL)?;
+
+    memory.flush()
+}
+
+/// Acknowledges the debug request trigger of the last halt request.
+///
+/// The trigger stays active until it is acknowledged, which would halt the core again directly after a restart.
+pub(crate) fn acknowledge_halt(memory: &mut Memory, base: u32) -> Result<(), Error> {
+    memory.write_word_32(base + CTIINTACK, 1 << DEBUG_REQUEST_TRIGGER)?;
+
+    let start = Instant::now();
+    while memory.read_word_32(base + CTITRIGOUTSTATUS)? & (1 << DEBUG_REQUEST_TRIGGER) != 0 {
+        if start.elapsed() > TRIGGER_TIMEOUT {
+            return Err(Error::Probe(DebugProbeError::Timeout));
+        }
+    }
+
+    Ok(())
+}
+
+/// Requests the halted core to restart.
+pub(crate) fn request_restart(memory: &mut Memory, base: u32) -> Result<(), Error> {
+    memory.write_word_32(base + CTIAPPPULSE, 1 << RESTART_CHANNEL)?;
+
+    memory.flush()
+}
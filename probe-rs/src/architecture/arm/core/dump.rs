@@ -0,0 +1,54 @@
+use crate::{core::RegisterValue, CoreDump, CoreType};
+use serde::{Deserialize, Serialize};
+
+/// Core information data which is downloaded from the target, represents its state and can be used for debugging.
+#[deprecated(since = "0.13.0", note = "Use `probe_rs::CoreDump` instead")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dump {
+    /// The register values at the time of the dump.
+    pub regs: [u32; 16],
+    stack_addr: u32,
+    stack: Vec<u8>,
+}
+
+impl Dump {
+    /// Create a new dump from a SP and a stack dump with zeroed out registers.
+    pub fn new(stack_addr: u32, stack: Vec<u8>) -> Dump {
+        Dump {
+            regs: [0u32; 16],
+            stack_addr,
+            stack,
+        }
+    }
+
+    /// Converts the dump into a [`CoreDump`] of a core of type `core_type`, e.g. [`CoreType::Armv7m`].
+    ///
+    /// The core dump contains the registers R0 to R15 and the stack. Other registers, e.g. XPSR, can't be read.
+    pub fn into_core_dump(self, core_type: CoreType) -> CoreDump {
+        let registers = (0u16..)
+            .zip(self.regs)
+            .map(|(address, value)| (address, RegisterValue::U32(value)))
+            .collect();
+
+        CoreDump::new(core_type, registers, vec![(self.stack_addr, self.stack)])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::MemoryInterface;
+
+    #[test]
+    fn converted_dump_contains_registers_and_stack() {
+        let mut dump = Dump::new(0x2000_0000, vec![0x78, 0x56, 0x34, 0x12]);
+        dump.regs[15] = 0x0800_0100;
+
+        let mut core_dump = dump.into_core_dump(CoreType::Armv7m);
+        let mut core = core_dump.core();
+
+        let pc = core.registers().program_counter();
+        assert_eq!(core.read_core_reg(pc).unwrap(), 0x0800_0100);
+        assert_eq!(core.read_word_32(0x2000_0000).unwrap(), 0x1234_5678);
+    }
+}
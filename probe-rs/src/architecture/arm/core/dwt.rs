@@ -0,0 +1,320 @@
+//! Data watchpoints using the comparators of the DWT (data watchpoint and trace) unit.
+//!
+//! ARMv6-M and ARMv7-M select the size of the watched region with the `DWT_MASKn` registers,
+//! while ARMv8-M encodes the access size in the `DWT_FUNCTIONn` registers.
+//!
+//! See ARMv7-M architecture reference manual C1.8 and ARMv8-M architecture reference manual B12.2.
+
+use super::armv7m::Demcr;
+use crate::core::{Watchpoint, WatchpointKind};
+use crate::error::Error;
+use crate::memory::Memory;
+use crate::{CoreRegister, MemoryInterface};
+use anyhow::anyhow;
+use bitfield::bitfield;
+
+/// The layout of the DWT comparator registers.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum DwtVersion {
+    /// The DWT unit of ARMv6-M and ARMv7-M cores.
+    V7,
+    /// The DWT unit of ARMv8-M cores.
+    V8,
+}
+
+bitfield! {
+    /// DWT Control Register, DWT_CTRL
+    #[derive(Copy, Clone)]
+    pub struct DwtCtrl(u32);
+    impl Debug;
+    /// The number of comparators implemented.
+    pub numcomp, _: 31, 28;
+}
+
+impl From<u32> for DwtCtrl {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<DwtCtrl> for u32 {
+    fn from(value: DwtCtrl) -> Self {
+        value.0
+    }
+}
+
+impl CoreRegister for DwtCtrl {
+    const ADDRESS: u32 = 0xE000_1000;
+    const NAME: &'static str = "DWT_CTRL";
+}
+
+/// The address of the first comparator register, DWT_COMP0.
+const DWT_COMP0: u32 = 0xE000_1020;
+/// The address of the first comparator mask register, DWT_MASK0. Not available on ARMv8-M.
+const DWT_MASK0: u32 = 0xE000_1024;
+/// The address of the first comparator function register, DWT_FUNCTION0.
+const DWT_FUNCTION0: u32 = 0xE000_1028;
+/// The distance between the registers of two consecutive comparators.
+const DWT_COMPARATOR_STRIDE: u32 = 16;
+
+bitfield! {
+    /// DWT Comparator Function Register, DWT_FUNCTIONn (ARMv6-M and ARMv7-M)
+    #[derive(Copy, Clone)]
+    pub struct FunctionV7(u32);
+    impl Debug;
+    /// Set when the comparator matched since the last read of the register.
+    pub matched, _: 24;
+    pub datavmatch, set_datavmatch: 8;
+    pub cycmatch, set_cycmatch: 7;
+    /// `0b0101`: Watchpoint on read.
+    /// `0b0110`: Watchpoint on write.
+    /// `0b0111`: Watchpoint on read or write.
+    pub function, set_function: 3, 0;
+}
+
+bitfield! {
+    /// DWT Comparator Function Register, DWT_FUNCTIONn (ARMv8-M)
+    #[derive(Copy, Clone)]
+    pub struct FunctionV8(u32);
+    impl Debug;
+    /// Set when the comparator matched since the last read of the register.
+    pub matched, _: 24;
+    /// `0b00`: Byte.
+    /// `0b01`: Halfword.
+    /// `0b10`: Word.
+    pub datavsize, set_datavsize: 11, 10;
+    /// `0b01`: Generate a debug event, which halts the core.
+    pub action, set_action: 5, 4;
+    /// `0b0100`: Data address, read or write.
+    /// `0b0101`: Data address, write.
+    /// `0b0110`: Data address, read.
+    pub match_, set_match: 3, 0;
+}
+
+impl DwtVersion {
+    /// Encodes the watchpoint kind into the DWT_FUNCTIONn register value for this version.
+    fn function(self, kind: WatchpointKind, size: u32) -> Result<u32, Error> {
+        match self {
+            DwtVersion::V7 => {
+                let mut function = FunctionV7(0);
+                function.set_function(match kind {
+                    WatchpointKind::Read => 0b0101,
+                    WatchpointKind::Write => 0b0110,
+                    WatchpointKind::ReadWrite => 0b0111,
+                });
+                Ok(function.0)
+            }
+            DwtVersion::V8 => {
+                let mut function = FunctionV8(0);
+                function.set_match(match kind {
+                    WatchpointKind::Read => 0b0110,
+                    WatchpointKind::Write => 0b0101,
+                    WatchpointKind::ReadWrite => 0b0100,
+                });
+                function.set_action(0b01);
+                function.set_datavsize(match size {
+                    1 => 0b00,
+                    2 => 0b01,
+                    4 => 0b10,
+                    _ => {
+                        return Err(Error::Other(anyhow!(
+                            "ARMv8-M watchpoints can only watch 1, 2 or 4 bytes, not {}",
+                            size
+                        )))
+                    }
+                });
+                Ok(function.0)
+            }
+        }
+    }
+
+    /// Decodes the watchpoint kind from a DWT_FUNCTIONn register value.
+    ///
+    /// Returns `None` if the comparator is disabled or used for something else than a watchpoint.
+    fn kind(self, function: u32) -> Option<WatchpointKind> {
+        match self {
+            DwtVersion::V7 => {
+                let function = FunctionV7(function);
+                if function.datavmatch() || function.cycmatch() {
+                    return None;
+                }
+                match function.function() {
+                    0b0101 => Some(WatchpointKind::Read),
+                    0b0110 => Some(WatchpointKind::Write),
+                    0b0111 => Some(WatchpointKind::ReadWrite),
+                    _ => None,
+                }
+            }
+            DwtVersion::V8 => {
+                let function = FunctionV8(function);
+                if function.action() != 0b01 {
+                    return None;
+                }
+                match function.match_() {
+                    0b0110 => Some(WatchpointKind::Read),
+                    0b0101 => Some(WatchpointKind::Write),
+                    0b0100 => Some(WatchpointKind::ReadWrite),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    fn matched(self, function: u32) -> bool {
+        match self {
+            DwtVersion::V7 => FunctionV7(function).matched(),
+            DwtVersion::V8 => FunctionV8(function).matched(),
+        }
+    }
+}
+
+fn register_address(base: u32, unit_index: usize) -> u32 {
+    base + unit_index as u32 * DWT_COMPARATOR_STRIDE
+}
+
+/// Returns the number of comparators of the DWT unit.
+pub(crate) fn available_watchpoint_units(memory: &mut Memory) -> Result<u32, Error> {
+    let ctrl = DwtCtrl(memory.read_word_32(DwtCtrl::ADDRESS)?);
+
+    Ok(ctrl.numcomp())
+}
+
+/// Reads the watchpoints configured in the DWT comparators.
+///
+/// Reading the DWT_FUNCTIONn registers clears their `MATCHED` bits.
+pub(crate) fn hw_watchpoints(
+    memory: &mut Memory,
+    version: DwtVersion,
+) -> Result<Vec<Option<Watchpoint>>, Error> {
+    let num_units = available_watchpoint_units(memory)? as usize;
+    let mut watchpoints = Vec::with_capacity(num_units);
+
+    for unit_index in 0..num_units {
+        let function = memory.read_word_32(register_address(DWT_FUNCTION0, unit_index))?;
+
+        let kind = match version.kind(function) {
+            Some(kind) => kind,
+            None => {
+                watchpoints.push(None);
+                continue;
+            }
+        };
+
+        let address = memory.read_word_32(register_address(DWT_COMP0, unit_index))?;
+
+        let size = match version {
+            DwtVersion::V7 => {
+                1 << (memory.read_word_32(register_address(DWT_MASK0, unit_index))? & 0x1f)
+            }
+            DwtVersion::V8 => 1 << FunctionV8(function).datavsize(),
+        };
+
+        watchpoints.push(Some(Watchpoint {
+            address,
+            size,
+            kind,
+        }));
+    }
+
+    Ok(watchpoints)
+}
+
+/// Configures DWT comparator `unit_index` to halt the core when the watched memory is accessed.
+pub(crate) fn set_hw_watchpoint(
+    memory: &mut Memory,
+    version: DwtVersion,
+    unit_index: usize,
+    watchpoint: Watchpoint,
+) -> Result<(), Error> {
+    let function = version.function(watchpoint.kind, watchpoint.size)?;
+
+    // The DWT unit only works if it is enabled globally.
+    // This bit is called DWTENA on ARMv6-M, but is at the same position.
+    let mut demcr = Demcr(memory.read_word_32(Demcr::ADDRESS)?);
+    if !demcr.trcena() {
+        demcr.set_trcena(true);
+        memory.write_word_32(Demcr::ADDRESS, demcr.into())?;
+    }
+
+    // Disable the comparator while it is reconfigured.
+    memory.write_word_32(register_address(DWT_FUNCTION0, unit_index), 0)?;
+    memory.write_word_32(register_address(DWT_COMP0, unit_index), watchpoint.address)?;
+
+    if version == DwtVersion::V7 {
+        let mask = watchpoint.size.trailing_zeros();
+        memory.write_word_32(register_address(DWT_MASK0, unit_index), mask)?;
+
+        // The maximum mask size is implementation defined, unsupported bits read as zero.
+        let mask_readback = memory.read_word_32(register_address(DWT_MASK0, unit_index))? & 0x1f;
+        if mask_readback != mask {
+            return Err(Error::Other(anyhow!(
+                "Watchpoints with a size of {} bytes are not supported by this core",
+                watchpoint.size
+            )));
+        }
+    }
+
+    memory.write_word_32(register_address(DWT_FUNCTION0, unit_index), function)?;
+
+    // Not all comparators support data address matching, which is only visible when reading back the configuration.
+    let function_readback = memory.read_word_32(register_address(DWT_FUNCTION0, unit_index))?;
+    if version.kind(function_readback) != Some(watchpoint.kind) {
+        memory.write_word_32(register_address(DWT_FUNCTION0, unit_index), 0)?;
+
+        return Err(Error::Other(anyhow!(
+            "DWT comparator #{} does not support data address watchpoints",
+            unit_index
+        )));
+    }
+
+    memory.flush()
+}
+
+/// Disables DWT comparator `unit_index`.
+pub(crate) fn clear_hw_watchpoint(memory: &mut Memory, unit_index: usize) -> Result<(), Error> {
+    memory.write_word_32(register_address(DWT_FUNCTION0, unit_index), 0)?;
+
+    memory.flush()
+}
+
+/// Returns the address of the watchpoint which caused the last halt, if it can be determined.
+///
+/// This clears the `MATCHED` bits of all comparators, so it should only be called once per halt.
+pub(crate) fn triggered_watchpoint(
+    memory: &mut Memory,
+    version: DwtVersion,
+) -> Result<Option<u32>, Error> {
+    let num_units = available_watchpoint_units(memory)? as usize;
+    let mut triggered = None;
+
+    // Read all comparators, so that no stale MATCHED bits are left for the next halt.
+    for unit_index in 0..num_units {
+        let function = memory.read_word_32(register_address(DWT_FUNCTION0, unit_index))?;
+
+        if triggered.is_none() && version.matched(function) && version.kind(function).is_some() {
+            triggered = Some(memory.read_word_32(register_address(DWT_COMP0, unit_index))?);
+        }
+    }
+
+    Ok(triggered)
+}
+
+#[test]
+fn watchpoint_function_roundtrip() {
+    for version in [DwtVersion::V7, DwtVersion::V8] {
+        for kind in [
+            WatchpointKind::Read,
+            WatchpointKind::Write,
+            WatchpointKind::ReadWrite,
+        ] {
+            let function = version.function(kind, 4).unwrap();
+
+            assert_eq!(version.kind(function), Some(kind));
+        }
+    }
+}
+
+#[test]
+fn unsupported_armv8m_watchpoint_size() {
+    assert!(DwtVersion::V8.function(WatchpointKind::Write, 8).is_err());
+}
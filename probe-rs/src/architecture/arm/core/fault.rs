@@ -0,0 +1,689 @@
+//! Analysis of faults on Cortex-M cores.
+//!
+//! The fault status registers in the System Control Block are decoded into a [`FaultReport`],
+//! see the ARMv7-M Architecture Reference Manual, Section B3.2, and the ARMv8-M Architecture Reference Manual, Section D1.2.
+
+use super::{register, Dfsr};
+use crate::core::CoreRegister;
+use crate::debug::exception::{exception_frame_address, ExceptionReturn};
+use crate::{Core, CoreType, Error, MemoryInterface};
+
+use bitfield::bitfield;
+use std::fmt;
+
+bitfield! {
+    /// Configurable Fault Status Register, CFSR (see ARMv7-M Architecture Reference Manual B3.2.15)
+    ///
+    /// Combines the MemManage Fault Status Register (MMFSR), the BusFault Status Register (BFSR)
+    /// and the UsageFault Status Register (UFSR).
+    #[derive(Copy, Clone)]
+    pub struct Cfsr(u32);
+    impl Debug;
+    /// Division by zero, if `CCR.DIV_0_TRP` is set.
+    pub divbyzero, _: 25;
+    /// Unaligned access, if `CCR.UNALIGN_TRP` is set, or for instructions which don't support unaligned accesses.
+    pub unaligned, _: 24;
+    /// Stack overflow, detected with the stack limit registers of ARMv8-M.
+    pub stkof, _: 20;
+    /// Access to a coprocessor which is not present or disabled.
+    pub nocp, _: 19;
+    /// Invalid `EXC_RETURN` value loaded into the PC.
+    pub invpc, _: 18;
+    /// Execution of an instruction with an invalid EPSR state, e.g. with the Thumb bit cleared.
+    pub invstate, _: 17;
+    /// Execution of an undefined instruction.
+    pub undefinstr, _: 16;
+    /// BFAR holds the address of the faulting access.
+    pub bfarvalid, _: 15;
+    /// Bus fault during lazy floating point state preservation.
+    pub lsperr, _: 13;
+    /// Bus fault while stacking for an exception entry.
+    pub stkerr, _: 12;
+    /// Bus fault while unstacking for an exception return.
+    pub unstkerr, _: 11;
+    /// Imprecise data bus error, the faulting instruction is not known.
+    pub impreciserr, _: 10;
+    /// Precise data bus error, the stacked PC points to the faulting instruction.
+    pub preciserr, _: 9;
+    /// Bus error on an instruction fetch.
+    pub ibuserr, _: 8;
+    /// MMFAR holds the address of the faulting access.
+    pub mmarvalid, _: 7;
+    /// MemManage fault during lazy floating point state preservation.
+    pub mlsperr, _: 5;
+    /// MemManage fault while stacking for an exception entry.
+    pub mstkerr, _: 4;
+    /// MemManage fault while unstacking for an exception return.
+    pub munstkerr, _: 3;
+    /// Data access violation, MMFAR holds the address of the access.
+    pub daccviol, _: 1;
+    /// Instruction access violation.
+    pub iaccviol, _: 0;
+}
+
+impl From<u32> for Cfsr {
+    fn from(value: u32) -> Self {
+        Cfsr(value)
+    }
+}
+
+impl From<Cfsr> for u32 {
+    fn from(register: Cfsr) -> Self {
+        register.0
+    }
+}
+
+impl CoreRegister for Cfsr {
+    const ADDRESS: u32 = 0xE000_ED28;
+    const NAME: &'static str = "CFSR";
+}
+
+bitfield! {
+    /// HardFault Status Register, HFSR (see ARMv7-M Architecture Reference Manual B3.2.16)
+    #[derive(Copy, Clone)]
+    pub struct Hfsr(u32);
+    impl Debug;
+    /// A debug event occurred while halting debug was disabled.
+    pub debugevt, _: 31;
+    /// A configurable fault was escalated to a HardFault.
+    pub forced, _: 30;
+    /// Bus fault while reading the vector table.
+    pub vecttbl, _: 1;
+}
+
+impl From<u32> for Hfsr {
+    fn from(value: u32) -> Self {
+        Hfsr(value)
+    }
+}
+
+impl From<Hfsr> for u32 {
+    fn from(register: Hfsr) -> Self {
+        register.0
+    }
+}
+
+impl CoreRegister for Hfsr {
+    const ADDRESS: u32 = 0xE000_ED2C;
+    const NAME: &'static str = "HFSR";
+}
+
+bitfield! {
+    /// Secure Fault Status Register, SFSR (see ARMv8-M Architecture Reference Manual D1.2.216)
+    #[derive(Copy, Clone)]
+    pub struct Sfsr(u32);
+    impl Debug;
+    /// Error during lazy state activation or deactivation.
+    pub lserr, _: 7;
+    /// SFAR holds the address of the faulting access.
+    pub sfarvalid, _: 6;
+    /// Error during lazy floating point state preservation.
+    pub lsperr, _: 5;
+    /// Invalid transition from secure to non-secure state.
+    pub invtran, _: 4;
+    /// Access violating the security attribution.
+    pub auviol, _: 3;
+    /// Invalid exception return.
+    pub inver, _: 2;
+    /// Invalid integrity signature in the exception frame.
+    pub invis, _: 1;
+    /// Branch to a secure address which is not a valid entry point.
+    pub invep, _: 0;
+}
+
+impl From<u32> for Sfsr {
+    fn from(value: u32) -> Self {
+        Sfsr(value)
+    }
+}
+
+impl From<Sfsr> for u32 {
+    fn from(register: Sfsr) -> Self {
+        register.0
+    }
+}
+
+impl CoreRegister for Sfsr {
+    const ADDRESS: u32 = 0xE000_EDE4;
+    const NAME: &'static str = "SFSR";
+}
+
+/// MemManage Fault Address Register
+const MMFAR: u32 = 0xE000_ED34;
+/// BusFault Address Register
+const BFAR: u32 = 0xE000_ED38;
+/// Auxiliary Fault Status Register
+const AFSR: u32 = 0xE000_ED3C;
+/// Secure Fault Address Register
+const SFAR: u32 = 0xE000_EDE8;
+
+/// The exception numbers of the fault handlers.
+const HARD_FAULT: u32 = 3;
+const MEM_MANAGE: u32 = 4;
+const BUS_FAULT: u32 = 5;
+const USAGE_FAULT: u32 = 6;
+const SECURE_FAULT: u32 = 7;
+
+/// The exception handler of a fault.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultKind {
+    /// HardFault, exception 3
+    HardFault,
+    /// MemManage fault, exception 4
+    MemManage,
+    /// BusFault, exception 5
+    BusFault,
+    /// UsageFault, exception 6
+    UsageFault,
+    /// SecureFault, exception 7, only on ARMv8-M
+    SecureFault,
+}
+
+impl FaultKind {
+    fn from_exception_number(exception_number: u32) -> Option<Self> {
+        match exception_number {
+            HARD_FAULT => Some(FaultKind::HardFault),
+            MEM_MANAGE => Some(FaultKind::MemManage),
+            BUS_FAULT => Some(FaultKind::BusFault),
+            USAGE_FAULT => Some(FaultKind::UsageFault),
+            SECURE_FAULT => Some(FaultKind::SecureFault),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FaultKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FaultKind::HardFault => "HardFault",
+            FaultKind::MemManage => "MemManage fault",
+            FaultKind::BusFault => "BusFault",
+            FaultKind::UsageFault => "UsageFault",
+            FaultKind::SecureFault => "SecureFault",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// A cause of a fault, decoded from the fault status registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultCause {
+    /// Bus fault while reading the vector table. (HFSR.VECTTBL)
+    VectorTableRead,
+    /// A configurable fault was escalated to a HardFault. (HFSR.FORCED)
+    Escalated,
+    /// A debug event occurred while halting debug was disabled. (HFSR.DEBUGEVT)
+    DebugEvent,
+    /// Instruction access violation. (MMFSR.IACCVIOL)
+    InstructionAccessViolation,
+    /// Data access violation. (MMFSR.DACCVIOL)
+    DataAccessViolation,
+    /// MemManage fault while unstacking for an exception return. (MMFSR.MUNSTKERR)
+    MemManageUnstacking,
+    /// MemManage fault while stacking for an exception entry. (MMFSR.MSTKERR)
+    MemManageStacking,
+    /// MemManage fault during lazy floating point state preservation. (MMFSR.MLSPERR)
+    MemManageLazyFpStacking,
+    /// Bus error on an instruction fetch. (BFSR.IBUSERR)
+    InstructionBusError,
+    /// Precise data bus error. (BFSR.PRECISERR)
+    PreciseDataBusError,
+    /// Imprecise data bus error. (BFSR.IMPRECISERR)
+    ImpreciseDataBusError,
+    /// Bus fault while unstacking for an exception return. (BFSR.UNSTKERR)
+    BusFaultUnstacking,
+    /// Bus fault while stacking for an exception entry. (BFSR.STKERR)
+    BusFaultStacking,
+    /// Bus fault during lazy floating point state preservation. (BFSR.LSPERR)
+    BusFaultLazyFpStacking,
+    /// Execution of an undefined instruction. (UFSR.UNDEFINSTR)
+    UndefinedInstruction,
+    /// Execution with an invalid EPSR state. (UFSR.INVSTATE)
+    InvalidState,
+    /// Invalid `EXC_RETURN` value. (UFSR.INVPC)
+    InvalidExceptionReturn,
+    /// Access to a missing or disabled coprocessor. (UFSR.NOCP)
+    NoCoprocessor,
+    /// Stack overflow. (UFSR.STKOF)
+    StackOverflow,
+    /// Unaligned access. (UFSR.UNALIGNED)
+    UnalignedAccess,
+    /// Division by zero. (UFSR.DIVBYZERO)
+    DivideByZero,
+    /// Branch to an invalid secure entry point. (SFSR.INVEP)
+    InvalidEntryPoint,
+    /// Invalid integrity signature. (SFSR.INVIS)
+    InvalidIntegritySignature,
+    /// Invalid secure exception return. (SFSR.INVER)
+    InvalidSecureExceptionReturn,
+    /// Security attribution violation. (SFSR.AUVIOL)
+    AttributionViolation,
+    /// Invalid transition from secure to non-secure state. (SFSR.INVTRAN)
+    InvalidTransition,
+    /// Secure fault during lazy floating point state preservation. (SFSR.LSPERR)
+    SecureLazyFpStacking,
+    /// Error during lazy state activation or deactivation. (SFSR.LSERR)
+    LazyStateError,
+}
+
+impl fmt::Display for FaultCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            FaultCause::VectorTableRead => "bus fault while reading the vector table",
+            FaultCause::Escalated => "configurable fault escalated to HardFault",
+            FaultCause::DebugEvent => "debug event while halting debug was disabled",
+            FaultCause::InstructionAccessViolation => "instruction access violation",
+            FaultCause::DataAccessViolation => "data access violation",
+            FaultCause::MemManageUnstacking => "MemManage fault on exception return",
+            FaultCause::MemManageStacking => "MemManage fault on exception entry",
+            FaultCause::MemManageLazyFpStacking => "MemManage fault during lazy FP stacking",
+            FaultCause::InstructionBusError => "bus error on instruction fetch",
+            FaultCause::PreciseDataBusError => "precise data bus error",
+            FaultCause::ImpreciseDataBusError => "imprecise data bus error",
+            FaultCause::BusFaultUnstacking => "bus fault on exception return",
+            FaultCause::BusFaultStacking => "bus fault on exception entry",
+            FaultCause::BusFaultLazyFpStacking => "bus fault during lazy FP stacking",
+            FaultCause::UndefinedInstruction => "undefined instruction",
+            FaultCause::InvalidState => "invalid EPSR state, e.g. Thumb bit cleared",
+            FaultCause::InvalidExceptionReturn => "invalid EXC_RETURN value",
+            FaultCause::NoCoprocessor => "coprocessor missing or disabled",
+            FaultCause::StackOverflow => "stack overflow",
+            FaultCause::UnalignedAccess => "unaligned access",
+            FaultCause::DivideByZero => "division by zero",
+            FaultCause::InvalidEntryPoint => "invalid secure entry point",
+            FaultCause::InvalidIntegritySignature => "invalid integrity signature",
+            FaultCause::InvalidSecureExceptionReturn => "invalid secure exception return",
+            FaultCause::AttributionViolation => "security attribution violation",
+            FaultCause::InvalidTransition => "invalid transition to non-secure state",
+            FaultCause::SecureLazyFpStacking => "secure fault during lazy FP stacking",
+            FaultCause::LazyStateError => "lazy state activation or deactivation error",
+        };
+
+        f.write_str(description)
+    }
+}
+
+/// The value of a fault address register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FaultAddress {
+    /// The value of the register.
+    pub address: u32,
+    /// Set if the register holds the address of the faulting access.
+    /// Otherwise, the value is meaningless.
+    pub valid: bool,
+}
+
+impl fmt::Display for FaultAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.valid {
+            write!(f, "{:#010x}", self.address)
+        } else {
+            write!(f, "{:#010x} (not valid)", self.address)
+        }
+    }
+}
+
+/// The decoded fault state of a Cortex-M core, which is halted in a fault handler.
+///
+/// Registers which are not implemented by the core are `None`. ARMv6-M only implements DFSR.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FaultReport {
+    /// The fault handler the core is executing.
+    pub kind: FaultKind,
+    /// The causes of the fault, decoded from HFSR, CFSR and SFSR.
+    pub causes: Vec<FaultCause>,
+    /// The PC of the faulting code, read from the exception frame.
+    ///
+    /// This is `None` if LR no longer holds the `EXC_RETURN` value, because the handler overwrote it.
+    pub stacked_pc: Option<u32>,
+    /// Set if the exception frame was located on the main stack with the current MSP. This is only
+    /// correct until the fault handler pushes to the stack, so the `stacked_pc` may be wrong.
+    /// [`DebugInfo::unwind`](crate::debug::DebugInfo::unwind) locates the frame with the CFA of the handler instead.
+    pub stacked_pc_unreliable: bool,
+    /// Configurable Fault Status Register
+    pub cfsr: Option<u32>,
+    /// HardFault Status Register
+    pub hfsr: Option<u32>,
+    /// Debug Fault Status Register
+    pub dfsr: u32,
+    /// Auxiliary Fault Status Register, the contents are implementation defined.
+    pub afsr: Option<u32>,
+    /// MemManage Fault Address Register
+    pub mmfar: Option<FaultAddress>,
+    /// BusFault Address Register
+    pub bfar: Option<FaultAddress>,
+    /// Secure Fault Status Register, only on ARMv8-M with the security extension.
+    pub sfsr: Option<u32>,
+    /// Secure Fault Address Register, only on ARMv8-M with the security extension.
+    pub sfar: Option<FaultAddress>,
+}
+
+impl FaultReport {
+    /// A single line description of the fault, e.g. for a stopped event.
+    pub fn summary(&self) -> String {
+        let mut summary = self.kind.to_string();
+
+        if !self.causes.is_empty() {
+            let causes: Vec<String> = self.causes.iter().map(|cause| cause.to_string()).collect();
+            summary.push_str(&format!(": {}", causes.join(", ")));
+        }
+
+        if let Some(pc) = self.stacked_pc {
+            summary.push_str(&format!(" at {:#010x}", pc));
+
+            if self.stacked_pc_unreliable {
+                summary.push_str(" (unreliable)");
+            }
+        }
+
+        summary
+    }
+}
+
+impl fmt::Display for FaultReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.kind)?;
+
+        for cause in &self.causes {
+            writeln!(f, "  - {}", cause)?;
+        }
+
+        match self.stacked_pc {
+            Some(pc) if self.stacked_pc_unreliable => writeln!(
+                f,
+                "Faulting PC: {:#010x} (unreliable, read with the current MSP)",
+                pc
+            )?,
+            Some(pc) => writeln!(f, "Faulting PC: {:#010x}", pc)?,
+            None => writeln!(f, "Faulting PC: unknown")?,
+        }
+
+        if let Some(cfsr) = self.cfsr {
+            writeln!(f, "CFSR:  {:#010x}", cfsr)?;
+        }
+        if let Some(hfsr) = self.hfsr {
+            writeln!(f, "HFSR:  {:#010x}", hfsr)?;
+        }
+        writeln!(f, "DFSR:  {:#010x}", self.dfsr)?;
+        if let Some(afsr) = self.afsr {
+            writeln!(f, "AFSR:  {:#010x}", afsr)?;
+        }
+        if let Some(mmfar) = self.mmfar {
+            writeln!(f, "MMFAR: {}", mmfar)?;
+        }
+        if let Some(bfar) = self.bfar {
+            writeln!(f, "BFAR:  {}", bfar)?;
+        }
+        if let Some(sfsr) = self.sfsr {
+            writeln!(f, "SFSR:  {:#010x}", sfsr)?;
+        }
+        if let Some(sfar) = self.sfar {
+            writeln!(f, "SFAR:  {}", sfar)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads and decodes the fault status registers, if the core is halted in a fault handler.
+///
+/// Returns `None` for cores which are not M-profile cores, or which are not executing a fault handler.
+/// The core has to be halted.
+pub fn analyze_fault(core: &mut Core, core_type: CoreType) -> Result<Option<FaultReport>, Error> {
+    let has_fault_status_registers = match core_type {
+        CoreType::Armv6m => false,
+        CoreType::Armv7m | CoreType::Armv7em | CoreType::Armv8m => true,
+        _ => return Ok(None),
+    };
+
+    // The exception number is stored in the IPSR, bits [8:0] of the xPSR.
+    let xpsr = core.read_core_reg(register::XPSR)?;
+    let kind = match FaultKind::from_exception_number(xpsr & 0x1ff) {
+        Some(kind) => kind,
+        None => return Ok(None),
+    };
+
+    let (stacked_pc, stacked_pc_unreliable) = match stacked_pc(core) {
+        Some((pc, unreliable)) => (Some(pc), unreliable),
+        None => (None, false),
+    };
+
+    let mut report = FaultReport {
+        kind,
+        causes: Vec::new(),
+        stacked_pc,
+        stacked_pc_unreliable,
+        cfsr: None,
+        hfsr: None,
+        dfsr: core.read_word_32(Dfsr::ADDRESS)?,
+        afsr: None,
+        mmfar: None,
+        bfar: None,
+        sfsr: None,
+        sfar: None,
+    };
+
+    if has_fault_status_registers {
+        let hfsr = Hfsr(core.read_word_32(Hfsr::ADDRESS)?);
+        let cfsr = Cfsr(core.read_word_32(Cfsr::ADDRESS)?);
+
+        report.causes.extend(configurable_fault_causes(hfsr, cfsr));
+
+        report.hfsr = Some(hfsr.0);
+        report.cfsr = Some(cfsr.0);
+        report.afsr = Some(core.read_word_32(AFSR)?);
+        let (mmfar, bfar) =
+            fault_addresses(cfsr, core.read_word_32(MMFAR)?, core.read_word_32(BFAR)?);
+        report.mmfar = Some(mmfar);
+        report.bfar = Some(bfar);
+    }
+
+    // The SFSR is only implemented with the security extension, which can't be detected
+    // from the non-secure side. Reading it is attempted, and it is omitted if the read fails.
+    if core_type == CoreType::Armv8m {
+        if let Ok(value) = core.read_word_32(Sfsr::ADDRESS) {
+            let sfsr = Sfsr(value);
+
+            report.causes.extend(secure_fault_causes(sfsr));
+
+            report.sfsr = Some(sfsr.0);
+            report.sfar = core.read_word_32(SFAR).ok().map(|address| FaultAddress {
+                address,
+                valid: sfsr.sfarvalid(),
+            });
+        }
+    }
+
+    Ok(Some(report))
+}
+
+/// Decodes the causes of a fault from the HFSR and the CFSR.
+fn configurable_fault_causes(hfsr: Hfsr, cfsr: Cfsr) -> Vec<FaultCause> {
+    let flags = [
+        (hfsr.vecttbl(), FaultCause::VectorTableRead),
+        (hfsr.forced(), FaultCause::Escalated),
+        (hfsr.debugevt(), FaultCause::DebugEvent),
+        (cfsr.iaccviol(), FaultCause::InstructionAccessViolation),
+        (cfsr.daccviol(), FaultCause::DataAccessViolation),
+        (cfsr.munstkerr(), FaultCause::MemManageUnstacking),
+        (cfsr.mstkerr(), FaultCause::MemManageStacking),
+        (cfsr.mlsperr(), FaultCause::MemManageLazyFpStacking),
+        (cfsr.ibuserr(), FaultCause::InstructionBusError),
+        (cfsr.preciserr(), FaultCause::PreciseDataBusError),
+        (cfsr.impreciserr(), FaultCause::ImpreciseDataBusError),
+        (cfsr.unstkerr(), FaultCause::BusFaultUnstacking),
+        (cfsr.stkerr(), FaultCause::BusFaultStacking),
+        (cfsr.lsperr(), FaultCause::BusFaultLazyFpStacking),
+        (cfsr.undefinstr(), FaultCause::UndefinedInstruction),
+        (cfsr.invstate(), FaultCause::InvalidState),
+        (cfsr.invpc(), FaultCause::InvalidExceptionReturn),
+        (cfsr.nocp(), FaultCause::NoCoprocessor),
+        (cfsr.stkof(), FaultCause::StackOverflow),
+        (cfsr.unaligned(), FaultCause::UnalignedAccess),
+        (cfsr.divbyzero(), FaultCause::DivideByZero),
+    ];
+
+    flags
+        .iter()
+        .filter(|(is_set, _)| *is_set)
+        .map(|(_, cause)| *cause)
+        .collect()
+}
+
+/// Decodes the causes of a SecureFault from the SFSR.
+fn secure_fault_causes(sfsr: Sfsr) -> Vec<FaultCause> {
+    let flags = [
+        (sfsr.invep(), FaultCause::InvalidEntryPoint),
+        (sfsr.invis(), FaultCause::InvalidIntegritySignature),
+        (sfsr.inver(), FaultCause::InvalidSecureExceptionReturn),
+        (sfsr.auviol(), FaultCause::AttributionViolation),
+        (sfsr.invtran(), FaultCause::InvalidTransition),
+        (sfsr.lsperr(), FaultCause::SecureLazyFpStacking),
+        (sfsr.lserr(), FaultCause::LazyStateError),
+    ];
+
+    flags
+        .iter()
+        .filter(|(is_set, _)| *is_set)
+        .map(|(_, cause)| *cause)
+        .collect()
+}
+
+/// Combines the values of MMFAR and BFAR with their valid bits in the CFSR.
+fn fault_addresses(cfsr: Cfsr, mmfar: u32, bfar: u32) -> (FaultAddress, FaultAddress) {
+    (
+        FaultAddress {
+            address: mmfar,
+            valid: cfsr.mmarvalid(),
+        },
+        FaultAddress {
+            address: bfar,
+            valid: cfsr.bfarvalid(),
+        },
+    )
+}
+
+/// Reads the return address from the exception frame, which the core stacked on entry of the fault handler.
+///
+/// The frame is located like in [`DebugInfo::unwind`](crate::debug::DebugInfo::unwind), but without debug
+/// information the CFA of the handler is not known. A frame on the main stack of the handler is then located
+/// with the current MSP, which only points to the frame until the handler pushes to the stack. The returned
+/// flag is set in that case, as the value may be wrong.
+///
+/// A corrupted stack pointer is a common cause of faults, so a failed read is not an error.
+fn stacked_pc(core: &mut Core) -> Option<(u32, bool)> {
+    let exception_return = ExceptionReturn::new(core.read_core_reg(register::LR).ok()?)?;
+
+    let unreliable = exception_return.uses_handler_stack(core);
+    let stack_pointer = core.read_core_reg(register::SP).ok();
+
+    let frame_address = match exception_frame_address(core, exception_return, None, stack_pointer) {
+        Ok(frame_address) => frame_address,
+        Err(e) => {
+            log::debug!("Failed to locate the exception frame: {}", e);
+            return None;
+        }
+    };
+
+    match core.read_word_32(frame_address.wrapping_add(exception_return.return_address_offset())) {
+        Ok(pc) => Some((pc, unreliable)),
+        Err(e) => {
+            log::debug!(
+                "Failed to read the exception frame at {:#010x}: {}",
+                frame_address,
+                e
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_configurable_fault_causes() {
+        assert!(configurable_fault_causes(Hfsr(0), Cfsr(0)).is_empty());
+
+        // HFSR.FORCED, with a precise bus fault and BFAR valid
+        assert_eq!(
+            configurable_fault_causes(Hfsr(1 << 30), Cfsr(0x0000_8200)),
+            vec![FaultCause::Escalated, FaultCause::PreciseDataBusError]
+        );
+
+        // DACCVIOL with MMFAR valid, and an undefined instruction
+        assert_eq!(
+            configurable_fault_causes(Hfsr(0), Cfsr(0x0001_0082)),
+            vec![
+                FaultCause::DataAccessViolation,
+                FaultCause::UndefinedInstruction
+            ]
+        );
+
+        assert_eq!(
+            configurable_fault_causes(Hfsr(1 << 1), Cfsr(0x0200_0000)),
+            vec![FaultCause::VectorTableRead, FaultCause::DivideByZero]
+        );
+    }
+
+    #[test]
+    fn decode_secure_fault_causes() {
+        assert!(secure_fault_causes(Sfsr(0)).is_empty());
+
+        // AUVIOL with SFAR valid
+        assert_eq!(
+            secure_fault_causes(Sfsr(0x48)),
+            vec![FaultCause::AttributionViolation]
+        );
+        assert_eq!(
+            secure_fault_causes(Sfsr(0x81)),
+            vec![FaultCause::InvalidEntryPoint, FaultCause::LazyStateError]
+        );
+    }
+
+    #[test]
+    fn fault_addresses_are_only_valid_with_valid_bits() {
+        let (mmfar, bfar) = fault_addresses(Cfsr(0x0000_0202), 0x2000_0000, 0x4000_0000);
+        assert!(!mmfar.valid);
+        assert!(!bfar.valid);
+        assert_eq!(mmfar.to_string(), "0x20000000 (not valid)");
+
+        let (mmfar, bfar) = fault_addresses(Cfsr(1 << 7), 0x2000_0000, 0x4000_0000);
+        assert!(mmfar.valid);
+        assert!(!bfar.valid);
+        assert_eq!(mmfar.to_string(), "0x20000000");
+
+        let (mmfar, bfar) = fault_addresses(Cfsr(1 << 15), 0x2000_0000, 0x4000_0000);
+        assert!(!mmfar.valid);
+        assert!(bfar.valid);
+        assert_eq!(bfar.address, 0x4000_0000);
+    }
+
+    #[test]
+    fn summary_marks_unreliable_pc() {
+        let mut report = FaultReport {
+            kind: FaultKind::HardFault,
+            causes: vec![FaultCause::Escalated],
+            stacked_pc: Some(0x0800_0100),
+            stacked_pc_unreliable: false,
+            cfsr: None,
+            hfsr: None,
+            dfsr: 0,
+            afsr: None,
+            mmfar: None,
+            bfar: None,
+            sfsr: None,
+            sfar: None,
+        };
+
+        assert_eq!(
+            report.summary(),
+            "HardFault: configurable fault escalated to HardFault at 0x08000100"
+        );
+
+        report.stacked_pc_unreliable = true;
+        assert!(report.summary().ends_with("at 0x08000100 (unreliable)"));
+    }
+}
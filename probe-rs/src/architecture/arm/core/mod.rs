@@ -1,33 +1,38 @@
 use crate::{
-    core::{CoreRegister, CoreRegisterAddress, RegisterDescription, RegisterFile, RegisterKind},
-    CoreStatus, HaltReason,
+    architecture::arm::{
+        ap::GenericAp,
+        communication_interface::MemoryApInformation,
+        memory::{Component, PeripheralType},
+        ApInformation,
+    },
+    core::{
+        CoreRegister, CoreRegisterAddress, RegisterDescription, RegisterFile, RegisterKind,
+        RegisterValue,
+    },
+    CoreStatus, Error, HaltReason, Memory,
 };
+use anyhow::anyhow;
 
 use bitfield::bitfield;
 
+#[macro_use]
+mod register_generation;
+
+// The implementation of the deprecated `Dump` uses it.
+#[allow(deprecated)]
+mod dump;
+
+#[allow(deprecated)]
+pub use dump::Dump;
+
 pub mod armv6m;
+pub mod armv7a;
 pub mod armv7m;
+pub mod armv8a;
 pub mod armv8m;
-
-/// Core information data which is downloaded from the target, represents its state and can be used for debugging.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Dump {
-    /// The register values at the time of the dump.
-    pub regs: [u32; 16],
-    stack_addr: u32,
-    stack: Vec<u8>,
-}
-
-impl Dump {
-    /// Create a new dump from a SP and a stack dump with zeroed out registers.
-    pub fn new(stack_addr: u32, stack: Vec<u8>) -> Dump {
-        Dump {
-            regs: [0u32; 16],
-            stack_addr,
-            stack,
-        }
-    }
-}
+pub(crate) mod cti;
+pub(crate) mod dwt;
+pub mod fault;
 
 pub(crate) mod register {
     use crate::{
@@ -39,36 +44,42 @@ pub(crate) mod register {
         name: "PC",
         _kind: RegisterKind::PC,
         address: CoreRegisterAddress(15),
+        size_in_bits: 32,
     };
 
     pub const XPSR: RegisterDescription = RegisterDescription {
         name: "XPSR",
         _kind: RegisterKind::General,
         address: CoreRegisterAddress(0b1_0000),
+        size_in_bits: 32,
     };
 
     pub const SP: RegisterDescription = RegisterDescription {
         name: "SP",
         _kind: RegisterKind::General,
         address: CoreRegisterAddress(13),
+        size_in_bits: 32,
     };
 
     pub const LR: RegisterDescription = RegisterDescription {
         name: "LR",
         _kind: RegisterKind::General,
         address: CoreRegisterAddress(14),
+        size_in_bits: 32,
     };
 
     pub const MSP: RegisterDescription = RegisterDescription {
         name: "MSP",
         _kind: RegisterKind::General,
         address: CoreRegisterAddress(0b10001),
+        size_in_bits: 32,
     };
 
     pub const PSP: RegisterDescription = RegisterDescription {
         name: "PSP",
         _kind: RegisterKind::General,
         address: CoreRegisterAddress(0b10010),
+        size_in_bits: 32,
     };
 
     // CONTROL bits [31:24], FAULTMASK bits [23:16],
@@ -77,6 +88,7 @@ pub(crate) mod register {
         name: "EXTRA",
         _kind: RegisterKind::General,
         address: CoreRegisterAddress(0b10100),
+        size_in_bits: 32,
     };
 
     // TODO: Floating point support
@@ -84,90 +96,107 @@ pub(crate) mod register {
         name: "FP",
         _kind: RegisterKind::General,
         address: CoreRegisterAddress(7),
+        size_in_bits: 32,
     };
 }
 
-static ARM_REGISTER_FILE: RegisterFile = RegisterFile {
+pub(crate) static ARM_REGISTER_FILE: RegisterFile = RegisterFile {
     platform_registers: &[
         RegisterDescription {
             name: "R0",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "R1",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(1),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "R2",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(2),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "R3",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(3),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "R4",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(4),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "R5",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(5),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "R6",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(6),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "R7",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(7),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "R8",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(8),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "R9",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(9),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "R10",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(10),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "R11",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(11),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "R12",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(12),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "R13",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(13),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "R14",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(14),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "R15",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(15),
+            size_in_bits: 32,
         },
     ],
 
@@ -181,21 +210,25 @@ static ARM_REGISTER_FILE: RegisterFile = RegisterFile {
             name: "a1",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "a2",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(1),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "a3",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(2),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "a4",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(3),
+            size_in_bits: 32,
         },
     ],
 
@@ -204,11 +237,13 @@ static ARM_REGISTER_FILE: RegisterFile = RegisterFile {
             name: "a1",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "a2",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(1),
+            size_in_bits: 32,
         },
     ],
 
@@ -258,7 +293,7 @@ impl Dfsr {
         } else if self.external() {
             HaltReason::External
         } else if self.dwttrap() {
-            HaltReason::Watchpoint
+            HaltReason::Watchpoint(None)
         } else if self.halted() {
             HaltReason::Request
         } else if self.vcatch() {
@@ -316,3 +351,153 @@ impl State {
         self.initialized
     }
 }
+
+/// The state of a Cortex-A core, which is kept between attaching to the core.
+#[derive(Debug)]
+pub struct CortexAState {
+    initialized: bool,
+
+    hw_breakpoints_enabled: bool,
+
+    current_state: CoreStatus,
+
+    /// The values of the registers which were read while the core is halted, and if they were modified.
+    ///
+    /// Modified registers are written back to the core before it is restarted.
+    register_cache: Vec<Option<(RegisterValue, bool)>>,
+
+    /// Whether the core is executing in AArch64 state.
+    is_64_bit: bool,
+
+    /// The base addresses of the debug registers and the CTI, once they are known.
+    base_addresses: Option<(u32, Option<u32>)>,
+}
+
+impl CortexAState {
+    pub(crate) fn new() -> Self {
+        Self {
+            initialized: false,
+            hw_breakpoints_enabled: false,
+            current_state: CoreStatus::Unknown,
+            register_cache: Vec::new(),
+            is_64_bit: false,
+            base_addresses: None,
+        }
+    }
+
+    fn initialize(&mut self) {
+        self.initialized = true;
+    }
+
+    fn initialized(&self) -> bool {
+        self.initialized
+    }
+
+    /// Discards all cached register values, which is required when the core is restarted.
+    fn invalidate_register_cache(&mut self, num_registers: usize) {
+        self.register_cache = vec![None; num_registers];
+    }
+
+    /// Returns the base addresses of the debug registers and the CTI of the core.
+    ///
+    /// They are determined by `find_base_addresses` when the core is attached the first time,
+    /// because looking them up in the ROM table requires a lot of memory accesses.
+    pub(crate) fn base_addresses(
+        &mut self,
+        find_base_addresses: impl FnOnce() -> Result<(u32, Option<u32>), Error>,
+    ) -> Result<(u32, Option<u32>), Error> {
+        match self.base_addresses {
+            Some(base_addresses) => Ok(base_addresses),
+            None => {
+                let base_addresses = find_base_addresses()?;
+                self.base_addresses = Some(base_addresses);
+
+                Ok(base_addresses)
+            }
+        }
+    }
+}
+
+/// Finds the debug registers and the CTI of a Cortex-A or Cortex-R core in the ROM table
+/// of the access port used by `memory`.
+///
+/// `index` is the position of the core among the cores of the access port, in the order in which
+/// their debug registers are listed in the ROM table. The CTI of a core is the first CTI which
+/// follows its debug registers, before the debug registers of the next core.
+pub(crate) fn find_debug_components(
+    memory: &mut Memory,
+    index: usize,
+) -> Result<(u32, Option<u32>), Error> {
+    let ap = memory.get_ap();
+    let rom_table_address = match memory
+        .get_arm_interface()?
+        .ap_information(GenericAp::new(ap))?
+    {
+        ApInformation::MemoryAp(MemoryApInformation {
+            debug_base_address, ..
+        }) => *debug_base_address,
+        ApInformation::Other { .. } => {
+            return Err(Error::Other(anyhow!(
+                "AP {:#x?} is not a MemoryAP, unable to read its ROM table.",
+                ap
+            )))
+        }
+    };
+
+    let rom_table =
+        Component::try_parse(memory, rom_table_address).map_err(Error::architecture_specific)?;
+
+    let is_core_debug = |component: &Component| {
+        component
+            .id()
+            .peripheral_id()
+            .is_of_type(PeripheralType::CoreDebug)
+    };
+
+    let (position, core_debug) = rom_table
+        .iter()
+        .enumerate()
+        .filter(|(_, component)| is_core_debug(component))
+        .nth(index)
+        .ok_or_else(|| {
+            anyhow!(
+                "The ROM table does not contain the debug registers of core {}",
+                index
+            )
+        })?;
+
+    let cti = rom_table
+        .iter()
+        .skip(position + 1)
+        .take_while(|component| !is_core_debug(component))
+        .find(|component| {
+            component
+                .id()
+                .peripheral_id()
+                .is_of_type(PeripheralType::Cti)
+        });
+
+    let address_32 = |component: &Component| -> Result<u32, Error> {
+        let address = component.id().component_address();
+
+        u32::try_from(address).map_err(|_| {
+            anyhow!(
+                "The component at {:#x} is outside of the 32 bit address space",
+                address
+            )
+            .into()
+        })
+    };
+
+    let debug_base = address_32(core_debug)?;
+    let cti_base = cti.map(address_32).transpose()?;
+
+    log::debug!(
+        "Found the debug registers of core {} at {:#010x} and its CTI at {:x?}",
+        index,
+        debug_base,
+        cti_base
+    );
+
+    Ok((debug_base, cti_base))
+}
@@ -0,0 +1,28 @@
+//! Helper macros to implement the memory mapped debug registers of a core.
+
+/// Implements [`DebugRegister`](crate::architecture::arm::component::DebugRegister)
+/// and the conversions from and to `u32` for a register type wrapping a `u32`.
+/// Takes
+/// - name: The register type.
+/// - address: The offset of the register from the debug base address of the core.
+/// - register_name: The name of the register.
+macro_rules! debug_register {
+    ($name:ident, $address:expr, $register_name:expr) => {
+        impl From<u32> for $name {
+            fn from(value: u32) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<$name> for u32 {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl $crate::architecture::arm::component::DebugRegister for $name {
+            const ADDRESS: u32 = $address;
+            const NAME: &'static str = $register_name;
+        }
+    };
+}
@@ -639,6 +639,7 @@ impl PeripheralID {
             ("ARM Ltd", 0x471, 0x00, 0x0000) => Some(PartInfo::new("Cortex-M0  ROM", PeripheralType::Rom)),
             ("ARM Ltd", 0x4C0, 0x00, 0x0000) => Some(PartInfo::new("Cortex-M0+ ROM", PeripheralType::Rom)),
             ("ARM Ltd", 0x4C4, 0x00, 0x0000) => Some(PartInfo::new("Cortex-M4 ROM", PeripheralType::Rom)),
+            ("ARM Ltd", 0x906, 0x14, 0x0000) => Some(PartInfo::new("CoreSight CTI", PeripheralType::Cti)),
             ("ARM Ltd", 0x907, 0x21, 0x0000) => Some(PartInfo::new("CoreSight ETB", PeripheralType::Etb)),
             ("ARM Ltd", 0x910, 0x00, 0x0000) => Some(PartInfo::new("CoreSight ETM9", PeripheralType::Etm)),
             ("ARM Ltd", 0x912, 0x11, 0x0000) => Some(PartInfo::new("CoreSight TPIU", PeripheralType::Tpiu)),
@@ -652,7 +653,18 @@ impl PeripheralID {
             ("ARM Ltd", 0x963, 0x63, 0x0a63) => Some(PartInfo::new("CoreSight STM", PeripheralType::Stm)),
             ("ARM Ltd", 0x975, 0x13, 0x4a13) => Some(PartInfo::new("Cortex-M7 ETM", PeripheralType::Etm)),
             ("ARM Ltd", 0x9A1, 0x11, 0x0000) => Some(PartInfo::new("Cortex-M4 TPIU", PeripheralType::Tpiu)),
+            ("ARM Ltd", 0x9A8, 0x14, 0x1A14) => Some(PartInfo::new("Cortex-A53 CTI", PeripheralType::Cti)),
             ("ARM Ltd", 0x9A9, 0x11, 0x0000) => Some(PartInfo::new("Cortex-M7 TPIU", PeripheralType::Tpiu)),
+            ("ARM Ltd", 0xC05, 0x15, 0x0000) => Some(PartInfo::new("Cortex-A5 Debug", PeripheralType::CoreDebug)),
+            ("ARM Ltd", 0xC07, 0x15, 0x0000) => Some(PartInfo::new("Cortex-A7 Debug", PeripheralType::CoreDebug)),
+            ("ARM Ltd", 0xC08, 0x15, 0x0000) => Some(PartInfo::new("Cortex-A8 Debug", PeripheralType::CoreDebug)),
+            ("ARM Ltd", 0xC09, 0x15, 0x0000) => Some(PartInfo::new("Cortex-A9 Debug", PeripheralType::CoreDebug)),
+            ("ARM Ltd", 0xC0F, 0x15, 0x0000) => Some(PartInfo::new("Cortex-A15 Debug", PeripheralType::CoreDebug)),
+            ("ARM Ltd", 0xC14, 0x15, 0x0000) => Some(PartInfo::new("Cortex-R4 Debug", PeripheralType::CoreDebug)),
+            ("ARM Ltd", 0xC15, 0x15, 0x0000) => Some(PartInfo::new("Cortex-R5 Debug", PeripheralType::CoreDebug)),
+            ("ARM Ltd", 0xC17, 0x15, 0x0000) => Some(PartInfo::new("Cortex-R7 Debug", PeripheralType::CoreDebug)),
+            ("ARM Ltd", 0xD03, 0x15, 0x6A15) => Some(PartInfo::new("Cortex-A53 Debug", PeripheralType::CoreDebug)),
+            ("ARM Ltd", 0xD08, 0x15, 0x6A15) => Some(PartInfo::new("Cortex-A72 Debug", PeripheralType::CoreDebug)),
             ("ARM Ltd", 0xD20, 0x00, 0x2A04) => Some(PartInfo::new("Cortex-M23 SCS", PeripheralType::Scs)),
             ("ARM Ltd", 0xD20, 0x11, 0x0000) => Some(PartInfo::new("Cortex-M23 TPIU", PeripheralType::Tpiu)),
             ("ARM Ltd", 0xD20, 0x13, 0x0000) => Some(PartInfo::new("Cortex-M23 ETM", PeripheralType::Etm)),
@@ -732,6 +744,10 @@ pub enum PeripheralType {
     Stm,
     /// Unknown
     Tsgen,
+    /// Cross Trigger Interface
+    Cti,
+    /// Debug registers of a Cortex-A or Cortex-R core
+    CoreDebug,
 }
 
 impl std::fmt::Display for PeripheralType {
@@ -749,6 +765,8 @@ impl std::fmt::Display for PeripheralType {
             PeripheralType::Swo => write!(f, "Swo (Single Wire Output)"),
             PeripheralType::Stm => write!(f, "Stm (System Trace Macrocell)"),
             PeripheralType::Tsgen => write!(f, "Tsgen (Time Stamp Generator)"),
+            PeripheralType::Cti => write!(f, "Cti (Cross Trigger Interface)"),
+            PeripheralType::CoreDebug => write!(f, "CoreDebug (Core Debug Registers)"),
         }
     }
 }
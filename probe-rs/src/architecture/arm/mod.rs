@@ -16,9 +16,14 @@ pub use communication_interface::{
 pub use swo::{SwoAccess, SwoConfig, SwoMode, SwoReader};
 pub use traits::*;
 
+#[allow(deprecated)]
+pub use self::core::Dump;
+
 pub use self::core::armv6m;
+pub use self::core::armv7a;
 pub use self::core::armv7m;
+pub use self::core::armv8a;
 pub use self::core::armv8m;
-pub use self::core::Dump;
+pub use self::core::fault;
 
 pub use communication_interface::ArmProbeInterface;
@@ -9,7 +9,9 @@ use std::{
     time::{Duration, Instant},
 };
 
-use crate::{architecture::arm::DapError, core::CoreRegister, DebugProbeError, Memory};
+use crate::{
+    architecture::arm::DapError, core::CoreRegister, DebugProbeError, Memory, WireProtocol,
+};
 
 use super::{
     ap::AccessPortError,
@@ -88,20 +90,42 @@ pub trait ArmDebugSequence: Send + Sync {
 
         // TODO: Use atomic block
 
-        // Ensure current debug interface is in reset state.
-        interface.swj_sequence(51, 0x0007_FFFF_FFFF_FFFF)?;
+        match interface.active_protocol() {
+            Some(WireProtocol::Jtag) => {
+                // Ensure current debug interface is in reset state.
+                interface.swj_sequence(51, 0x0007_FFFF_FFFF_FFFF)?;
+
+                // Execute SWJ-DP Switch Sequence SWD to JTAG (0xE73C).
+                interface.swj_sequence(16, 0xE73C)?;
+
+                // Execute at least >5 TCK cycles with TMS high to enter the Test-Logic-Reset state.
+                interface.swj_sequence(6, 0x3F)?;
 
-        // Execute SWJ-DP Switch Sequence JTAG to SWD (0xE79E).
-        // Change if SWJ-DP uses deprecated switch code (0xEDB6).
-        interface.swj_sequence(16, 0xE79E)?;
+                // Enter Run-Test-Idle state.
+                interface.swj_sequence(1, 0x00)?;
 
-        interface.swj_sequence(51, 0x0007_FFFF_FFFF_FFFF)?; // > 50 cycles SWDIO/TMS High.
-        interface.swj_sequence(3, 0x00)?; // At least 2 idle cycles (SWDIO/TMS Low).
+                // End of atomic block.
+
+                // Read the IDCODE of the JTAG-DP.
+                let _ = interface.raw_read_register(PortType::DebugPort, DPIDR::ADDRESS);
+            }
+            Some(WireProtocol::Swd) | None => {
+                // Ensure current debug interface is in reset state.
+                interface.swj_sequence(51, 0x0007_FFFF_FFFF_FFFF)?;
 
-        // End of atomic block.
+                // Execute SWJ-DP Switch Sequence JTAG to SWD (0xE79E).
+                // Change if SWJ-DP uses deprecated switch code (0xEDB6).
+                interface.swj_sequence(16, 0xE79E)?;
 
-        // Read DPIDR to enable SWD interface.
-        let _ = interface.raw_read_register(PortType::DebugPort, DPIDR::ADDRESS);
+                interface.swj_sequence(51, 0x0007_FFFF_FFFF_FFFF)?; // > 50 cycles SWDIO/TMS High.
+                interface.swj_sequence(3, 0x00)?; // At least 2 idle cycles (SWDIO/TMS Low).
+
+                // End of atomic block.
+
+                // Read DPIDR to enable SWD interface.
+                let _ = interface.raw_read_register(PortType::DebugPort, DPIDR::ADDRESS);
+            }
+        }
 
         // TODO: Figure a way how to do this.
         // interface.read_dpidr()?;
@@ -8,14 +8,17 @@ use super::{
     dtm::{DmiOperation, DmiOperationStatus, Dtm},
     register, Dmcontrol, Dmstatus,
 };
-use crate::DebugProbeError;
 use crate::{
     architecture::riscv::*,
     probe::{CommandResult, DeferredResultIndex},
 };
+use crate::{DebugProbe, DebugProbeError};
 use crate::{MemoryInterface, Probe};
 
-use crate::{probe::JTAGAccess, CoreRegisterAddress, Error as ProbeRsError};
+use crate::{
+    probe::{AsDebugProbe, JTAGAccess},
+    CoreRegisterAddress, Error as ProbeRsError,
+};
 
 use bitfield::bitfield;
 use std::{
@@ -1271,6 +1274,16 @@ impl<'probe> RiscvCommunicationInterface {
         Ok(())
     }
 
+    /// Returns the probe driver which is used by the interface.
+    pub fn probe(&self) -> &dyn DebugProbe {
+        self.dtm.probe.as_debug_probe()
+    }
+
+    /// Returns the probe driver which is used by the interface, e.g. to change its speed.
+    pub fn probe_mut(&mut self) -> &mut dyn DebugProbe {
+        self.dtm.probe.as_debug_probe_mut()
+    }
+
     /// Destruct the interface and return the stored probe driver.
     pub fn close(self) -> Probe {
         Probe::from_attached_probe(self.dtm.probe.into_probe())
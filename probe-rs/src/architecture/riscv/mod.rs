@@ -9,10 +9,12 @@ use communication_interface::{
     AbstractCommandErrorKind, DebugRegister, RiscvCommunicationInterface, RiscvError,
 };
 
-use crate::core::{CoreInformation, RegisterFile};
-use crate::{CoreRegisterAddress, CoreStatus, Error, HaltReason, MemoryInterface};
+use crate::core::{CoreInformation, RegisterFile, Watchpoint, WatchpointKind};
+use crate::{
+    CoreRegisterAddress, CoreStatus, Error, HaltReason, MemoryInterface, VectorCatchCondition,
+};
 use bitfield::bitfield;
-use register::RISCV_REGISTERS;
+pub(crate) use register::RISCV_REGISTERS;
 use std::time::{Duration, Instant};
 
 #[macro_use]
@@ -54,6 +56,162 @@ impl<'probe> Riscv32<'probe> {
         }
     }
 
+    /// Reads the trigger with index `unit_index`, and returns it if it is configured as a data watchpoint.
+    fn read_watchpoint_trigger(&mut self, unit_index: usize) -> Result<Option<Watchpoint>, Error> {
+        let tselect = 0x7a0;
+        let tdata1 = 0x7a1;
+        let tdata2 = 0x7a2;
+
+        self.write_csr(tselect, unit_index as u32)?;
+
+        let tdata_value = Mcontrol(self.read_csr(tdata1)?);
+
+        let trigger_any_mode_active = tdata_value.m() || tdata_value.s() || tdata_value.u();
+
+        if tdata_value.type_() != 0b10
+            || tdata_value.action() != 1
+            || !trigger_any_mode_active
+            || tdata_value.execute()
+            || !(tdata_value.load() || tdata_value.store())
+        {
+            return Ok(None);
+        }
+
+        let compare_value = self.read_csr(tdata2)?;
+
+        let (address, size) = match tdata_value.match_() {
+            0 => (compare_value, 1),
+            // A compare value of all ones would describe a range larger than the address space.
+            1 if compare_value != u32::MAX => {
+                let size_bits = compare_value.trailing_ones() + 1;
+                let size = 1 << size_bits;
+                (compare_value & !(size - 1), size)
+            }
+            _ => return Ok(None),
+        };
+
+        let kind = match (tdata_value.load(), tdata_value.store()) {
+            (true, true) => WatchpointKind::ReadWrite,
+            (true, false) => WatchpointKind::Read,
+            _ => WatchpointKind::Write,
+        };
+
+        Ok(Some(Watchpoint {
+            address,
+            size,
+            kind,
+        }))
+    }
+
+    /// Determines why the trigger module halted the core, using the `hit` bits of the triggers.
+    ///
+    /// The `hit` bit is optional, so if no trigger reports a hit, a breakpoint is assumed.
+    fn triggered_halt_reason(&mut self) -> Result<HaltReason, Error> {
+        let tselect = 0x7a0;
+        let tdata1 = 0x7a1;
+
+        let num_triggers = self.available_breakpoint_units()? as usize;
+
+        for unit_index in 0..num_triggers {
+            self.write_csr(tselect, unit_index as u32)?;
+
+            let tdata1_value = self.read_csr(tdata1)?;
+
+            let mut exception_trigger = Etrigger(tdata1_value);
+
+            if exception_trigger.type_() == 0b101 && exception_trigger.hit() {
+                exception_trigger.set_hit(false);
+                self.write_csr(tdata1, exception_trigger.0)?;
+
+                return Ok(HaltReason::Exception);
+            }
+
+            let mut tdata_value = Mcontrol(tdata1_value);
+
+            if tdata_value.type_() != 0b10 || !tdata_value.hit() {
+                continue;
+            }
+
+            // Clear the hit bit, so the next halt is reported correctly.
+            tdata_value.set_hit(false);
+            self.write_csr(tdata1, tdata_value.0)?;
+
+            if let Some(watchpoint) = self.read_watchpoint_trigger(unit_index)? {
+                return Ok(HaltReason::Watchpoint(Some(watchpoint.address)));
+            }
+
+            return Ok(HaltReason::Breakpoint);
+        }
+
+        Ok(HaltReason::Breakpoint)
+    }
+
+    /// Configures an exception trigger (`etrigger`), which halts the core when an exception is raised.
+    ///
+    /// The trigger is placed on the last trigger which supports it and is not used as a breakpoint or
+    /// watchpoint, so it does not get in the way of breakpoints, which are allocated from the first trigger.
+    /// Any previously configured exception trigger is removed.
+    fn configure_exception_trigger(&mut self, enable: bool) -> Result<(), Error> {
+        let tselect = 0x7a0;
+        let tdata1 = 0x7a1;
+        let tdata2 = 0x7a2;
+
+        let num_triggers = self.available_breakpoint_units()?;
+
+        let mut configured = !enable;
+
+        for unit_index in (0..num_triggers).rev() {
+            self.write_csr(tselect, unit_index)?;
+
+            let tdata_value = Mcontrol(self.read_csr(tdata1)?);
+
+            let trigger_in_use = match tdata_value.type_() {
+                // No trigger, or trigger which does not exist
+                0b0000 | 0b1111 => false,
+                // Breakpoint or watchpoint, which is only in use if it is active in any mode
+                0b0010 => tdata_value.m() || tdata_value.s() || tdata_value.u(),
+                // Exception trigger configured by an earlier call
+                0b0101 => {
+                    self.write_csr(tdata1, 0)?;
+                    false
+                }
+                _ => true,
+            };
+
+            if trigger_in_use || configured {
+                continue;
+            }
+
+            let mut exception_trigger = Etrigger(0);
+            exception_trigger.set_type(0b101);
+            exception_trigger.set_dmode(true);
+            exception_trigger.set_m(true);
+            exception_trigger.set_s(true);
+            exception_trigger.set_u(true);
+
+            // Enter debug mode
+            exception_trigger.set_action(1);
+
+            self.write_csr(tdata1, exception_trigger.0)?;
+
+            // The type is WARL, so it only sticks if the trigger supports exception triggers.
+            let readback = Etrigger(self.read_csr(tdata1)?);
+
+            if readback.type_() == 0b101 {
+                self.write_csr(tdata2, EXCEPTION_TRIGGER_CAUSES)?;
+                configured = true;
+            } else {
+                self.write_csr(tdata1, tdata_value.0)?;
+            }
+        }
+
+        if !configured {
+            return Err(anyhow!("No trigger is available for catching exceptions").into());
+        }
+
+        Ok(())
+    }
+
     fn write_csr(&mut self, address: u16, value: u32) -> Result<(), RiscvError> {
         log::debug!("Writing CSR {:#x}", address);
 
@@ -116,7 +274,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
 
         let pc = self.read_core_reg(register::RISCV_REGISTERS.program_counter.address)?;
 
-        Ok(CoreInformation { pc })
+        Ok(CoreInformation { pc: pc.into() })
     }
 
     fn run(&mut self) -> Result<(), crate::Error> {
@@ -265,7 +423,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
 
         let pc = self.read_core_reg(CoreRegisterAddress(0x7b1))?;
 
-        Ok(CoreInformation { pc })
+        Ok(CoreInformation { pc: pc.into() })
     }
 
     fn step(&mut self) -> Result<crate::core::CoreInformation, crate::Error> {
@@ -288,7 +446,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
 
         self.write_csr(0x7b0, dcsr.0)?;
 
-        Ok(CoreInformation { pc })
+        Ok(CoreInformation { pc: pc.into() })
     }
 
     fn read_core_reg(&mut self, address: crate::CoreRegisterAddress) -> Result<u32, crate::Error> {
@@ -434,6 +592,110 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         Ok(())
     }
 
+    fn available_watchpoint_units(&mut self) -> Result<u32, crate::Error> {
+        // Watchpoints use the same triggers as breakpoints.
+        self.available_breakpoint_units()
+    }
+
+    /// See docs on the [`CoreInterface::hw_watchpoints`] trait
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        let num_triggers = self.available_breakpoint_units()? as usize;
+
+        let mut watchpoints = Vec::with_capacity(num_triggers);
+        for unit_index in 0..num_triggers {
+            watchpoints.push(self.read_watchpoint_trigger(unit_index)?);
+        }
+
+        Ok(watchpoints)
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        unit_index: usize,
+        watchpoint: Watchpoint,
+    ) -> Result<(), crate::Error> {
+        let tselect = 0x7a0;
+        let tdata1 = 0x7a1;
+        let tdata2 = 0x7a2;
+
+        self.write_csr(tselect, unit_index as u32)?;
+
+        // verify the trigger has the correct type
+        let tdata_value = Mcontrol(self.read_csr(tdata1)?);
+
+        let trigger_type = tdata_value.type_();
+        if trigger_type != 0b10 {
+            return Err(RiscvError::UnexpectedTriggerType(trigger_type).into());
+        }
+
+        // Ranges are matched using the NAPOT encoding, where the number of trailing
+        // ones in tdata2 determines the size of the naturally aligned range.
+        let (match_, compare_value) = if watchpoint.size == 1 {
+            (0, watchpoint.address)
+        } else {
+            let size_bits = watchpoint.size.trailing_zeros();
+
+            if size_bits > tdata_value.maskmax() {
+                return Err(anyhow!(
+                    "Watchpoints with a size of {} bytes are not supported by this core",
+                    watchpoint.size
+                )
+                .into());
+            }
+
+            (1, watchpoint.address | ((1 << (size_bits - 1)) - 1))
+        };
+
+        let mut data_watchpoint = Mcontrol(0);
+
+        // Enter debug mode
+        data_watchpoint.set_action(1);
+
+        data_watchpoint.set_match(match_);
+
+        data_watchpoint.set_m(true);
+        data_watchpoint.set_s(true);
+        data_watchpoint.set_u(true);
+
+        // Trigger on the requested kind of memory access
+        data_watchpoint.set_load(watchpoint.kind != WatchpointKind::Write);
+        data_watchpoint.set_store(watchpoint.kind != WatchpointKind::Read);
+
+        data_watchpoint.set_dmode(true);
+
+        // Match address
+        data_watchpoint.set_select(false);
+
+        self.write_csr(tdata1, data_watchpoint.0)?;
+        self.write_csr(tdata2, compare_value)?;
+
+        // Not all triggers support load / store matches, or the requested match type.
+        let readback = Mcontrol(self.read_csr(tdata1)?);
+        if readback.match_() != match_
+            || readback.load() != data_watchpoint.load()
+            || readback.store() != data_watchpoint.store()
+        {
+            self.write_csr(tdata1, 0)?;
+
+            return Err(anyhow!(
+                "Trigger #{} does not support data watchpoints of {} bytes",
+                unit_index,
+                watchpoint.size
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+
+    fn clear_hw_watchpoint(&mut self, unit_index: usize) -> Result<(), crate::Error> {
+        self.clear_hw_breakpoint(unit_index)
+    }
+
+    fn watchpoints_share_breakpoint_units(&self) -> bool {
+        true
+    }
+
     fn registers(&self) -> &'static RegisterFile {
         &RISCV_REGISTERS
     }
@@ -448,6 +710,55 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         Architecture::Riscv
     }
 
+    fn set_vector_catch(&mut self, conditions: &[VectorCatchCondition]) -> Result<(), Error> {
+        if let Some(condition) = conditions.iter().find(|condition| {
+            !matches!(
+                condition,
+                VectorCatchCondition::CoreReset
+                    | VectorCatchCondition::Ebreak
+                    | VectorCatchCondition::Exception
+            )
+        }) {
+            return Err(anyhow!(
+                "Vector catch condition {:?} is not supported by RISC-V cores",
+                condition
+            )
+            .into());
+        }
+
+        let catch_reset = conditions.contains(&VectorCatchCondition::CoreReset);
+        let catch_ebreak = conditions.contains(&VectorCatchCondition::Ebreak);
+        let catch_exception = conditions.contains(&VectorCatchCondition::Exception);
+
+        if catch_reset {
+            let status: Dmstatus = self.interface.read_dm_register()?;
+
+            if !status.hasresethaltreq() {
+                return Err(anyhow!("The debug module does not support halting on reset").into());
+            }
+        }
+
+        let mut dmcontrol = Dmcontrol(0);
+        dmcontrol.set_dmactive(true);
+        if catch_reset {
+            dmcontrol.set_resethaltreq(true);
+        } else {
+            dmcontrol.set_clrresethaltreq(true);
+        }
+
+        self.interface.write_dm_register(dmcontrol)?;
+
+        let mut dcsr = Dcsr(self.read_core_reg(CoreRegisterAddress(0x7b0))?);
+
+        dcsr.set_ebreakm(catch_ebreak);
+        dcsr.set_ebreaks(catch_ebreak);
+        dcsr.set_ebreaku(catch_ebreak);
+
+        self.write_csr(0x7b0, dcsr.0)?;
+
+        self.configure_exception_trigger(catch_exception)
+    }
+
     fn status(&mut self) -> Result<crate::core::CoreStatus, crate::Error> {
         // TODO: We should use hartsum to determine if any hart is halted
         //       quickly
@@ -462,7 +773,7 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
                 // An ebreak instruction was hit
                 1 => HaltReason::Breakpoint,
                 // Trigger module caused halt
-                2 => HaltReason::Breakpoint,
+                2 => self.triggered_halt_reason()?,
                 // Debugger requested a halt
                 3 => HaltReason::Request,
                 // Core halted after single step
@@ -505,15 +816,13 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
             // The trigger must be active in at least a single mode
             let trigger_any_mode_active = tdata_value.m() || tdata_value.s() || tdata_value.u();
 
-            let trigger_any_action_enabled =
-                tdata_value.execute() || tdata_value.store() || tdata_value.load();
-
             // Only return if the trigger if it is for an execution debug action in all modes.
+            // Load and store triggers are reported by `hw_watchpoints`.
             if tdata_value.type_() == 0b10
                 && tdata_value.action() == 1
                 && tdata_value.match_() == 0
                 && trigger_any_mode_active
-                && trigger_any_action_enabled
+                && tdata_value.execute()
             {
                 let breakpoint = self.read_csr(tdata2)?;
                 breakpoints.push(Some(breakpoint));
@@ -761,6 +1070,29 @@ data_register! { pub Progbuf13, 0x2D, "progbuf13" }
 data_register! { pub Progbuf14, 0x2E, "progbuf14" }
 data_register! { pub Progbuf15, 0x2F, "progbuf15" }
 
+/// The exception causes which are caught by the exception trigger, i.e. all synchronous exceptions
+/// except for environment calls and breakpoints.
+///
+/// Bit `n` corresponds to the exception code `n` in `mcause`: instruction address misaligned (0),
+/// instruction access fault (1), illegal instruction (2), load address misaligned (4),
+/// load access fault (5), store/AMO address misaligned (6), store/AMO access fault (7),
+/// instruction page fault (12), load page fault (13) and store/AMO page fault (15).
+const EXCEPTION_TRIGGER_CAUSES: u32 = 0b1011_0000_1111_0111;
+
+bitfield! {
+    /// `tdata1` of an exception trigger (`etrigger`), see RISC-V External Debug Support 0.13.2, Section 5.2.8
+    struct Etrigger(u32);
+    impl Debug;
+
+    type_, set_type: 31, 28;
+    dmode, set_dmode: 27;
+    hit, set_hit: 26;
+    m, set_m: 9;
+    s, set_s: 7;
+    u, set_u: 6;
+    action, set_action: 5, 0;
+}
+
 bitfield! {
     struct Mcontrol(u32);
     impl Debug;
@@ -57,6 +57,7 @@ static PC: RegisterDescription = RegisterDescription {
     _kind: RegisterKind::PC,
     /// This is a CSR register
     address: CoreRegisterAddress(0x7b1),
+    size_in_bits: 32,
 };
 
 static RA: RegisterDescription = RegisterDescription {
@@ -64,6 +65,7 @@ static RA: RegisterDescription = RegisterDescription {
     _kind: RegisterKind::General,
     /// This is a CSR register
     address: CoreRegisterAddress(0x1001),
+    size_in_bits: 32,
 };
 
 static SP: RegisterDescription = RegisterDescription {
@@ -71,6 +73,7 @@ static SP: RegisterDescription = RegisterDescription {
     _kind: RegisterKind::General,
     /// This is a CSR register
     address: CoreRegisterAddress(0x1002),
+    size_in_bits: 32,
 };
 
 static FP: RegisterDescription = RegisterDescription {
@@ -78,6 +81,7 @@ static FP: RegisterDescription = RegisterDescription {
     _kind: RegisterKind::General,
     /// This is a CSR register
     address: CoreRegisterAddress(0x1008),
+    size_in_bits: 32,
 };
 
 pub static S0: RegisterDescription = RegisterDescription {
@@ -85,6 +89,7 @@ pub static S0: RegisterDescription = RegisterDescription {
     _kind: RegisterKind::General,
     /// This is a CSR register
     address: CoreRegisterAddress(0x1008),
+    size_in_bits: 32,
 };
 
 pub static S1: RegisterDescription = RegisterDescription {
@@ -92,169 +97,202 @@ pub static S1: RegisterDescription = RegisterDescription {
     _kind: RegisterKind::General,
     /// This is a CSR register
     address: CoreRegisterAddress(0x1009),
+    size_in_bits: 32,
 };
 
-pub(super) static RISCV_REGISTERS: RegisterFile = RegisterFile {
+pub(crate) static RISCV_REGISTERS: RegisterFile = RegisterFile {
     platform_registers: &[
         RegisterDescription {
             name: "x0",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x1000),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x1",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x1001),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x2",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x1002),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x3",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x1003),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x4",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x1004),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x5",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x1005),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x6",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x1006),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x7",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x1007),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x8",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x1008),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x9",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x1009),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x10",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x100A),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x11",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x100B),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x12",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x100C),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x13",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x100D),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x14",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x100E),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x15",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x100F),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x16",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x1010),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x17",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x1011),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x18",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x1012),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x19",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x1013),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x20",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x1014),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x21",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x1015),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x22",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x1016),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x23",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x1017),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x24",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x1018),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x25",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x1019),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x26",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x101A),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x27",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x101B),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x28",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x101C),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x29",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x101D),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x30",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x101E),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "x31",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x101F),
+            size_in_bits: 32,
         },
     ],
 
@@ -271,41 +309,49 @@ pub(super) static RISCV_REGISTERS: RegisterFile = RegisterFile {
             name: "a0",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x100A),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "a1",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x100B),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "a2",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x100C),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "a3",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x100D),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "a4",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x100E),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "a5",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x100F),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "a6",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x1010),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "a7",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x1011),
+            size_in_bits: 32,
         },
     ],
 
@@ -314,11 +360,13 @@ pub(super) static RISCV_REGISTERS: RegisterFile = RegisterFile {
             name: "a0",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x100A),
+            size_in_bits: 32,
         },
         RegisterDescription {
             name: "a1",
             _kind: RegisterKind::General,
             address: CoreRegisterAddress(0x100B),
+            size_in_bits: 32,
         },
     ],
 
@@ -1,6 +1,8 @@
 use probe_rs_target::{Architecture, ChipFamily};
 
-use super::{Core, MemoryRegion, RawFlashAlgorithm, RegistryError, TargetDescriptionSource};
+use super::{
+    Core, MemoryRegion, NvmRegion, RawFlashAlgorithm, RegistryError, TargetDescriptionSource,
+};
 
 use crate::architecture::arm::sequences::nrf53::Nrf5340;
 use crate::architecture::arm::sequences::nxp::LPC55S69;
@@ -148,6 +150,14 @@ impl Target {
         self.cores.iter().position(|c| c.name == name)
     }
 
+    /// Gets the [NvmRegion] with the given name.
+    pub fn nvm_region_by_name(&self, name: &str) -> Option<&NvmRegion> {
+        self.memory_map.iter().find_map(|region| match region {
+            MemoryRegion::Nvm(region) if region.name.as_deref() == Some(name) => Some(region),
+            _ => None,
+        })
+    }
+
     /// Gets the first found [MemoryRegion] that contains the given address
     pub(crate) fn get_memory_region_by_address(&self, address: u32) -> Option<&MemoryRegion> {
         self.memory_map.iter().find(|region| match region {
@@ -0,0 +1,755 @@
+//! Core dumps, which allow debugging a core without a connected target.
+//!
+//! A core dump contains the registers of a core, and the contents of selected memory ranges.
+//! It is stored as an ELF core file. Besides the standard `NT_PRSTATUS` and `NT_ARM_VFP` notes,
+//! which are understood by other tools, the file contains a `PROBE-RS` note with all registers
+//! captured by probe-rs.
+
+use std::{collections::BTreeMap, fs, ops::Range, path::Path, time::Duration};
+
+use anyhow::anyhow;
+use object::{
+    elf::{
+        FileHeader32, EM_AARCH64, EM_ARM, EM_RISCV, ET_CORE, NT_PRSTATUS, PF_R, PF_W, PT_LOAD,
+        PT_NOTE,
+    },
+    read::elf::{FileHeader, ProgramHeader},
+    Endianness,
+};
+use probe_rs_target::CoreType;
+
+use super::{
+    Architecture, Core, CoreInformation, CoreInterface, CoreRegisterAddress, CoreState, CoreStatus,
+    HaltReason, RegisterFile, RegisterValue, VectorCatchCondition, Watchpoint,
+};
+use crate::architecture::{
+    arm::core::{
+        armv7a::ARMV7A_REGISTER_FILE, armv8a::AARCH64_REGISTER_FILE, register::XPSR,
+        ARM_REGISTER_FILE,
+    },
+    riscv::RISCV_REGISTERS,
+};
+use crate::elf_writer::{self, align_4, Segment};
+use crate::{Error, MemoryInterface};
+
+/// Name of the note containing the information stored by probe-rs.
+const PROBE_RS_NOTE_NAME: &[u8] = b"PROBE-RS";
+
+/// Type of the note containing the core type and the registers.
+const NT_PROBE_RS_CORE_INFO: u32 = 1;
+
+/// Name of the standard notes, e.g. `NT_PRSTATUS`.
+const CORE_NOTE_NAME: &[u8] = b"CORE";
+
+/// Type of the note containing the floating point registers of an ARM core.
+const NT_ARM_VFP: u32 = 0x400;
+
+/// Size of the fields of `struct elf_prstatus` which precede the registers.
+const PRSTATUS_HEADER_SIZE: usize = 72;
+
+/// Offset of the `pr_cursig` field in `struct elf_prstatus`.
+const PRSTATUS_CURSIG_OFFSET: usize = 12;
+
+/// Signal reported in `pr_cursig`, a core dump is treated like a stop at a breakpoint.
+const SIGTRAP: u16 = 5;
+
+/// The FPSCR of an ARMv7-M or ARMv8-M core.
+const FPSCR: CoreRegisterAddress = CoreRegisterAddress(0x21);
+
+/// The first single precision floating point register S0 of an ARMv7-M or ARMv8-M core.
+const S0: u16 = 0x40;
+
+/// The number of single precision floating point registers of an ARMv7-M or ARMv8-M core.
+const NUM_SINGLE_PRECISION_REGISTERS: u16 = 32;
+
+/// The number of double precision registers in a `NT_ARM_VFP` note.
+const NUM_VFP_REGISTERS: usize = 32;
+
+/// An error which occurred while storing or loading a core dump.
+#[derive(thiserror::Error, Debug)]
+pub enum CoreDumpError {
+    /// The core dump file could not be read or written.
+    #[error("Failed to access the core dump file")]
+    Io(#[from] std::io::Error),
+    /// The core dump file is not a valid ELF file.
+    #[error("Failed to parse the core dump file")]
+    Elf(#[from] object::read::Error),
+    /// The information stored by probe-rs could not be encoded or decoded.
+    #[error("Failed to encode or decode the core information")]
+    CoreInfo(#[from] bincode::Error),
+    /// The file is not a core dump created by probe-rs.
+    #[error("Invalid core dump: {0}")]
+    InvalidCoreDump(&'static str),
+}
+
+/// The information stored in the `PROBE-RS` note of a core dump.
+#[derive(Serialize, Deserialize)]
+struct CoreInfo {
+    core_type: CoreType,
+    registers: BTreeMap<u16, RegisterValue>,
+}
+
+/// A snapshot of the registers and selected memory ranges of a core.
+///
+/// The [`Core`] returned by [`CoreDump::core`] can be used like the core of a connected target,
+/// e.g. to unwind the stack with [`DebugInfo::unwind`](crate::debug::DebugInfo::unwind).
+/// Only the memory which was captured can be accessed, and the core can not be resumed.
+#[derive(Debug)]
+pub struct CoreDump {
+    core_type: CoreType,
+    registers: BTreeMap<u16, RegisterValue>,
+    memory: Vec<(u32, Vec<u8>)>,
+    core_state: CoreState,
+}
+
+impl CoreDump {
+    /// Captures the registers of `core`, and the memory in `ranges`.
+    ///
+    /// The core should be halted, otherwise the captured state is not consistent.
+    pub fn capture(
+        core: &mut Core,
+        core_type: CoreType,
+        ranges: &[Range<u32>],
+    ) -> Result<Self, Error> {
+        let mut registers = BTreeMap::new();
+
+        for address in register_addresses(core.registers(), core_type) {
+            registers.insert(address.0, core.read_core_reg_value(address)?);
+        }
+
+        for address in floating_point_register_addresses(core_type) {
+            // The floating point unit is optional, so its registers are skipped if they can not be read.
+            match core.read_core_reg_value(address) {
+                Ok(value) => {
+                    registers.insert(address.0, value);
+                }
+                Err(e) => {
+                    log::debug!("Not capturing register {:#x}: {}", address.0, e);
+                }
+            }
+        }
+
+        let mut memory = Vec::new();
+
+        for range in ranges {
+            let mut data = vec![0u8; (range.end - range.start) as usize];
+            core.read(range.start, &mut data)?;
+
+            memory.push((range.start, data));
+        }
+
+        Ok(Self {
+            core_type,
+            registers,
+            memory,
+            core_state: CoreState::new(core.id()),
+        })
+    }
+
+    /// Creates a core dump from the captured `registers`, indexed by their address, and `memory`.
+    pub(crate) fn new(
+        core_type: CoreType,
+        registers: BTreeMap<u16, RegisterValue>,
+        memory: Vec<(u32, Vec<u8>)>,
+    ) -> Self {
+        Self {
+            core_type,
+            registers,
+            memory,
+            core_state: CoreState::new(0),
+        }
+    }
+
+    /// Loads a core dump, which was stored with [`CoreDump::store`].
+    pub fn load(path: &Path) -> Result<Self, CoreDumpError> {
+        Self::from_elf(&fs::read(path)?)
+    }
+
+    /// Stores the core dump as an ELF core file.
+    pub fn store(&self, path: &Path) -> Result<(), CoreDumpError> {
+        fs::write(path, self.to_elf()?)?;
+
+        Ok(())
+    }
+
+    fn from_elf(data: &[u8]) -> Result<Self, CoreDumpError> {
+        let header = FileHeader32::<Endianness>::parse(data)?;
+        let endian = header.endian()?;
+
+        if header.e_type(endian) != ET_CORE {
+            return Err(CoreDumpError::InvalidCoreDump("Not an ELF core file"));
+        }
+
+        let mut core_info = None;
+        let mut memory = Vec::new();
+
+        for segment in header.program_headers(endian, data)? {
+            match segment.p_type(endian) {
+                PT_LOAD => {
+                    let segment_data = segment.data(endian, data).map_err(|_| {
+                        CoreDumpError::InvalidCoreDump("Failed to access data for an ELF segment")
+                    })?;
+
+                    memory.push((segment.p_vaddr(endian), segment_data.to_vec()));
+                }
+                PT_NOTE => {
+                    if let Some(mut notes) = segment.notes(endian, data)? {
+                        while let Some(note) = notes.next()? {
+                            // Depending on the parser, the name can include the terminating NUL byte.
+                            let name = note.name().strip_suffix(&[0]).unwrap_or(note.name());
+
+                            if name == PROBE_RS_NOTE_NAME
+                                && note.n_type(endian) == NT_PROBE_RS_CORE_INFO
+                            {
+                                core_info = Some(bincode::deserialize::<CoreInfo>(note.desc())?);
+                            }
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let core_info = core_info.ok_or(CoreDumpError::InvalidCoreDump(
+            "The core information stored by probe-rs is missing",
+        ))?;
+
+        Ok(Self {
+            core_type: core_info.core_type,
+            registers: core_info.registers,
+            memory,
+            core_state: CoreState::new(0),
+        })
+    }
+
+    /// The type of the captured core.
+    pub fn core_type(&self) -> CoreType {
+        self.core_type
+    }
+
+    /// The captured memory ranges.
+    pub fn memory_ranges(&self) -> impl Iterator<Item = Range<u32>> + '_ {
+        self.memory
+            .iter()
+            .map(|(address, data)| *address..*address + data.len() as u32)
+    }
+
+    /// Returns a [`Core`] which accesses the captured registers and memory.
+    ///
+    /// Writes to registers and captured memory change the core dump.
+    pub fn core(&mut self) -> Core<'_> {
+        Core::new(
+            DumpCore {
+                core_type: self.core_type,
+                registers: &mut self.registers,
+                memory: &mut self.memory,
+            },
+            &mut self.core_state,
+        )
+    }
+
+    fn to_elf(&self) -> Result<Vec<u8>, CoreDumpError> {
+        let notes = self.notes()?;
+
+        let segments: Vec<_> = std::iter::once(Segment {
+            segment_type: PT_NOTE,
+            flags: PF_R,
+            address: 0,
+            data: &notes,
+            section: None,
+        })
+        .chain(self.memory.iter().map(|(address, data)| Segment {
+            segment_type: PT_LOAD,
+            flags: PF_R | PF_W,
+            address: *address,
+            data,
+            section: None,
+        }))
+        .collect();
+
+        Ok(elf_writer::write(
+            ET_CORE,
+            machine(self.core_type),
+            &segments,
+        ))
+    }
+
+    fn notes(&self) -> Result<Vec<u8>, CoreDumpError> {
+        let mut notes = Vec::new();
+
+        let vfp_registers = self.vfp_registers();
+
+        if let Some(registers) = self.prstatus_registers() {
+            let mut prstatus = vec![0u8; PRSTATUS_HEADER_SIZE];
+            prstatus[PRSTATUS_CURSIG_OFFSET..][..2].copy_from_slice(&SIGTRAP.to_le_bytes());
+
+            for value in registers {
+                prstatus.extend_from_slice(&value.to_le_bytes());
+            }
+
+            // pr_fpvalid
+            prstatus.extend_from_slice(&u32::from(vfp_registers.is_some()).to_le_bytes());
+
+            push_note(&mut notes, CORE_NOTE_NAME, NT_PRSTATUS, &prstatus);
+        }
+
+        if let Some(vfp_registers) = vfp_registers {
+            push_note(&mut notes, CORE_NOTE_NAME, NT_ARM_VFP, &vfp_registers);
+        }
+
+        let core_info = bincode::serialize(&CoreInfo {
+            core_type: self.core_type,
+            registers: self.registers.clone(),
+        })?;
+
+        push_note(
+            &mut notes,
+            PROBE_RS_NOTE_NAME,
+            NT_PROBE_RS_CORE_INFO,
+            &core_info,
+        );
+
+        Ok(notes)
+    }
+
+    fn register_u32(&self, address: CoreRegisterAddress) -> u32 {
+        self.registers
+            .get(&address.0)
+            .and_then(|value| u32::try_from(*value).ok())
+            .unwrap_or_default()
+    }
+
+    /// Returns the registers in the layout of `elf_prstatus.pr_reg` used by Linux,
+    /// or `None` if the layout is not known for the core.
+    fn prstatus_registers(&self) -> Option<Vec<u32>> {
+        let register_file = register_file(self.core_type);
+
+        match self.core_type {
+            CoreType::Armv8a => None,
+            // PC, followed by the registers x1 to x31
+            CoreType::Riscv => Some(
+                std::iter::once(register_file.program_counter())
+                    .chain(register_file.platform_registers.iter().skip(1))
+                    .map(|register| self.register_u32(register.into()))
+                    .collect(),
+            ),
+            // R0 to R15, the xPSR or CPSR, and the unused ORIG_R0
+            _ => Some(
+                (0..=16)
+                    .map(|address| self.register_u32(CoreRegisterAddress(address)))
+                    .chain(std::iter::once(0))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Returns the contents of a `NT_ARM_VFP` note, or `None` if no floating point registers were captured.
+    fn vfp_registers(&self) -> Option<Vec<u8>> {
+        if !self.registers.contains_key(&FPSCR.0) {
+            return None;
+        }
+
+        let mut vfp_registers = Vec::new();
+
+        // Two single precision registers form one of the double precision registers D0 to D15.
+        for index in (0..NUM_SINGLE_PRECISION_REGISTERS).step_by(2) {
+            let low = self.register_u32(CoreRegisterAddress(S0 + index));
+            let high = self.register_u32(CoreRegisterAddress(S0 + index + 1));
+
+            vfp_registers.extend_from_slice(&low.to_le_bytes());
+            vfp_registers.extend_from_slice(&high.to_le_bytes());
+        }
+
+        // The registers D16 to D31 are not implemented on ARMv7-M and ARMv8-M.
+        vfp_registers.resize(NUM_VFP_REGISTERS * 8, 0);
+        vfp_registers.extend_from_slice(&self.register_u32(FPSCR).to_le_bytes());
+
+        Some(vfp_registers)
+    }
+}
+
+/// A [`CoreInterface`] which accesses the registers and memory captured in a [`CoreDump`].
+struct DumpCore<'dump> {
+    core_type: CoreType,
+    registers: &'dump mut BTreeMap<u16, RegisterValue>,
+    memory: &'dump mut Vec<(u32, Vec<u8>)>,
+}
+
+impl DumpCore<'_> {
+    /// Returns the captured memory from `address` to the end of its memory range.
+    fn memory_at(&mut self, address: u32) -> Result<&mut [u8], Error> {
+        for (start, data) in self.memory.iter_mut() {
+            if let Some(offset) = address.checked_sub(*start) {
+                if (offset as usize) < data.len() {
+                    return Ok(&mut data[offset as usize..]);
+                }
+            }
+        }
+
+        Err(anyhow!(
+            "Memory at address {:#010x} is not part of the core dump",
+            address
+        )
+        .into())
+    }
+
+    fn read_memory(&mut self, address: u32, data: &mut [u8]) -> Result<(), Error> {
+        let mut offset = 0;
+
+        // The accessed memory can span multiple captured ranges.
+        while offset < data.len() {
+            let memory = self.memory_at(address + offset as u32)?;
+            let length = memory.len().min(data.len() - offset);
+
+            data[offset..][..length].copy_from_slice(&memory[..length]);
+            offset += length;
+        }
+
+        Ok(())
+    }
+
+    fn write_memory(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
+        let mut offset = 0;
+
+        while offset < data.len() {
+            let memory = self.memory_at(address + offset as u32)?;
+            let length = memory.len().min(data.len() - offset);
+
+            memory[..length].copy_from_slice(&data[offset..][..length]);
+            offset += length;
+        }
+
+        Ok(())
+    }
+}
+
+impl MemoryInterface for DumpCore<'_> {
+    fn read_word_32(&mut self, address: u32) -> Result<u32, Error> {
+        let mut data = [0u8; 4];
+        self.read_memory(address, &mut data)?;
+
+        Ok(u32::from_le_bytes(data))
+    }
+
+    fn read_word_8(&mut self, address: u32) -> Result<u8, Error> {
+        let mut data = [0u8; 1];
+        self.read_memory(address, &mut data)?;
+
+        Ok(data[0])
+    }
+
+    fn read_32(&mut self, address: u32, data: &mut [u32]) -> Result<(), Error> {
+        let mut buffer = vec![0u8; data.len() * 4];
+        self.read_memory(address, &mut buffer)?;
+
+        for (word, bytes) in data.iter_mut().zip(buffer.chunks_exact(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+
+        Ok(())
+    }
+
+    fn read_8(&mut self, address: u32, data: &mut [u8]) -> Result<(), Error> {
+        self.read_memory(address, data)
+    }
+
+    fn write_word_32(&mut self, address: u32, data: u32) -> Result<(), Error> {
+        self.write_memory(address, &data.to_le_bytes())
+    }
+
+    fn write_word_8(&mut self, address: u32, data: u8) -> Result<(), Error> {
+        self.write_memory(address, &[data])
+    }
+
+    fn write_32(&mut self, address: u32, data: &[u32]) -> Result<(), Error> {
+        let buffer: Vec<u8> = data.iter().flat_map(|word| word.to_le_bytes()).collect();
+
+        self.write_memory(address, &buffer)
+    }
+
+    fn write_8(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
+        self.write_memory(address, data)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl CoreInterface for DumpCore<'_> {
+    fn wait_for_core_halted(&mut self, _timeout: Duration) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn core_halted(&mut self) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    fn status(&mut self) -> Result<CoreStatus, Error> {
+        Ok(CoreStatus::Halted(HaltReason::Request))
+    }
+
+    fn halt(&mut self, _timeout: Duration) -> Result<CoreInformation, Error> {
+        let pc_address = self.registers().program_counter().into();
+        let pc = self.read_core_reg(pc_address)?;
+
+        Ok(CoreInformation { pc: pc.into() })
+    }
+
+    /// A core dump can not be resumed, so the core stays halted.
+    fn run(&mut self) -> Result<(), Error> {
+        log::warn!("A core dump can not be resumed, the core stays halted.");
+
+        Ok(())
+    }
+
+    fn reset(&mut self) -> Result<(), Error> {
+        Err(anyhow!("A core dump can not be reset").into())
+    }
+
+    fn reset_and_halt(&mut self, _timeout: Duration) -> Result<CoreInformation, Error> {
+        Err(anyhow!("A core dump can not be reset").into())
+    }
+
+    /// A core dump can not be resumed, so stepping does not change the state of the core.
+    fn step(&mut self) -> Result<CoreInformation, Error> {
+        log::warn!("A core dump can not be stepped, the core stays halted.");
+
+        self.halt(Duration::from_millis(0))
+    }
+
+    fn read_core_reg(&mut self, address: CoreRegisterAddress) -> Result<u32, Error> {
+        u32::try_from(self.read_core_reg_value(address)?)
+    }
+
+    fn write_core_reg(&mut self, address: CoreRegisterAddress, value: u32) -> anyhow::Result<()> {
+        self.registers.insert(address.0, RegisterValue::U32(value));
+
+        Ok(())
+    }
+
+    fn read_core_reg_value(
+        &mut self,
+        address: CoreRegisterAddress,
+    ) -> Result<RegisterValue, Error> {
+        self.registers
+            .get(&address.0)
+            .copied()
+            .ok_or_else(|| anyhow!("Register {:#x} is not part of the core dump", address.0).into())
+    }
+
+    fn write_core_reg_value(
+        &mut self,
+        address: CoreRegisterAddress,
+        value: RegisterValue,
+    ) -> Result<(), Error> {
+        self.registers.insert(address.0, value);
+
+        Ok(())
+    }
+
+    fn available_breakpoint_units(&mut self) -> Result<u32, Error> {
+        Ok(0)
+    }
+
+    fn hw_breakpoints(&mut self) -> Result<Vec<Option<u32>>, Error> {
+        Ok(Vec::new())
+    }
+
+    fn enable_breakpoints(&mut self, _state: bool) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn set_hw_breakpoint(&mut self, _unit_index: usize, _addr: u32) -> Result<(), Error> {
+        Err(anyhow!("Breakpoints are not supported for core dumps").into())
+    }
+
+    fn clear_hw_breakpoint(&mut self, _unit_index: usize) -> Result<(), Error> {
+        Err(anyhow!("Breakpoints are not supported for core dumps").into())
+    }
+
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        Ok(0)
+    }
+
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        Ok(Vec::new())
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        _unit_index: usize,
+        _watchpoint: Watchpoint,
+    ) -> Result<(), Error> {
+        Err(anyhow!("Watchpoints are not supported for core dumps").into())
+    }
+
+    fn clear_hw_watchpoint(&mut self, _unit_index: usize) -> Result<(), Error> {
+        Err(anyhow!("Watchpoints are not supported for core dumps").into())
+    }
+
+    fn watchpoints_share_breakpoint_units(&self) -> bool {
+        false
+    }
+
+    fn registers(&self) -> &'static RegisterFile {
+        register_file(self.core_type)
+    }
+
+    fn hw_breakpoints_enabled(&self) -> bool {
+        false
+    }
+
+    fn architecture(&self) -> Architecture {
+        self.core_type.architecture()
+    }
+
+    fn set_vector_catch(&mut self, conditions: &[VectorCatchCondition]) -> Result<(), Error> {
+        if conditions.is_empty() {
+            return Ok(());
+        }
+
+        Err(anyhow!("Vector catch is not supported for core dumps").into())
+    }
+}
+
+fn register_file(core_type: CoreType) -> &'static RegisterFile {
+    match core_type {
+        CoreType::Armv6m | CoreType::Armv7m | CoreType::Armv7em | CoreType::Armv8m => {
+            &ARM_REGISTER_FILE
+        }
+        CoreType::Armv7a | CoreType::Armv7r => &ARMV7A_REGISTER_FILE,
+        CoreType::Armv8a => &AARCH64_REGISTER_FILE,
+        CoreType::Riscv => &RISCV_REGISTERS,
+    }
+}
+
+/// Returns the addresses of all registers of the register file of a core.
+fn register_addresses(
+    register_file: &RegisterFile,
+    core_type: CoreType,
+) -> Vec<CoreRegisterAddress> {
+    let mut addresses: Vec<CoreRegisterAddress> = register_file
+        .platform_registers
+        .iter()
+        .chain([
+            register_file.program_counter,
+            register_file.stack_pointer,
+            register_file.return_address,
+            register_file.frame_pointer,
+        ])
+        .chain(register_file.msp)
+        .chain(register_file.psp)
+        .chain(register_file.extra)
+        .map(|register| register.address)
+        .collect();
+
+    // The xPSR is not part of the register file of M-profile cores.
+    if matches!(
+        core_type,
+        CoreType::Armv6m | CoreType::Armv7m | CoreType::Armv7em | CoreType::Armv8m
+    ) {
+        addresses.push(XPSR.address);
+    }
+
+    addresses.sort_by_key(|address| address.0);
+    addresses.dedup();
+
+    addresses
+}
+
+/// Returns the addresses of the floating point registers, for cores which can have a floating point unit.
+fn floating_point_register_addresses(core_type: CoreType) -> Vec<CoreRegisterAddress> {
+    match core_type {
+        CoreType::Armv7em | CoreType::Armv8m => std::iter::once(FPSCR)
+            .chain((S0..S0 + NUM_SINGLE_PRECISION_REGISTERS).map(CoreRegisterAddress))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn machine(core_type: CoreType) -> u16 {
+    match core_type.architecture() {
+        Architecture::Arm if core_type == CoreType::Armv8a => EM_AARCH64,
+        Architecture::Arm => EM_ARM,
+        Architecture::Riscv => EM_RISCV,
+    }
+}
+
+fn push_note(notes: &mut Vec<u8>, name: &[u8], note_type: u32, desc: &[u8]) {
+    // The name includes a terminating NUL byte, name and descriptor are padded to 4 bytes.
+    notes.extend_from_slice(&(name.len() as u32 + 1).to_le_bytes());
+    notes.extend_from_slice(&(desc.len() as u32).to_le_bytes());
+    notes.extend_from_slice(&note_type.to_le_bytes());
+
+    notes.extend_from_slice(name);
+    notes.push(0);
+    notes.resize(align_4(notes.len()), 0);
+
+    notes.extend_from_slice(desc);
+    notes.resize(align_4(notes.len()), 0);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn test_dump() -> CoreDump {
+        let mut registers = BTreeMap::new();
+
+        for address in register_addresses(&ARM_REGISTER_FILE, CoreType::Armv7em) {
+            registers.insert(address.0, RegisterValue::U32(u32::from(address.0) * 0x100));
+        }
+
+        CoreDump {
+            core_type: CoreType::Armv7em,
+            registers,
+            memory: vec![
+                (0x2000_0000, vec![0x11; 0x10]),
+                (0x2000_0010, (0u8..0x10).collect()),
+            ],
+            core_state: CoreState::new(0),
+        }
+    }
+
+    #[test]
+    fn read_memory_across_ranges() {
+        let mut dump = test_dump();
+        let mut core = dump.core();
+
+        let mut data = [0u8; 4];
+        core.read_8(0x2000_000e, &mut data).unwrap();
+
+        assert_eq!(data, [0x11, 0x11, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn read_memory_outside_of_dump() {
+        let mut dump = test_dump();
+        let mut core = dump.core();
+
+        assert!(core.read_word_32(0x2000_001e).is_err());
+        assert!(core.read_word_32(0x1fff_fffc).is_err());
+    }
+
+    #[test]
+    fn read_registers() {
+        let mut dump = test_dump();
+        let mut core = dump.core();
+
+        let pc = core.registers().program_counter();
+        assert_eq!(core.read_core_reg(pc).unwrap(), 15 * 0x100);
+        assert_eq!(core.read_core_reg(XPSR.address).unwrap(), 16 * 0x100);
+    }
+
+    #[test]
+    fn elf_round_trip() {
+        let dump = test_dump();
+
+        let loaded = CoreDump::from_elf(&dump.to_elf().unwrap()).unwrap();
+
+        assert_eq!(loaded.core_type, dump.core_type);
+        assert_eq!(loaded.registers, dump.registers);
+        assert_eq!(loaded.memory, dump.memory);
+    }
+}
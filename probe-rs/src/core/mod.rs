@@ -1,11 +1,14 @@
 pub(crate) mod communication_interface;
+mod dump;
 
 pub use communication_interface::CommunicationInterface;
+pub use dump::{CoreDump, CoreDumpError};
 pub use probe_rs_target::Architecture;
 use probe_rs_target::CoreType;
 
 use crate::architecture::{
-    arm::core::State, riscv::communication_interface::RiscvCommunicationInterface,
+    arm::core::{CortexAState, State},
+    riscv::communication_interface::RiscvCommunicationInterface,
 };
 use crate::error;
 use crate::Target;
@@ -41,7 +44,86 @@ impl From<u16> for CoreRegisterAddress {
 #[derive(Debug, Clone)]
 pub struct CoreInformation {
     /// The current Program Counter.
-    pub pc: u32,
+    pub pc: u64,
+}
+
+/// The value of a core register, which can be wider than 32 bits.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RegisterValue {
+    /// The value of a 32 bit register.
+    U32(u32),
+    /// The value of a 64 bit register, e.g. the general purpose registers of AArch64.
+    U64(u64),
+    /// The value of a 128 bit register, e.g. the SIMD registers of AArch64.
+    U128(u128),
+}
+
+impl From<u32> for RegisterValue {
+    fn from(value: u32) -> Self {
+        RegisterValue::U32(value)
+    }
+}
+
+impl From<u64> for RegisterValue {
+    fn from(value: u64) -> Self {
+        RegisterValue::U64(value)
+    }
+}
+
+impl From<u128> for RegisterValue {
+    fn from(value: u128) -> Self {
+        RegisterValue::U128(value)
+    }
+}
+
+impl TryFrom<RegisterValue> for u32 {
+    type Error = Error;
+
+    fn try_from(value: RegisterValue) -> Result<Self, Self::Error> {
+        match value {
+            RegisterValue::U32(value) => Ok(value),
+            RegisterValue::U64(value) => u32::try_from(value).map_err(|_| {
+                anyhow!("Register value {:#x} does not fit into 32 bits", value).into()
+            }),
+            RegisterValue::U128(value) => u32::try_from(value).map_err(|_| {
+                anyhow!("Register value {:#x} does not fit into 32 bits", value).into()
+            }),
+        }
+    }
+}
+
+impl TryFrom<RegisterValue> for u64 {
+    type Error = Error;
+
+    fn try_from(value: RegisterValue) -> Result<Self, Self::Error> {
+        match value {
+            RegisterValue::U32(value) => Ok(value.into()),
+            RegisterValue::U64(value) => Ok(value),
+            RegisterValue::U128(value) => u64::try_from(value).map_err(|_| {
+                anyhow!("Register value {:#x} does not fit into 64 bits", value).into()
+            }),
+        }
+    }
+}
+
+impl From<RegisterValue> for u128 {
+    fn from(value: RegisterValue) -> Self {
+        match value {
+            RegisterValue::U32(value) => value.into(),
+            RegisterValue::U64(value) => value.into(),
+            RegisterValue::U128(value) => value,
+        }
+    }
+}
+
+impl std::fmt::Display for RegisterValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegisterValue::U32(value) => write!(f, "{:#010x}", value),
+            RegisterValue::U64(value) => write!(f, "{:#018x}", value),
+            RegisterValue::U128(value) => write!(f, "{:#034x}", value),
+        }
+    }
 }
 
 /// Describes a register with its properties.
@@ -50,12 +132,18 @@ pub struct RegisterDescription {
     pub(crate) name: &'static str,
     pub(crate) _kind: RegisterKind,
     pub(crate) address: CoreRegisterAddress,
+    pub(crate) size_in_bits: usize,
 }
 
 impl RegisterDescription {
     pub fn name(&self) -> &'static str {
         self.name
     }
+
+    /// The width of the register in bits.
+    pub fn size_in_bits(&self) -> usize {
+        self.size_in_bits
+    }
 }
 
 impl From<RegisterDescription> for CoreRegisterAddress {
@@ -242,6 +330,31 @@ pub trait CoreInterface: MemoryInterface {
     /// Write the value of a core register.
     fn write_core_reg(&mut self, address: CoreRegisterAddress, value: u32) -> Result<()>;
 
+    /// Read the value of a core register, which can be wider than 32 bits.
+    ///
+    /// The default implementation reads the register with [`CoreInterface::read_core_reg`],
+    /// for cores whose registers are at most 32 bits wide.
+    fn read_core_reg_value(
+        &mut self,
+        address: CoreRegisterAddress,
+    ) -> Result<RegisterValue, error::Error> {
+        self.read_core_reg(address).map(RegisterValue::U32)
+    }
+
+    /// Write the value of a core register, which can be wider than 32 bits.
+    ///
+    /// The default implementation writes the register with [`CoreInterface::write_core_reg`],
+    /// and returns an error if the value does not fit into 32 bits.
+    fn write_core_reg_value(
+        &mut self,
+        address: CoreRegisterAddress,
+        value: RegisterValue,
+    ) -> Result<(), error::Error> {
+        self.write_core_reg(address, u32::try_from(value)?)?;
+
+        Ok(())
+    }
+
     /// Returns all the available breakpoint units of the core.
     fn available_breakpoint_units(&mut self) -> Result<u32, error::Error>;
 
@@ -254,11 +367,47 @@ pub trait CoreInterface: MemoryInterface {
     fn enable_breakpoints(&mut self, state: bool) -> Result<(), error::Error>;
 
     /// Sets a breakpoint at `addr`. It does so by using unit `bp_unit_index`.
+    ///
+    /// Only breakpoints in the lower 4 GiB of the address space can be set, also on 64 bit cores.
     fn set_hw_breakpoint(&mut self, unit_index: usize, addr: u32) -> Result<(), error::Error>;
 
     /// Clears the breakpoint configured in unit `unit_index`.
     fn clear_hw_breakpoint(&mut self, unit_index: usize) -> Result<(), error::Error>;
 
+    /// Returns the number of hardware watchpoint units of the core.
+    ///
+    /// The default implementation is for cores without watchpoint units.
+    fn available_watchpoint_units(&mut self) -> Result<u32, error::Error> {
+        Ok(0)
+    }
+
+    /// Read the watchpoints configured in the watchpoint units of the core.
+    /// A value of None in any position of the Vector indicates that the position is unset/available.
+    /// Units which are used for other purposes, e.g. data tracing, are reported as unset.
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, error::Error> {
+        Ok(Vec::new())
+    }
+
+    /// Sets a watchpoint using unit `unit_index`.
+    fn set_hw_watchpoint(
+        &mut self,
+        _unit_index: usize,
+        _watchpoint: Watchpoint,
+    ) -> Result<(), error::Error> {
+        Err(anyhow!("Watchpoints are not supported by this core").into())
+    }
+
+    /// Clears the watchpoint configured in unit `unit_index`.
+    fn clear_hw_watchpoint(&mut self, _unit_index: usize) -> Result<(), error::Error> {
+        Err(anyhow!("Watchpoints are not supported by this core").into())
+    }
+
+    /// Returns `true` if watchpoints and breakpoints are set using the same units,
+    /// e.g. the triggers of the RISC-V trigger module.
+    fn watchpoints_share_breakpoint_units(&self) -> bool {
+        false
+    }
+
     /// Returns a list of all the registers of this core.
     fn registers(&self) -> &'static RegisterFile;
 
@@ -267,6 +416,21 @@ pub trait CoreInterface: MemoryInterface {
 
     /// Get the `Architecture` of the Core.
     fn architecture(&self) -> Architecture;
+
+    /// Configures the core to halt on exactly the given conditions.
+    ///
+    /// Returns an error if a condition is not supported by the core.
+    /// The default implementation is for cores without vector catch support.
+    fn set_vector_catch(
+        &mut self,
+        conditions: &[VectorCatchCondition],
+    ) -> Result<(), error::Error> {
+        if conditions.is_empty() {
+            return Ok(());
+        }
+
+        Err(anyhow!("Vector catch is not supported by this core").into())
+    }
 }
 
 impl<'probe> MemoryInterface for Core<'probe> {
@@ -302,6 +466,26 @@ impl<'probe> MemoryInterface for Core<'probe> {
         self.inner.write_8(addr, data)
     }
 
+    fn supports_native_64bit_access(&mut self) -> bool {
+        self.inner.supports_native_64bit_access()
+    }
+
+    fn read_word_64(&mut self, address: u64) -> Result<u64, Error> {
+        self.inner.read_word_64(address)
+    }
+
+    fn read_64(&mut self, address: u64, data: &mut [u64]) -> Result<(), Error> {
+        self.inner.read_64(address, data)
+    }
+
+    fn write_word_64(&mut self, address: u64, data: u64) -> Result<(), Error> {
+        self.inner.write_word_64(address, data)
+    }
+
+    fn write_64(&mut self, address: u64, data: &[u64]) -> Result<(), Error> {
+        self.inner.write_64(address, data)
+    }
+
     fn flush(&mut self) -> Result<(), Error> {
         self.inner.flush()
     }
@@ -331,12 +515,18 @@ impl CoreState {
 pub enum SpecificCoreState {
     /// The state of an ARMv6-M core.
     Armv6m(State),
+    /// The state of an ARMv7-A core.
+    Armv7a(CortexAState),
     /// The state of an ARMv7-M core.
     Armv7m(State),
     /// The state of an ARMv7-EM core.
     Armv7em(State),
+    /// The state of an ARMv7-R core.
+    Armv7r(CortexAState),
     /// The state of an ARMv8-M core.
     Armv8m(State),
+    /// The state of an ARMv8-A core.
+    Armv8a(CortexAState),
     /// The state of an RISC-V core.
     Riscv,
 }
@@ -345,9 +535,12 @@ impl SpecificCoreState {
     pub(crate) fn from_core_type(typ: CoreType) -> Self {
         match typ {
             CoreType::Armv6m => SpecificCoreState::Armv6m(State::new()),
+            CoreType::Armv7a => SpecificCoreState::Armv7a(CortexAState::new()),
             CoreType::Armv7m => SpecificCoreState::Armv7m(State::new()),
             CoreType::Armv7em => SpecificCoreState::Armv7m(State::new()),
+            CoreType::Armv7r => SpecificCoreState::Armv7r(CortexAState::new()),
             CoreType::Armv8m => SpecificCoreState::Armv8m(State::new()),
+            CoreType::Armv8a => SpecificCoreState::Armv8a(CortexAState::new()),
             CoreType::Riscv => SpecificCoreState::Riscv,
         }
     }
@@ -355,9 +548,12 @@ impl SpecificCoreState {
     pub(crate) fn core_type(&self) -> CoreType {
         match self {
             SpecificCoreState::Armv6m(_) => CoreType::Armv6m,
+            SpecificCoreState::Armv7a(_) => CoreType::Armv7a,
             SpecificCoreState::Armv7m(_) => CoreType::Armv7m,
             SpecificCoreState::Armv7em(_) => CoreType::Armv7em,
+            SpecificCoreState::Armv7r(_) => CoreType::Armv7r,
             SpecificCoreState::Armv8m(_) => CoreType::Armv8m,
+            SpecificCoreState::Armv8a(_) => CoreType::Armv8a,
             SpecificCoreState::Riscv => CoreType::Riscv,
         }
     }
@@ -365,7 +561,7 @@ impl SpecificCoreState {
     pub(crate) fn attach_arm<'probe, 'target: 'probe>(
         &'probe mut self,
         state: &'probe mut CoreState,
-        memory: Memory<'probe>,
+        mut memory: Memory<'probe>,
         target: &'target Target,
     ) -> Result<Core<'probe>, Error> {
         let debug_sequence = match &target.debug_sequence {
@@ -390,6 +586,31 @@ impl SpecificCoreState {
                 crate::architecture::arm::armv8m::Armv8m::new(memory, s, debug_sequence)?,
                 state,
             ),
+            SpecificCoreState::Armv7a(s) | SpecificCoreState::Armv7r(s) => {
+                let (debug_base, cti_base) =
+                    s.base_addresses(|| cortex_a_base_addresses(target, state.id(), &mut memory))?;
+
+                Core::new(
+                    crate::architecture::arm::armv7a::Armv7a::new(memory, s, debug_base, cti_base)?,
+                    state,
+                )
+            }
+            SpecificCoreState::Armv8a(s) => {
+                let (debug_base, cti_base) =
+                    s.base_addresses(|| cortex_a_base_addresses(target, state.id(), &mut memory))?;
+                // ARMv8-A cores can only be halted through their CTI.
+                let cti_base = cti_base.ok_or_else(|| {
+                    anyhow!(
+                        "No CTI was found for core {}, which is required to halt it",
+                        state.id()
+                    )
+                })?;
+
+                Core::new(
+                    crate::architecture::arm::armv8a::Armv8a::new(memory, s, debug_base, cti_base)?,
+                    state,
+                )
+            }
             _ => {
                 return Err(Error::UnableToOpenProbe(
                     "Core architecture and Probe mismatch.",
@@ -416,6 +637,70 @@ impl SpecificCoreState {
     }
 }
 
+/// Returns the base addresses of the debug registers and the CTI of a Cortex-A or Cortex-R core.
+///
+/// In contrast to Cortex-M cores, these addresses are not fixed. If the target description
+/// does not specify the base address of the debug registers, both are looked up in the ROM table.
+fn cortex_a_base_addresses(
+    target: &Target,
+    core_index: usize,
+    memory: &mut Memory,
+) -> Result<(u32, Option<u32>), Error> {
+    let options = match target
+        .cores
+        .get(core_index)
+        .map(|core| &core.core_access_options)
+    {
+        Some(probe_rs_target::CoreAccessOptions::Arm(options)) => options,
+        _ => return Err(Error::CoreNotFound(core_index)),
+    };
+
+    let base_address = |address: u64, name: &str| -> Result<u32, Error> {
+        u32::try_from(address).map_err(|_| {
+            anyhow!(
+                "The {} base address {:#x} of core {} is outside of the 32 bit address space",
+                name,
+                address,
+                core_index
+            )
+            .into()
+        })
+    };
+
+    match options.debug_base {
+        Some(debug_base) => Ok((
+            base_address(debug_base, "debug")?,
+            options
+                .cti_base
+                .map(|cti_base| base_address(cti_base, "CTI"))
+                .transpose()?,
+        )),
+        None => {
+            // The ROM table of an access port lists the debug registers of all its cores,
+            // in the same order as the cores of the target description.
+            let rom_table_index = target.cores[..core_index]
+                .iter()
+                .filter(|core| match &core.core_access_options {
+                    probe_rs_target::CoreAccessOptions::Arm(core_options) => {
+                        !core.core_type.is_cortex_m() && core_options.ap == options.ap
+                    }
+                    _ => false,
+                })
+                .count();
+
+            let (debug_base, cti_base) =
+                crate::architecture::arm::core::find_debug_components(memory, rom_table_index)?;
+
+            let cti_base = match options.cti_base {
+                Some(cti_base) => Some(base_address(cti_base, "CTI")?),
+                None => cti_base,
+            };
+
+            Ok((debug_base, cti_base))
+        }
+    }
+}
+
 /// Generic core handle representing a physical core on an MCU.
 ///
 /// This should be considere as a temporary view of the core which locks the debug probe driver to as single consumer by borrowing it.
@@ -512,6 +797,24 @@ impl<'probe> Core<'probe> {
         Ok(self.inner.write_core_reg(address, value)?)
     }
 
+    /// Read the value of a core register, which can be wider than 32 bits.
+    pub fn read_core_reg_value(
+        &mut self,
+        address: impl Into<CoreRegisterAddress>,
+    ) -> Result<RegisterValue, error::Error> {
+        self.inner.read_core_reg_value(address.into())
+    }
+
+    /// Write the value of a core register, which can be wider than 32 bits.
+    pub fn write_core_reg_value(
+        &mut self,
+        address: impl Into<CoreRegisterAddress>,
+        value: impl Into<RegisterValue>,
+    ) -> Result<(), error::Error> {
+        self.inner
+            .write_core_reg_value(address.into(), value.into())
+    }
+
     /// Returns all the available breakpoint units of the core.
     pub fn available_breakpoint_units(&mut self) -> Result<u32, error::Error> {
         self.inner.available_breakpoint_units()
@@ -529,9 +832,18 @@ impl<'probe> Core<'probe> {
 
     /// Find the index of the next available HW breakpoint comparator.
     fn find_free_breakpoint_comparator_index(&mut self) -> Result<usize, error::Error> {
+        let watchpoints = if self.inner.watchpoints_share_breakpoint_units() {
+            self.inner.hw_watchpoints()?
+        } else {
+            vec![]
+        };
+
         let mut next_available_hw_breakpoint = 0;
         for breakpoint in self.inner.hw_breakpoints()? {
-            if breakpoint.is_none() {
+            let used_by_watchpoint =
+                matches!(watchpoints.get(next_available_hw_breakpoint), Some(Some(_)));
+
+            if breakpoint.is_none() && !used_by_watchpoint {
                 return Ok(next_available_hw_breakpoint);
             } else {
                 next_available_hw_breakpoint += 1;
@@ -616,10 +928,151 @@ impl<'probe> Core<'probe> {
         Ok(())
     }
 
+    /// Returns the address of the hardware breakpoint configured in each unit, or `None` for unused units.
+    pub(crate) fn hw_breakpoints(&mut self) -> Result<Vec<Option<u32>>, error::Error> {
+        self.inner.hw_breakpoints()
+    }
+
+    /// Returns the number of hardware watchpoint units of the core.
+    pub fn available_watchpoint_units(&mut self) -> Result<u32, error::Error> {
+        self.inner.available_watchpoint_units()
+    }
+
+    /// Find the index of the next available HW watchpoint unit.
+    fn find_free_watchpoint_unit_index(&mut self) -> Result<usize, error::Error> {
+        let breakpoints = if self.inner.watchpoints_share_breakpoint_units() {
+            self.inner.hw_breakpoints()?
+        } else {
+            vec![]
+        };
+
+        for (unit_index, watchpoint) in self.inner.hw_watchpoints()?.iter().enumerate() {
+            let used_by_breakpoint = matches!(breakpoints.get(unit_index), Some(Some(_)));
+
+            if watchpoint.is_none() && !used_by_breakpoint {
+                return Ok(unit_index);
+            }
+        }
+
+        Err(error::Error::Other(anyhow!(
+            "No available hardware watchpoints"
+        )))
+    }
+
+    /// Set a hardware watchpoint
+    ///
+    /// This function will try to set a hardware watchpoint, which halts the core when
+    /// `size` bytes starting at `address` are accessed as specified by `kind`.
+    ///
+    /// `size` has to be a power of two, and `address` has to be aligned to `size`. Which
+    /// sizes are supported is chip specific. The amount of hardware watchpoints is chip
+    /// specific as well, and can be queried using the `available_watchpoint_units` function.
+    pub fn set_hw_watchpoint(
+        &mut self,
+        address: u32,
+        size: u32,
+        kind: WatchpointKind,
+    ) -> Result<(), error::Error> {
+        if !size.is_power_of_two() {
+            return Err(error::Error::Other(anyhow!(
+                "The watchpoint size {} is not a power of two",
+                size
+            )));
+        }
+
+        if address % size != 0 {
+            return Err(error::Error::Other(anyhow!(
+                "The watchpoint address {:#010x} is not aligned to its size of {} bytes",
+                address,
+                size
+            )));
+        }
+
+        let watchpoint = Watchpoint {
+            address,
+            size,
+            kind,
+        };
+
+        // If there is a watchpoint set already for this address, reuse its unit, else find the next free one.
+        let unit_index = match self
+            .inner
+            .hw_watchpoints()?
+            .iter()
+            .position(|wp| matches!(wp, Some(wp) if wp.address == address))
+        {
+            Some(unit_index) => unit_index,
+            None => self.find_free_watchpoint_unit_index()?,
+        };
+
+        log::debug!(
+            "Trying to set HW watchpoint #{} with {:?}",
+            unit_index,
+            watchpoint
+        );
+
+        self.inner.set_hw_watchpoint(unit_index, watchpoint)
+    }
+
+    /// Clear a hardware watchpoint
+    ///
+    /// This function will try to clear a hardware watchpoint at `address` if there exists a watchpoint at that address.
+    pub fn clear_hw_watchpoint(&mut self, address: u32) -> Result<(), error::Error> {
+        let unit_index = self
+            .inner
+            .hw_watchpoints()?
+            .iter()
+            .position(|wp| matches!(wp, Some(wp) if wp.address == address));
+
+        match unit_index {
+            Some(unit_index) => {
+                log::debug!(
+                    "Will clear HW watchpoint #{} with address {:#010x}",
+                    unit_index,
+                    address
+                );
+
+                self.inner.clear_hw_watchpoint(unit_index)
+            }
+            None => Err(error::Error::Other(anyhow!(
+                "No watchpoint found at address {:#010x}",
+                address
+            ))),
+        }
+    }
+
+    /// Clear all hardware watchpoints
+    ///
+    /// This function will clear all HW watchpoints which are configured on the target,
+    /// regardless if they are set by probe-rs. Units which are used for other purposes,
+    /// e.g. data tracing, are not touched.
+    pub fn clear_all_hw_watchpoints(&mut self) -> Result<(), error::Error> {
+        for (unit_index, watchpoint) in self.inner.hw_watchpoints()?.iter().enumerate() {
+            if watchpoint.is_some() {
+                self.inner.clear_hw_watchpoint(unit_index)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Returns the architecture of the core.
     pub fn architecture(&self) -> Architecture {
         self.inner.architecture()
     }
+
+    /// Configures the core to halt on the given conditions, e.g. on a HardFault.
+    ///
+    /// Conditions which are not in the list are disabled, so an empty list disables vector catch.
+    /// When the core halts because of one of the conditions, the halt reason is [`HaltReason::Exception`],
+    /// except for [`VectorCatchCondition::Ebreak`], which is reported as [`HaltReason::Breakpoint`].
+    ///
+    /// Returns an error if a condition is not supported by the core, see [`VectorCatchCondition`].
+    pub fn set_vector_catch(
+        &mut self,
+        conditions: &[VectorCatchCondition],
+    ) -> Result<(), error::Error> {
+        self.inner.set_vector_catch(conditions)
+    }
 }
 
 /// The id of a breakpoint.
@@ -633,6 +1086,30 @@ impl BreakpointId {
     }
 }
 
+/// The kind of memory access which triggers a watchpoint.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WatchpointKind {
+    /// Halt when the watched memory is read.
+    Read,
+    /// Halt when the watched memory is written.
+    Write,
+    /// Halt when the watched memory is read or written.
+    ReadWrite,
+}
+
+/// A hardware watchpoint, which halts the core when a range of memory is accessed.
+///
+/// Watchpoints can only be set in the lower 4 GiB of the address space, also on 64 bit cores.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Watchpoint {
+    /// The start address of the watched memory.
+    pub address: u32,
+    /// The number of watched bytes.
+    pub size: u32,
+    /// The kind of access which triggers the watchpoint.
+    pub kind: WatchpointKind,
+}
+
 /// The status of the core.
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum CoreStatus {
@@ -655,6 +1132,51 @@ impl CoreStatus {
     }
 }
 
+/// A condition on which the core halts, instead of handling the exception.
+///
+/// The conditions are set with [`Core::set_vector_catch`]. Each condition is only supported by some architectures.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum VectorCatchCondition {
+    /// Halt on the reset vector.
+    ///
+    /// ARM M-profile: `DEMCR.VC_CORERESET`. ARMv8-A: `EDECR.RCE`. RISC-V: the reset halt request of the debug module.
+    CoreReset,
+    /// Halt on a HardFault.
+    ///
+    /// ARM M-profile: `DEMCR.VC_HARDERR`.
+    HardFault,
+    /// Halt on a fault during exception entry or exception return.
+    ///
+    /// ARMv7-M and ARMv8-M: `DEMCR.VC_INTERR`.
+    ExceptionEntryOrReturn,
+    /// Halt on a MemManage fault.
+    ///
+    /// ARMv7-M and ARMv8-M: `DEMCR.VC_MMERR`.
+    MemManage,
+    /// Halt on a BusFault.
+    ///
+    /// ARMv7-M and ARMv8-M: `DEMCR.VC_BUSERR`.
+    BusFault,
+    /// Halt on a UsageFault, caused by a state error, a checking error or a coprocessor access.
+    ///
+    /// ARMv7-M and ARMv8-M: `DEMCR.VC_STATERR`, `DEMCR.VC_CHKERR` and `DEMCR.VC_NOCPERR`.
+    UsageFault,
+    /// Halt on a SecureFault.
+    ///
+    /// ARMv8-M with the security extension: `DEMCR.VC_SFERR`.
+    SecureFault,
+    /// Halt on `ebreak` instructions, instead of raising a breakpoint exception.
+    ///
+    /// RISC-V: `dcsr.ebreakm`, `dcsr.ebreaks` and `dcsr.ebreaku`. Like a `bkpt` instruction on ARM,
+    /// the halt is reported as [`HaltReason::Breakpoint`].
+    Ebreak,
+    /// Halt on synchronous exceptions, e.g. illegal instructions, misaligned accesses and access faults.
+    ///
+    /// RISC-V: an exception trigger (`etrigger`) of the trigger module. ARMv8-A: exception catch (`EDECCR`),
+    /// which halts on every exception taken to EL1, EL2 or EL3, including interrupts.
+    Exception,
+}
+
 /// The reason why a core was halted.
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum HaltReason {
@@ -668,10 +1190,13 @@ pub enum HaltReason {
     /// a *soft* or a *hard* breakpoint.
     Breakpoint,
     /// Core halted due to an exception, e.g. an
-    /// an interrupt.
+    /// an interrupt, or one of the conditions set with [`Core::set_vector_catch`].
     Exception,
-    /// Core halted due to a data watchpoint
-    Watchpoint,
+    /// Core halted due to a data watchpoint.
+    ///
+    /// Contains the address of the watchpoint which triggered,
+    /// if it could be determined and is in the lower 4 GiB of the address space.
+    Watchpoint(Option<u32>),
     /// Core halted after single step
     Step,
     /// Core halted because of a debugger request
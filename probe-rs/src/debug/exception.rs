@@ -0,0 +1,467 @@
+//! Unwinding through the exception frames, which a Cortex-M core stacks on exception entry.
+//!
+//! See the [ARMv7-M Architecture Reference Manual](https://developer.arm.com/documentation/ddi0403/ee), Section B1.5.6 and B1.5.8,
+//! and the [ARMv8-M Architecture Reference Manual](https://developer.arm.com/documentation/ddi0553/latest), Section B3.19 and B3.21.
+
+use super::{get_sequential_key, Registers, StackFrame};
+use crate::{
+    core::{Core, CoreRegisterAddress},
+    Error, MemoryInterface,
+};
+use anyhow::anyhow;
+
+/// Bits [31:7] of an `EXC_RETURN` value are always set.
+const EXC_RETURN_MASK: u32 = 0xFFFF_FF80;
+
+/// The number of words in the basic frame, with R0-R3, R12, LR, the return address and xPSR.
+const BASIC_FRAME_WORDS: usize = 8;
+
+/// The number of words stored by the FPU, with S0-S15, FPSCR and a reserved word.
+const FP_CONTEXT_WORDS: usize = 18;
+
+/// The number of words of the additional FP context of ARMv8-M, with S16-S31, which is stacked
+/// for secure code if FPCCR.TS is set.
+const ADDITIONAL_FP_CONTEXT_WORDS: usize = 16;
+
+/// The number of words in the additional state context of ARMv8-M, with the integrity signature,
+/// a reserved word and R4-R11.
+const ADDITIONAL_STATE_CONTEXT_WORDS: usize = 10;
+
+/// The xPSR bit which is set if an additional word was stacked to align the frame to 8 bytes.
+const XPSR_STACK_ALIGNED: u32 = 1 << 9;
+
+/// The address of the ID_PFR1 register, whose bits [7:4] are non-zero on an ARMv8-M core with the security extension.
+const ID_PFR1: u32 = 0xE000_ED44;
+
+/// The address of the FPCCR register.
+const FPCCR: u32 = 0xE000_EF34;
+
+/// The FPCCR.TS bit, which makes the secure state stack S16-S31 on exception entry.
+const FPCCR_TS: u32 = 1 << 26;
+
+/// The banked stack pointers of an ARMv8-M core with the security extension, as selected in the DCRSR.
+const MSP_NS: CoreRegisterAddress = CoreRegisterAddress(0b001_1000);
+const PSP_NS: CoreRegisterAddress = CoreRegisterAddress(0b001_1001);
+const MSP_S: CoreRegisterAddress = CoreRegisterAddress(0b001_1010);
+const PSP_S: CoreRegisterAddress = CoreRegisterAddress(0b001_1011);
+
+/// The `EXC_RETURN` value, which is loaded into LR when a Cortex-M core enters an exception handler.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ExceptionReturn(u32);
+
+impl ExceptionReturn {
+    /// Returns the `EXC_RETURN` value contained in the return address, if there is one.
+    ///
+    /// Only cores with a process stack pointer, i.e. M-profile cores, use `EXC_RETURN` values.
+    pub(crate) fn from_registers(registers: &Registers) -> Option<Self> {
+        registers.register_description.psp()?;
+
+        registers.get_return_address().and_then(Self::new)
+    }
+
+    /// Returns `value` as an `EXC_RETURN` value, if it is one.
+    ///
+    /// The reset value `0xFFFFFFFF` of LR is not a valid `EXC_RETURN` value.
+    pub(crate) fn new(value: u32) -> Option<Self> {
+        if value & EXC_RETURN_MASK == EXC_RETURN_MASK && value != u32::MAX {
+            Some(Self(value))
+        } else {
+            None
+        }
+    }
+
+    /// The frame was stacked on the process stack (PSP), instead of the main stack (MSP).
+    pub(crate) fn uses_process_stack(self) -> bool {
+        self.0 & (1 << 2) != 0
+    }
+
+    /// The interrupted code ran in thread mode, instead of handler mode.
+    fn returns_to_thread_mode(self) -> bool {
+        self.0 & (1 << 3) != 0
+    }
+
+    /// The frame is an extended frame, which contains the FPU context.
+    fn has_fp_context(self) -> bool {
+        self.0 & (1 << 4) == 0
+    }
+
+    /// On ARMv8-M, the callee-saved registers are stacked in an additional state context,
+    /// when a secure exception interrupts non-secure code.
+    fn has_additional_state_context(self) -> bool {
+        self.0 & (1 << 5) == 0
+    }
+
+    /// On ARMv8-M with the security extension, the frame was stacked on a secure stack.
+    fn uses_secure_stack(self) -> bool {
+        self.0 & (1 << 6) != 0
+    }
+
+    /// On ARMv8-M with the security extension, the exception is handled in the secure state.
+    fn handled_in_secure_state(self) -> bool {
+        self.0 & 1 != 0
+    }
+
+    /// The offset of the basic frame from the start of the exception frame, in words.
+    fn basic_frame_offset(self) -> usize {
+        if self.has_additional_state_context() {
+            ADDITIONAL_STATE_CONTEXT_WORDS
+        } else {
+            0
+        }
+    }
+
+    /// The offset of the stacked return address from the start of the exception frame, in bytes.
+    pub(crate) fn return_address_offset(self) -> u32 {
+        (self.basic_frame_offset() as u32 + 6) * 4
+    }
+
+    /// The size of the exception frame in words, without the word added for alignment.
+    fn frame_words(self, additional_fp_context: bool) -> usize {
+        let mut frame_words = self.basic_frame_offset() + BASIC_FRAME_WORDS;
+
+        if self.has_fp_context() {
+            frame_words += FP_CONTEXT_WORDS;
+
+            if additional_fp_context {
+                frame_words += ADDITIONAL_FP_CONTEXT_WORDS;
+            }
+        }
+
+        frame_words
+    }
+
+    /// Returns the register containing the stack pointer which was used to stack the frame, or `None`
+    /// if it is the stack pointer of the exception handler.
+    ///
+    /// On ARMv8-M with the security extension, the stack pointers are banked between the security states.
+    fn banked_stack_pointer(
+        self,
+        core: &Core,
+        security_extension: bool,
+    ) -> Option<CoreRegisterAddress> {
+        match (self.uses_process_stack(), security_extension) {
+            (true, false) => core.registers().psp().map(Into::into),
+            (true, true) if self.uses_secure_stack() => Some(PSP_S),
+            (true, true) => Some(PSP_NS),
+            // A handler in the other security state uses the other main stack.
+            (false, true) if self.uses_secure_stack() != self.handled_in_secure_state() => {
+                Some(if self.uses_secure_stack() {
+                    MSP_S
+                } else {
+                    MSP_NS
+                })
+            }
+            (false, _) => None,
+        }
+    }
+
+    /// Returns `true` if the frame was stacked on the stack which the exception handler uses.
+    ///
+    /// Without the CFA of the handler, the frame can then only be located with the current stack pointer.
+    pub(crate) fn uses_handler_stack(self, core: &mut Core) -> bool {
+        let security_extension = has_security_extension(core);
+
+        self.banked_stack_pointer(core, security_extension)
+            .is_none()
+    }
+
+    /// Returns the name of the stack on which the frame was stacked.
+    fn stack_name(self, security_extension: bool) -> &'static str {
+        match (
+            self.uses_process_stack(),
+            security_extension,
+            self.uses_secure_stack(),
+        ) {
+            (true, false, _) => "PSP",
+            (true, true, true) => "PSP_S",
+            (true, true, false) => "PSP_NS",
+            (false, false, _) => "MSP",
+            (false, true, true) => "MSP_S",
+            (false, true, false) => "MSP_NS",
+        }
+    }
+
+    /// Restores the registers of the interrupted code from the words of the exception frame at `frame_address`.
+    ///
+    /// The `frame` has to contain at least the additional state context, if there is one, and the basic frame.
+    fn unstack(
+        self,
+        frame_address: u32,
+        frame: &[u32],
+        additional_fp_context: bool,
+    ) -> UnstackedRegisters {
+        let mut general_purpose = [None; 13];
+
+        // The additional state context starts with the integrity signature and a reserved word, followed by R4-R11.
+        if self.has_additional_state_context() {
+            for (register, value) in (4..12).zip(&frame[2..ADDITIONAL_STATE_CONTEXT_WORDS]) {
+                general_purpose[register] = Some(*value);
+            }
+        }
+
+        let basic_frame = &frame[self.basic_frame_offset()..];
+
+        for (register, value) in (0..4).zip(basic_frame) {
+            general_purpose[register] = Some(*value);
+        }
+        general_purpose[12] = Some(basic_frame[4]);
+
+        let xpsr = basic_frame[7];
+
+        let mut stack_pointer = frame_address + self.frame_words(additional_fp_context) as u32 * 4;
+        if xpsr & XPSR_STACK_ALIGNED != 0 {
+            stack_pointer += 4;
+        }
+
+        UnstackedRegisters {
+            general_purpose,
+            return_address: basic_frame[5],
+            program_counter: basic_frame[6] & !0b1,
+            stack_pointer,
+        }
+    }
+}
+
+/// The registers of the interrupted code, restored from an exception frame.
+#[derive(Debug, PartialEq)]
+struct UnstackedRegisters {
+    /// R0-R12, indexed by their number. R4-R11 are only stacked in the additional state context.
+    general_purpose: [Option<u32>; 13],
+    return_address: u32,
+    program_counter: u32,
+    stack_pointer: u32,
+}
+
+/// Returns `true` if the core implements the security extension of ARMv8-M.
+///
+/// The ID_PFR1 register reads as zero on cores without it, or is not implemented.
+fn has_security_extension(core: &mut Core) -> bool {
+    matches!(core.read_word_32(ID_PFR1), Ok(id_pfr1) if id_pfr1 & 0xF0 != 0)
+}
+
+/// Returns the address of the exception frame for `exception_return`.
+///
+/// Frames on the main stack are located with the CFA of the exception handler, as the handler usually
+/// modifies the MSP, or with `stack_pointer` if the CFA is not known. Frames stacked on another stack than the
+/// one of the handler are located with the banked stack pointer register.
+pub(crate) fn exception_frame_address(
+    core: &mut Core,
+    exception_return: ExceptionReturn,
+    handler_cfa: Option<u32>,
+    stack_pointer: Option<u32>,
+) -> Result<u32, Error> {
+    let security_extension = has_security_extension(core);
+
+    match exception_return.banked_stack_pointer(core, security_extension) {
+        Some(register) => Ok(core.read_core_reg(register)?),
+        None => handler_cfa.or(stack_pointer).ok_or_else(|| {
+            anyhow!("Unable to locate the exception frame on the main stack").into()
+        }),
+    }
+}
+
+/// Pops the exception frame for `exception_return`, and updates `registers` with the state of the interrupted code.
+///
+/// The frame is located with [`exception_frame_address`], using the stack pointer in `registers` if the CFA of
+/// the exception handler is not known.
+///
+/// Returns a pseudo [`StackFrame`], which marks the exception entry in the backtrace.
+pub(crate) fn unwind_exception_frame(
+    core: &mut Core,
+    exception_return: ExceptionReturn,
+    handler_cfa: Option<u32>,
+    registers: &mut Registers,
+) -> Result<StackFrame, Error> {
+    let security_extension = has_security_extension(core);
+    let frame_address = exception_frame_address(
+        core,
+        exception_return,
+        handler_cfa,
+        registers.get_stack_pointer(),
+    )?;
+
+    log::trace!(
+        "UNWIND: Exception frame at {:#010x}, EXC_RETURN={:#010x}",
+        frame_address,
+        exception_return.0
+    );
+
+    // With FPCCR.TS set, the secure state additionally stacks S16-S31 in extended frames.
+    let additional_fp_context = security_extension
+        && exception_return.has_fp_context()
+        && exception_return.uses_secure_stack()
+        && core.read_word_32(FPCCR)? & FPCCR_TS != 0;
+
+    let mut frame = vec![0u32; exception_return.basic_frame_offset() + BASIC_FRAME_WORDS];
+    core.read_32(frame_address, &mut frame)?;
+
+    let unstacked = exception_return.unstack(frame_address, &frame, additional_fp_context);
+
+    for (register, value) in unstacked.general_purpose.iter().enumerate() {
+        if value.is_some() {
+            registers.set_by_dwarf_register_number(register as u32, *value);
+        }
+    }
+
+    registers.set_return_address(Some(unstacked.return_address));
+    registers.set_program_counter(Some(unstacked.program_counter));
+    registers.set_stack_pointer(Some(unstacked.stack_pointer));
+
+    let mode = if exception_return.returns_to_thread_mode() {
+        "thread mode"
+    } else {
+        "handler mode"
+    };
+
+    Ok(StackFrame {
+        id: get_sequential_key(),
+        function_name: format!(
+            "<exception entry from {}, {}>",
+            mode,
+            exception_return.stack_name(security_extension)
+        ),
+        source_location: None,
+        registers: registers.clone(),
+        pc: exception_return.0,
+        is_inlined: false,
+        static_variables: None,
+        local_variables: None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// An exception frame with an additional state context, where each word contains its index.
+    fn frame() -> Vec<u32> {
+        (0..(ADDITIONAL_STATE_CONTEXT_WORDS + BASIC_FRAME_WORDS) as u32).collect()
+    }
+
+    #[test]
+    fn exc_return_values() {
+        assert_eq!(
+            ExceptionReturn::new(0xFFFF_FFF9),
+            Some(ExceptionReturn(0xFFFF_FFF9))
+        );
+        assert_eq!(ExceptionReturn::new(0xFFFF_FFFF), None);
+        assert_eq!(ExceptionReturn::new(0x0800_0401), None);
+
+        let thread_psp = ExceptionReturn(0xFFFF_FFFD);
+        assert!(thread_psp.uses_process_stack());
+        assert!(thread_psp.returns_to_thread_mode());
+        assert!(!thread_psp.has_fp_context());
+        assert!(!thread_psp.has_additional_state_context());
+
+        let handler_msp_fp = ExceptionReturn(0xFFFF_FFE1);
+        assert!(!handler_msp_fp.uses_process_stack());
+        assert!(!handler_msp_fp.returns_to_thread_mode());
+        assert!(handler_msp_fp.has_fp_context());
+    }
+
+    #[test]
+    fn unstack_basic_frame() {
+        let exception_return = ExceptionReturn(0xFFFF_FFFD);
+        let frame: Vec<u32> = vec![10, 11, 12, 13, 14, 0x0800_0101, 0x0800_0201, 0x0100_0000];
+
+        let unstacked = exception_return.unstack(0x2000_0000, &frame, false);
+
+        assert_eq!(
+            unstacked,
+            UnstackedRegisters {
+                general_purpose: [
+                    Some(10),
+                    Some(11),
+                    Some(12),
+                    Some(13),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some(14),
+                ],
+                return_address: 0x0800_0101,
+                program_counter: 0x0800_0200,
+                stack_pointer: 0x2000_0020,
+            }
+        );
+    }
+
+    #[test]
+    fn unstack_aligned_frame() {
+        let exception_return = ExceptionReturn(0xFFFF_FFF9);
+        let frame: Vec<u32> = vec![0, 0, 0, 0, 0, 0, 0, 0x0100_0000 | XPSR_STACK_ALIGNED];
+
+        let unstacked = exception_return.unstack(0x2000_0000, &frame, false);
+
+        assert_eq!(unstacked.stack_pointer, 0x2000_0024);
+    }
+
+    #[test]
+    fn unstack_fp_frame() {
+        let exception_return = ExceptionReturn(0xFFFF_FFED);
+        let frame: Vec<u32> = vec![0, 0, 0, 0, 0, 0, 0x0800_0200, 0x0100_0000];
+
+        let unstacked = exception_return.unstack(0x2000_0000, &frame, false);
+
+        assert_eq!(unstacked.program_counter, 0x0800_0200);
+        assert_eq!(unstacked.stack_pointer, 0x2000_0000 + 26 * 4);
+
+        // The additional FP context is only part of extended frames.
+        let unstacked = ExceptionReturn(0xFFFF_FFFD).unstack(0x2000_0000, &frame, true);
+        assert_eq!(unstacked.stack_pointer, 0x2000_0020);
+    }
+
+    #[test]
+    fn unstack_secure_frame_with_additional_contexts() {
+        // Thread mode, secure PSP, extended frame and additional state context, handled in the non-secure state.
+        let exception_return = ExceptionReturn(0xFFFF_FFCC);
+        assert!(exception_return.uses_secure_stack());
+        assert!(!exception_return.handled_in_secure_state());
+        assert_eq!(exception_return.return_address_offset(), 16 * 4);
+
+        let unstacked = exception_return.unstack(0x2000_0000, &frame(), false);
+
+        assert_eq!(
+            unstacked.general_purpose,
+            [
+                Some(10),
+                Some(11),
+                Some(12),
+                Some(13),
+                Some(2),
+                Some(3),
+                Some(4),
+                Some(5),
+                Some(6),
+                Some(7),
+                Some(8),
+                Some(9),
+                Some(14),
+            ]
+        );
+        assert_eq!(unstacked.return_address, 15);
+        assert_eq!(unstacked.program_counter, 16);
+        assert_eq!(unstacked.stack_pointer, 0x2000_0000 + (10 + 8 + 18) * 4);
+
+        // With FPCCR.TS set, S16-S31 follow the FP context.
+        let unstacked = exception_return.unstack(0x2000_0000, &frame(), true);
+        assert_eq!(
+            unstacked.stack_pointer,
+            0x2000_0000 + (10 + 8 + 18 + 16) * 4
+        );
+    }
+
+    #[test]
+    fn stack_names() {
+        let secure_psp = ExceptionReturn(0xFFFF_FFCC);
+        assert_eq!(secure_psp.stack_name(true), "PSP_S");
+        assert_eq!(ExceptionReturn(0xFFFF_FFBC).stack_name(true), "PSP_NS");
+        assert_eq!(ExceptionReturn(0xFFFF_FFF9).stack_name(false), "MSP");
+        assert_eq!(ExceptionReturn(0xFFFF_FFB8).stack_name(true), "MSP_NS");
+    }
+}
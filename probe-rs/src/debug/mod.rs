@@ -8,6 +8,8 @@
 // Bad things happen to the VSCode debug extenison and debug_adapter if we panic at the wrong time.
 #![warn(clippy::unwrap_used, clippy::panic, clippy::expect_used)]
 
+pub(crate) mod exception;
+mod stepping;
 mod variable;
 
 use crate::{
@@ -17,6 +19,7 @@ use crate::{
 };
 use num_traits::Zero;
 use probe_rs_target::Architecture;
+pub use stepping::SteppingMode;
 pub use variable::{Variable, VariableCache, VariableLocation, VariableName, VariantRole};
 
 use std::{
@@ -24,6 +27,7 @@ use std::{
     collections::HashMap,
     io,
     num::NonZeroU64,
+    ops::Range,
     path::{Path, PathBuf},
     rc::Rc,
     str::{from_utf8, Utf8Error},
@@ -311,6 +315,28 @@ pub struct SourceLocation {
     pub high_pc: Option<u32>,
 }
 
+/// The row of a line program which applies to an address, used to find statement boundaries while stepping.
+#[derive(Clone, Debug, PartialEq)]
+struct LineLocation {
+    /// The path of the source file.
+    path: Option<PathBuf>,
+    /// The line number, or `None` for code which is not associated with a source line.
+    line: Option<u64>,
+    /// Set if the row starts exactly at the address, and is a recommended breakpoint location.
+    is_statement: bool,
+    /// The addresses covered by the row.
+    range: Range<u64>,
+}
+
+/// A function, or an inlined function, which contains an address.
+#[derive(Clone, Debug, PartialEq)]
+struct FunctionScope {
+    /// The unit and the offset of the function DIE, which identify the function independent of the address.
+    die: (Option<gimli::DebugInfoOffset>, UnitOffset),
+    /// The address range of the function which contains the address.
+    range: Range<u64>,
+}
+
 type GimliReader = gimli::EndianReader<gimli::LittleEndian, std::rc::Rc<[u8]>>;
 type GimliAttribute = gimli::Attribute<GimliReader>;
 
@@ -529,6 +555,90 @@ impl DebugInfo {
         None
     }
 
+    /// Find the row of the line programs which applies to the given address.
+    fn get_line_location(&self, address: u64) -> Result<Option<LineLocation>, DebugError> {
+        let mut units = self.dwarf.units();
+
+        while let Some(header) = units.next()? {
+            let unit = self.dwarf.unit(header)?;
+
+            let mut ranges = self.dwarf.unit_ranges(&unit)?;
+            let mut unit_contains_address = false;
+            while let Some(range) = ranges.next()? {
+                if (range.begin <= address) && (address < range.end) {
+                    unit_contains_address = true;
+                    break;
+                }
+            }
+
+            let line_program = match unit.line_program.clone() {
+                Some(line_program) if unit_contains_address => line_program,
+                _ => continue,
+            };
+
+            let (program, sequences) = line_program.sequences()?;
+
+            let sequence = match sequences
+                .iter()
+                .find(|sequence| (sequence.start <= address) && (address < sequence.end))
+            {
+                Some(sequence) => sequence,
+                None => continue,
+            };
+
+            let mut rows = program.resume_from(sequence);
+            let mut previous_row: Option<gimli::LineRow> = None;
+
+            while let Some((header, row)) = rows.next_row()? {
+                if row.address() > address {
+                    // The previous row applies to all addresses up to the start of this row.
+                    return Ok(previous_row.map(|previous_row| {
+                        let path = previous_row
+                            .file(header)
+                            .and_then(|file_entry| self.get_path(&unit, header, file_entry));
+
+                        LineLocation {
+                            path,
+                            line: previous_row.line().map(NonZeroU64::get),
+                            is_statement: previous_row.is_stmt()
+                                && previous_row.address() == address,
+                            range: previous_row.address()..row.address(),
+                        }
+                    }));
+                }
+
+                previous_row = Some(*row);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Find the function containing the given address, and the functions inlined into it at this address.
+    ///
+    /// The outermost function is the first entry of the returned list, and the innermost inlined function the last.
+    fn get_function_scopes(&self, address: u64) -> Result<Vec<FunctionScope>, DebugError> {
+        let mut units = self.get_units();
+
+        while let Some(unit_info) = self.get_next_unit_info(&mut units) {
+            let functions = unit_info.get_function_dies(address, true)?;
+
+            if !functions.is_empty() {
+                let unit_offset = unit_info.unit.header.offset().as_debug_info_offset();
+
+                return Ok(functions
+                    .iter()
+                    .map(|function| FunctionScope {
+                        die: (unit_offset, function.function_die.offset()),
+                        range: function.low_pc..function.high_pc,
+                    })
+                    .collect());
+            }
+        }
+
+        Ok(vec![])
+    }
+
     fn get_units(&self) -> UnitIter {
         self.dwarf.units()
     }
@@ -967,7 +1077,7 @@ impl DebugInfo {
     /// The unwind loop will continue until we meet one of the following conditions:
     /// - We can no longer unwind a valid PC value to be used for the next frame.
     /// - We encounter a LR register value of 0xFFFFFFFF which is the 'Reset` value for that register.
-    /// - TODO: Catch the situation where the PC value indicates a hard-fault or other non-recoverable exception
+    /// - On Cortex-M cores, an `EXC_RETURN` value in the LR register marks an exception handler. The registers of the interrupted code are then restored from the exception frame stacked by the hardware, and a pseudo `StackFrame` for the exception entry is added, before the unwind continues.
     /// - We can not intelligently calculate a valid LR register value from the other registers, or the gimli::RegisterRule result is a value of 0x0. Note: [DWARF](https://dwarfstd.org) 6.4.4 - CIE defines the return register address used in the `gimli::RegisterRule` tables for unwind operations. Theoretically, if we encounter a function that has `Undefined` `gimli::RegisterRule` for the return register address, it means we have reached the bottom of the stack OR the function is a 'no return' type of function. I have found actual examples (e.g. local functions) where we get `Undefined` for register rule when we cannot apply this logic. Example 1: local functions in main.rs will have LR rule as `Undefined`. Example 2: main()-> ! that is called from a trampoline will have a valid LR rule.
     /// - Similarly, certain error conditions encountered in `StackFrameIterator` will also break out of the unwind loop.
     /// Note: In addition to populating the `StackFrame`s, this function will also populate the `DebugInfo::VariableCache` with `Variable`s for available Registers as well as static and function variables.
@@ -1028,7 +1138,8 @@ impl DebugInfo {
             };
 
             // Part 1-b: Check LR values to determine if we can continue unwinding.
-            // TODO: ARM has special ranges of LR addresses to indicate fault conditions. We should check those also.
+            // On Cortex-M, an `EXC_RETURN` value in LR means that this frame is an exception handler. It is unwound in PART 2-d.
+            let exception_return = exception::ExceptionReturn::from_registers(&unwind_registers);
             if let Some(check_return_address) = unwind_registers.get_return_address() {
                 if check_return_address == u32::MAX {
                     // When we encounter the starting (after reset) return address, we've reached the bottom of the stack, so no more unwinding after this.
@@ -1066,11 +1177,31 @@ impl DebugInfo {
                         error
                     );
                     stack_frames.push(return_frame);
+                    // Exception handlers without debug information, e.g. trampolines written in assembly, can still be unwound, as long as they did not use the stack.
+                    if let Some(exception_return) = exception_return {
+                        match exception::unwind_exception_frame(
+                            core,
+                            exception_return,
+                            None,
+                            &mut unwind_registers,
+                        ) {
+                            Ok(exception_frame) => {
+                                stack_frames.push(exception_frame);
+                                continue;
+                            }
+                            Err(error) => {
+                                log::error!(
+                                    "UNWIND: Failed to unwind the exception frame: {}",
+                                    error
+                                );
+                            }
+                        }
+                    }
                     break;
                 }
             };
 
-            match frame_descriptor_entry.unwind_info_for_address(
+            let unwind_cfa = match frame_descriptor_entry.unwind_info_for_address(
                 &self.frame_section,
                 &unwind_bases,
                 &mut unwind_context,
@@ -1247,6 +1378,7 @@ impl DebugInfo {
                             register_rule_string,
                         );
                     }
+                    unwind_cfa
                 }
                 Err(error) => {
                     log::trace!("UNWIND: Stack unwind complete. No available debug info for program counter {:#x}: {}", frame_pc, error);
@@ -1255,6 +1387,26 @@ impl DebugInfo {
                 }
             };
 
+            // PART 2-d: For an exception handler, the registers of the interrupted code, which the handler did not save itself, are restored from the exception frame.
+            if let Some(exception_return) = exception_return {
+                stack_frames.push(return_frame);
+                match exception::unwind_exception_frame(
+                    core,
+                    exception_return,
+                    unwind_cfa,
+                    &mut unwind_registers,
+                ) {
+                    Ok(exception_frame) => {
+                        stack_frames.push(exception_frame);
+                        continue;
+                    }
+                    Err(error) => {
+                        log::error!("UNWIND: Failed to unwind the exception frame: {}", error);
+                        break;
+                    }
+                }
+            }
+
             // PART 3: In order to set the correct value of the previous frame we need to peek one frame deeper in the stack.
             // NOTE: ARM Specific.
             // TODO: Investigate and document why and under which circumstances this extra step is necessary. It was added during PR#895.
@@ -0,0 +1,500 @@
+//! Source level stepping, based on the line programs and function DIEs of the debug information.
+
+use super::{DebugError, DebugInfo, FunctionScope, LineLocation};
+use crate::{core::Core, CoreStatus, HaltReason};
+use anyhow::anyhow;
+use std::thread;
+use std::time::Duration;
+
+/// The maximum number of instructions which are stepped while looking for the next statement.
+///
+/// This prevents the debugger from hanging on lines which never finish, e.g. `loop {}`.
+const MAX_INSTRUCTION_STEPS: usize = 1000;
+
+/// The interval in which the core is polled, while it runs a called function.
+const RETURN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The different ways to step through a program.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SteppingMode {
+    /// Execute a single instruction.
+    StepInstruction,
+    /// Run to the next statement of the current function, stepping over calls and inlined functions.
+    OverStatement,
+    /// Run to the next statement, following calls into functions which have debug information.
+    IntoStatement,
+    /// Run until the current function returns to its caller.
+    OutOfStatement,
+}
+
+impl SteppingMode {
+    /// Step the halted `core` according to this mode.
+    ///
+    /// Returns the status of the core and its program counter afterwards. The status is
+    /// [`HaltReason::Step`] if the step completed, or the reason for the halt if the core
+    /// stopped for another reason, e.g. because a breakpoint was hit while stepping over a call.
+    ///
+    /// Called functions run until they return, however long that takes.
+    /// Use [`SteppingMode::step_interruptible`] to be able to pause them.
+    pub fn step(
+        &self,
+        core: &mut Core<'_>,
+        debug_info: &DebugInfo,
+    ) -> Result<(CoreStatus, u32), DebugError> {
+        self.step_interruptible(core, debug_info, &mut || false)
+    }
+
+    /// Like [`SteppingMode::step`], but `interrupted` is called while the core runs a called
+    /// function. If it returns `true`, e.g. because the user paused the program, the core is
+    /// halted where it is, and the status is [`HaltReason::Request`].
+    pub fn step_interruptible(
+        &self,
+        core: &mut Core<'_>,
+        debug_info: &DebugInfo,
+        interrupted: &mut dyn FnMut() -> bool,
+    ) -> Result<(CoreStatus, u32), DebugError> {
+        let status = match self {
+            SteppingMode::StepInstruction => {
+                core.step()?;
+                CoreStatus::Halted(HaltReason::Step)
+            }
+            SteppingMode::OverStatement => step_to_statement(core, debug_info, false, interrupted)?,
+            SteppingMode::IntoStatement => step_to_statement(core, debug_info, true, interrupted)?,
+            SteppingMode::OutOfStatement => step_out(core, debug_info, interrupted)?,
+        };
+
+        let program_counter = read_program_counter(core)?;
+
+        Ok((status, program_counter))
+    }
+}
+
+/// Single steps the core until it reaches the start of a statement on a different source line.
+///
+/// Calls are either followed, if `into_calls` is set and the called function has debug information,
+/// or skipped by running to the return address.
+fn step_to_statement(
+    core: &mut Core<'_>,
+    debug_info: &DebugInfo,
+    into_calls: bool,
+    interrupted: &mut dyn FnMut() -> bool,
+) -> Result<CoreStatus, DebugError> {
+    let mut program_counter = read_program_counter(core)?;
+
+    let (mut origin_location, mut origin_scopes) =
+        match current_location(debug_info, program_counter)? {
+            Some(location) => location,
+            None => {
+                log::debug!(
+                    "No debug information at {:#010x}, stepping once",
+                    program_counter
+                );
+                core.step()?;
+                return Ok(CoreStatus::Halted(HaltReason::Step));
+            }
+        };
+    let mut row_range = origin_location.range.clone();
+
+    for _ in 0..MAX_INSTRUCTION_STEPS {
+        let stack_pointer = read_stack_pointer(core)?;
+        let previous_program_counter = program_counter;
+        core.step()?;
+        program_counter = read_program_counter(core)?;
+
+        if let Some(return_address) =
+            called_function_return(core, previous_program_counter, program_counter)?
+        {
+            if into_calls {
+                if let Some((location, scopes)) = current_location(debug_info, program_counter)? {
+                    // Continue in the called function, until the first statement after its prologue.
+                    row_range = location.range.clone();
+                    origin_location = location;
+                    origin_scopes = scopes;
+                    continue;
+                }
+            }
+
+            if let Some(status) = run_to_return(core, return_address, stack_pointer, interrupted)? {
+                return Ok(status);
+            }
+            program_counter = return_address;
+            continue;
+        }
+
+        // No need to look at the debug information, while the core is still inside the same row of the line program.
+        if row_range.start < program_counter as u64 && (program_counter as u64) < row_range.end {
+            continue;
+        }
+
+        let (location, scopes) = match current_location(debug_info, program_counter)? {
+            Some(location) => location,
+            None => {
+                // The function returned to code without debug information.
+                return Ok(CoreStatus::Halted(HaltReason::Step));
+            }
+        };
+        row_range = location.range.clone();
+
+        if is_step_target(
+            &origin_location,
+            &origin_scopes,
+            &location,
+            &scopes,
+            into_calls,
+        ) {
+            return Ok(CoreStatus::Halted(HaltReason::Step));
+        }
+    }
+
+    log::warn!(
+        "Did not reach a new statement after stepping {} instructions, stopping at {:#010x}",
+        MAX_INSTRUCTION_STEPS,
+        program_counter
+    );
+    Ok(CoreStatus::Halted(HaltReason::Step))
+}
+
+/// Returns `true` if stepping from `origin_location` stops at `location`.
+///
+/// This is the case for the start of a statement on a different source line, unless it is
+/// in an inlined function which is stepped over. When the function returned to its caller,
+/// stepping stops there, even in the middle of a line.
+fn is_step_target(
+    origin_location: &LineLocation,
+    origin_scopes: &[FunctionScope],
+    location: &LineLocation,
+    scopes: &[FunctionScope],
+    into_calls: bool,
+) -> bool {
+    if scopes.first().map(|scope| scope.die) != origin_scopes.first().map(|scope| scope.die) {
+        return true;
+    }
+
+    let entered_inlined_function = scopes.len() > origin_scopes.len()
+        && scopes
+            .iter()
+            .zip(origin_scopes.iter())
+            .all(|(scope, origin_scope)| scope.die == origin_scope.die);
+    if entered_inlined_function && !into_calls {
+        return false;
+    }
+
+    location.is_statement
+        && location.line.is_some()
+        && (location.line != origin_location.line || location.path != origin_location.path)
+}
+
+/// Runs the core until the current function returns, and stops at the return address in the caller.
+fn step_out(
+    core: &mut Core<'_>,
+    debug_info: &DebugInfo,
+    interrupted: &mut dyn FnMut() -> bool,
+) -> Result<CoreStatus, DebugError> {
+    let program_counter = read_program_counter(core)?;
+
+    let scopes = debug_info.get_function_scopes(program_counter as u64)?;
+    if scopes.len() > 1 {
+        if let Some(inlined_scope) = scopes.last() {
+            // Inlined functions have no return address, so the core is stepped until it leaves the inlined code.
+            return step_out_of_inlined_function(core, debug_info, inlined_scope, interrupted);
+        }
+    }
+
+    let stack_frames = debug_info.unwind(core, program_counter as u64)?;
+
+    let caller = stack_frames.get(1).ok_or_else(|| {
+        anyhow!(
+            "Unable to step out of the function at {:#010x}, because its caller is unknown",
+            program_counter
+        )
+    })?;
+
+    // The unwound program counter of the caller points to the call instruction,
+    // and is calculated by subtracting 4 bytes from the return address.
+    let return_address = caller.pc + 4;
+
+    // When the function returns, the stack pointer is restored to the value of the caller.
+    let stack_pointer = caller.registers.get_stack_pointer().ok_or_else(|| {
+        anyhow!(
+            "Unable to step out of the function at {:#010x}, because the stack pointer of its caller is unknown",
+            program_counter
+        )
+    })?;
+
+    Ok(
+        run_to_return(core, return_address, stack_pointer, interrupted)?
+            .unwrap_or(CoreStatus::Halted(HaltReason::Step)),
+    )
+}
+
+/// Single steps the core until it leaves the inlined function `inlined_scope`, stepping over calls.
+fn step_out_of_inlined_function(
+    core: &mut Core<'_>,
+    debug_info: &DebugInfo,
+    inlined_scope: &FunctionScope,
+    interrupted: &mut dyn FnMut() -> bool,
+) -> Result<CoreStatus, DebugError> {
+    let mut program_counter = read_program_counter(core)?;
+
+    for _ in 0..MAX_INSTRUCTION_STEPS {
+        let stack_pointer = read_stack_pointer(core)?;
+        let previous_program_counter = program_counter;
+        core.step()?;
+        program_counter = read_program_counter(core)?;
+
+        if let Some(return_address) =
+            called_function_return(core, previous_program_counter, program_counter)?
+        {
+            if let Some(status) = run_to_return(core, return_address, stack_pointer, interrupted)? {
+                return Ok(status);
+            }
+            program_counter = return_address;
+        }
+
+        if inlined_scope.range.contains(&(program_counter as u64)) {
+            continue;
+        }
+
+        // Inlined functions can consist of multiple address ranges.
+        if !debug_info
+            .get_function_scopes(program_counter as u64)?
+            .iter()
+            .any(|scope| scope.die == inlined_scope.die)
+        {
+            return Ok(CoreStatus::Halted(HaltReason::Step));
+        }
+    }
+
+    log::warn!(
+        "Did not leave the inlined function after stepping {} instructions, stopping at {:#010x}",
+        MAX_INSTRUCTION_STEPS,
+        program_counter
+    );
+    Ok(CoreStatus::Halted(HaltReason::Step))
+}
+
+/// Looks up the line program row and the function scopes for `address`.
+///
+/// Returns `None` if there is no debug information for the address.
+fn current_location(
+    debug_info: &DebugInfo,
+    address: u32,
+) -> Result<Option<(LineLocation, Vec<FunctionScope>)>, DebugError> {
+    let location = match debug_info.get_line_location(address as u64)? {
+        Some(location) => location,
+        None => return Ok(None),
+    };
+
+    let scopes = debug_info.get_function_scopes(address as u64)?;
+    if scopes.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some((location, scopes)))
+}
+
+/// Checks if the instruction at `previous_program_counter`, which was just stepped, called a function.
+///
+/// A call stores the address of the following instruction in the link register,
+/// so this returns the return address of the called function in that case.
+fn called_function_return(
+    core: &mut Core<'_>,
+    previous_program_counter: u32,
+    program_counter: u32,
+) -> Result<Option<u32>, DebugError> {
+    let return_address = core.read_core_reg(core.registers().return_address())? & !0b1;
+
+    // Both 16 and 32 bit call instructions exist.
+    let is_call = [2, 4].iter().any(|instruction_size| {
+        return_address == previous_program_counter + instruction_size
+            && program_counter != return_address
+    });
+
+    Ok(if is_call { Some(return_address) } else { None })
+}
+
+/// Runs the core until a called function returns to `return_address`, using a temporary hardware breakpoint.
+///
+/// `stack_pointer` is the stack pointer of the calling function, which is used to
+/// ignore the return address being hit by recursive calls.
+///
+/// Returns the status of the core if it halted for another reason, or because it was `interrupted`.
+fn run_to_return(
+    core: &mut Core<'_>,
+    return_address: u32,
+    stack_pointer: u32,
+    interrupted: &mut dyn FnMut() -> bool,
+) -> Result<Option<CoreStatus>, DebugError> {
+    // Don't remove breakpoints which were set by the user.
+    let breakpoint_exists = core.hw_breakpoints()?.contains(&Some(return_address));
+    if !breakpoint_exists {
+        core.set_hw_breakpoint(return_address)?;
+    }
+
+    let result = wait_for_return(core, return_address, stack_pointer, interrupted);
+
+    if !breakpoint_exists {
+        core.clear_hw_breakpoint(return_address)?;
+    }
+
+    result
+}
+
+fn wait_for_return(
+    core: &mut Core<'_>,
+    return_address: u32,
+    stack_pointer: u32,
+    interrupted: &mut dyn FnMut() -> bool,
+) -> Result<Option<CoreStatus>, DebugError> {
+    loop {
+        core.run()?;
+
+        while !core.core_halted()? {
+            if interrupted() {
+                log::debug!(
+                    "Interrupted while waiting for the return to {:#010x}, halting the core",
+                    return_address
+                );
+                core.halt(Duration::from_millis(100))?;
+                return Ok(Some(core.status()?));
+            }
+
+            thread::sleep(RETURN_POLL_INTERVAL);
+        }
+
+        // Update the status of the core, which is used to step over the breakpoint when running again.
+        let status = core.status()?;
+
+        if read_program_counter(core)? != return_address {
+            return Ok(Some(status));
+        }
+
+        if read_stack_pointer(core)? >= stack_pointer {
+            return Ok(None);
+        }
+
+        log::debug!(
+            "Return address {:#010x} was reached by a recursive call, continuing",
+            return_address
+        );
+    }
+}
+
+fn read_program_counter(core: &mut Core<'_>) -> Result<u32, DebugError> {
+    Ok(core.read_core_reg(core.registers().program_counter())?)
+}
+
+fn read_stack_pointer(core: &mut Core<'_>) -> Result<u32, DebugError> {
+    Ok(core.read_core_reg(core.registers().stack_pointer())?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::is_step_target;
+    use crate::debug::{FunctionScope, LineLocation};
+    use gimli::UnitOffset;
+    use std::path::PathBuf;
+
+    fn location(line: u64, is_statement: bool) -> LineLocation {
+        LineLocation {
+            path: Some(PathBuf::from("src/main.rs")),
+            line: Some(line),
+            is_statement,
+            range: 0x1000..0x1004,
+        }
+    }
+
+    fn scope(offset: usize) -> FunctionScope {
+        FunctionScope {
+            die: (None, UnitOffset(offset)),
+            range: 0x1000..0x1100,
+        }
+    }
+
+    #[test]
+    fn stops_at_statement_on_next_line() {
+        let scopes = [scope(1)];
+
+        assert!(is_step_target(
+            &location(10, true),
+            &scopes,
+            &location(11, true),
+            &scopes,
+            false
+        ));
+    }
+
+    #[test]
+    fn skips_rows_which_are_no_statements() {
+        let scopes = [scope(1)];
+
+        assert!(!is_step_target(
+            &location(10, true),
+            &scopes,
+            &location(11, false),
+            &scopes,
+            false
+        ));
+    }
+
+    #[test]
+    fn skips_rows_on_the_same_line() {
+        let scopes = [scope(1)];
+
+        assert!(!is_step_target(
+            &location(10, true),
+            &scopes,
+            &location(10, true),
+            &scopes,
+            false
+        ));
+    }
+
+    #[test]
+    fn skips_code_without_line() {
+        let scopes = [scope(1)];
+        let mut compiler_generated = location(0, true);
+        compiler_generated.line = None;
+
+        assert!(!is_step_target(
+            &location(10, true),
+            &scopes,
+            &compiler_generated,
+            &scopes,
+            false
+        ));
+    }
+
+    #[test]
+    fn steps_over_inlined_functions() {
+        let origin_scopes = [scope(1)];
+        let inlined_scopes = [scope(1), scope(2)];
+
+        assert!(!is_step_target(
+            &location(10, true),
+            &origin_scopes,
+            &location(20, true),
+            &inlined_scopes,
+            false
+        ));
+        assert!(is_step_target(
+            &location(10, true),
+            &origin_scopes,
+            &location(20, true),
+            &inlined_scopes,
+            true
+        ));
+    }
+
+    #[test]
+    fn stops_in_caller_after_return() {
+        // The caller is stopped at, even in the middle of a line.
+        assert!(is_step_target(
+            &location(10, true),
+            &[scope(2)],
+            &location(30, false),
+            &[scope(1)],
+            false
+        ));
+    }
+}
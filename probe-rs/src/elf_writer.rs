@@ -0,0 +1,202 @@
+//! A minimal writer for 32 bit little endian ELF files, used for core dumps and memory images.
+
+use object::elf::{
+    ELFCLASS32, ELFDATA2LSB, ELFMAG, EV_CURRENT, PF_W, PF_X, PT_LOAD, SHF_ALLOC, SHF_EXECINSTR,
+    SHF_WRITE, SHT_PROGBITS, SHT_STRTAB,
+};
+
+/// Size of the ELF header of a 32 bit ELF file.
+const ELF_HEADER_SIZE: usize = 52;
+
+/// Size of a program header of a 32 bit ELF file.
+const PROGRAM_HEADER_SIZE: usize = 32;
+
+/// Size of a section header of a 32 bit ELF file.
+const SECTION_HEADER_SIZE: usize = 40;
+
+/// A segment of an ELF file.
+pub(crate) struct Segment<'data> {
+    /// The segment type, e.g. `PT_LOAD`.
+    pub segment_type: u32,
+    /// The segment flags, e.g. `PF_R`.
+    pub flags: u32,
+    /// The virtual and physical address of the segment.
+    pub address: u32,
+    /// The contents of the segment.
+    pub data: &'data [u8],
+    /// The name of a section covering the segment, or `None` if the segment has no section.
+    pub section: Option<String>,
+}
+
+/// Creates an ELF file of `file_type` for `machine`, containing `segments`.
+///
+/// The data of the segments follows the program headers in the order of `segments`. Section
+/// headers and a section name string table are only added if a segment has a section.
+pub(crate) fn write(file_type: u16, machine: u16, segments: &[Segment]) -> Vec<u8> {
+    let sections: Vec<_> = segments
+        .iter()
+        .enumerate()
+        .filter_map(|(index, segment)| segment.section.as_deref().map(|name| (index, name)))
+        .collect();
+
+    let mut string_table = vec![0u8];
+    let mut name_offsets = Vec::new();
+    for name in sections
+        .iter()
+        .map(|(_, name)| *name)
+        .chain(Some(".shstrtab"))
+    {
+        name_offsets.push(string_table.len() as u32);
+        string_table.extend_from_slice(name.as_bytes());
+        string_table.push(0);
+    }
+
+    let data_offset = ELF_HEADER_SIZE + segments.len() * PROGRAM_HEADER_SIZE;
+    let mut segment_offsets = Vec::new();
+    let mut offset = data_offset;
+    for segment in segments {
+        segment_offsets.push(offset);
+        offset += segment.data.len();
+    }
+    let string_table_offset = offset;
+    let section_headers_offset = align_4(string_table_offset + string_table.len());
+
+    // The sections of the segments, the string table and the null section.
+    let num_sections = if sections.is_empty() {
+        0
+    } else {
+        sections.len() + 2
+    };
+
+    let mut elf = Vec::new();
+
+    // ELF header
+    elf.extend_from_slice(&ELFMAG);
+    elf.extend_from_slice(&[ELFCLASS32, ELFDATA2LSB, EV_CURRENT]);
+    elf.resize(16, 0);
+    elf.extend_from_slice(&file_type.to_le_bytes());
+    elf.extend_from_slice(&machine.to_le_bytes());
+    elf.extend_from_slice(&u32::from(EV_CURRENT).to_le_bytes());
+    // Entry point, program header offset, section header offset and flags
+    elf.extend_from_slice(&0u32.to_le_bytes());
+    elf.extend_from_slice(&(ELF_HEADER_SIZE as u32).to_le_bytes());
+    if num_sections == 0 {
+        elf.extend_from_slice(&0u32.to_le_bytes());
+    } else {
+        elf.extend_from_slice(&(section_headers_offset as u32).to_le_bytes());
+    }
+    elf.extend_from_slice(&0u32.to_le_bytes());
+    elf.extend_from_slice(&(ELF_HEADER_SIZE as u16).to_le_bytes());
+    elf.extend_from_slice(&(PROGRAM_HEADER_SIZE as u16).to_le_bytes());
+    elf.extend_from_slice(&(segments.len() as u16).to_le_bytes());
+    if num_sections == 0 {
+        elf.extend_from_slice(&[0u8; 6]);
+    } else {
+        elf.extend_from_slice(&(SECTION_HEADER_SIZE as u16).to_le_bytes());
+        elf.extend_from_slice(&(num_sections as u16).to_le_bytes());
+        // The string table is the last section.
+        elf.extend_from_slice(&((num_sections - 1) as u16).to_le_bytes());
+    }
+
+    // Program headers, the segment data follows after all headers.
+    for (segment, offset) in segments.iter().zip(&segment_offsets) {
+        for value in [
+            segment.segment_type,
+            *offset as u32,
+            // Virtual and physical address
+            segment.address,
+            segment.address,
+            // Size in the file and in memory
+            segment.data.len() as u32,
+            segment.data.len() as u32,
+            segment.flags,
+            // Alignment
+            1,
+        ] {
+            elf.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    for segment in segments {
+        elf.extend_from_slice(segment.data);
+    }
+
+    if num_sections == 0 {
+        return elf;
+    }
+
+    elf.extend_from_slice(&string_table);
+    elf.resize(section_headers_offset, 0);
+
+    // Section headers, starting with the null section.
+    elf.resize(elf.len() + SECTION_HEADER_SIZE, 0);
+
+    for ((index, _), name_offset) in sections.iter().zip(&name_offsets) {
+        let segment = &segments[*index];
+
+        let mut flags = 0;
+        if segment.segment_type == PT_LOAD {
+            flags |= SHF_ALLOC;
+        }
+        if segment.flags & PF_W != 0 {
+            flags |= SHF_WRITE;
+        }
+        if segment.flags & PF_X != 0 {
+            flags |= SHF_EXECINSTR;
+        }
+
+        push_section_header(
+            &mut elf,
+            *name_offset,
+            SHT_PROGBITS,
+            flags,
+            segment.address,
+            segment_offsets[*index],
+            segment.data.len(),
+        );
+    }
+
+    push_section_header(
+        &mut elf,
+        name_offsets[sections.len()],
+        SHT_STRTAB,
+        0,
+        0,
+        string_table_offset,
+        string_table.len(),
+    );
+
+    elf
+}
+
+fn push_section_header(
+    elf: &mut Vec<u8>,
+    name_offset: u32,
+    section_type: u32,
+    flags: u32,
+    address: u32,
+    offset: usize,
+    size: usize,
+) {
+    for value in [
+        name_offset,
+        section_type,
+        flags,
+        address,
+        offset as u32,
+        size as u32,
+        // Link and info
+        0,
+        0,
+        // Alignment and entry size
+        1,
+        0,
+    ] {
+        elf.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+/// Rounds `length` up to a multiple of 4.
+pub(crate) fn align_4(length: usize) -> usize {
+    (length + 3) & !3
+}
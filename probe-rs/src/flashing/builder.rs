@@ -46,6 +46,11 @@ impl FlashPage {
         &self.data
     }
 
+    /// Returns the address range of the page.
+    fn range(&self) -> Range<u32> {
+        self.address..self.address + self.size()
+    }
+
     /// Returns the mut data slice of the page.
     pub(super) fn data_mut(&mut self) -> &mut [u8] {
         &mut self.data
@@ -69,6 +74,11 @@ impl FlashSector {
     pub fn size(&self) -> u32 {
         self.size
     }
+
+    /// Returns the address range of the sector.
+    fn range(&self) -> Range<u32> {
+        self.address..self.address + self.size
+    }
 }
 
 /// A struct to hold all the information about one region
@@ -135,6 +145,68 @@ impl FlashLayout {
     pub fn visualize(&self) -> FlashVisualizer {
         FlashVisualizer::new(self)
     }
+
+    /// Returns the contents of `sector` after flashing. Bytes which are not covered by any page are erased.
+    pub(super) fn sector_contents(&self, sector: &FlashSector, erased_byte_value: u8) -> Vec<u8> {
+        let mut contents = vec![erased_byte_value; sector.size as usize];
+
+        for page in &self.pages {
+            let start = page.address.max(sector.address);
+            let end = page.range().end.min(sector.range().end);
+
+            if start < end {
+                contents[(start - sector.address) as usize..(end - sector.address) as usize]
+                    .copy_from_slice(
+                        &page.data[(start - page.address) as usize..(end - page.address) as usize],
+                    );
+            }
+        }
+
+        contents
+    }
+
+    /// Determines which sectors and pages can be skipped, given which sectors already contain their [contents](FlashLayout::sector_contents).
+    ///
+    /// Returns whether each sector can be skipped, and whether each page can be skipped.
+    /// A page can only be skipped if all sectors it overlaps are unchanged, and a sector can
+    /// only be skipped if it is unchanged and all pages it overlaps are skipped.
+    pub(super) fn skippable_sectors_and_pages(
+        &self,
+        unchanged_sectors: &[bool],
+    ) -> (Vec<bool>, Vec<bool>) {
+        let skippable_pages: Vec<bool> = self
+            .pages
+            .iter()
+            .map(|page| {
+                let mut overlapping_sectors = self
+                    .sectors
+                    .iter()
+                    .zip(unchanged_sectors)
+                    .filter(|(sector, _)| sector.range().intersects_range(&page.range()))
+                    .peekable();
+
+                overlapping_sectors.peek().is_some()
+                    && overlapping_sectors.all(|(_, unchanged)| *unchanged)
+            })
+            .collect();
+
+        let skippable_sectors = self
+            .sectors
+            .iter()
+            .zip(unchanged_sectors)
+            .map(|(sector, unchanged)| {
+                *unchanged
+                    && self
+                        .pages
+                        .iter()
+                        .zip(&skippable_pages)
+                        .filter(|(page, _)| page.range().intersects_range(&sector.range()))
+                        .all(|(_, skippable)| *skippable)
+            })
+            .collect();
+
+        (skippable_sectors, skippable_pages)
+    }
 }
 
 /// A block of data that is to be written to flash.
@@ -1139,4 +1211,73 @@ mod tests {
             }
         )
     }
+
+    #[test]
+    fn sector_contents_of_pages_larger_than_sectors() {
+        let (region, flash_algorithm) = assemble_demo_flash2();
+        let mut flash_builder = FlashBuilder::new();
+        flash_builder.add_data(0x7f, &[1, 2]).unwrap();
+        let flash_layout = flash_builder
+            .build_sectors_and_pages(&region, &flash_algorithm, false)
+            .unwrap();
+
+        let erased_byte_value = flash_algorithm.flash_properties.erased_byte_value;
+
+        let mut expected = vec![erased_byte_value; 128];
+        expected[127] = 1;
+        assert_eq!(
+            flash_layout.sector_contents(&flash_layout.sectors()[0], erased_byte_value),
+            expected
+        );
+
+        let mut expected = vec![erased_byte_value; 128];
+        expected[0] = 2;
+        assert_eq!(
+            flash_layout.sector_contents(&flash_layout.sectors()[1], erased_byte_value),
+            expected
+        );
+    }
+
+    #[test]
+    fn skip_unchanged_sectors_and_pages() {
+        let (region, flash_algorithm) = assemble_demo_flash1();
+        let mut flash_builder = FlashBuilder::new();
+        flash_builder.add_data(0, &[42; 0x2000]).unwrap();
+        let flash_layout = flash_builder
+            .build_sectors_and_pages(&region, &flash_algorithm, false)
+            .unwrap();
+
+        assert_eq!(
+            flash_layout.skippable_sectors_and_pages(&[true, false]),
+            (
+                vec![true, false],
+                vec![true, true, true, true, false, false, false, false]
+            )
+        );
+    }
+
+    #[test]
+    fn keep_unchanged_sectors_of_changed_pages() {
+        let (region, flash_algorithm) = assemble_demo_flash2();
+        let mut flash_builder = FlashBuilder::new();
+        flash_builder.add_data(0x7f, &[1, 2]).unwrap();
+        let flash_layout = flash_builder
+            .build_sectors_and_pages(&region, &flash_algorithm, false)
+            .unwrap();
+
+        // All eight sectors share the first page, so they can only be skipped together.
+        assert_eq!(flash_layout.sectors().len(), 8);
+
+        let mut unchanged_sectors = vec![true; 8];
+        unchanged_sectors[1] = false;
+        assert_eq!(
+            flash_layout.skippable_sectors_and_pages(&unchanged_sectors),
+            (vec![false; 8], vec![false])
+        );
+
+        assert_eq!(
+            flash_layout.skippable_sectors_and_pages(&[true; 8]),
+            (vec![true; 8], vec![true])
+        );
+    }
 }
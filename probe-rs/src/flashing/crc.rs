@@ -0,0 +1,56 @@
+//! The CRC32 used to compare memory contents without reading them back.
+
+/// The polynomial of the CRC32, which is calculated MSB first.
+pub(super) const CRC32_POLYNOMIAL: u32 = 0x04C1_1DB7;
+/// The initial value of the CRC32, see [`crc32_update`]. The result is not inverted.
+pub const CRC32_INITIAL_VALUE: u32 = 0xFFFF_FFFF;
+
+/// Calculates the CRC32 of `data`.
+///
+/// This is the checksum calculated by the routine in the header of ARM flash algorithms, and the
+/// one GDB expects in reply to a `qCRC` packet: the polynomial 0x04C11DB7, calculated MSB first,
+/// starting with 0xFFFFFFFF and without inverting the result.
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32_update(CRC32_INITIAL_VALUE, data)
+}
+
+/// Continues the CRC32 `crc` of the previous data with `data`, e.g. to calculate the checksum of
+/// data which is read in chunks.
+///
+/// Starting with `0xFFFFFFFF`, the result is the same as [`crc32`] of all the data.
+pub fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    data.iter().fold(crc, |crc, byte| {
+        (0..8).fold(crc ^ (u32::from(*byte) << 24), |crc, _| {
+            if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ CRC32_POLYNOMIAL
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{crc32, crc32_update, CRC32_INITIAL_VALUE};
+
+    #[test]
+    fn check_value() {
+        assert_eq!(crc32(b"123456789"), 0x0376_E6E7);
+    }
+
+    #[test]
+    fn empty_and_erased_data() {
+        assert_eq!(crc32(&[]), 0xFFFF_FFFF);
+        assert_eq!(crc32(&[0xFF; 4]), 0);
+    }
+
+    #[test]
+    fn update_in_chunks() {
+        let crc = [&b"1234"[..], b"", b"56789"]
+            .iter()
+            .fold(CRC32_INITIAL_VALUE, |crc, chunk| crc32_update(crc, chunk));
+
+        assert_eq!(crc, crc32(b"123456789"));
+    }
+}
@@ -4,7 +4,7 @@ use object::{
 };
 use probe_rs_target::MemoryRange;
 
-use std::{fs::File, path::Path, str::FromStr};
+use std::{fs::File, path::Path, str::FromStr, time::Duration};
 
 use super::*;
 use crate::session::Session;
@@ -18,6 +18,15 @@ pub struct BinOptions {
     pub skip: u32,
 }
 
+/// Extended options for flashing a UF2 file.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct Uf2Options {
+    /// Only the blocks of this family are flashed. Blocks without a family ID are always flashed.
+    ///
+    /// If this is not set, the file must not contain blocks of more than one family.
+    pub family_id: Option<u32>,
+}
+
 /// A finite list of all the available binary formats probe-rs understands.
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub enum Format {
@@ -29,6 +38,11 @@ pub enum Format {
     Hex,
     /// Marks a file in the [ELF](https://en.wikipedia.org/wiki/Executable_and_Linkable_Format) format.
     Elf,
+    /// Marks a file in the [UF2](https://github.com/microsoft/uf2) format.
+    /// [Uf2Options] can be used to select the blocks of a single family.
+    Uf2(Uf2Options),
+    /// Marks a file in [Motorola S-record](https://en.wikipedia.org/wiki/SREC_(file_format)) format.
+    Srec,
 }
 
 impl FromStr for Format {
@@ -42,6 +56,8 @@ impl FromStr for Format {
             })),
             "hex" | "ihex" | "intelhex" => Ok(Format::Hex),
             "elf" => Ok(Format::Elf),
+            "uf2" => Ok(Format::Uf2(Uf2Options::default())),
+            "srec" | "s19" | "s28" | "s37" | "mot" => Ok(Format::Srec),
             _ => Err(format!("Format '{}' is unknown.", s)),
         }
     }
@@ -75,6 +91,33 @@ pub enum FileDownloadError {
     /// This is most likely because of a bad linker script.
     #[error("No loadable ELF sections were found.")]
     NoLoadableSegments,
+    /// The given block of the UF2 file is invalid.
+    #[error("UF2 block {block} is invalid: {reason}.")]
+    InvalidUf2 {
+        /// The number of the block in the file.
+        block: usize,
+        /// Why the block is invalid.
+        reason: &'static str,
+    },
+    /// The UF2 file contains blocks of several families, but no family was selected.
+    #[error("The UF2 file contains blocks of the families {0:#010x?}, select one of them.")]
+    Uf2FamilyAmbiguous(Vec<u32>),
+    /// The UF2 file contains no blocks of the selected family.
+    #[error("The UF2 file contains no blocks of family {family_id:#010x}, but of the families {families:#010x?}.")]
+    Uf2FamilyNotFound {
+        /// The selected family.
+        family_id: u32,
+        /// The families contained in the file.
+        families: Vec<u32>,
+    },
+    /// The given line of the S-record file is invalid.
+    #[error("S-record in line {line} is invalid: {reason}.")]
+    InvalidSrec {
+        /// The number of the line, starting at 1.
+        line: usize,
+        /// Why the record is invalid.
+        reason: &'static str,
+    },
 }
 
 /// Options for downloading a file onto a target chip.
@@ -111,10 +154,25 @@ pub struct DownloadOptions<'progress> {
     /// If the chip was pre-erased with external erasers, this flag can set to true to skip erasing
     /// It may be useful for mass production.
     pub skip_erase: bool,
-    /// After flashing, read back all the flashed data to verify it has been written correctly.
+    /// After flashing, verify that all the data has been written correctly.
+    ///
+    /// The flash is checked with a CRC32 routine running on the target if the flash algorithm
+    /// has one, otherwise the data is read back.
     pub verify: bool,
     /// Disable double buffering when loading flash.
     pub disable_double_buffering: bool,
+    /// Compare the sectors with the data to be flashed before erasing them, and skip the sectors whose contents already match.
+    /// The sectors are compared in the same way as the flash is verified.
+    /// This speeds up flashing an image which only changed in a few sectors.
+    ///
+    /// This has no effect if a chip erase is done.
+    pub skip_unchanged_sectors: bool,
+    /// The time the CRC32 routine of the flash algorithm may take per KiB of data, in addition to one second,
+    /// when the flash is verified or compared with `skip_unchanged_sectors`.
+    /// If the routine takes longer, e.g. because the core runs at a low clock, the flash is read back instead.
+    ///
+    /// If this is `None`, 16 ms per KiB are used, which is enough for cores running at a few MHz.
+    pub crc_timeout_per_kib: Option<Duration>,
 }
 
 impl<'progress> DownloadOptions<'progress> {
@@ -155,11 +213,7 @@ pub fn download_file_with_options<P: AsRef<Path>>(
 
     let mut loader = session.target().flash_loader();
 
-    match format {
-        Format::Bin(options) => loader.load_bin_data(&mut file, options),
-        Format::Elf => loader.load_elf_data(&mut file),
-        Format::Hex => loader.load_hex_data(&mut file),
-    }?;
+    loader.load(format, &mut file)?;
 
     loader
         .commit(session, options)
@@ -285,7 +339,7 @@ pub(super) fn extract_from_elf<'data>(
 mod tests {
     use std::str::FromStr;
 
-    use super::{BinOptions, Format};
+    use super::{BinOptions, Format, Uf2Options};
 
     #[test]
     fn parse_format() {
@@ -328,6 +382,12 @@ mod tests {
         );
         assert_eq!(Format::from_str("Elf"), Ok(Format::Elf));
         assert_eq!(Format::from_str("elf"), Ok(Format::Elf));
+        assert_eq!(
+            Format::from_str("UF2"),
+            Ok(Format::Uf2(Uf2Options { family_id: None }))
+        );
+        assert_eq!(Format::from_str("srec"), Ok(Format::Srec));
+        assert_eq!(Format::from_str("S19"), Ok(Format::Srec));
         assert_eq!(
             Format::from_str("elfbin"),
             Err("Format 'elfbin' is unknown.".to_string())
@@ -1,8 +1,9 @@
 use std::collections::HashMap;
+use std::ops::Range;
 
-use probe_rs_target::{MemoryRange, MemoryRegion, NvmRegion};
+use probe_rs_target::{MemoryRange, MemoryRegion, NvmRegion, SectorInfo};
 
-use crate::flashing::{flasher::Flasher, FlashError, FlashLoader};
+use crate::flashing::{flasher::Flasher, FlashAlgorithm, FlashError, FlashLoader};
 use crate::Session;
 
 /// Mass-erase all nonvolatile memory.
@@ -82,3 +83,207 @@ pub fn erase_all(session: &mut Session) -> Result<(), FlashError> {
 
     Ok(())
 }
+
+/// Erases the sectors in `range`.
+///
+/// The range may span several NVM regions, but it must not contain memory outside of them.
+/// It has to start and end at sector boundaries, unless `extend_to_sectors` is `true`, in which
+/// case all sectors overlapping the range are erased.
+pub fn erase_range(
+    session: &mut Session,
+    range: Range<u32>,
+    extend_to_sectors: bool,
+) -> Result<(), FlashError> {
+    log::debug!("Erasing {:08x}-{:08x}", range.start, range.end);
+
+    // Determine all sectors first, so nothing is erased if the range is invalid.
+    let mut algos: HashMap<(String, String), Vec<SectorInfo>> = HashMap::new();
+    let mut nvm_size = 0;
+    for region in &session.target().memory_map {
+        let region = match region {
+            MemoryRegion::Nvm(region) => region,
+            _ => continue,
+        };
+
+        let start = region.range.start.max(range.start);
+        let end = region.range.end.min(range.end);
+        if start >= end {
+            continue;
+        }
+        nvm_size += end - start;
+
+        let algo = FlashLoader::get_flash_algorithm_for_region(region, session.target())?;
+
+        // Get the first core that can access the region
+        let core_name = region
+            .cores
+            .first()
+            .ok_or_else(|| FlashError::NoNvmCoreAccess(region.clone()))?;
+
+        // Only the flash properties are needed to iterate over the sectors.
+        let flash_algorithm = FlashAlgorithm {
+            flash_properties: algo.flash_properties.clone(),
+            ..Default::default()
+        };
+
+        algos
+            .entry((algo.name.clone(), core_name.clone()))
+            .or_default()
+            .extend(sectors_in_range(
+                &flash_algorithm,
+                &(start..end),
+                extend_to_sectors,
+            )?);
+    }
+
+    if nvm_size != range.end - range.start {
+        return Err(FlashError::NoSuitableNvm {
+            start: range.start,
+            end: range.end,
+            description_source: session.target().source().clone(),
+        });
+    }
+
+    for ((algo_name, core_name), mut sectors) in algos {
+        log::debug!("Erasing with algorithm: {}", algo_name);
+
+        // Sectors may have been added for two adjacent regions.
+        sectors.sort_by_key(|info| info.base_address);
+        sectors.dedup();
+
+        // This can't fail, algo_name comes from the target.
+        let algo = session.target().flash_algorithm_by_name(&algo_name);
+        let algo = algo.unwrap().clone();
+
+        let core_index = session.target().core_index_by_name(&core_name).unwrap();
+        let mut flasher = Flasher::new(session, core_index, &algo)?;
+
+        flasher.run_erase(|active| {
+            for info in sectors {
+                log::debug!(
+                    "    sector: {:08x}-{:08x} ({} bytes)",
+                    info.base_address,
+                    info.base_address + info.size,
+                    info.size
+                );
+
+                active.erase_sector(info.base_address)?;
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Erases the NVM region named `name`.
+pub fn erase_region(session: &mut Session, name: &str) -> Result<(), FlashError> {
+    let range = session
+        .target()
+        .nvm_region_by_name(name)
+        .ok_or_else(|| FlashError::NoSuchNvmRegion(name.to_string()))?
+        .range
+        .clone();
+
+    erase_range(session, range, false)
+}
+
+/// Returns the sectors of `flash_algorithm` which overlap `range`.
+///
+/// Unless `extend_to_sectors` is `true`, the sectors have to cover exactly the range.
+fn sectors_in_range(
+    flash_algorithm: &FlashAlgorithm,
+    range: &Range<u32>,
+    extend_to_sectors: bool,
+) -> Result<Vec<SectorInfo>, FlashError> {
+    let sectors = flash_algorithm
+        .iter_sectors()
+        .filter(|info| info.base_address < range.end && range.start < info.base_address + info.size)
+        .collect::<Vec<_>>();
+
+    let sector_start = sectors
+        .first()
+        .map_or(range.start, |info| info.base_address);
+    let sector_end = sectors
+        .last()
+        .map_or(range.start, |info| info.base_address + info.size);
+
+    if !extend_to_sectors && (sector_start != range.start || sector_end != range.end) {
+        return Err(FlashError::RangeNotSectorAligned {
+            start: range.start,
+            end: range.end,
+            sector_start,
+            sector_end,
+        });
+    }
+
+    Ok(sectors)
+}
+
+#[cfg(test)]
+mod test {
+    use probe_rs_target::{FlashProperties, SectorDescription, SectorInfo};
+
+    use super::sectors_in_range;
+    use crate::flashing::{FlashAlgorithm, FlashError};
+
+    fn flash_algorithm() -> FlashAlgorithm {
+        FlashAlgorithm {
+            flash_properties: FlashProperties {
+                sectors: vec![
+                    SectorDescription {
+                        size: 0x1000,
+                        address: 0x0,
+                    },
+                    SectorDescription {
+                        size: 0x4000,
+                        address: 0x4000,
+                    },
+                ],
+                address_range: 0x800_0000..0x801_0000,
+                page_size: 0x100,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn aligned_range() {
+        let sectors =
+            sectors_in_range(&flash_algorithm(), &(0x800_3000..0x800_8000), false).unwrap();
+
+        assert_eq!(
+            sectors,
+            vec![
+                SectorInfo {
+                    base_address: 0x800_3000,
+                    size: 0x1000,
+                },
+                SectorInfo {
+                    base_address: 0x800_4000,
+                    size: 0x4000,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unaligned_range() {
+        let range = 0x800_0800..0x800_5000;
+
+        assert!(matches!(
+            sectors_in_range(&flash_algorithm(), &range, false),
+            Err(FlashError::RangeNotSectorAligned {
+                sector_start: 0x800_0000,
+                sector_end: 0x800_8000,
+                ..
+            })
+        ));
+
+        let sectors = sectors_in_range(&flash_algorithm(), &range, true).unwrap();
+        assert_eq!(sectors.len(), 5);
+        assert_eq!(sectors[0].base_address, 0x800_0000);
+        assert_eq!(sectors[4].base_address, 0x800_4000);
+    }
+}
@@ -148,4 +148,19 @@ pub enum FlashError {
     /// No core can access this RAM region.
     #[error("No core can access the ram region {0:?}.")]
     NoRamCoreAccess(RamRegion),
+    /// The range to erase does not start and end at sector boundaries.
+    #[error("The range {start:#010x}..{end:#010x} does not start and end at sector boundaries, the sectors cover {sector_start:#010x}..{sector_end:#010x}.")]
+    RangeNotSectorAligned {
+        /// The start of the range to erase.
+        start: u32,
+        /// The end of the range to erase.
+        end: u32,
+        /// The start of the first sector overlapping the range.
+        sector_start: u32,
+        /// The end of the last sector overlapping the range.
+        sector_end: u32,
+    },
+    /// The target has no NVM region with this name.
+    #[error("The target has no NVM region named '{0}'.")]
+    NoSuchNvmRegion(String),
 }
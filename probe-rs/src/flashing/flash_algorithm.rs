@@ -42,6 +42,9 @@ pub struct FlashAlgorithm {
     /// least as large as the region's `page_size` attribute. If at least 2 buffers are included in
     /// the list, then double buffered programming will be enabled.
     pub page_buffers: Vec<u32>,
+    /// Address of the CRC32 routine in the algorithm header, if the architecture has one.
+    /// See [`crc32`](super::crc32) for the checksum it calculates.
+    pub pc_crc32: Option<u32>,
 
     /// The properties of the flash on the device.
     pub flash_properties: FlashProperties,
@@ -160,6 +163,8 @@ impl FlashAlgorithm {
     // Header for RISCV Flash Algorithms
     const RISCV_FLASH_BLOB_HEADER: [u32; 2] = [riscv::assembly::EBREAK, riscv::assembly::EBREAK];
 
+    /// The breakpoint the routines return to, followed by a CRC32 routine at
+    /// [`Self::ARM_CRC32_OFFSET`].
     const ARM_FLASH_BLOB_HEADER: [u32; 8] = [
         0xE00A_BE00,
         0x062D_780D,
@@ -168,9 +173,15 @@ impl FlashAlgorithm {
         0x1E64_4058,
         0x1C49_D1FA,
         0x2A00_1E52,
-        0x0477_0D1F,
+        0x4770_D1F2,
     ];
 
+    /// The offset of the CRC32 routine in [`Self::ARM_FLASH_BLOB_HEADER`].
+    ///
+    /// The routine takes the initial value in `r0`, the address in `r1`, the length in `r2`,
+    /// which must not be 0, and the polynomial in `r3`. It returns the checksum in `r0`.
+    const ARM_CRC32_OFFSET: u32 = 4;
+
     fn get_algorithm_header(architecture: Architecture) -> &'static [u32] {
         match architecture {
             Architecture::Arm => &Self::ARM_FLASH_BLOB_HEADER,
@@ -275,6 +286,10 @@ impl FlashAlgorithm {
             begin_stack: addr_stack,
             begin_data: page_buffers[0],
             page_buffers: page_buffers.clone(),
+            pc_crc32: match target.architecture() {
+                Architecture::Arm => Some(addr_load + Self::ARM_CRC32_OFFSET),
+                Architecture::Riscv => None,
+            },
             flash_properties: raw.flash_properties.clone(),
         })
     }
@@ -1,5 +1,6 @@
 use probe_rs_target::{MemoryRegion, RawFlashAlgorithm};
 
+use super::crc::{crc32, CRC32_INITIAL_VALUE, CRC32_POLYNOMIAL};
 use super::{
     FlashAlgorithm, FlashBuilder, FlashError, FlashFill, FlashLayout, FlashPage, FlashProgress,
 };
@@ -55,9 +56,14 @@ pub(super) struct Flasher<'session> {
     session: &'session mut Session,
     core_index: usize,
     flash_algorithm: FlashAlgorithm,
+    /// The time the CRC32 routine of the flash algorithm may take per KiB, see [`Flasher::set_crc_timeout_per_kib`].
+    crc_timeout_per_kib: Duration,
 }
 
 impl<'session> Flasher<'session> {
+    /// The routine takes about 50 cycles per byte, so this leaves enough time for cores running at a few MHz.
+    const DEFAULT_CRC_TIMEOUT_PER_KIB: Duration = Duration::from_millis(16);
+
     pub(super) fn new(
         session: &'session mut Session,
         core_index: usize,
@@ -90,6 +96,7 @@ impl<'session> Flasher<'session> {
             session,
             core_index,
             flash_algorithm,
+            crc_timeout_per_kib: Self::DEFAULT_CRC_TIMEOUT_PER_KIB,
         };
 
         this.load()?;
@@ -105,6 +112,12 @@ impl<'session> Flasher<'session> {
         self.flash_algorithm.page_buffers.len() > 1
     }
 
+    /// Sets the time the CRC32 routine of the flash algorithm may take per KiB of compared data, in addition to one second.
+    /// If the routine takes longer, the flash is read back instead.
+    pub(super) fn set_crc_timeout_per_kib(&mut self, crc_timeout_per_kib: Duration) {
+        self.crc_timeout_per_kib = crc_timeout_per_kib;
+    }
+
     fn load(&mut self) -> Result<(), FlashError> {
         log::debug!("Initializing the flash algorithm.");
         let algo = &mut self.flash_algorithm;
@@ -226,6 +239,10 @@ impl<'session> Flasher<'session> {
     /// If `restore_unwritten_bytes` is `true`, all bytes of a sector,
     /// that are not to be written during flashing will be read from the flash first
     /// and written again once the sector is erased.
+    ///
+    /// If `skip_unchanged_sectors` is `true`, the sectors are compared with the new contents first,
+    /// and the sectors which already have the new contents are neither erased nor programmed.
+    #[allow(clippy::too_many_arguments)]
     pub(super) fn program(
         &mut self,
         region: &NvmRegion,
@@ -233,6 +250,7 @@ impl<'session> Flasher<'session> {
         restore_unwritten_bytes: bool,
         enable_double_buffering: bool,
         skip_erasing: bool,
+        skip_unchanged_sectors: bool,
         progress: &FlashProgress,
     ) -> Result<(), FlashError> {
         log::debug!("Starting program procedure.");
@@ -274,22 +292,94 @@ impl<'session> Flasher<'session> {
         // We successfully finished filling.
         progress.finished_filling();
 
+        let (skipped_sectors, skipped_pages) = if skip_unchanged_sectors {
+            let unchanged_sectors = self.compare_sectors(&flash_layout)?;
+            flash_layout.skippable_sectors_and_pages(&unchanged_sectors)
+        } else {
+            (
+                vec![false; flash_layout.sectors().len()],
+                vec![false; flash_layout.pages().len()],
+            )
+        };
+
         // Skip erase if necessary
         if !skip_erasing {
             // Erase all necessary sectors
-            self.sector_erase(&flash_layout, progress)?;
+            self.sector_erase(&flash_layout, &skipped_sectors, progress)?;
         }
 
         // Flash all necessary pages.
         if self.double_buffering_supported() && enable_double_buffering {
-            self.program_double_buffer(&flash_layout, progress)?;
+            self.program_double_buffer(&flash_layout, &skipped_pages, progress)?;
         } else {
-            self.program_simple(&flash_layout, progress)?;
+            self.program_simple(&flash_layout, &skipped_pages, progress)?;
         };
 
         Ok(())
     }
 
+    /// Compares the sectors given in `flash_layout` with the flash, and returns for each sector
+    /// whether it already has the contents it will have after flashing.
+    fn compare_sectors(&mut self, flash_layout: &FlashLayout) -> Result<Vec<bool>, FlashError> {
+        let erased_byte_value = self.flash_algorithm.flash_properties.erased_byte_value;
+        let crc_timeout_per_kib = self.crc_timeout_per_kib;
+
+        let unchanged_sectors = self.run_verify(|active| {
+            flash_layout
+                .sectors()
+                .iter()
+                .map(|sector| {
+                    active.memory_matches(
+                        sector.address(),
+                        &flash_layout.sector_contents(sector, erased_byte_value),
+                        crc_timeout_per_kib,
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })?;
+
+        log::info!(
+            "{} of {} sectors are unchanged",
+            unchanged_sectors
+                .iter()
+                .filter(|unchanged| **unchanged)
+                .count(),
+            unchanged_sectors.len()
+        );
+
+        Ok(unchanged_sectors)
+    }
+
+    /// Verifies that the flash in `region` contains the data of `flash_builder`.
+    ///
+    /// The flash is compared with the CRC32 routine of the flash algorithm if it has one,
+    /// otherwise, or if the routine fails, it is read back.
+    pub(super) fn verify(
+        &mut self,
+        region: &NvmRegion,
+        flash_builder: &FlashBuilder,
+    ) -> Result<(), FlashError> {
+        if self.flash_algorithm.pc_crc32.is_none() {
+            log::debug!("The flash algorithm has no CRC32 routine, reading back the flash.");
+        }
+
+        let crc_timeout_per_kib = self.crc_timeout_per_kib;
+        self.run_verify(|active| {
+            for (address, data) in flash_builder.data_in_range(&region.range) {
+                if !active.memory_matches(address, data, crc_timeout_per_kib)? {
+                    log::error!(
+                        "Verification failed for {:08x}-{:08x}",
+                        address,
+                        address + data.len() as u32
+                    );
+                    return Err(FlashError::Verify);
+                }
+            }
+
+            Ok(())
+        })
+    }
+
     /// Fills all the bytes of `current_page`.
     ///
     /// If `restore_unwritten_bytes` is `true`, all bytes of the page,
@@ -310,17 +400,23 @@ impl<'session> Flasher<'session> {
         })
     }
 
-    /// Programs the pages given in `flash_layout` into the flash.
+    /// Programs the pages given in `flash_layout` into the flash, except the `skipped_pages`.
     fn program_simple(
         &mut self,
         flash_layout: &FlashLayout,
+        skipped_pages: &[bool],
         progress: &FlashProgress,
     ) -> Result<(), FlashError> {
         progress.started_programming();
 
         let mut t = std::time::Instant::now();
         let result = self.run_program(|active| {
-            for page in flash_layout.pages() {
+            for (page, skipped) in flash_layout.pages().iter().zip(skipped_pages) {
+                if *skipped {
+                    progress.page_skipped(page.size());
+                    continue;
+                }
+
                 active
                     .program_page(page.address(), page.data())
                     .map_err(|error| FlashError::PageWrite {
@@ -342,17 +438,23 @@ impl<'session> Flasher<'session> {
         result
     }
 
-    /// Perform an erase of all sectors given in `flash_layout`.
+    /// Perform an erase of all sectors given in `flash_layout`, except the `skipped_sectors`.
     fn sector_erase(
         &mut self,
         flash_layout: &FlashLayout,
+        skipped_sectors: &[bool],
         progress: &FlashProgress,
     ) -> Result<(), FlashError> {
         progress.started_erasing();
 
         let mut t = std::time::Instant::now();
         let result = self.run_erase(|active| {
-            for sector in flash_layout.sectors() {
+            for (sector, skipped) in flash_layout.sectors().iter().zip(skipped_sectors) {
+                if *skipped {
+                    progress.sector_skipped(sector.size());
+                    continue;
+                }
+
                 active
                     .erase_sector(sector.address())
                     .map_err(|e| FlashError::EraseFailed {
@@ -384,9 +486,12 @@ impl<'session> Flasher<'session> {
     ///
     /// This is only possible if the RAM is large enough to
     /// fit at least two page buffers. See [Flasher::double_buffering_supported].
+    ///
+    /// The `skipped_pages` are not programmed.
     fn program_double_buffer(
         &mut self,
         flash_layout: &FlashLayout,
+        skipped_pages: &[bool],
         progress: &FlashProgress,
     ) -> Result<(), FlashError> {
         let mut current_buf = 0;
@@ -396,7 +501,12 @@ impl<'session> Flasher<'session> {
         let mut t = std::time::Instant::now();
         let result = self.run_program(|active| {
             let mut last_page_address = 0;
-            for page in flash_layout.pages() {
+            for (page, skipped) in flash_layout.pages().iter().zip(skipped_pages) {
+                if *skipped {
+                    progress.page_skipped(page.size());
+                    continue;
+                }
+
                 // At the start of each loop cycle load the next page buffer into RAM.
                 active.load_page_buffer(page.address(), page.data(), current_buf)?;
 
@@ -632,6 +742,68 @@ impl<'probe, O: Operation> ActiveFlasher<'probe, O> {
     }
 }
 
+impl<'probe> ActiveFlasher<'probe, Verify> {
+    /// Returns `true` if the memory at `address` contains `data`.
+    ///
+    /// If the flash algorithm has a CRC32 routine, the checksum of the memory is calculated on
+    /// the target and compared with the checksum of `data`, which is a lot faster than reading
+    /// the memory back over the probe. The routine may take `crc_timeout_per_kib` per KiB of
+    /// `data`, in addition to one second.
+    pub(super) fn memory_matches(
+        &mut self,
+        address: u32,
+        data: &[u8],
+        crc_timeout_per_kib: Duration,
+    ) -> Result<bool, FlashError> {
+        if data.is_empty() {
+            return Ok(true);
+        }
+
+        if let Some(pc_crc32) = self.flash_algorithm.pc_crc32 {
+            let kib = (data.len() as u32 + 1023) / 1024;
+            let timeout =
+                Duration::from_secs(1).saturating_add(crc_timeout_per_kib.saturating_mul(kib));
+
+            let result = self.call_function_and_wait(
+                &Registers {
+                    pc: pc_crc32,
+                    r0: Some(CRC32_INITIAL_VALUE),
+                    r1: Some(address),
+                    r2: Some(data.len() as u32),
+                    r3: Some(CRC32_POLYNOMIAL),
+                },
+                false,
+                timeout,
+            );
+
+            match result {
+                Ok(crc) => return Ok(crc == crc32(data)),
+                // E.g. a core running at a low clock may not finish in time. The memory is still
+                // compared, by reading it back.
+                Err(error) => {
+                    log::warn!(
+                        "The CRC32 routine of the flash algorithm failed, reading back the flash instead: {}",
+                        error
+                    );
+                    self.core
+                        .halt(Duration::from_millis(100))
+                        .map_err(FlashError::Core)?;
+
+                    // The routine is not called again for the remaining data of this operation.
+                    self.flash_algorithm.pc_crc32 = None;
+                }
+            }
+        }
+
+        let mut contents = vec![0; data.len()];
+        self.core
+            .read(address, &mut contents)
+            .map_err(FlashError::Core)?;
+
+        Ok(contents == data)
+    }
+}
+
 impl<'probe> ActiveFlasher<'probe, Erase> {
     pub(super) fn erase_all(&mut self) -> Result<(), FlashError> {
         log::debug!("Erasing entire chip.");
@@ -0,0 +1,340 @@
+//! Programming several targets of the same type in parallel, e.g. on a production line.
+
+use std::error::Error;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::{DownloadOptions, FlashLoader};
+use crate::config::TargetSelector;
+use crate::{DebugProbeInfo, DebugProbeSelector, Permissions, Probe, Session, WireProtocol};
+
+/// An error in the selection of the probes for [`gang_program`].
+#[derive(Debug, thiserror::Error)]
+pub enum GangError {
+    /// No connected probe matches the selector.
+    #[error("No connected probe matches {0}")]
+    ProbeNotFound(DebugProbeSelector),
+    /// Several connected probes match the selector.
+    #[error("Several connected probes match {selector}, select one of them with its serial number: {serial_numbers:?}")]
+    AmbiguousProbe {
+        /// The selector, as given to [`gang_program`].
+        selector: DebugProbeSelector,
+        /// The serial numbers of the matching probes, if they have one.
+        serial_numbers: Vec<Option<String>>,
+    },
+    /// The selector selects the same probe as a selector before it.
+    #[error("{selector} selects the same probe as {previous}")]
+    DuplicateProbe {
+        /// The selector, as given to [`gang_program`].
+        selector: DebugProbeSelector,
+        /// The selector before it, which selects the same probe.
+        previous: DebugProbeSelector,
+    },
+}
+
+/// Options for [`gang_program`].
+#[derive(Debug, Clone, Default)]
+pub struct GangOptions {
+    /// The wire protocol used by the probes.
+    pub protocol: Option<WireProtocol>,
+    /// The speed of the probes in kHz.
+    pub speed_khz: Option<u32>,
+    /// Attach to the targets while their reset pins are asserted.
+    pub connect_under_reset: bool,
+    /// The permissions of the sessions.
+    pub permissions: Permissions,
+    /// See [`DownloadOptions::do_chip_erase`].
+    pub do_chip_erase: bool,
+    /// See [`DownloadOptions::verify`].
+    pub verify: bool,
+    /// See [`DownloadOptions::skip_unchanged_sectors`].
+    pub skip_unchanged_sectors: bool,
+    /// See [`DownloadOptions::disable_double_buffering`].
+    pub disable_double_buffering: bool,
+    /// Reset the targets after they were programmed.
+    pub reset: bool,
+}
+
+/// The result of programming the target connected to one probe.
+#[derive(Debug, Clone, Serialize)]
+pub struct GangResult {
+    /// The probe the target is connected to, as given to [`gang_program`].
+    pub probe: String,
+    /// The serial number of the probe which was opened, if it has one.
+    pub serial_number: Option<String>,
+    /// Whether the target was programmed successfully.
+    pub passed: bool,
+    /// The error which occurred, including its causes.
+    pub error: Option<String>,
+    /// The time it took to attach to the target and to program it, in milliseconds.
+    pub duration_ms: u64,
+}
+
+/// Programs the data of `loader` into the targets connected to `probes`.
+///
+/// Each selector has to match exactly one of the connected probes, and no probe may be selected
+/// twice, so every target is programmed by its own probe. Otherwise nothing is programmed.
+///
+/// The probes are opened and attached one after the other, so they don't interfere while they
+/// are enumerated. Then each target is programmed in its own thread. A board which fails does not
+/// stop the others, its error is reported in its result.
+///
+/// The results are returned in the order of `probes`.
+pub fn gang_program(
+    probes: &[DebugProbeSelector],
+    target: TargetSelector,
+    loader: FlashLoader,
+    options: &GangOptions,
+) -> Result<Vec<GangResult>, GangError> {
+    let resolved = resolve_probes(probes, &Probe::list_all())?;
+    let loader = Arc::new(loader);
+
+    let mut boards = Vec::new();
+    for (selector, resolved) in probes.iter().zip(resolved) {
+        let started = Instant::now();
+
+        let thread = attach(&resolved, target.clone(), options).and_then(|session| {
+            let loader = loader.clone();
+            let options = options.clone();
+
+            thread::Builder::new()
+                .name(format!("gang-{}", selector))
+                .spawn(move || {
+                    let started = Instant::now();
+                    let result = program(session, &loader, &options);
+                    (result, started.elapsed())
+                })
+                .map_err(|error| error_chain(&error))
+        });
+
+        boards.push((selector, resolved, started.elapsed(), thread));
+    }
+
+    let results = boards
+        .into_iter()
+        .map(|(selector, resolved, attach_duration, thread)| {
+            let (result, program_duration) = match thread {
+                Ok(thread) => thread.join().unwrap_or_else(|_| {
+                    (
+                        Err("The programming thread panicked".to_string()),
+                        Duration::ZERO,
+                    )
+                }),
+                Err(error) => (Err(error), Duration::ZERO),
+            };
+
+            match &result {
+                Ok(()) => log::info!("Programmed the target connected to {}", selector),
+                Err(error) => log::error!(
+                    "Failed to program the target connected to {}: {}",
+                    selector,
+                    error
+                ),
+            }
+
+            GangResult {
+                probe: selector.to_string(),
+                serial_number: resolved.serial_number,
+                passed: result.is_ok(),
+                error: result.err(),
+                duration_ms: (attach_duration + program_duration).as_millis() as u64,
+            }
+        })
+        .collect();
+
+    Ok(results)
+}
+
+/// Resolves each of `selectors` to the one of `probes` it matches, and returns a selector for
+/// that probe, which includes its serial number if it has one.
+///
+/// Fails if a selector matches none or several of the probes, or the same probe as another
+/// selector.
+fn resolve_probes(
+    selectors: &[DebugProbeSelector],
+    probes: &[DebugProbeInfo],
+) -> Result<Vec<DebugProbeSelector>, GangError> {
+    let mut selected: Vec<(usize, &DebugProbeSelector)> = Vec::new();
+
+    for selector in selectors {
+        let matching: Vec<_> = probes
+            .iter()
+            .enumerate()
+            .filter(|(_, probe)| {
+                probe.vendor_id == selector.vendor_id
+                    && probe.product_id == selector.product_id
+                    && (selector.serial_number.is_none()
+                        || probe.serial_number == selector.serial_number)
+            })
+            .collect();
+
+        let index = match matching[..] {
+            [(index, _)] => index,
+            [] => return Err(GangError::ProbeNotFound(selector.clone())),
+            _ => {
+                return Err(GangError::AmbiguousProbe {
+                    selector: selector.clone(),
+                    serial_numbers: matching
+                        .iter()
+                        .map(|(_, probe)| probe.serial_number.clone())
+                        .collect(),
+                })
+            }
+        };
+
+        if let Some((_, previous)) = selected
+            .iter()
+            .find(|(selected_index, _)| *selected_index == index)
+        {
+            return Err(GangError::DuplicateProbe {
+                selector: selector.clone(),
+                previous: (*previous).clone(),
+            });
+        }
+        selected.push((index, selector));
+    }
+
+    Ok(selected
+        .into_iter()
+        .map(|(index, _)| DebugProbeSelector::from(&probes[index]))
+        .collect())
+}
+
+/// Opens the probe given by `selector`, and attaches to its target.
+fn attach(
+    selector: &DebugProbeSelector,
+    target: TargetSelector,
+    options: &GangOptions,
+) -> Result<Session, String> {
+    let mut probe = Probe::open(selector.clone()).map_err(|error| error_chain(&error))?;
+
+    if let Some(protocol) = options.protocol {
+        probe
+            .select_protocol(protocol)
+            .map_err(|error| error_chain(&error))?;
+    }
+
+    if let Some(speed_khz) = options.speed_khz {
+        probe
+            .set_speed(speed_khz)
+            .map_err(|error| error_chain(&error))?;
+    }
+
+    let permissions = options.permissions.clone();
+    if options.connect_under_reset {
+        probe.attach_under_reset(target, permissions)
+    } else {
+        probe.attach(target, permissions)
+    }
+    .map_err(|error| error_chain(&error))
+}
+
+/// Programs the data of `loader`, and resets the target if requested.
+fn program(
+    mut session: Session,
+    loader: &FlashLoader,
+    options: &GangOptions,
+) -> Result<(), String> {
+    let mut download_options = DownloadOptions::new();
+    download_options.do_chip_erase = options.do_chip_erase;
+    download_options.verify = options.verify;
+    download_options.skip_unchanged_sectors = options.skip_unchanged_sectors;
+    download_options.disable_double_buffering = options.disable_double_buffering;
+
+    loader
+        .commit(&mut session, download_options)
+        .map_err(|error| error_chain(&error))?;
+
+    if options.reset {
+        session
+            .core(0)
+            .and_then(|mut core| core.reset())
+            .map_err(|error| error_chain(&error))?;
+    }
+
+    Ok(())
+}
+
+/// Formats `error` followed by all its causes.
+fn error_chain(error: &dyn Error) -> String {
+    let mut message = error.to_string();
+
+    let mut source = error.source();
+    while let Some(error) = source {
+        message.push_str(": ");
+        message.push_str(&error.to_string());
+        source = error.source();
+    }
+
+    message
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::DebugProbeType;
+
+    fn probe(product_id: u16, serial_number: Option<&str>) -> DebugProbeInfo {
+        DebugProbeInfo::new(
+            "probe",
+            0x1234,
+            product_id,
+            serial_number.map(String::from),
+            DebugProbeType::CmsisDap,
+            None,
+        )
+    }
+
+    fn selector(product_id: u16, serial_number: Option<&str>) -> DebugProbeSelector {
+        DebugProbeSelector {
+            vendor_id: 0x1234,
+            product_id,
+            serial_number: serial_number.map(String::from),
+        }
+    }
+
+    #[test]
+    fn resolve_probes_to_serial_numbers() {
+        let probes = [
+            probe(1, Some("A")),
+            probe(1, Some("B")),
+            probe(2, Some("C")),
+            probe(3, None),
+        ];
+
+        let resolved = resolve_probes(
+            &[selector(2, None), selector(1, Some("B")), selector(3, None)],
+            &probes,
+        )
+        .unwrap();
+
+        assert_eq!(
+            resolved.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["1234:0002:C", "1234:0001:B", "1234:0003"]
+        );
+    }
+
+    #[test]
+    fn reject_unknown_ambiguous_and_duplicate_probes() {
+        let probes = [
+            probe(1, Some("A")),
+            probe(1, Some("B")),
+            probe(2, Some("C")),
+        ];
+
+        assert!(matches!(
+            resolve_probes(&[selector(1, Some("D"))], &probes),
+            Err(GangError::ProbeNotFound(_))
+        ));
+        assert!(matches!(
+            resolve_probes(&[selector(1, None)], &probes),
+            Err(GangError::AmbiguousProbe { serial_numbers, .. })
+                if serial_numbers == vec![Some("A".to_string()), Some("B".to_string())]
+        ));
+        assert!(matches!(
+            resolve_probes(&[selector(2, None), selector(2, Some("C"))], &probes),
+            Err(GangError::DuplicateProbe { .. })
+        ));
+    }
+}
@@ -0,0 +1,516 @@
+//! Reading memory contents from the target, and storing them as BIN, IHEX or ELF files.
+
+use std::collections::BTreeMap;
+use std::ops::{Bound, Range};
+use std::str::FromStr;
+
+use ihex::Record;
+use object::elf::{EM_ARM, EM_RISCV, ET_EXEC, PF_R, PF_W, PF_X, PT_LOAD};
+
+use crate::config::MemoryRegion;
+use crate::elf_writer::{self, Segment};
+use crate::{Architecture, MemoryInterface, Session};
+
+/// The size of the chunks in which the memory is read.
+const READ_CHUNK_SIZE: u32 = 16 * 1024;
+
+/// The maximum number of bytes in an IHEX data record.
+const IHEX_RECORD_SIZE: usize = 16;
+
+/// An error which occurred while reading memory contents or storing them.
+#[derive(thiserror::Error, Debug)]
+pub enum ImageError {
+    /// The memory could not be read.
+    #[error("Failed to read the memory at {address:#010x}")]
+    Read {
+        /// The address of the chunk which could not be read.
+        address: u32,
+        /// The error which occurred while reading.
+        #[source]
+        source: crate::Error,
+    },
+    /// Data was added to the image which overlaps the data added before.
+    #[error("The data at {address:#010x} overlaps the data added before")]
+    Overlap {
+        /// The address of the added data.
+        address: u32,
+    },
+    /// A BIN file can only store a single contiguous range.
+    #[error("The memory contents are not contiguous, they can not be stored as a binary file")]
+    NotContiguous,
+    /// The IHEX file could not be created.
+    #[error("Failed to create the IHEX file")]
+    Ihex(#[from] ihex::WriterError),
+}
+
+/// The formats in which a [`MemoryImage`] can be stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// The memory contents 1:1. Only a single contiguous range can be stored.
+    Bin,
+    /// [Intel HEX](https://en.wikipedia.org/wiki/Intel_HEX) format.
+    Hex,
+    /// [ELF](https://en.wikipedia.org/wiki/Executable_and_Linkable_Format) format, with a
+    /// segment and a section for each contiguous range.
+    Elf,
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_lowercase()[..] {
+            "bin" | "binary" => Ok(ImageFormat::Bin),
+            "hex" | "ihex" | "intelhex" => Ok(ImageFormat::Hex),
+            "elf" => Ok(ImageFormat::Elf),
+            _ => Err(format!("Format '{}' is unknown.", s)),
+        }
+    }
+}
+
+/// A range in which the memory differs from the expected contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageDifference {
+    /// The address of the first differing byte.
+    pub address: u32,
+    /// The expected contents.
+    pub expected: Vec<u8>,
+    /// The actual contents, or `None` if the range is not part of the image.
+    pub actual: Option<Vec<u8>>,
+}
+
+/// Memory contents, stored as contiguous ranges.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryImage {
+    segments: BTreeMap<u32, Vec<u8>>,
+}
+
+impl MemoryImage {
+    /// Creates an empty image.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sorts `ranges`, and merges the ranges which overlap or are adjacent. Empty ranges are
+    /// removed.
+    pub fn merge_ranges(ranges: &[Range<u32>]) -> Vec<Range<u32>> {
+        let mut ranges: Vec<_> = ranges
+            .iter()
+            .filter(|range| !range.is_empty())
+            .cloned()
+            .collect();
+        ranges.sort_by_key(|range| range.start);
+
+        let mut merged: Vec<Range<u32>> = Vec::new();
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+
+        merged
+    }
+
+    /// Reads `ranges` from the target.
+    ///
+    /// The ranges are merged first, see [`MemoryImage::merge_ranges`], so each byte is read once.
+    /// Each range is read by the first core which can access its memory region. The memory is
+    /// read in chunks, and `progress` is called with the number of bytes read after each chunk.
+    pub fn read(
+        session: &mut Session,
+        ranges: &[Range<u32>],
+        mut progress: impl FnMut(u32),
+    ) -> Result<Self, ImageError> {
+        let mut image = Self::new();
+
+        for range in Self::merge_ranges(ranges) {
+            let core_name = match session.target().get_memory_region_by_address(range.start) {
+                Some(MemoryRegion::Ram(region)) => region.cores.first(),
+                Some(MemoryRegion::Generic(region)) => region.cores.first(),
+                Some(MemoryRegion::Nvm(region)) => region.cores.first(),
+                None => None,
+            };
+            let core_index = core_name
+                .and_then(|name| session.target().core_index_by_name(name))
+                .unwrap_or(0);
+
+            let mut core = session
+                .core(core_index)
+                .map_err(|source| ImageError::Read {
+                    address: range.start,
+                    source,
+                })?;
+
+            let mut data = vec![0; range.len()];
+            for (address, chunk) in (range.start..range.end)
+                .step_by(READ_CHUNK_SIZE as usize)
+                .zip(data.chunks_mut(READ_CHUNK_SIZE as usize))
+            {
+                core.read(address, chunk)
+                    .map_err(|source| ImageError::Read { address, source })?;
+                progress(chunk.len() as u32);
+            }
+
+            image.add(range.start, &data)?;
+        }
+
+        Ok(image)
+    }
+
+    /// Adds `data` at `address`. The data must not overlap the data added before.
+    ///
+    /// Data which is adjacent to the data added before is merged into a single range, regardless
+    /// of the order in which it is added.
+    pub fn add(&mut self, address: u32, data: &[u8]) -> Result<(), ImageError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let end = u64::from(address) + data.len() as u64;
+        let previous = self
+            .segments
+            .range(..=address)
+            .next_back()
+            .map(|(start, data)| (*start, u64::from(*start) + data.len() as u64));
+        let next = self
+            .segments
+            .range((Bound::Excluded(address), Bound::Unbounded))
+            .next()
+            .map(|(start, _)| u64::from(*start));
+
+        let overlaps_previous =
+            matches!(previous, Some((_, previous_end)) if previous_end > u64::from(address));
+        let overlaps_next = matches!(next, Some(next_address) if next_address < end);
+        if overlaps_previous || overlaps_next {
+            return Err(ImageError::Overlap { address });
+        }
+
+        let mut data = data.to_vec();
+        if next == Some(end) {
+            if let Some(next_data) = self.segments.remove(&(end as u32)) {
+                data.extend(next_data);
+            }
+        }
+
+        let previous_data = previous
+            .filter(|(_, previous_end)| *previous_end == u64::from(address))
+            .and_then(|(previous_address, _)| self.segments.get_mut(&previous_address));
+        match previous_data {
+            Some(previous_data) => previous_data.extend(data),
+            None => {
+                self.segments.insert(address, data);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the contiguous ranges of the image, ordered by address.
+    pub fn segments(&self) -> impl Iterator<Item = (u32, &[u8])> {
+        self.segments
+            .iter()
+            .map(|(address, data)| (*address, data.as_slice()))
+    }
+
+    /// Returns the byte at `address`, if it is part of the image.
+    fn byte_at(&self, address: u32) -> Option<u8> {
+        let (segment_address, data) = self.segments.range(..=address).next_back()?;
+        data.get((address - segment_address) as usize).copied()
+    }
+
+    /// Compares the image with `expected`, and returns the ranges in which they differ.
+    ///
+    /// Only the ranges contained in `expected` are compared.
+    pub fn differences(&self, expected: &MemoryImage) -> Vec<ImageDifference> {
+        let mut differences: Vec<ImageDifference> = Vec::new();
+
+        for (segment_address, data) in expected.segments() {
+            for (offset, expected_byte) in data.iter().copied().enumerate() {
+                let address = segment_address + offset as u32;
+                let actual_byte = self.byte_at(address);
+                if actual_byte == Some(expected_byte) {
+                    continue;
+                }
+
+                // Extend the previous difference, if it directly precedes this byte.
+                match differences.last_mut() {
+                    Some(difference)
+                        if difference.address + difference.expected.len() as u32 == address
+                            && difference.actual.is_some() == actual_byte.is_some() =>
+                    {
+                        difference.expected.push(expected_byte);
+                        if let (Some(actual), Some(actual_byte)) =
+                            (&mut difference.actual, actual_byte)
+                        {
+                            actual.push(actual_byte);
+                        }
+                    }
+                    _ => differences.push(ImageDifference {
+                        address,
+                        expected: vec![expected_byte],
+                        actual: actual_byte.map(|byte| vec![byte]),
+                    }),
+                }
+            }
+        }
+
+        differences
+    }
+
+    /// Encodes the image in `format`.
+    ///
+    /// The `architecture` is stored in the header of ELF files.
+    pub fn encode(
+        &self,
+        format: ImageFormat,
+        architecture: Architecture,
+    ) -> Result<Vec<u8>, ImageError> {
+        match format {
+            ImageFormat::Bin => self.to_bin(),
+            ImageFormat::Hex => self.to_hex(),
+            ImageFormat::Elf => Ok(self.to_elf(architecture)),
+        }
+    }
+
+    fn to_bin(&self) -> Result<Vec<u8>, ImageError> {
+        let mut segments = self.segments();
+        match (segments.next(), segments.next()) {
+            (Some((_, data)), None) => Ok(data.to_vec()),
+            (None, _) => Ok(Vec::new()),
+            (Some(_), Some(_)) => Err(ImageError::NotContiguous),
+        }
+    }
+
+    fn to_hex(&self) -> Result<Vec<u8>, ImageError> {
+        let mut records = Vec::new();
+        let mut upper_address = None;
+
+        for (segment_address, data) in self.segments() {
+            let mut address = segment_address;
+            let mut data = data;
+
+            while !data.is_empty() {
+                if upper_address != Some(address >> 16) {
+                    upper_address = Some(address >> 16);
+                    records.push(Record::ExtendedLinearAddress((address >> 16) as u16));
+                }
+
+                // Records must not cross a 64 KiB boundary.
+                let remaining_in_block = 0x1_0000 - (address & 0xffff) as usize;
+                let (record_data, rest) =
+                    data.split_at(data.len().min(IHEX_RECORD_SIZE).min(remaining_in_block));
+
+                records.push(Record::Data {
+                    offset: address as u16,
+                    value: record_data.to_vec(),
+                });
+
+                address = address.wrapping_add(record_data.len() as u32);
+                data = rest;
+            }
+        }
+
+        records.push(Record::EndOfFile);
+
+        Ok(ihex::create_object_file_representation(&records)?.into_bytes())
+    }
+
+    fn to_elf(&self, architecture: Architecture) -> Vec<u8> {
+        let machine = match architecture {
+            Architecture::Arm => EM_ARM,
+            Architecture::Riscv => EM_RISCV,
+        };
+
+        let segments: Vec<_> = self
+            .segments()
+            .enumerate()
+            .map(|(index, (address, data))| Segment {
+                segment_type: PT_LOAD,
+                flags: PF_R | PF_W | PF_X,
+                address,
+                data,
+                section: Some(format!(".load{}", index)),
+            })
+            .collect();
+
+        elf_writer::write(ET_EXEC, machine, &segments)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::flashing::download::extract_from_elf;
+
+    fn test_image() -> MemoryImage {
+        let mut image = MemoryImage::new();
+        image.add(0x1000, &[1, 2, 3, 4]).unwrap();
+        image.add(0x1004, &[5, 6]).unwrap();
+        image.add(0x2000, &[7, 8]).unwrap();
+        image
+    }
+
+    #[test]
+    fn merge_adjacent_data() {
+        let image = test_image();
+
+        assert_eq!(
+            image.segments().collect::<Vec<_>>(),
+            vec![(0x1000, &[1, 2, 3, 4, 5, 6][..]), (0x2000, &[7, 8][..])]
+        );
+    }
+
+    #[test]
+    fn merge_unordered_data() {
+        let mut image = MemoryImage::new();
+        image.add(0x1004, &[5, 6]).unwrap();
+        image.add(0x1008, &[7]).unwrap();
+        image.add(0x1000, &[1, 2, 3, 4]).unwrap();
+        image.add(0x1006, &[9, 9]).unwrap();
+
+        assert_eq!(
+            image.segments().collect::<Vec<_>>(),
+            vec![(0x1000, &[1, 2, 3, 4, 5, 6, 9, 9, 7][..])]
+        );
+        assert_eq!(
+            image.encode(ImageFormat::Bin, Architecture::Arm).unwrap(),
+            vec![1, 2, 3, 4, 5, 6, 9, 9, 7]
+        );
+    }
+
+    #[test]
+    fn reject_overlapping_data() {
+        let mut image = test_image();
+
+        for (address, length) in [
+            // Same start as a segment
+            (0x1000, 2),
+            // Overlaps the end of a segment
+            (0x1005, 2),
+            // Overlaps the start of a segment
+            (0x1ffe, 4),
+            // Covers a segment
+            (0x0fff, 0x10),
+        ] {
+            assert!(matches!(
+                image.add(address, &vec![0; length]),
+                Err(ImageError::Overlap { address: error_address }) if error_address == address
+            ));
+        }
+
+        // The image is unchanged.
+        assert_eq!(image, test_image());
+
+        // Empty data never overlaps.
+        image.add(0x1001, &[]).unwrap();
+        assert_eq!(image, test_image());
+    }
+
+    #[test]
+    fn merge_ranges() {
+        assert_eq!(
+            MemoryImage::merge_ranges(&[
+                0x3000..0x3100,
+                0x1000..0x1100,
+                0x1080..0x1200,
+                0x1200..0x1300,
+                0x2000..0x2000,
+                0x1000..0x1010,
+            ]),
+            vec![0x1000..0x1300, 0x3000..0x3100]
+        );
+        assert!(MemoryImage::merge_ranges(&[]).is_empty());
+    }
+
+    #[test]
+    fn report_differences() {
+        let image = test_image();
+
+        let mut expected = MemoryImage::new();
+        expected.add(0x1000, &[1, 9, 9, 4]).unwrap();
+        expected.add(0x1ffe, &[0, 0, 7, 0]).unwrap();
+
+        assert_eq!(
+            image.differences(&expected),
+            vec![
+                ImageDifference {
+                    address: 0x1001,
+                    expected: vec![9, 9],
+                    actual: Some(vec![2, 3]),
+                },
+                ImageDifference {
+                    address: 0x1ffe,
+                    expected: vec![0, 0],
+                    actual: None,
+                },
+                ImageDifference {
+                    address: 0x2001,
+                    expected: vec![0],
+                    actual: Some(vec![8]),
+                },
+            ]
+        );
+        assert!(image.differences(&image).is_empty());
+    }
+
+    #[test]
+    fn encode_bin() {
+        assert!(matches!(
+            test_image().encode(ImageFormat::Bin, Architecture::Arm),
+            Err(ImageError::NotContiguous)
+        ));
+
+        let mut image = MemoryImage::new();
+        image.add(0x1000, &[1, 2, 3]).unwrap();
+        assert_eq!(
+            image.encode(ImageFormat::Bin, Architecture::Arm).unwrap(),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn encode_hex() {
+        let mut image = MemoryImage::new();
+        image.add(0x0800_fffe, &[1, 2, 3]).unwrap();
+
+        let hex = image.encode(ImageFormat::Hex, Architecture::Arm).unwrap();
+        let records = ihex::Reader::new(std::str::from_utf8(&hex).unwrap())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            records,
+            vec![
+                Record::ExtendedLinearAddress(0x0800),
+                Record::Data {
+                    offset: 0xfffe,
+                    value: vec![1, 2],
+                },
+                Record::ExtendedLinearAddress(0x0801),
+                Record::Data {
+                    offset: 0,
+                    value: vec![3],
+                },
+                Record::EndOfFile,
+            ]
+        );
+    }
+
+    #[test]
+    fn elf_round_trip() {
+        let image = test_image();
+        let elf = image.encode(ImageFormat::Elf, Architecture::Arm).unwrap();
+
+        let mut extracted = Vec::new();
+        extract_from_elf(&mut extracted, &elf).unwrap();
+
+        assert_eq!(
+            extracted
+                .iter()
+                .map(|data| (data.address, data.data))
+                .collect::<Vec<_>>(),
+            image.segments().collect::<Vec<_>>()
+        );
+    }
+}
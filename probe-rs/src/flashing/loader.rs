@@ -7,9 +7,11 @@ use std::io::{Read, Seek, SeekFrom};
 use std::ops::Range;
 
 use super::builder::FlashBuilder;
+use super::srec::parse_srec;
+use super::uf2::{check_uf2_addresses, parse_uf2};
 use super::{
     extract_from_elf, BinOptions, DownloadOptions, FileDownloadError, FlashError, FlashProgress,
-    Flasher,
+    Flasher, Format, Uf2Options,
 };
 use crate::memory::MemoryInterface;
 use crate::session::Session;
@@ -74,6 +76,14 @@ impl FlashLoader {
         self.builder.add_data(address, data)
     }
 
+    /// Returns the staged data as contiguous chunks, ordered by address.
+    pub fn data(&self) -> impl Iterator<Item = (u32, &[u8])> {
+        self.builder
+            .data
+            .iter()
+            .map(|(address, data)| (*address, data.as_slice()))
+    }
+
     pub(super) fn get_region_for_address(
         memory_map: &[MemoryRegion],
         address: u32,
@@ -91,6 +101,22 @@ impl FlashLoader {
         None
     }
 
+    /// Reads the data of `file` in the given `format`, and adds it to the loader.
+    /// This does not create and flash loader instructions yet.
+    pub fn load<T: Read + Seek>(
+        &mut self,
+        format: Format,
+        file: &mut T,
+    ) -> Result<(), FileDownloadError> {
+        match format {
+            Format::Bin(options) => self.load_bin_data(file, options),
+            Format::Elf => self.load_elf_data(file),
+            Format::Hex => self.load_hex_data(file),
+            Format::Uf2(options) => self.load_uf2_data(file, options),
+            Format::Srec => self.load_srec_data(file),
+        }
+    }
+
     /// Reads the data from the binary file and adds it to the loader without splitting it into flash instructions yet.
     pub fn load_bin_data<T: Read + Seek>(
         &mut self,
@@ -147,6 +173,40 @@ impl FlashLoader {
         Ok(())
     }
 
+    /// Reads the blocks of the UF2 file and adds them as loadable data blocks to the loader.
+    /// This does not create and flash loader instructions yet.
+    ///
+    /// Blocks which are not meant for the main flash, and blocks of other families than the one selected in `options`, are skipped.
+    /// The other blocks have to be in the flash memory of the target.
+    pub fn load_uf2_data<T: Read>(
+        &mut self,
+        file: &mut T,
+        options: Uf2Options,
+    ) -> Result<(), FileDownloadError> {
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+
+        let blocks = parse_uf2(&data, options.family_id)?;
+        check_uf2_addresses(&blocks, &self.memory_map)?;
+
+        for block in blocks {
+            self.add_data(block.address, block.data)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the data records of the S-record file and adds them as loadable data blocks to the loader.
+    /// This does not create and flash loader instructions yet.
+    pub fn load_srec_data<T: Read>(&mut self, file: &mut T) -> Result<(), FileDownloadError> {
+        let mut data = String::new();
+        file.read_to_string(&mut data)?;
+
+        for record in parse_srec(&data)? {
+            self.add_data(record.address, &record.data)?;
+        }
+        Ok(())
+    }
+
     /// Prepares the data sections that have to be loaded into flash from an ELF file.
     /// This will validate the ELF file and transform all its data into sections but no flash loader commands yet.
     pub fn load_elf_data<T: Read>(&mut self, file: &mut T) -> Result<(), FileDownloadError> {
@@ -303,6 +363,9 @@ impl FlashLoader {
                 .position(|c| c.name == core_name)
                 .unwrap();
             let mut flasher = Flasher::new(session, core, &algo)?;
+            if let Some(crc_timeout_per_kib) = options.crc_timeout_per_kib {
+                flasher.set_crc_timeout_per_kib(crc_timeout_per_kib);
+            }
 
             let mut do_chip_erase = options.do_chip_erase;
 
@@ -343,8 +406,15 @@ impl FlashLoader {
                     options.keep_unwritten_bytes,
                     do_use_double_buffering,
                     options.skip_erase || do_chip_erase,
+                    options.skip_unchanged_sectors && !do_chip_erase,
                     options.progress.unwrap_or(&FlashProgress::new(|_| {})),
                 )?;
+
+                // Verify while the flash algorithm is loaded, so its CRC32 routine can be used.
+                if options.verify {
+                    log::debug!("    verifying region");
+                    flasher.verify(&region, &self.builder)?;
+                }
             }
         }
 
@@ -408,7 +478,8 @@ impl FlashLoader {
                 let core_name = match associated_region {
                     MemoryRegion::Ram(r) => &r.cores,
                     MemoryRegion::Generic(r) => &r.cores,
-                    MemoryRegion::Nvm(r) => &r.cores,
+                    // The flash was already verified after it was programmed.
+                    MemoryRegion::Nvm(_) => continue,
                 }
                 .first()
                 .unwrap();
@@ -2,9 +2,13 @@
 //!
 //! This modules provides a means to do flash unlocking, erasing and programming.
 //!
-//! It provides a convenient highlevel interface that can flash an ELF, IHEX or BIN file
+//! It provides a convenient highlevel interface that can flash an ELF, IHEX, UF2, S-record or BIN file
 //! as well as a lower level block based interface.
 //!
+//! Memory contents can be read back into BIN, IHEX or ELF files with [`MemoryImage`].
+//!
+//! Several targets of the same type can be programmed in parallel with [`gang_program`].
+//!
 //!
 //! ## Examples
 //!
@@ -44,22 +48,30 @@
 //!
 
 mod builder;
+mod crc;
 mod download;
 mod erase;
 mod error;
 mod flash_algorithm;
 mod flasher;
+mod gang;
+mod image;
 mod loader;
 mod progress;
+mod srec;
+mod uf2;
 mod visualizer;
 
 use builder::*;
 use flasher::*;
 
+pub use crc::*;
 pub use download::*;
 pub use erase::*;
 pub use error::*;
 pub use flash_algorithm::*;
+pub use gang::*;
+pub use image::*;
 pub use loader::*;
 pub use progress::*;
 pub use visualizer::*;
@@ -61,6 +61,16 @@ impl FlashProgress {
         self.emit(ProgressEvent::SectorErased { size, time });
     }
 
+    /// Signalize that a sector was not erased, because its contents are unchanged.
+    pub(super) fn sector_skipped(&self, size: u32) {
+        self.emit(ProgressEvent::SectorSkipped { size });
+    }
+
+    /// Signalize that a page was not programmed, because its contents are unchanged.
+    pub(super) fn page_skipped(&self, size: u32) {
+        self.emit(ProgressEvent::PageSkipped { size });
+    }
+
     /// Signalize that the page filling procedure has made progress.
     pub(super) fn page_filled(&self, size: u32, time: Duration) {
         self.emit(ProgressEvent::PageFilled { size, time });
@@ -107,12 +117,14 @@ impl FlashProgress {
 /// * `PageFilled` for every page
 /// * `FinishedFilling`
 /// * `StartedErasing`
-/// * `SectorErased` for every sector
+/// * `SectorErased` for every sector, or `SectorSkipped` if its contents are unchanged
 /// * `FinishedErasing`
 /// * `StartedProgramming`
-/// * `PageProgrammed` for every page
+/// * `PageProgrammed` for every page, or `PageSkipped` if its contents are unchanged
 /// * `FinishedProgramming`
 ///
+/// Sectors and pages are only skipped if [`DownloadOptions::skip_unchanged_sectors`](super::DownloadOptions::skip_unchanged_sectors) is set.
+///
 /// If an erorr occurs in any stage, one of the `Failed*` event will be returned,
 /// and no further events will be returned.
 #[derive(Debug)]
@@ -147,6 +159,11 @@ pub enum ProgressEvent {
         /// The time it took to erase this sector.
         time: Duration,
     },
+    /// A sector was not erased, because its contents are unchanged.
+    SectorSkipped {
+        /// The size of the sector in bytes.
+        size: u32,
+    },
     /// Erasing of the flash has failed.
     FailedErasing,
     /// Erasing of the flash has finished successfully.
@@ -160,6 +177,11 @@ pub enum ProgressEvent {
         /// The time it took to program this page.
         time: Duration,
     },
+    /// A flash page was not programmed, because its contents are unchanged.
+    PageSkipped {
+        /// The size of this page in bytes.
+        size: u32,
+    },
     /// Programming of the flash failed.
     FailedProgramming,
     /// Programming of the flash has finished successfully.
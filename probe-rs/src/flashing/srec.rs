@@ -0,0 +1,143 @@
+//! Parsing of [Motorola S-record](https://en.wikipedia.org/wiki/SREC_(file_format)) files.
+
+use super::FileDownloadError;
+
+/// A data record of an S-record file.
+#[derive(Debug, PartialEq)]
+pub(super) struct SrecData {
+    pub(super) address: u32,
+    pub(super) data: Vec<u8>,
+}
+
+/// Returns the data records of `file`. Header, count and start address records are checked, but
+/// otherwise ignored.
+pub(super) fn parse_srec(file: &str) -> Result<Vec<SrecData>, FileDownloadError> {
+    let mut records = Vec::new();
+
+    for (index, line) in file.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let invalid = |reason| FileDownloadError::InvalidSrec {
+            line: index + 1,
+            reason,
+        };
+
+        let record_type = match line.strip_prefix('S').map(str::as_bytes) {
+            Some([record_type, ..]) => *record_type,
+            _ => return Err(invalid("the record does not start with 'S'")),
+        };
+
+        let bytes = line
+            .get(2..)
+            .and_then(parse_hex)
+            .ok_or_else(|| invalid("the record is not hexadecimal"))?;
+
+        // The count includes the address, the data and the checksum.
+        match bytes.split_first() {
+            Some((&count, rest)) if count as usize == rest.len() && count > 0 => {}
+            _ => return Err(invalid("the byte count does not match the record")),
+        }
+
+        let checksum = bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+        if checksum != 0xff {
+            return Err(invalid("the checksum is wrong"));
+        }
+
+        let address_size = match record_type {
+            b'0' | b'1' | b'5' | b'9' => 2,
+            b'2' | b'6' | b'8' => 3,
+            b'3' | b'7' => 4,
+            _ => return Err(invalid("the record type is unknown")),
+        };
+
+        // Skip the count, and drop the checksum.
+        let fields = &bytes[1..bytes.len() - 1];
+        if fields.len() < address_size {
+            return Err(invalid("the record is too short for its address"));
+        }
+
+        let (address, data) = fields.split_at(address_size);
+        let address = address
+            .iter()
+            .fold(0u32, |address, byte| address << 8 | u32::from(*byte));
+
+        if matches!(record_type, b'1' | b'2' | b'3') {
+            records.push(SrecData {
+                address,
+                data: data.to_vec(),
+            });
+        }
+    }
+
+    Ok(records)
+}
+
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_records() {
+        let file = "S00F000068656C6C6F202020202000003C\n\
+                    S11F00007C0802A6900100049421FFF07C6C1B787C8C23783C6000003863000026\n\
+                    S2080100000102030AE6\n\
+                    S309000100000102030AE5\n\
+                    S5030003F9\n\
+                    S9030000FC\n";
+
+        let records = parse_srec(file).unwrap();
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].address, 0x0000);
+        assert_eq!(records[0].data.len(), 28);
+        assert_eq!(
+            records[1],
+            SrecData {
+                address: 0x010000,
+                data: vec![0x01, 0x02, 0x03, 0x0a]
+            }
+        );
+        assert_eq!(
+            records[2],
+            SrecData {
+                address: 0x00010000,
+                data: vec![0x01, 0x02, 0x03, 0x0a]
+            }
+        );
+    }
+
+    #[test]
+    fn reject_invalid_records() {
+        for (record, line) in [
+            ("S2080100000102030AE1", 1),
+            ("S2090100000102030AE6", 1),
+            ("S4030000FC", 1),
+            ("\nX9030000FC", 2),
+            ("S9030000F", 1),
+            ("S2030000FC", 1),
+        ] {
+            assert!(
+                matches!(
+                    parse_srec(record),
+                    Err(FileDownloadError::InvalidSrec { line: l, .. }) if l == line
+                ),
+                "{}",
+                record
+            );
+        }
+    }
+}
@@ -0,0 +1,291 @@
+//! Parsing of [UF2](https://github.com/microsoft/uf2) files.
+
+use super::FileDownloadError;
+use probe_rs_target::MemoryRegion;
+
+/// The size of each block of a UF2 file.
+const BLOCK_SIZE: usize = 512;
+/// The maximum payload size of a block.
+const MAX_PAYLOAD_SIZE: u32 = 476;
+
+const MAGIC_START0: u32 = 0x0A32_4655;
+const MAGIC_START1: u32 = 0x9E5D_5157;
+const MAGIC_END: u32 = 0x0AB1_6F30;
+
+/// The block is not meant for the main flash, and has to be skipped.
+const FLAG_NOT_MAIN_FLASH: u32 = 0x0000_0001;
+/// The block is part of a file container, the family ID field contains the file size.
+const FLAG_FILE_CONTAINER: u32 = 0x0000_1000;
+/// The family ID field contains the family ID.
+const FLAG_FAMILY_ID_PRESENT: u32 = 0x0000_2000;
+
+/// A block of a UF2 file, which has to be programmed.
+#[derive(Debug, PartialEq)]
+pub(super) struct Uf2Block<'data> {
+    /// The number of the block in the file.
+    pub(super) index: usize,
+    pub(super) address: u32,
+    pub(super) data: &'data [u8],
+}
+
+/// Returns the blocks of `file` which have to be programmed.
+///
+/// If `family_id` is given, only the blocks of this family are returned. Otherwise the file may
+/// only contain blocks of a single family.
+pub(super) fn parse_uf2(
+    file: &[u8],
+    family_id: Option<u32>,
+) -> Result<Vec<Uf2Block<'_>>, FileDownloadError> {
+    if file.len() % BLOCK_SIZE != 0 {
+        return Err(FileDownloadError::InvalidUf2 {
+            block: file.len() / BLOCK_SIZE,
+            reason: "the file size is not a multiple of the block size",
+        });
+    }
+
+    let mut families = Vec::new();
+    let mut blocks = Vec::new();
+
+    for (index, block) in file.chunks_exact(BLOCK_SIZE).enumerate() {
+        let invalid = |reason| FileDownloadError::InvalidUf2 {
+            block: index,
+            reason,
+        };
+        let word = |offset: usize| {
+            u32::from_le_bytes([
+                block[offset],
+                block[offset + 1],
+                block[offset + 2],
+                block[offset + 3],
+            ])
+        };
+
+        if word(0) != MAGIC_START0 || word(4) != MAGIC_START1 || word(BLOCK_SIZE - 4) != MAGIC_END {
+            return Err(invalid("the magic numbers are wrong"));
+        }
+
+        let flags = word(8);
+        let address = word(12);
+        let payload_size = word(16);
+        let block_number = word(20);
+        let block_count = word(24);
+
+        if payload_size > MAX_PAYLOAD_SIZE {
+            return Err(invalid("the payload size exceeds the block"));
+        }
+        if block_number >= block_count {
+            return Err(invalid("the block number exceeds the number of blocks"));
+        }
+        if address.checked_add(payload_size).is_none() {
+            return Err(invalid("the payload exceeds the address space"));
+        }
+
+        if flags & (FLAG_NOT_MAIN_FLASH | FLAG_FILE_CONTAINER) != 0 {
+            log::debug!("Skipping UF2 block {}, which is not for the flash", index);
+            continue;
+        }
+
+        let block_family_id = (flags & FLAG_FAMILY_ID_PRESENT != 0).then(|| word(28));
+        if let Some(block_family_id) = block_family_id {
+            if !families.contains(&block_family_id) {
+                families.push(block_family_id);
+            }
+        }
+
+        match (family_id, block_family_id) {
+            (Some(family_id), Some(block_family_id)) if family_id != block_family_id => continue,
+            _ => {}
+        }
+
+        blocks.push(Uf2Block {
+            index,
+            address,
+            data: &block[32..32 + payload_size as usize],
+        });
+    }
+
+    match family_id {
+        Some(family_id) if !families.is_empty() && !families.contains(&family_id) => {
+            Err(FileDownloadError::Uf2FamilyNotFound {
+                family_id,
+                families,
+            })
+        }
+        None if families.len() > 1 => Err(FileDownloadError::Uf2FamilyAmbiguous(families)),
+        _ => Ok(blocks),
+    }
+}
+
+/// Checks that `blocks` are in the flash memory of `memory_map`.
+///
+/// A block may cross the boundary of two adjacent flash regions.
+pub(super) fn check_uf2_addresses(
+    blocks: &[Uf2Block],
+    memory_map: &[MemoryRegion],
+) -> Result<(), FileDownloadError> {
+    for block in blocks {
+        let end = block.address + block.data.len() as u32;
+
+        let mut address = block.address;
+        while address < end {
+            let region_end = memory_map.iter().find_map(|region| match region {
+                MemoryRegion::Nvm(region) if region.range.contains(&address) => {
+                    Some(region.range.end)
+                }
+                _ => None,
+            });
+
+            match region_end {
+                Some(region_end) => address = region_end,
+                None => {
+                    return Err(FileDownloadError::InvalidUf2 {
+                        block: block.index,
+                        reason: "the block is outside of the flash memory of the target",
+                    })
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn block(address: u32, family_id: Option<u32>, data: &[u8]) -> Vec<u8> {
+        let mut block = vec![0; BLOCK_SIZE];
+        let flags = if family_id.is_some() {
+            FLAG_FAMILY_ID_PRESENT
+        } else {
+            0
+        };
+
+        for (offset, word) in [
+            (0, MAGIC_START0),
+            (4, MAGIC_START1),
+            (8, flags),
+            (12, address),
+            (16, data.len() as u32),
+            (20, 0),
+            (24, 1),
+            (28, family_id.unwrap_or(0)),
+            (BLOCK_SIZE - 4, MAGIC_END),
+        ] {
+            block[offset..offset + 4].copy_from_slice(&word.to_le_bytes());
+        }
+        block[32..32 + data.len()].copy_from_slice(data);
+
+        block
+    }
+
+    #[test]
+    fn parse_blocks() {
+        let mut file = block(0x1000, None, &[1, 2, 3]);
+        file.extend(block(0x2000, None, &[4]));
+
+        assert_eq!(
+            parse_uf2(&file, None).unwrap(),
+            vec![
+                Uf2Block {
+                    index: 0,
+                    address: 0x1000,
+                    data: &[1, 2, 3]
+                },
+                Uf2Block {
+                    index: 1,
+                    address: 0x2000,
+                    data: &[4]
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn filter_families() {
+        let mut file = block(0x1000, Some(0xe48bff56), &[1]);
+        file.extend(block(0x2000, Some(0xada52840), &[2]));
+
+        assert_eq!(
+            parse_uf2(&file, Some(0xada52840)).unwrap(),
+            vec![Uf2Block {
+                index: 1,
+                address: 0x2000,
+                data: &[2]
+            }]
+        );
+        assert!(matches!(
+            parse_uf2(&file, None),
+            Err(FileDownloadError::Uf2FamilyAmbiguous(_))
+        ));
+        assert!(matches!(
+            parse_uf2(&file, Some(0x12345678)),
+            Err(FileDownloadError::Uf2FamilyNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn reject_invalid_blocks() {
+        let mut file = block(0x1000, None, &[1]);
+        file[0] = 0;
+        assert!(matches!(
+            parse_uf2(&file, None),
+            Err(FileDownloadError::InvalidUf2 { block: 0, .. })
+        ));
+
+        let mut file = block(0x1000, None, &[1]);
+        file[16..20].copy_from_slice(&500u32.to_le_bytes());
+        assert!(matches!(
+            parse_uf2(&file, None),
+            Err(FileDownloadError::InvalidUf2 { block: 0, .. })
+        ));
+
+        let mut file = block(0xffff_ffff, None, &[1, 2]);
+        assert!(parse_uf2(&file, None).is_err());
+
+        file.truncate(100);
+        assert!(parse_uf2(&file, None).is_err());
+    }
+
+    #[test]
+    fn check_addresses_against_memory_map() {
+        use probe_rs_target::{NvmRegion, RamRegion};
+
+        let nvm = |range| {
+            MemoryRegion::Nvm(NvmRegion {
+                name: None,
+                range,
+                is_boot_memory: false,
+                cores: Vec::new(),
+            })
+        };
+        let memory_map = [
+            nvm(0x0000..0x1000),
+            nvm(0x1000..0x2000),
+            MemoryRegion::Ram(RamRegion {
+                name: None,
+                range: 0x2000_0000..0x2000_1000,
+                is_boot_memory: false,
+                cores: Vec::new(),
+            }),
+        ];
+        let block = |index, address| Uf2Block {
+            index,
+            address,
+            data: &[0; 256],
+        };
+
+        // Blocks may cross adjacent flash regions.
+        assert!(check_uf2_addresses(&[block(0, 0x0000), block(1, 0x0f80)], &memory_map).is_ok());
+
+        assert!(matches!(
+            check_uf2_addresses(&[block(0, 0x0000), block(1, 0x1f80)], &memory_map),
+            Err(FileDownloadError::InvalidUf2 { block: 1, .. })
+        ));
+        assert!(matches!(
+            check_uf2_addresses(&[block(0, 0x2000_0000)], &memory_map),
+            Err(FileDownloadError::InvalidUf2 { block: 0, .. })
+        ));
+    }
+}
@@ -74,6 +74,7 @@ pub mod config;
 #[warn(missing_docs)]
 mod core;
 pub mod debug;
+mod elf_writer;
 mod error;
 #[warn(missing_docs)]
 pub mod flashing;
@@ -82,13 +83,16 @@ mod memory;
 #[warn(missing_docs)]
 mod probe;
 #[warn(missing_docs)]
+pub mod semihosting;
+#[warn(missing_docs)]
 mod session;
 
 pub use crate::config::{CoreType, Target};
 pub use crate::core::{
-    Architecture, BreakpointId, CommunicationInterface, Core, CoreInformation, CoreInterface,
-    CoreRegister, CoreRegisterAddress, CoreState, CoreStatus, HaltReason, RegisterFile,
-    SpecificCoreState,
+    Architecture, BreakpointId, CommunicationInterface, Core, CoreDump, CoreDumpError,
+    CoreInformation, CoreInterface, CoreRegister, CoreRegisterAddress, CoreState, CoreStatus,
+    HaltReason, RegisterFile, RegisterValue, SpecificCoreState, VectorCatchCondition, Watchpoint,
+    WatchpointKind,
 };
 pub use crate::error::Error;
 pub use crate::memory::{Memory, MemoryInterface};
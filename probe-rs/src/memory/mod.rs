@@ -15,6 +15,11 @@ use anyhow::anyhow;
 use anyhow::Result;
 
 /// An interface to be implemented for drivers that allow target memory access.
+///
+/// The 8 and 32 bit accesses take 32 bit addresses, so they only access the lower 4 GiB of the
+/// address space. Memory above 4 GiB, e.g. of ARMv8-A cores, can only be accessed with the 64 bit
+/// accesses, and only if the interface
+/// [supports native 64 bit accesses](MemoryInterface::supports_native_64bit_access).
 pub trait MemoryInterface {
     /// Read a 32bit word of at `address`.
     ///
@@ -90,6 +95,59 @@ pub trait MemoryInterface {
     /// Write a block of 8bit words at `address`.
     fn write_8(&mut self, address: u32, data: &[u8]) -> Result<(), error::Error>;
 
+    /// Returns `true` if the interface can access memory above 4 GiB with native 64 bit accesses.
+    ///
+    /// If this returns `false`, the 64 bit methods are emulated with two 32 bit accesses,
+    /// and only the lower 4 GiB of the address space can be accessed.
+    fn supports_native_64bit_access(&mut self) -> bool {
+        false
+    }
+
+    /// Read a 64bit word of at `address`.
+    ///
+    /// The address where the read should be performed at has to be word aligned.
+    fn read_word_64(&mut self, address: u64) -> Result<u64, error::Error> {
+        let mut data = [0u32; 2];
+        self.read_32(address_32(address, 8)?, &mut data)?;
+
+        Ok(u64::from(data[0]) | (u64::from(data[1]) << 32))
+    }
+
+    /// Read a block of 64bit words at `address`.
+    ///
+    /// The number of words read is `data.len()`.
+    /// The address where the read should be performed at has to be word aligned.
+    fn read_64(&mut self, address: u64, data: &mut [u64]) -> Result<(), error::Error> {
+        let mut buffer = vec![0u32; data.len() * 2];
+        self.read_32(address_32(address, buffer.len() * 4)?, &mut buffer)?;
+
+        for (value, words) in data.iter_mut().zip(buffer.chunks_exact(2)) {
+            *value = u64::from(words[0]) | (u64::from(words[1]) << 32);
+        }
+
+        Ok(())
+    }
+
+    /// Write a 64bit word at `address`.
+    ///
+    /// The address where the write should be performed at has to be word aligned.
+    fn write_word_64(&mut self, address: u64, data: u64) -> Result<(), error::Error> {
+        self.write_32(address_32(address, 8)?, &[data as u32, (data >> 32) as u32])
+    }
+
+    /// Write a block of 64bit words at `address`.
+    ///
+    /// The number of words written is `data.len()`.
+    /// The address where the write should be performed at has to be word aligned.
+    fn write_64(&mut self, address: u64, data: &[u64]) -> Result<(), error::Error> {
+        let buffer: Vec<u32> = data
+            .iter()
+            .flat_map(|value| [*value as u32, (*value >> 32) as u32])
+            .collect();
+
+        self.write_32(address_32(address, buffer.len() * 4)?, &buffer)
+    }
+
     /// Flush any outstanding operations.
     ///
     /// For performance, debug probe implementations may choose to batch writes;
@@ -99,6 +157,19 @@ pub trait MemoryInterface {
     fn flush(&mut self) -> Result<(), error::Error>;
 }
 
+/// Converts a 64 bit address for an access of `length` bytes to a 32 bit address.
+///
+/// Used by the default implementations of the 64 bit accesses of [`MemoryInterface`].
+fn address_32(address: u64, length: usize) -> Result<u32, error::Error> {
+    match address.checked_add(length as u64) {
+        Some(end) if end <= 1 << 32 => Ok(address as u32),
+        _ => Err(error::Error::Other(anyhow!(
+            "Address {:#x} is outside of the 32 bit address space of this memory interface",
+            address
+        ))),
+    }
+}
+
 impl<T> MemoryInterface for &mut T
 where
     T: MemoryInterface,
@@ -135,6 +206,26 @@ where
         (*self).write_8(address, data)
     }
 
+    fn supports_native_64bit_access(&mut self) -> bool {
+        (*self).supports_native_64bit_access()
+    }
+
+    fn read_word_64(&mut self, address: u64) -> Result<u64, error::Error> {
+        (*self).read_word_64(address)
+    }
+
+    fn read_64(&mut self, address: u64, data: &mut [u64]) -> Result<(), error::Error> {
+        (*self).read_64(address, data)
+    }
+
+    fn write_word_64(&mut self, address: u64, data: u64) -> Result<(), error::Error> {
+        (*self).write_word_64(address, data)
+    }
+
+    fn write_64(&mut self, address: u64, data: &[u64]) -> Result<(), error::Error> {
+        (*self).write_64(address, data)
+    }
+
     fn flush(&mut self) -> Result<(), error::Error> {
         (*self).flush()
     }
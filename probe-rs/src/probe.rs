@@ -419,6 +419,13 @@ impl Probe {
         self.inner.speed_khz()
     }
 
+    /// Get the transport protocol which is currently used by the probe.
+    ///
+    /// Not all probes report this, in which case `None` is returned.
+    pub fn protocol(&self) -> Option<WireProtocol> {
+        self.inner.active_protocol()
+    }
+
     /// Check if the probe has an interface to
     /// debug ARM chips.
     pub fn has_arm_interface(&self) -> bool {
@@ -613,6 +620,13 @@ pub trait DebugProbe: Send + fmt::Debug {
     fn get_target_voltage(&mut self) -> Result<Option<f32>, DebugProbeError> {
         Ok(None)
     }
+
+    /// Get the transport protocol which is currently used by the probe.
+    ///
+    /// Returns `None` if the probe does not report this, or if no protocol has been selected yet.
+    fn active_protocol(&self) -> Option<WireProtocol> {
+        None
+    }
 }
 
 /// Denotes the type of a given [`DebugProbe`].
@@ -786,11 +800,33 @@ impl fmt::Display for DebugProbeSelector {
     }
 }
 
+/// Access to a probe driver as a [`DebugProbe`] trait object.
+///
+/// This is implemented for all probe drivers, so a `dyn JTAGAccess` or a `dyn DapProbe`
+/// can be used as a `dyn DebugProbe`.
+pub trait AsDebugProbe {
+    /// Returns a reference to the probe driver as a [`DebugProbe`].
+    fn as_debug_probe(&self) -> &dyn DebugProbe;
+
+    /// Returns a mutable reference to the probe driver as a [`DebugProbe`].
+    fn as_debug_probe_mut(&mut self) -> &mut dyn DebugProbe;
+}
+
+impl<T: DebugProbe> AsDebugProbe for T {
+    fn as_debug_probe(&self) -> &dyn DebugProbe {
+        self
+    }
+
+    fn as_debug_probe_mut(&mut self) -> &mut dyn DebugProbe {
+        self
+    }
+}
+
 /// Low-Level Access to the JTAG protocol
 ///
 /// This trait should be implemented by all probes which offer low-level access to
 /// the JTAG protocol, i.e. directo control over the bytes sent and received.
-pub trait JTAGAccess: DebugProbe {
+pub trait JTAGAccess: DebugProbe + AsDebugProbe {
     fn read_register(&mut self, address: u32, len: u32) -> Result<Vec<u8>, DebugProbeError>;
 
     /// For Riscv, and possibly other interfaces, the JTAG interface has to remain in
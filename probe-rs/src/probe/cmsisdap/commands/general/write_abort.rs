@@ -0,0 +1,36 @@
+use super::super::{CommandId, Request, SendError, Status};
+
+use scroll::{Pwrite, LE};
+
+/// The DAP_WriteABORT command writes an abort request to the CoreSight ABORT register.
+///
+/// In JTAG mode the ABORT register is not accessible through DAP_Transfer,
+/// so this command has to be used instead.
+#[derive(Clone, Copy, Debug)]
+pub struct WriteAbortRequest {
+    /// Zero based device index of the selected JTAG device. For SWD mode the value is ignored.
+    pub(crate) dap_index: u8,
+    /// Value which is written to the ABORT register.
+    pub(crate) abort: u32,
+}
+
+impl Request for WriteAbortRequest {
+    const COMMAND_ID: CommandId = CommandId::WriteAbort;
+
+    type Response = WriteAbortResponse;
+
+    fn to_bytes(&self, buffer: &mut [u8]) -> Result<usize, SendError> {
+        buffer[0] = self.dap_index;
+        buffer
+            .pwrite_with(self.abort, 1, LE)
+            .expect("Buffer for CMSIS-DAP command is too small. This is a bug, please report it.");
+        Ok(5)
+    }
+
+    fn from_bytes(&self, buffer: &[u8]) -> Result<Self::Response, SendError> {
+        Ok(WriteAbortResponse(Status::from_byte(buffer[0])?))
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct WriteAbortResponse(pub(crate) Status);
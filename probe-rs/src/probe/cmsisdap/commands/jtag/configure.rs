@@ -0,0 +1,43 @@
+/// Implementation of the DAP_JTAG_Configure command
+///
+use super::super::{CmsisDapError, CommandId, Request, SendError, Status};
+
+/// The DAP_JTAG_Configure command sets the JTAG device chain information
+/// for communication with the DAP_Transfer and DAP_TransferBlock commands.
+#[derive(Clone, Debug)]
+pub struct ConfigureRequest {
+    /// The IR length of each device in the chain, in scan chain order.
+    ir_lengths: Vec<u8>,
+}
+
+impl ConfigureRequest {
+    pub(crate) fn new(ir_lengths: Vec<u8>) -> Result<ConfigureRequest, CmsisDapError> {
+        // The device count is transmitted as a single byte.
+        if ir_lengths.len() > u8::MAX as usize {
+            return Err(CmsisDapError::TooMuchData);
+        }
+
+        Ok(ConfigureRequest { ir_lengths })
+    }
+}
+
+impl Request for ConfigureRequest {
+    const COMMAND_ID: CommandId = CommandId::JtagConfigure;
+
+    type Response = ConfigureResponse;
+
+    fn to_bytes(&self, buffer: &mut [u8]) -> Result<usize, SendError> {
+        buffer[0] = self.ir_lengths.len() as u8;
+        buffer[1..1 + self.ir_lengths.len()].copy_from_slice(&self.ir_lengths);
+
+        // device count + IR length of each device
+        Ok(1 + self.ir_lengths.len())
+    }
+
+    fn from_bytes(&self, buffer: &[u8]) -> Result<Self::Response, SendError> {
+        Ok(ConfigureResponse(Status::from_byte(buffer[0])?))
+    }
+}
+
+#[derive(Debug)]
+pub struct ConfigureResponse(pub(crate) Status);
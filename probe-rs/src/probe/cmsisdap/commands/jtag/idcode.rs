@@ -0,0 +1,46 @@
+/// Implementation of the DAP_JTAG_IDCODE command
+///
+use super::super::{CommandId, Request, SendError, Status};
+
+use scroll::{Pread, LE};
+
+/// The DAP_JTAG_IDCODE command reads the IDCODE register of
+/// the JTAG device with the given index in the scan chain.
+///
+/// The chain has to be configured with the DAP_JTAG_Configure command first.
+#[derive(Clone, Copy, Debug)]
+pub struct IdCodeRequest {
+    /// Zero based index of the JTAG device in the scan chain.
+    pub(crate) dap_index: u8,
+}
+
+impl Request for IdCodeRequest {
+    const COMMAND_ID: CommandId = CommandId::JtagIdcode;
+
+    type Response = IdCodeResponse;
+
+    fn to_bytes(&self, buffer: &mut [u8]) -> Result<usize, SendError> {
+        buffer[0] = self.dap_index;
+        Ok(1)
+    }
+
+    fn from_bytes(&self, buffer: &[u8]) -> Result<Self::Response, SendError> {
+        let status = Status::from_byte(buffer[0])?;
+
+        if buffer.len() < 5 {
+            return Err(SendError::NotEnoughData);
+        }
+
+        let idcode = buffer
+            .pread_with(1, LE)
+            .map_err(|_| SendError::NotEnoughData)?;
+
+        Ok(IdCodeResponse { status, idcode })
+    }
+}
+
+#[derive(Debug)]
+pub struct IdCodeResponse {
+    pub(crate) status: Status,
+    pub(crate) idcode: u32,
+}
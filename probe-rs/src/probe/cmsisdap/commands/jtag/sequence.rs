@@ -0,0 +1,148 @@
+/// Implementation of the DAP_JTAG_Sequence command
+///
+use super::super::{CmsisDapError, CommandId, Request, SendError, Status};
+
+/// A single JTAG sequence, which clocks up to 64 TCK cycles
+/// with a fixed TMS value.
+#[derive(Clone, Copy, Debug)]
+pub struct JtagSequence {
+    /// Number of TCK cycles, 1..=64.
+    tck_cycles: u8,
+    /// Capture the TDO data during the sequence.
+    tdo_capture: bool,
+    /// Value of TMS during the sequence.
+    tms: bool,
+    /// TDI data, LSB first.
+    data: [u8; 8],
+}
+
+impl JtagSequence {
+    pub(crate) fn new(
+        tck_cycles: u8,
+        tdo_capture: bool,
+        tms: bool,
+        data: [u8; 8],
+    ) -> Result<JtagSequence, CmsisDapError> {
+        if tck_cycles == 0 || tck_cycles > 64 {
+            return Err(CmsisDapError::TooMuchData);
+        }
+
+        Ok(JtagSequence {
+            tck_cycles,
+            tdo_capture,
+            tms,
+            data,
+        })
+    }
+
+    /// Number of data bytes required for the TDI and TDO data of this sequence.
+    pub(crate) fn data_len(&self) -> usize {
+        (self.tck_cycles as usize + 7) / 8
+    }
+
+    /// Number of bytes this sequence occupies in a request.
+    pub(crate) fn request_len(&self) -> usize {
+        1 + self.data_len()
+    }
+
+    /// Number of bytes this sequence occupies in the response.
+    pub(crate) fn response_len(&self) -> usize {
+        if self.tdo_capture {
+            self.data_len()
+        } else {
+            0
+        }
+    }
+
+    fn info_byte(&self) -> u8 {
+        // A value of 0 in the cycle count field means 64 cycles.
+        (self.tck_cycles & 0x3f)
+            | (if self.tms { 1 } else { 0 }) << 6
+            | (if self.tdo_capture { 1 } else { 0 }) << 7
+    }
+}
+
+/// The DAP_JTAG_Sequence command generates TDI, TMS and
+/// captures TDO for multiple JTAG sequences.
+#[derive(Clone, Debug)]
+pub struct SequenceRequest {
+    sequences: Vec<JtagSequence>,
+}
+
+impl SequenceRequest {
+    pub(crate) fn new(sequences: Vec<JtagSequence>) -> Result<SequenceRequest, CmsisDapError> {
+        // The sequence count is transmitted as a single byte.
+        if sequences.is_empty() || sequences.len() > u8::MAX as usize {
+            return Err(CmsisDapError::TooMuchData);
+        }
+
+        Ok(SequenceRequest { sequences })
+    }
+}
+
+impl Request for SequenceRequest {
+    const COMMAND_ID: CommandId = CommandId::JtagSequence;
+
+    type Response = SequenceResponse;
+
+    fn to_bytes(&self, buffer: &mut [u8]) -> Result<usize, SendError> {
+        buffer[0] = self.sequences.len() as u8;
+
+        let mut offset = 1;
+
+        for sequence in &self.sequences {
+            let data_len = sequence.data_len();
+
+            buffer[offset] = sequence.info_byte();
+            buffer[offset + 1..offset + 1 + data_len].copy_from_slice(&sequence.data[..data_len]);
+
+            offset += sequence.request_len();
+        }
+
+        Ok(offset)
+    }
+
+    fn from_bytes(&self, buffer: &[u8]) -> Result<Self::Response, SendError> {
+        let status = Status::from_byte(buffer[0])?;
+
+        let tdo_len: usize = self.sequences.iter().map(|s| s.response_len()).sum();
+
+        if buffer.len() < 1 + tdo_len {
+            return Err(SendError::NotEnoughData);
+        }
+
+        Ok(SequenceResponse {
+            status,
+            tdo: buffer[1..1 + tdo_len].to_vec(),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct SequenceResponse {
+    pub(crate) status: Status,
+    /// Captured TDO data of all sequences with `tdo_capture` set,
+    /// concatenated in the order of the sequences. The data of each
+    /// sequence starts on a byte boundary.
+    pub(crate) tdo: Vec<u8>,
+}
+
+#[test]
+fn jtag_sequence_to_bytes() {
+    let request = SequenceRequest::new(vec![
+        JtagSequence::new(3, false, true, [0; 8]).unwrap(),
+        JtagSequence::new(64, true, false, [0xAA; 8]).unwrap(),
+    ])
+    .unwrap();
+
+    let mut buffer = [0u8; 32];
+
+    let len = request.to_bytes(&mut buffer).unwrap();
+
+    assert_eq!(len, 1 + 2 + 9);
+    assert_eq!(buffer[0], 2);
+    assert_eq!(buffer[1], 0x43);
+    assert_eq!(buffer[2], 0x00);
+    assert_eq!(buffer[3], 0x80);
+    assert_eq!(&buffer[4..12], &[0xAA; 8]);
+}
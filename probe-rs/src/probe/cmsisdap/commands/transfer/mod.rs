@@ -135,9 +135,9 @@ pub struct TransferRequest {
 }
 
 impl TransferRequest {
-    pub fn new(transfers: &[InnerTransferRequest]) -> Self {
+    pub fn new(dap_index: u8, transfers: &[InnerTransferRequest]) -> Self {
         Self {
-            dap_index: 0,
+            dap_index,
             transfer_count: transfers.len() as u8,
             transfers: transfers.into(),
         }
@@ -317,7 +317,12 @@ impl Request for TransferBlockRequest {
 }
 
 impl TransferBlockRequest {
-    pub(crate) fn write_request(address: u8, port: PortType, data: Vec<u32>) -> Self {
+    pub(crate) fn write_request(
+        dap_index: u8,
+        address: u8,
+        port: PortType,
+        data: Vec<u32>,
+    ) -> Self {
         let inner = InnerTransferBlockRequest {
             ap_n_dp: port,
             r_n_w: RW::W,
@@ -326,14 +331,19 @@ impl TransferBlockRequest {
         };
 
         TransferBlockRequest {
-            dap_index: 0,
+            dap_index,
             transfer_count: data.len() as u16,
             transfer_request: inner,
             transfer_data: data,
         }
     }
 
-    pub(crate) fn read_request(address: u8, port: PortType, read_count: u16) -> Self {
+    pub(crate) fn read_request(
+        dap_index: u8,
+        address: u8,
+        port: PortType,
+        read_count: u16,
+    ) -> Self {
         let inner = InnerTransferBlockRequest {
             ap_n_dp: port,
             r_n_w: RW::R,
@@ -342,7 +352,7 @@ impl TransferBlockRequest {
         };
 
         TransferBlockRequest {
-            dap_index: 0,
+            dap_index,
             transfer_count: read_count,
             transfer_request: inner,
             transfer_data: Vec::new(),
@@ -10,12 +10,14 @@ use crate::{
         ArmCommunicationInterface, DapError, DpAddress, Pins, PortType, RawDapAccess, Register,
         SwoAccess, SwoConfig, SwoMode,
     },
+    architecture::riscv::communication_interface::RiscvCommunicationInterface,
     probe::{
         cmsisdap::commands::{
             general::info::{CapabilitiesCommand, PacketCountCommand, SWOTraceBufferSizeCommand},
             CmsisDapError,
         },
-        BatchCommand,
+        jlink::bits_to_byte,
+        BatchCommand, JTAGAccess,
     },
     DebugProbe, DebugProbeError, DebugProbeSelector, Error as ProbeRsError, WireProtocol,
 };
@@ -27,6 +29,12 @@ use commands::{
         host_status::{HostStatusRequest, HostStatusResponse},
         info::Capabilities,
         reset::{ResetRequest, ResetResponse},
+        write_abort::{WriteAbortRequest, WriteAbortResponse},
+    },
+    jtag::{
+        self,
+        idcode::{IdCodeRequest, IdCodeResponse},
+        sequence::JtagSequence,
     },
     swd,
     swj::{
@@ -43,7 +51,53 @@ use commands::{
     CmsisDapDevice, Status,
 };
 
-use std::time::Duration;
+use std::{iter, time::Duration};
+
+/// Maximum number of TAPs which are detected in a JTAG scan chain.
+const JTAG_MAX_CHAIN_LENGTH: usize = 8;
+
+/// The JTAG scan chain, as detected when attaching with the JTAG protocol.
+#[derive(Clone, Debug)]
+struct JtagChain {
+    /// IR length of each TAP, in scan chain order. The first TAP
+    /// is the one closest to TDO.
+    ir_lengths: Vec<u8>,
+    /// Index of the TAP which is used for debugging.
+    tap_index: usize,
+}
+
+impl JtagChain {
+    /// IR length of the selected TAP.
+    fn irlen(&self) -> usize {
+        self.ir_lengths[self.tap_index] as usize
+    }
+
+    /// Number of IR bits before the selected TAP.
+    fn irpre(&self) -> usize {
+        self.ir_lengths[..self.tap_index]
+            .iter()
+            .map(|&len| len as usize)
+            .sum()
+    }
+
+    /// Number of IR bits after the selected TAP.
+    fn irpost(&self) -> usize {
+        self.ir_lengths[self.tap_index + 1..]
+            .iter()
+            .map(|&len| len as usize)
+            .sum()
+    }
+
+    /// Number of TAPs in BYPASS before the selected TAP.
+    fn drpre(&self) -> usize {
+        self.tap_index
+    }
+
+    /// Number of TAPs in BYPASS after the selected TAP.
+    fn drpost(&self) -> usize {
+        self.ir_lengths.len() - self.tap_index - 1
+    }
+}
 
 pub struct CmsisDap {
     pub device: CmsisDapDevice,
@@ -62,6 +116,16 @@ pub struct CmsisDap {
     speed_khz: u32,
 
     batch: Vec<BatchCommand>,
+
+    /// The JTAG scan chain, only available when attached using JTAG.
+    jtag_chain: Option<JtagChain>,
+
+    /// Idle cycles necessary between consecutive
+    /// accesses to the DMI register
+    jtag_idle_cycles: u8,
+
+    /// Value of the IR register of the selected TAP, if known.
+    current_ir_reg: Option<u32>,
 }
 
 impl std::fmt::Debug for CmsisDap {
@@ -75,6 +139,7 @@ impl std::fmt::Debug for CmsisDap {
             .field("swo_active", &self.swo_active)
             .field("swo_streaming", &self.swo_streaming)
             .field("speed_khz", &self.speed_khz)
+            .field("jtag_chain", &self.jtag_chain)
             .finish()
     }
 }
@@ -113,9 +178,22 @@ impl CmsisDap {
             swo_streaming: false,
             speed_khz: 1_000,
             batch: Vec::new(),
+            jtag_chain: None,
+            jtag_idle_cycles: 0,
+            current_ir_reg: None,
         })
     }
 
+    /// Zero based index of the TAP used for DAP transfers in JTAG mode.
+    ///
+    /// For SWD, this value is ignored by the probe.
+    fn dap_index(&self) -> u8 {
+        self.jtag_chain
+            .as_ref()
+            .map(|chain| chain.tap_index as u8)
+            .unwrap_or(0)
+    }
+
     /// Set maximum JTAG/SWD clock frequency to use, in Hz.
     ///
     /// The actual clock frequency used by the device might be lower.
@@ -151,6 +229,394 @@ impl CmsisDap {
             })
     }
 
+    fn configure_jtag(
+        &mut self,
+        request: jtag::configure::ConfigureRequest,
+    ) -> Result<(), CmsisDapError> {
+        commands::send_command::<jtag::configure::ConfigureRequest>(&mut self.device, request)
+            .map_err(CmsisDapError::from)
+            .and_then(|v| match v {
+                jtag::configure::ConfigureResponse(Status::DAPOk) => Ok(()),
+                jtag::configure::ConfigureResponse(Status::DAPError) => {
+                    Err(CmsisDapError::ErrorResponse)
+                }
+            })
+    }
+
+    /// Read the IDCODE of the selected TAP, using the DAP_JTAG_IDCODE command.
+    fn jtag_idcode(&mut self) -> Result<u32, CmsisDapError> {
+        let request = IdCodeRequest {
+            dap_index: self.dap_index(),
+        };
+
+        commands::send_command::<IdCodeRequest>(&mut self.device, request)
+            .map_err(CmsisDapError::from)
+            .and_then(|v| match v {
+                IdCodeResponse {
+                    status: Status::DAPOk,
+                    idcode,
+                } => Ok(idcode),
+                IdCodeResponse {
+                    status: Status::DAPError,
+                    ..
+                } => Err(CmsisDapError::ErrorResponse),
+            })
+    }
+
+    /// Write to the ABORT register of the DP, using the DAP_WriteABORT command.
+    fn write_abort(&mut self, abort: u32) -> Result<(), CmsisDapError> {
+        let request = WriteAbortRequest {
+            dap_index: self.dap_index(),
+            abort,
+        };
+
+        commands::send_command::<WriteAbortRequest>(&mut self.device, request)
+            .map_err(CmsisDapError::from)
+            .and_then(|v| match v {
+                WriteAbortResponse(Status::DAPOk) => Ok(()),
+                WriteAbortResponse(Status::DAPError) => Err(CmsisDapError::ErrorResponse),
+            })
+    }
+
+    /// Clock out the given TMS and TDI bits using DAP_JTAG_Sequence commands.
+    ///
+    /// `tms` and `tdi` have to contain the same number of bits. If `capture` is set,
+    /// the TDO value of each clock cycle is returned, otherwise the result is empty.
+    fn jtag_io(
+        &mut self,
+        tms: impl IntoIterator<Item = bool>,
+        tdi: impl IntoIterator<Item = bool>,
+        capture: bool,
+    ) -> Result<Vec<bool>, DebugProbeError> {
+        // Ensure that all queued DAP transfers have been executed first.
+        self.process_batch()?;
+
+        // Group the bits into sequences with the same TMS value,
+        // as a single sequence can only use a fixed TMS value.
+        let mut groups: Vec<(bool, Vec<bool>)> = Vec::new();
+
+        for (tms, tdi) in tms.into_iter().zip(tdi.into_iter()) {
+            match groups.last_mut() {
+                Some((group_tms, bits)) if *group_tms == tms && bits.len() < 64 => bits.push(tdi),
+                _ => groups.push((tms, vec![tdi])),
+            }
+        }
+
+        let mut result = Vec::new();
+
+        // One byte for the command ID, and one byte for the sequence count resp. status.
+        let max_len = self.packet_size as usize - 2;
+
+        let mut groups = groups.into_iter().peekable();
+
+        while groups.peek().is_some() {
+            let mut sequences = Vec::new();
+            let mut bit_counts = Vec::new();
+            let mut request_len = 0;
+            let mut response_len = 0;
+
+            while let Some((tms, bits)) = groups.peek() {
+                let mut data = [0u8; 8];
+                for (i, bit) in bits.iter().enumerate() {
+                    data[i / 8] |= (*bit as u8) << (i % 8);
+                }
+
+                let sequence = JtagSequence::new(bits.len() as u8, capture, *tms, data)?;
+
+                if request_len + sequence.request_len() > max_len
+                    || response_len + sequence.response_len() > max_len
+                    || sequences.len() == u8::MAX as usize
+                {
+                    break;
+                }
+
+                request_len += sequence.request_len();
+                response_len += sequence.response_len();
+
+                bit_counts.push(bits.len());
+                sequences.push(sequence);
+
+                groups.next();
+            }
+
+            let response = commands::send_command(
+                &mut self.device,
+                jtag::sequence::SequenceRequest::new(sequences)?,
+            )?;
+
+            if let Status::DAPError = response.status {
+                return Err(CmsisDapError::ErrorResponse.into());
+            }
+
+            if capture {
+                let mut tdo = &response.tdo[..];
+
+                for bit_count in bit_counts {
+                    let byte_count = (bit_count + 7) / 8;
+
+                    result.extend((0..bit_count).map(|i| (tdo[i / 8] >> (i % 8)) & 1 == 1));
+
+                    tdo = &tdo[byte_count..];
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Move the JTAG state machine to the Test-Logic-Reset state, and then to Run-Test/Idle.
+    fn jtag_reset(&mut self) -> Result<(), DebugProbeError> {
+        let tms = [true, true, true, true, true, false];
+        let tdi = iter::repeat(false).take(tms.len());
+
+        self.jtag_io(tms, tdi, false)?;
+
+        self.current_ir_reg = None;
+
+        Ok(())
+    }
+
+    /// Shift `data` through the IR (if `ir` is set) or DR scan path of the whole chain,
+    /// starting and ending in the Run-Test/Idle state.
+    ///
+    /// Returns the data shifted out of the scan path.
+    fn jtag_shift(
+        &mut self,
+        ir: bool,
+        data: &[bool],
+        idle_cycles: usize,
+    ) -> Result<Vec<bool>, DebugProbeError> {
+        assert!(!data.is_empty());
+
+        let tms_enter_shift: &[bool] = if ir {
+            &[true, true, false, false]
+        } else {
+            &[true, false, false]
+        };
+
+        // The last bit is shifted when exiting the shift state.
+        let tms_shift = iter::repeat(false).take(data.len() - 1);
+
+        let tms_enter_idle = [true, true, false];
+
+        let tms = tms_enter_shift
+            .iter()
+            .copied()
+            .chain(tms_shift)
+            .chain(tms_enter_idle)
+            .chain(iter::repeat(false).take(idle_cycles));
+
+        let tdi = iter::repeat(false)
+            .take(tms_enter_shift.len())
+            .chain(data.iter().copied())
+            .chain(iter::repeat(false).take(2 + idle_cycles));
+
+        let response = self.jtag_io(tms, tdi, true)?;
+
+        Ok(response[tms_enter_shift.len()..tms_enter_shift.len() + data.len()].to_vec())
+    }
+
+    /// Write the IR of the selected TAP, all other TAPs in the chain are put into BYPASS.
+    fn write_ir(&mut self, address: u32) -> Result<(), DebugProbeError> {
+        let chain = self
+            .jtag_chain
+            .clone()
+            .ok_or(DebugProbeError::NotAttached)?;
+
+        if chain.irlen() < 32 && address >= (1 << chain.irlen()) {
+            return Err(DebugProbeError::Other(anyhow::anyhow!(
+                "Invalid JTAG register address {:#x} for an IR length of {} bits",
+                address,
+                chain.irlen()
+            )));
+        }
+
+        log::debug!("Write IR: {:#x}, len={}", address, chain.irlen());
+
+        let bits: Vec<bool> = iter::repeat(true)
+            .take(chain.irpre())
+            .chain((0..chain.irlen()).map(|i| (address >> i) & 1 == 1))
+            .chain(iter::repeat(true).take(chain.irpost()))
+            .collect();
+
+        self.jtag_shift(true, &bits, 0)?;
+
+        self.current_ir_reg = Some(address);
+
+        Ok(())
+    }
+
+    /// Write the DR of the selected TAP, and return the data shifted out of it.
+    fn write_dr(&mut self, data: &[u8], register_bits: usize) -> Result<Vec<u8>, DebugProbeError> {
+        let chain = self
+            .jtag_chain
+            .clone()
+            .ok_or(DebugProbeError::NotAttached)?;
+
+        if data.len() * 8 < register_bits {
+            return Err(DebugProbeError::Other(anyhow::anyhow!(
+                "Not enough data for a DR write of {} bits",
+                register_bits
+            )));
+        }
+
+        log::debug!("Write DR: {:?}, len={}", data, register_bits);
+
+        let bits: Vec<bool> = iter::repeat(false)
+            .take(chain.drpre())
+            .chain((0..register_bits).map(|i| (data[i / 8] >> (i % 8)) & 1 == 1))
+            .chain(iter::repeat(false).take(chain.drpost()))
+            .collect();
+
+        let response = self.jtag_shift(false, &bits, self.jtag_idle_cycles as usize)?;
+
+        let result = response[chain.drpre()..chain.drpre() + register_bits]
+            .chunks(8)
+            .map(|byte| bits_to_byte(byte.iter().copied()) as u8)
+            .collect();
+
+        log::trace!("result: {:?}", result);
+
+        Ok(result)
+    }
+
+    /// Scan the JTAG chain, and return the IDCODE of each TAP.
+    ///
+    /// TAPs which only have a BYPASS register after reset are reported as `None`.
+    fn jtag_scan_idcodes(&mut self) -> Result<Vec<Option<u32>>, DebugProbeError> {
+        self.jtag_reset()?;
+
+        // After a reset, every TAP has either the IDCODE or the BYPASS
+        // instruction loaded. IDCODEs always start with a 1 bit, while
+        // the BYPASS register contains a single 0 bit.
+        let data = vec![true; JTAG_MAX_CHAIN_LENGTH * 32];
+        let response = self.jtag_shift(false, &data, 0)?;
+
+        let mut idcodes = Vec::new();
+        let mut bits = &response[..];
+
+        while !bits.is_empty() && idcodes.len() < JTAG_MAX_CHAIN_LENGTH {
+            if !bits[0] {
+                log::debug!("tap found in bypass");
+                idcodes.push(None);
+                bits = &bits[1..];
+            } else if bits.len() >= 32 {
+                let idcode = bits_to_byte(bits[..32].iter().copied());
+
+                if idcode == 0xffff_ffff {
+                    // We are seeing our own data, end of the chain.
+                    break;
+                }
+
+                log::debug!("tap found: {:08x}", idcode);
+                idcodes.push(Some(idcode));
+                bits = &bits[32..];
+            } else {
+                break;
+            }
+        }
+
+        Ok(idcodes)
+    }
+
+    /// Detect the IR length of all TAPs in a chain of `tap_count` TAPs.
+    fn jtag_scan_ir_lengths(&mut self, tap_count: usize) -> Result<Vec<u8>, DebugProbeError> {
+        self.jtag_reset()?;
+
+        // This uses the same approach as the FTDI probe driver:
+        //
+        // If there is a single TAP, we fill the IR with `1` bits, and then shift in `0` bits.
+        // The number of `1` bits coming out afterwards is the IR length.
+        //
+        // For multiple TAPs, we rely on the fact that the IR capture value has to end with
+        // `0b01`, and that most targets capture `0b00..001`.
+        if tap_count == 1 {
+            let data: Vec<bool> = iter::repeat(true)
+                .take(64)
+                .chain(iter::repeat(false).take(64))
+                .collect();
+            let response = self.jtag_shift(true, &data, 0)?;
+
+            let irlen = response[64..].iter().take_while(|&&bit| bit).count();
+            log::debug!("tap irlen: {}", irlen);
+
+            if irlen == 0 || irlen == 64 {
+                return Err(DebugProbeError::TargetNotFound);
+            }
+
+            Ok(vec![irlen as u8])
+        } else {
+            let data = vec![true; JTAG_MAX_CHAIN_LENGTH * 32];
+            let response = self.jtag_shift(true, &data, 0)?;
+
+            let mut ir_lengths = Vec::with_capacity(tap_count);
+            let mut pos = 0;
+
+            for i in 0..tap_count {
+                if response.get(pos) == Some(&true) && response.get(pos + 1) == Some(&false) {
+                    let irlen = 1 + response[pos + 1..].iter().take_while(|&&bit| !bit).count();
+                    log::debug!("tap {} irlen: {}", i, irlen);
+
+                    ir_lengths.push(irlen as u8);
+                    pos += irlen;
+                } else {
+                    log::debug!("invalid irlen for tap {}", i);
+                    return Err(DebugProbeError::Other(anyhow::anyhow!(
+                        "Invalid IR sequence during the JTAG chain scan"
+                    )));
+                }
+            }
+
+            Ok(ir_lengths)
+        }
+    }
+
+    /// Detect the JTAG scan chain, configure the probe for it,
+    /// and select the TAP which is used for debugging.
+    fn jtag_setup_chain(&mut self) -> Result<(), DebugProbeError> {
+        // The target might use a SWJ-DP which is currently in SWD mode,
+        // so ensure that it is switched to JTAG first.
+        self.send_swj_sequences(SequenceRequest::new(&[0xff; 7], 51)?)?;
+        self.send_swj_sequences(SequenceRequest::new(&0xE73Cu16.to_le_bytes(), 16)?)?;
+
+        let idcodes = self.jtag_scan_idcodes()?;
+
+        if idcodes.is_empty() {
+            log::warn!("no JTAG taps detected");
+            return Err(DebugProbeError::TargetNotFound);
+        }
+
+        let ir_lengths = self.jtag_scan_ir_lengths(idcodes.len())?;
+
+        // Prefer an ARM JTAG-DP, if there is one. Otherwise, use the first TAP.
+        let tap_index = idcodes
+            .iter()
+            .position(|idcode| matches!(idcode, Some(idcode) if idcode & 0xfff == 0x477))
+            .unwrap_or(0);
+
+        if idcodes.len() > 1 {
+            log::info!(
+                "Found {} TAPs in the JTAG chain, using TAP {}",
+                idcodes.len(),
+                tap_index
+            );
+        }
+
+        self.configure_jtag(jtag::configure::ConfigureRequest::new(ir_lengths.clone())?)?;
+
+        self.jtag_chain = Some(JtagChain {
+            ir_lengths,
+            tap_index,
+        });
+
+        self.jtag_reset()?;
+
+        let idcode = self.jtag_idcode()?;
+        log::info!("JTAG IDCODE: {:#010x}", idcode);
+
+        Ok(())
+    }
+
     fn send_swj_sequences(&mut self, request: SequenceRequest) -> Result<(), CmsisDapError> {
         commands::send_command::<SequenceRequest>(&mut self.device, request)
             .map_err(CmsisDapError::from)
@@ -176,6 +642,9 @@ impl CmsisDap {
 
         log::debug!("{} items in batch", batch.len());
 
+        // In JTAG mode, DAP transfers change the IR of the selected TAP.
+        self.current_ir_reg = None;
+
         for retry in (0..5).rev() {
             log::debug!("Attempting batch of {} items", batch.len());
 
@@ -193,7 +662,7 @@ impl CmsisDap {
 
             let response = commands::send_command::<TransferRequest>(
                 &mut self.device,
-                TransferRequest::new(&transfers),
+                TransferRequest::new(self.dap_index(), &transfers),
             )
             .map_err(CmsisDapError::from)?;
 
@@ -427,7 +896,7 @@ impl DebugProbe for CmsisDap {
             ConnectRequest::DefaultPort
         };
 
-        let _result = commands::send_command(&mut self.device, protocol)
+        let protocol = commands::send_command(&mut self.device, protocol)
             .map_err(CmsisDapError::from)
             .and_then(|v| match v {
                 ConnectResponse::SuccessfulInitForSWD => Ok(WireProtocol::Swd),
@@ -435,6 +904,11 @@ impl DebugProbe for CmsisDap {
                 ConnectResponse::InitFailed => Err(CmsisDapError::ErrorResponse),
             })?;
 
+        log::debug!("Connected using protocol '{}'", protocol);
+
+        // When connecting to the default port, the probe decides which protocol is used.
+        self.protocol = Some(protocol);
+
         // Set speed after connecting as it can be reset during protocol selection
         self.set_speed(self.speed_khz)?;
 
@@ -444,7 +918,10 @@ impl DebugProbe for CmsisDap {
             match_retry: 0,
         })?;
 
-        self.configure_swd(swd::configure::ConfigureRequest {})?;
+        match protocol {
+            WireProtocol::Swd => self.configure_swd(swd::configure::ConfigureRequest {})?,
+            WireProtocol::Jtag => self.jtag_setup_chain()?,
+        }
 
         // Tell the probe we are connected so it can turn on an LED.
         let _: Result<HostStatusResponse, _> =
@@ -477,10 +954,13 @@ impl DebugProbe for CmsisDap {
     fn select_protocol(&mut self, protocol: WireProtocol) -> Result<(), DebugProbeError> {
         match protocol {
             WireProtocol::Jtag => {
-                log::warn!(
-                    "Support for JTAG protocol is not yet implemented for CMSIS-DAP based probes."
-                );
-                Err(DebugProbeError::UnsupportedProtocol(WireProtocol::Jtag))
+                if self.capabilities.jtag_implemented {
+                    self.protocol = Some(WireProtocol::Jtag);
+                    Ok(())
+                } else {
+                    log::warn!("The CMSIS-DAP probe does not support the JTAG protocol.");
+                    Err(DebugProbeError::UnsupportedProtocol(WireProtocol::Jtag))
+                }
             }
             WireProtocol::Swd => {
                 self.protocol = Some(WireProtocol::Swd);
@@ -534,6 +1014,27 @@ impl DebugProbe for CmsisDap {
         true
     }
 
+    fn try_get_riscv_interface(
+        self: Box<Self>,
+    ) -> Result<RiscvCommunicationInterface, (Box<dyn DebugProbe>, DebugProbeError)> {
+        if self.protocol != Some(WireProtocol::Jtag) {
+            log::warn!("RISC-V debugging with CMSIS-DAP probes requires the JTAG protocol.");
+            return Err((
+                DebugProbe::into_probe(self),
+                DebugProbeError::InterfaceNotAvailable("JTAG"),
+            ));
+        }
+
+        match RiscvCommunicationInterface::new(self) {
+            Ok(interface) => Ok(interface),
+            Err((probe, err)) => Err((probe.into_probe(), err)),
+        }
+    }
+
+    fn has_riscv_interface(&self) -> bool {
+        self.capabilities.jtag_implemented
+    }
+
     fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe> {
         self
     }
@@ -541,6 +1042,10 @@ impl DebugProbe for CmsisDap {
     fn try_as_dap_probe(&mut self) -> Option<&mut dyn DapProbe> {
         Some(self)
     }
+
+    fn active_protocol(&self) -> Option<WireProtocol> {
+        self.protocol
+    }
 }
 
 impl RawDapAccess for CmsisDap {
@@ -604,6 +1109,19 @@ impl RawDapAccess for CmsisDap {
         addr: u8,
         value: u32,
     ) -> Result<(), DebugProbeError> {
+        if self.protocol == Some(WireProtocol::Jtag)
+            && port == PortType::DebugPort
+            && addr == Abort::ADDRESS
+        {
+            // The JTAG-DP uses a separate instruction for the ABORT register,
+            // which can't be accessed with DAP_Transfer.
+            self.process_batch()?;
+            self.current_ir_reg = None;
+            self.write_abort(value)?;
+
+            return Ok(());
+        }
+
         self.batch_add(BatchCommand::Write(port, addr as u16, value))
             .map(|_| ())
     }
@@ -615,6 +1133,7 @@ impl RawDapAccess for CmsisDap {
         values: &[u32],
     ) -> Result<(), DebugProbeError> {
         self.process_batch()?;
+        self.current_ir_reg = None;
 
         // the overhead for a single packet is 6 bytes
         //
@@ -631,8 +1150,12 @@ impl RawDapAccess for CmsisDap {
         let data_chunk_len = max_packet_size_words as usize;
 
         for (i, chunk) in values.chunks(data_chunk_len).enumerate() {
-            let request =
-                TransferBlockRequest::write_request(register_address as u8, port, Vec::from(chunk));
+            let request = TransferBlockRequest::write_request(
+                self.dap_index(),
+                register_address as u8,
+                port,
+                Vec::from(chunk),
+            );
 
             log::debug!("Transfer block: chunk={}, len={} bytes", i, chunk.len() * 4);
 
@@ -654,6 +1177,7 @@ impl RawDapAccess for CmsisDap {
         values: &mut [u32],
     ) -> Result<(), DebugProbeError> {
         self.process_batch()?;
+        self.current_ir_reg = None;
 
         // the overhead for a single packet is 6 bytes
         //
@@ -671,6 +1195,7 @@ impl RawDapAccess for CmsisDap {
 
         for (i, chunk) in values.chunks_mut(data_chunk_len).enumerate() {
             let request = TransferBlockRequest::read_request(
+                self.dap_index(),
                 register_address as u8,
                 port,
                 chunk.len() as u16,
@@ -703,6 +1228,9 @@ impl RawDapAccess for CmsisDap {
     fn swj_sequence(&mut self, bit_len: u8, bits: u64) -> Result<(), DebugProbeError> {
         let data = bits.to_le_bytes();
 
+        // The sequence might move the JTAG state machine through Test-Logic-Reset.
+        self.current_ir_reg = None;
+
         self.send_swj_sequences(SequenceRequest::new(&data, bit_len)?)?;
 
         Ok(())
@@ -724,6 +1252,35 @@ impl RawDapAccess for CmsisDap {
 
 impl DapProbe for CmsisDap {}
 
+impl JTAGAccess for CmsisDap {
+    fn read_register(&mut self, address: u32, len: u32) -> Result<Vec<u8>, DebugProbeError> {
+        let data = vec![0u8; (len as usize + 7) / 8];
+
+        self.write_register(address, &data, len)
+    }
+
+    fn set_idle_cycles(&mut self, idle_cycles: u8) {
+        self.jtag_idle_cycles = idle_cycles;
+    }
+
+    fn get_idle_cycles(&self) -> u8 {
+        self.jtag_idle_cycles
+    }
+
+    fn write_register(
+        &mut self,
+        address: u32,
+        data: &[u8],
+        len: u32,
+    ) -> Result<Vec<u8>, DebugProbeError> {
+        if self.current_ir_reg != Some(address) {
+            self.write_ir(address)?;
+        }
+
+        self.write_dr(data, len as usize)
+    }
+}
+
 impl SwoAccess for CmsisDap {
     fn enable_swo(&mut self, config: &SwoConfig) -> Result<(), ProbeRsError> {
         let caps = self.capabilities;
@@ -310,6 +310,14 @@ impl ArmProbeInterface for FakeArmInterface<Initialized> {
         Ok(None)
     }
 
+    fn probe(&self) -> &dyn DebugProbe {
+        &*self.probe
+    }
+
+    fn probe_mut(&mut self) -> &mut dyn DebugProbe {
+        &mut *self.probe
+    }
+
     fn close(self: Box<Self>) -> Probe {
         Probe::from_attached_probe(self.probe)
     }
@@ -622,6 +622,10 @@ impl DebugProbe for JLink {
         // Convert the integer millivolts value from self.handle to volts as an f32.
         Ok(Some((self.handle.read_target_voltage()? as f32) / 1000f32))
     }
+
+    fn active_protocol(&self) -> Option<WireProtocol> {
+        self.protocol
+    }
 }
 
 impl JTAGAccess for JLink {
@@ -1334,6 +1334,14 @@ impl<'probe> ArmProbeInterface for StlinkArmDebug {
         Ok(self.ap_information.len())
     }
 
+    fn probe(&self) -> &dyn DebugProbe {
+        &*self.probe
+    }
+
+    fn probe_mut(&mut self) -> &mut dyn DebugProbe {
+        &mut *self.probe
+    }
+
     fn close(self: Box<Self>) -> Probe {
         Probe::from_attached_probe(self.probe)
     }
@@ -0,0 +1,562 @@
+//! Host side of the ARM semihosting interface.
+//!
+//! A target requests a semihosting operation by executing `BKPT 0xAB`, with the
+//! operation number in `r0` and a parameter, usually the address of a parameter
+//! block, in `r1`. The debugger services the request on the host, writes the
+//! result to `r0` and resumes the core after the breakpoint instruction.
+//!
+//! See the [ARM semihosting specification](https://github.com/ARM-software/abi-aa/blob/main/semihosting/semihosting.rst)
+//! for the details of each operation.
+
+use crate::{Architecture, Core, CoreStatus, Error, HaltReason, MemoryInterface};
+use anyhow::anyhow;
+use object::read::{Object, ObjectSection};
+use object::SectionKind;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// The Thumb encoding of `BKPT 0xAB`.
+const SEMIHOSTING_BKPT: u16 = 0xBEAB;
+
+const SYS_OPEN: u32 = 0x01;
+const SYS_CLOSE: u32 = 0x02;
+const SYS_WRITEC: u32 = 0x03;
+const SYS_WRITE0: u32 = 0x04;
+const SYS_WRITE: u32 = 0x05;
+const SYS_READ: u32 = 0x06;
+const SYS_READC: u32 = 0x07;
+const SYS_ISERROR: u32 = 0x08;
+const SYS_ISTTY: u32 = 0x09;
+const SYS_SEEK: u32 = 0x0A;
+const SYS_FLEN: u32 = 0x0C;
+const SYS_REMOVE: u32 = 0x0E;
+const SYS_RENAME: u32 = 0x0F;
+const SYS_CLOCK: u32 = 0x10;
+const SYS_TIME: u32 = 0x11;
+const SYS_ERRNO: u32 = 0x13;
+const SYS_GET_CMDLINE: u32 = 0x15;
+const SYS_HEAPINFO: u32 = 0x16;
+const SYS_EXIT: u32 = 0x18;
+const SYS_EXIT_EXTENDED: u32 = 0x20;
+
+/// The reason code used by `SYS_EXIT` for a normal application exit.
+const ADP_STOPPED_APPLICATION_EXIT: u32 = 0x20026;
+
+/// The special file name used to open the console.
+const CONSOLE_FILE_NAME: &[u8] = b":tt";
+
+/// Handles for the console streams, regular files are numbered after these.
+const STDIN_HANDLE: u32 = 1;
+const STDOUT_HANDLE: u32 = 2;
+const STDERR_HANDLE: u32 = 3;
+const FIRST_FILE_HANDLE: u32 = 4;
+
+/// The errno value reported if the host error does not map to an OS error.
+const EIO: i32 = 5;
+/// The errno value reported for invalid file handles.
+const EBADF: i32 = 9;
+/// The errno value reported for file names longer than [`MAX_STRING_LENGTH`].
+const ENAMETOOLONG: i32 = 36;
+
+/// The maximum length of a string read by `SYS_WRITE0`, and of the file names of `SYS_OPEN`, `SYS_REMOVE` and `SYS_RENAME`.
+const MAX_STRING_LENGTH: usize = 4096;
+
+/// The maximum number of bytes transferred by a single `SYS_READ` or `SYS_WRITE` request.
+/// The target transfers the remaining bytes with further requests.
+const MAX_TRANSFER_LENGTH: u32 = 0x1_0000;
+
+/// The result of checking a core for a pending semihosting request.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SemihostingStatus {
+    /// The core is not halted on a semihosting request.
+    NoRequest,
+    /// A request was serviced, and the core is running again.
+    Handled,
+    /// The target application exited with the given exit code. The core stays halted.
+    Exit(i32),
+}
+
+/// Returns `true` if the ELF file `elf_data` may request semihosting operations,
+/// i.e. if one of its code sections contains a `BKPT 0xAB` instruction.
+///
+/// Constant data in a code section can match as well, so a `true` result is not a guarantee.
+pub fn elf_uses_semihosting(elf_data: &[u8]) -> bool {
+    let file = match object::File::parse(elf_data) {
+        Ok(file) => file,
+        Err(error) => {
+            log::debug!("Unable to parse the ELF file: {}", error);
+            return false;
+        }
+    };
+
+    file.sections()
+        .filter(|section| section.kind() == SectionKind::Text)
+        .filter_map(|section| section.data().ok())
+        .any(|data| {
+            data.chunks_exact(2)
+                .any(|halfword| u16::from_le_bytes([halfword[0], halfword[1]]) == SEMIHOSTING_BKPT)
+        })
+}
+
+/// Where console reads and writes of the target go.
+#[derive(Debug)]
+enum Console {
+    /// The console of the host process.
+    Stdio,
+    /// Output is buffered until it is retrieved using [`Semihosting::take_console_output`].
+    /// Reads from the console return end of file.
+    Captured { output: Vec<u8> },
+}
+
+/// Services semihosting requests of a target application.
+///
+/// Files opened by the target stay open as long as this struct exists,
+/// so a single instance should be used for the whole run of an application.
+#[derive(Debug)]
+pub struct Semihosting {
+    console: Console,
+    files: HashMap<u32, File>,
+    next_handle: u32,
+    errno: i32,
+    start: Instant,
+}
+
+impl Default for Semihosting {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Semihosting {
+    /// Creates a new semihosting handler, which uses the console of the host process.
+    pub fn new() -> Self {
+        Self::with_console(Console::Stdio)
+    }
+
+    /// Creates a new semihosting handler, which buffers console output
+    /// until it is retrieved using [`Semihosting::take_console_output`].
+    pub fn captured() -> Self {
+        Self::with_console(Console::Captured { output: vec![] })
+    }
+
+    fn with_console(console: Console) -> Self {
+        Self {
+            console,
+            files: HashMap::new(),
+            next_handle: FIRST_FILE_HANDLE,
+            errno: 0,
+            start: Instant::now(),
+        }
+    }
+
+    /// Returns the console output written by the target since the last call.
+    ///
+    /// This is always empty if the handler uses the console of the host process.
+    pub fn take_console_output(&mut self) -> Vec<u8> {
+        match &mut self.console {
+            Console::Stdio => vec![],
+            Console::Captured { output } => std::mem::take(output),
+        }
+    }
+
+    /// Checks if the core is halted on a semihosting breakpoint, and if so services the request.
+    ///
+    /// After servicing the request, the core is resumed, unless the target application exited.
+    pub fn handle(&mut self, core: &mut Core) -> Result<SemihostingStatus, Error> {
+        if core.architecture() != Architecture::Arm
+            || core.status()? != CoreStatus::Halted(HaltReason::Breakpoint)
+        {
+            return Ok(SemihostingStatus::NoRequest);
+        }
+
+        let pc = core.read_core_reg(core.registers().program_counter())?;
+
+        let mut instruction = [0u8; 2];
+        core.read_8(pc, &mut instruction)?;
+
+        if u16::from_le_bytes(instruction) != SEMIHOSTING_BKPT {
+            return Ok(SemihostingStatus::NoRequest);
+        }
+
+        let operation = core.read_core_reg(core.registers().platform_register(0))?;
+        let parameter = core.read_core_reg(core.registers().platform_register(1))?;
+
+        log::debug!(
+            "Semihosting request {:#04x} with parameter {:#010x}",
+            operation,
+            parameter
+        );
+
+        let result = match operation {
+            SYS_EXIT => {
+                let exit_code = if parameter == ADP_STOPPED_APPLICATION_EXIT {
+                    0
+                } else {
+                    1
+                };
+                return Ok(SemihostingStatus::Exit(exit_code));
+            }
+            SYS_EXIT_EXTENDED => {
+                let mut block = [0u32; 2];
+                core.read_32(parameter, &mut block)?;
+
+                let exit_code = if block[0] == ADP_STOPPED_APPLICATION_EXIT {
+                    block[1] as i32
+                } else {
+                    1
+                };
+                return Ok(SemihostingStatus::Exit(exit_code));
+            }
+            _ => self.service(core, operation, parameter)?,
+        };
+
+        core.write_core_reg(core.registers().platform_register(0).into(), result as u32)?;
+        // Continue after the breakpoint instruction.
+        core.write_core_reg(core.registers().program_counter().into(), pc + 2)?;
+        core.run()?;
+
+        Ok(SemihostingStatus::Handled)
+    }
+
+    /// Services all requests which do not stop the application, and returns the value for `r0`.
+    fn service(&mut self, core: &mut Core, operation: u32, parameter: u32) -> Result<i32, Error> {
+        let result = match operation {
+            SYS_OPEN => {
+                let mut block = [0u32; 3];
+                core.read_32(parameter, &mut block)?;
+                let [name, mode, length] = block;
+
+                match read_name(core, name, length)? {
+                    Some(name) => self.open(&name, mode),
+                    None => self.name_too_long(),
+                }
+            }
+            SYS_CLOSE => {
+                let handle = core.read_word_32(parameter)?;
+
+                // Closing the console is a no-op.
+                if handle < FIRST_FILE_HANDLE || self.files.remove(&handle).is_some() {
+                    0
+                } else {
+                    self.errno = EBADF;
+                    -1
+                }
+            }
+            SYS_WRITEC => {
+                let character = core.read_word_8(parameter)?;
+                self.write_console(STDOUT_HANDLE, &[character]);
+                0
+            }
+            SYS_WRITE0 => {
+                let string = read_c_string(core, parameter)?;
+                self.write_console(STDOUT_HANDLE, &string);
+                0
+            }
+            SYS_WRITE => {
+                let mut block = [0u32; 3];
+                core.read_32(parameter, &mut block)?;
+                let [handle, data, length] = block;
+
+                let mut buffer = vec![0u8; length.min(MAX_TRANSFER_LENGTH) as usize];
+                core.read_8(data, &mut buffer)?;
+                let not_written = (length as usize - buffer.len()) as i32;
+
+                // Returns the number of bytes which were not written.
+                match handle {
+                    STDIN_HANDLE => length as i32,
+                    STDOUT_HANDLE | STDERR_HANDLE => {
+                        self.write_console(handle, &buffer);
+                        not_written
+                    }
+                    _ => match self.files.get_mut(&handle) {
+                        Some(file) => match file.write_all(&buffer) {
+                            Ok(()) => not_written,
+                            Err(e) => {
+                                self.errno = errno(&e);
+                                length as i32
+                            }
+                        },
+                        None => {
+                            self.errno = EBADF;
+                            length as i32
+                        }
+                    },
+                }
+            }
+            SYS_READ => {
+                let mut block = [0u32; 3];
+                core.read_32(parameter, &mut block)?;
+                let [handle, data, length] = block;
+
+                let mut buffer = vec![0u8; length.min(MAX_TRANSFER_LENGTH) as usize];
+                let read = match self.read(handle, &mut buffer) {
+                    Ok(read) => read,
+                    Err(errno) => {
+                        self.errno = errno;
+                        0
+                    }
+                };
+                core.write_8(data, &buffer[..read])?;
+
+                // Returns the number of bytes which were not read.
+                (length as usize - read) as i32
+            }
+            SYS_READC => {
+                let mut character = [0u8];
+                match self.read(STDIN_HANDLE, &mut character) {
+                    Ok(1) => character[0] as i32,
+                    _ => -1,
+                }
+            }
+            SYS_ISERROR => {
+                let status = core.read_word_32(parameter)? as i32;
+                (status < 0) as i32
+            }
+            SYS_ISTTY => {
+                let handle = core.read_word_32(parameter)?;
+                (handle < FIRST_FILE_HANDLE) as i32
+            }
+            SYS_SEEK => {
+                let mut block = [0u32; 2];
+                core.read_32(parameter, &mut block)?;
+                let [handle, position] = block;
+
+                match self.files.get_mut(&handle) {
+                    Some(file) => match file.seek(SeekFrom::Start(position as u64)) {
+                        Ok(_) => 0,
+                        Err(e) => {
+                            self.errno = errno(&e);
+                            -1
+                        }
+                    },
+                    None => {
+                        self.errno = EBADF;
+                        -1
+                    }
+                }
+            }
+            SYS_FLEN => {
+                let handle = core.read_word_32(parameter)?;
+
+                match self.files.get(&handle) {
+                    Some(file) => match file.metadata() {
+                        Ok(metadata) => metadata.len() as i32,
+                        Err(e) => {
+                            self.errno = errno(&e);
+                            -1
+                        }
+                    },
+                    None if handle < FIRST_FILE_HANDLE => 0,
+                    None => {
+                        self.errno = EBADF;
+                        -1
+                    }
+                }
+            }
+            SYS_REMOVE => {
+                let mut block = [0u32; 2];
+                core.read_32(parameter, &mut block)?;
+                let path = match read_path(core, block[0], block[1])? {
+                    Some(path) => path,
+                    None => return Ok(self.name_too_long()),
+                };
+
+                match std::fs::remove_file(path) {
+                    Ok(()) => 0,
+                    Err(e) => {
+                        self.errno = errno(&e);
+                        -1
+                    }
+                }
+            }
+            SYS_RENAME => {
+                let mut block = [0u32; 4];
+                core.read_32(parameter, &mut block)?;
+                let (from, to) = match (
+                    read_path(core, block[0], block[1])?,
+                    read_path(core, block[2], block[3])?,
+                ) {
+                    (Some(from), Some(to)) => (from, to),
+                    _ => return Ok(self.name_too_long()),
+                };
+
+                match std::fs::rename(from, to) {
+                    Ok(()) => 0,
+                    Err(e) => {
+                        self.errno = errno(&e);
+                        -1
+                    }
+                }
+            }
+            SYS_CLOCK => (self.start.elapsed().as_millis() / 10) as i32,
+            SYS_TIME => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.as_secs() as i32)
+                .unwrap_or(0),
+            SYS_ERRNO => self.errno,
+            SYS_GET_CMDLINE => {
+                let mut block = [0u32; 2];
+                core.read_32(parameter, &mut block)?;
+
+                // There are no command line arguments, so return an empty string.
+                core.write_word_8(block[0], 0)?;
+                core.write_word_32(parameter + 4, 0)?;
+                0
+            }
+            SYS_HEAPINFO => {
+                let block_address = core.read_word_32(parameter)?;
+
+                // Zero tells the target to use its default heap and stack locations.
+                core.write_32(block_address, &[0; 4])?;
+                0
+            }
+            other => {
+                log::warn!("Semihosting operation {:#04x} is not supported", other);
+                -1
+            }
+        };
+
+        Ok(result)
+    }
+
+    /// Reports that a file name is longer than [`MAX_STRING_LENGTH`], and returns the result of the failed request.
+    fn name_too_long(&mut self) -> i32 {
+        self.errno = ENAMETOOLONG;
+        -1
+    }
+
+    fn open(&mut self, name: &[u8], mode: u32) -> i32 {
+        if name == CONSOLE_FILE_NAME {
+            let handle = match mode {
+                0..=3 => STDIN_HANDLE,
+                4..=7 => STDOUT_HANDLE,
+                _ => STDERR_HANDLE,
+            };
+            return handle as i32;
+        }
+
+        // The modes follow the order of the ISO C `fopen` modes "r", "rb", "r+", "r+b", "w", ...
+        let mut options = OpenOptions::new();
+        match mode {
+            0 | 1 => options.read(true),
+            2 | 3 => options.read(true).write(true),
+            4 | 5 => options.write(true).create(true).truncate(true),
+            6 | 7 => options.read(true).write(true).create(true).truncate(true),
+            8 | 9 => options.append(true).create(true),
+            10 | 11 => options.read(true).append(true).create(true),
+            _ => {
+                self.errno = EIO;
+                return -1;
+            }
+        };
+
+        let path = String::from_utf8_lossy(name).into_owned();
+
+        match options.open(&path) {
+            Ok(file) => {
+                let handle = self.next_handle;
+                self.next_handle += 1;
+                self.files.insert(handle, file);
+                handle as i32
+            }
+            Err(e) => {
+                log::debug!("Semihosting: failed to open '{}': {}", path, e);
+                self.errno = errno(&e);
+                -1
+            }
+        }
+    }
+
+    /// Reads from a handle, and returns the number of bytes read or an errno value.
+    fn read(&mut self, handle: u32, buffer: &mut [u8]) -> Result<usize, i32> {
+        let result = match handle {
+            STDIN_HANDLE => match self.console {
+                Console::Stdio => std::io::stdin().read(buffer),
+                Console::Captured { .. } => Ok(0),
+            },
+            STDOUT_HANDLE | STDERR_HANDLE => return Err(EBADF),
+            _ => match self.files.get_mut(&handle) {
+                Some(file) => file.read(buffer),
+                None => return Err(EBADF),
+            },
+        };
+
+        result.map_err(|e| errno(&e))
+    }
+
+    fn write_console(&mut self, handle: u32, data: &[u8]) {
+        match &mut self.console {
+            Console::Stdio => {
+                let result = if handle == STDERR_HANDLE {
+                    let mut stderr = std::io::stderr();
+                    stderr.write_all(data).and_then(|_| stderr.flush())
+                } else {
+                    let mut stdout = std::io::stdout();
+                    stdout.write_all(data).and_then(|_| stdout.flush())
+                };
+
+                if let Err(e) = result {
+                    log::warn!("Failed to write semihosting output: {}", e);
+                }
+            }
+            Console::Captured { output } => output.extend_from_slice(data),
+        }
+    }
+}
+
+/// Reads a null-terminated string from the target.
+fn read_c_string(core: &mut Core, address: u32) -> Result<Vec<u8>, Error> {
+    // Read in small aligned chunks, to avoid reading past the end of a memory region.
+    const CHUNK_SIZE: u32 = 32;
+
+    let mut string = vec![];
+    let start = address;
+    let mut address = address;
+
+    while string.len() < MAX_STRING_LENGTH {
+        let chunk_length = CHUNK_SIZE - address % CHUNK_SIZE;
+        let mut chunk = vec![0u8; chunk_length as usize];
+        core.read_8(address, &mut chunk)?;
+
+        match chunk.iter().position(|&byte| byte == 0) {
+            Some(end) => {
+                string.extend_from_slice(&chunk[..end]);
+                return Ok(string);
+            }
+            None => string.extend_from_slice(&chunk),
+        }
+
+        address = address.checked_add(chunk_length).ok_or_else(|| {
+            anyhow!(
+                "The string at {:#010x} is not terminated before the end of the address space",
+                start
+            )
+        })?;
+    }
+
+    Ok(string)
+}
+
+/// Reads a file name of `length` bytes, or returns `None` if it is longer than [`MAX_STRING_LENGTH`].
+fn read_name(core: &mut Core, address: u32, length: u32) -> Result<Option<Vec<u8>>, Error> {
+    if length as usize > MAX_STRING_LENGTH {
+        return Ok(None);
+    }
+
+    let mut name = vec![0u8; length as usize];
+    core.read_8(address, &mut name)?;
+
+    Ok(Some(name))
+}
+
+fn read_path(core: &mut Core, address: u32, length: u32) -> Result<Option<String>, Error> {
+    let path = read_name(core, address, length)?;
+
+    Ok(path.map(|path| String::from_utf8_lossy(&path).into_owned()))
+}
+
+fn errno(error: &std::io::Error) -> i32 {
+    error.raw_os_error().unwrap_or(EIO)
+}
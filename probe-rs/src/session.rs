@@ -14,7 +14,7 @@ use crate::{
     },
     config::DebugSequence,
 };
-use crate::{AttachMethod, Core, CoreType, Error, Probe};
+use crate::{AttachMethod, Core, CoreType, DebugProbe, Error, Probe};
 use anyhow::anyhow;
 use std::{fmt, time::Duration};
 
@@ -179,8 +179,11 @@ impl Session {
                     // Enable debug mode
                     sequence_handle.debug_device_unlock(&mut memory_interface, &permissions)?;
 
-                    // Enable debug mode
-                    sequence_handle.debug_core_start(&mut memory_interface)?;
+                    // Enable debug mode. Cortex-A cores are enabled through their
+                    // external debug interface when they are attached.
+                    if target.cores[0].core_type.is_cortex_m() {
+                        sequence_handle.debug_core_start(&mut memory_interface)?;
+                    }
                 }
 
                 let session = if attach_method == AttachMethod::UnderReset {
@@ -274,6 +277,7 @@ impl Session {
         };
 
         session.clear_all_hw_breakpoints()?;
+        session.clear_all_hw_watchpoints()?;
 
         Ok(session)
     }
@@ -424,6 +428,22 @@ impl Session {
         &self.target
     }
 
+    /// Returns the debug probe of the session, e.g. to show its name or speed.
+    pub fn probe(&self) -> &dyn DebugProbe {
+        match &self.interface {
+            ArchitectureInterface::Arm(interface) => interface.probe(),
+            ArchitectureInterface::Riscv(interface) => interface.probe(),
+        }
+    }
+
+    /// Returns the debug probe of the session, e.g. to change its speed or to reset the target.
+    pub fn probe_mut(&mut self) -> &mut dyn DebugProbe {
+        match &mut self.interface {
+            ArchitectureInterface::Arm(interface) => interface.probe_mut(),
+            ArchitectureInterface::Riscv(interface) => interface.probe_mut(),
+        }
+    }
+
     /// Configure the target and probe for serial wire view (SWV) tracing.
     pub fn setup_swv(&mut self, core_index: usize, config: &SwoConfig) -> Result<(), Error> {
         // Configure SWO on the probe
@@ -494,6 +514,14 @@ impl Session {
                 .and_then(|mut core| core.clear_all_hw_breakpoints())
         })
     }
+
+    /// Clears all hardware watchpoints on all cores
+    pub fn clear_all_hw_watchpoints(&mut self) -> Result<(), Error> {
+        { 0..self.cores.len() }.try_for_each(|n| {
+            self.core(n)
+                .and_then(|mut core| core.clear_all_hw_watchpoints())
+        })
+    }
 }
 
 // This test ensures that [Session] is fully [Send] + [Sync].
@@ -510,6 +538,15 @@ impl Drop for Session {
         if let Err(err) = result {
             log::warn!("Could not clear all hardware breakpoints: {:?}", err);
         }
+
+        let result = { 0..self.cores.len() }.try_for_each(|i| {
+            self.core(i)
+                .and_then(|mut core| core.clear_all_hw_watchpoints())
+        });
+
+        if let Err(err) = result {
+            log::warn!("Could not clear all hardware watchpoints: {:?}", err);
+        }
     }
 }
 
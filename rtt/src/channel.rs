@@ -16,7 +16,7 @@ pub trait RttChannel {
     fn buffer_size(&self) -> usize;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct Channel {
     number: usize,
     core_id: usize,
@@ -89,6 +89,19 @@ impl Channel {
         }))
     }
 
+    /// Creates a channel which is not backed by target memory, for tests.
+    #[cfg(test)]
+    pub(crate) fn test_channel(number: usize) -> Channel {
+        Channel {
+            number,
+            core_id: 0,
+            ptr: 0,
+            name: None,
+            buffer_ptr: 0,
+            size: 0,
+        }
+    }
+
     /// Validate that the Core id of a request is the same as the Core id against which the Channel was created.
     pub(crate) fn validate_core_id(&self, core: &mut Core) -> Result<(), Error> {
         if core.id() == self.core_id {
@@ -138,7 +151,7 @@ impl Channel {
 }
 
 /// RTT up (target to host) channel.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct UpChannel(pub(crate) Channel);
 
 impl UpChannel {
@@ -270,7 +283,7 @@ impl RttChannel for UpChannel {
 }
 
 /// RTT down (host to target) channel.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct DownChannel(pub(crate) Channel);
 
 impl DownChannel {
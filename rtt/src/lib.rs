@@ -52,6 +52,12 @@ pub use channels::Channels;
 mod rtt;
 pub use rtt::*;
 
+mod server;
+pub use server::{RttServer, RttServerAddress};
+
+mod stream;
+pub use stream::{RttData, RttStream, RttStreamBuilder};
+
 /// Error type for RTT operations.
 #[derive(Error, Debug)]
 pub enum Error {
@@ -84,4 +90,8 @@ pub enum Error {
     /// Wraps errors propagated up from reading memory on the target.
     #[error("Unexpected error while reading {0} from target memory. Please report this as a bug.")]
     MemoryRead(String),
+
+    /// Wraps I/O errors, e.g. of the sockets used by [`RttServer`], or of starting the thread of [`RttStream`].
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
 }
@@ -249,6 +249,18 @@ impl Rtt {
         self.ptr
     }
 
+    /// Checks if the control block is still present in target memory.
+    ///
+    /// The control block is usually lost when the target is reset, as the startup code clears the
+    /// RAM before the firmware initializes RTT again. The firmware may then place the control block
+    /// and the channel buffers at different addresses, so the host has to attach again.
+    pub fn is_control_block_present(&self, core: &mut Core) -> Result<bool, Error> {
+        let mut id = [0u8; Self::RTT_ID.len()];
+        core.read(self.ptr + Self::O_ID as u32, &mut id)?;
+
+        Ok(id == Self::RTT_ID)
+    }
+
     /// Gets the detected up channels.
     pub fn up_channels(&mut self) -> &mut Channels<UpChannel> {
         &mut self.up_channels
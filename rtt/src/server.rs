@@ -0,0 +1,572 @@
+//! Serves RTT channels on local sockets, so other programs can connect to them.
+//!
+//! Each up channel is paired with the down channel of the same number, and each pair is served on
+//! its own TCP port or Unix domain socket. The data of the up channel is sent to all connected
+//! clients, and the data received from any client is written to the down channel. Clients can
+//! connect and disconnect at any time.
+
+use crate::{DownChannel, Error, Rtt, RttChannel, UpChannel};
+use probe_rs::Core;
+use std::collections::BTreeMap;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::str::FromStr;
+
+#[cfg(unix)]
+use std::{
+    fs,
+    os::unix::{
+        fs::FileTypeExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::PathBuf,
+};
+
+/// The maximum amount of up channel data a client did not receive yet. Slower clients are disconnected.
+const MAX_PENDING_DATA: usize = 1024 * 1024;
+
+/// The maximum amount of client data which did not fit into the down channel yet. While it is
+/// reached, the clients are not read, so the data stays in the socket buffers.
+const MAX_DOWN_DATA: usize = 64 * 1024;
+
+/// The address an [`RttServer`] serves the channels on.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RttServerAddress {
+    /// Each channel is served on a TCP port, which is the port of the address plus the channel number.
+    Tcp(SocketAddr),
+    /// Each channel is served on a Unix domain socket in the directory. The socket is named after
+    /// the number and the name of the channel, e.g. `0-Terminal.sock`.
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl FromStr for RttServerAddress {
+    type Err = String;
+
+    /// Parses a port on localhost, an `<ip>:<port>` address, or `unix:<directory>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        #[cfg(unix)]
+        if let Some(directory) = s.strip_prefix("unix:") {
+            return Ok(Self::Unix(PathBuf::from(directory)));
+        }
+
+        if let Ok(port) = s.parse::<u16>() {
+            return Ok(Self::Tcp(SocketAddr::from((Ipv4Addr::LOCALHOST, port))));
+        }
+
+        s.parse().map(Self::Tcp).map_err(|_| {
+            format!(
+                "'{}' is not a valid address, use a port, '<ip>:<port>' or 'unix:<directory>'",
+                s
+            )
+        })
+    }
+}
+
+/// Serves the channels of an RTT control block on local sockets.
+///
+/// The server does not use a thread of its own, [`RttServer::poll`] has to be called regularly
+/// to transfer data and to accept new clients.
+#[derive(Debug)]
+pub struct RttServer {
+    address: RttServerAddress,
+    sockets: Vec<ChannelSocket>,
+    buffer: Vec<u8>,
+}
+
+impl RttServer {
+    /// Serves the channels of `rtt` on sockets at `address`.
+    ///
+    /// The channels are taken from `rtt`, so they are no longer available in
+    /// [`Rtt::up_channels`] and [`Rtt::down_channels`].
+    pub fn new(rtt: &mut Rtt, address: &RttServerAddress) -> Result<Self, Error> {
+        let mut server = Self {
+            address: address.clone(),
+            sockets: Vec::new(),
+            buffer: vec![0; 1024],
+        };
+
+        server.replace_channels(rtt)?;
+
+        Ok(server)
+    }
+
+    /// Serves the channels of `rtt` instead of the current channels, e.g. after the target was
+    /// reset and initialized a new control block.
+    ///
+    /// The sockets of channels with the same number are kept, so their clients stay connected.
+    /// Sockets are created for new channels. Sockets whose channels are gone are kept without
+    /// channels, as the target may create them again.
+    pub fn replace_channels(&mut self, rtt: &mut Rtt) -> Result<(), Error> {
+        let mut pairs: BTreeMap<usize, (Option<UpChannel>, Option<DownChannel>)> = BTreeMap::new();
+
+        for channel in rtt.up_channels().drain() {
+            pairs.entry(channel.number()).or_default().0 = Some(channel);
+        }
+
+        for channel in rtt.down_channels().drain() {
+            pairs.entry(channel.number()).or_default().1 = Some(channel);
+        }
+
+        for socket in self.sockets.iter_mut() {
+            let (up_channel, down_channel) = pairs.remove(&socket.number).unwrap_or_default();
+            socket.up_channel = up_channel;
+            socket.down_channel = down_channel;
+        }
+
+        for (number, (up_channel, down_channel)) in pairs {
+            let name = up_channel
+                .as_ref()
+                .and_then(|channel| channel.name())
+                .or_else(|| down_channel.as_ref().and_then(|channel| channel.name()))
+                .map(String::from);
+
+            let listener = Listener::bind(&self.address, number, name.as_deref())?;
+            log::info!("Serving RTT channel {} on {}", number, listener.address);
+
+            self.sockets.push(ChannelSocket {
+                number,
+                name,
+                up_channel,
+                down_channel,
+                listener,
+                clients: Vec::new(),
+                down_data: Vec::new(),
+            });
+        }
+
+        self.sockets.sort_by_key(|socket| socket.number);
+
+        Ok(())
+    }
+
+    /// Returns the number, the name and the socket address of each served channel.
+    pub fn channels(&self) -> impl Iterator<Item = (usize, Option<&str>, &str)> + '_ {
+        self.sockets.iter().map(|socket| {
+            (
+                socket.number,
+                socket.name.as_deref(),
+                socket.listener.address.as_str(),
+            )
+        })
+    }
+
+    /// Accepts new clients, and transfers the available data between the channels and the clients.
+    ///
+    /// Up channels are only read while a client is connected, so their data stays in the target
+    /// buffer until a client connects.
+    ///
+    /// Returns `true` if any data was transferred.
+    pub fn poll(&mut self, core: &mut Core) -> Result<bool, Error> {
+        let mut transferred = false;
+
+        for socket in self.sockets.iter_mut() {
+            transferred |= socket.poll(core, &mut self.buffer)?;
+        }
+
+        Ok(transferred)
+    }
+}
+
+/// An up and down channel pair, and the clients connected to its socket.
+#[derive(Debug)]
+struct ChannelSocket {
+    number: usize,
+    name: Option<String>,
+    up_channel: Option<UpChannel>,
+    down_channel: Option<DownChannel>,
+    listener: Listener,
+    clients: Vec<Client>,
+    /// Data received from the clients, which did not fit into the down channel buffer yet.
+    /// It is limited to [`MAX_DOWN_DATA`].
+    down_data: Vec<u8>,
+}
+
+impl ChannelSocket {
+    fn poll(&mut self, core: &mut Core, buffer: &mut [u8]) -> Result<bool, Error> {
+        let mut transferred = false;
+
+        while let Some(stream) = self.listener.accept()? {
+            log::info!("Client connected to RTT channel {}", self.number);
+            self.clients.push(Client {
+                stream,
+                pending_data: Vec::new(),
+            });
+        }
+
+        match &self.up_channel {
+            Some(up_channel) if !self.clients.is_empty() => {
+                let count = up_channel.read(core, buffer)?;
+                for client in self.clients.iter_mut() {
+                    client.pending_data.extend_from_slice(&buffer[..count]);
+                }
+                transferred |= count > 0;
+            }
+            _ => {}
+        }
+
+        for mut client in std::mem::take(&mut self.clients) {
+            let mut connected = client.receive(buffer, &mut self.down_data, &mut transferred);
+            connected &= client.send();
+
+            if connected {
+                self.clients.push(client);
+            } else {
+                log::info!("Client disconnected from RTT channel {}", self.number);
+            }
+        }
+
+        match &self.down_channel {
+            Some(down_channel) if !self.down_data.is_empty() => {
+                let count = down_channel.write(core, &self.down_data)?;
+                self.down_data.drain(..count);
+            }
+            // Without a down channel, the data of the clients is discarded.
+            _ => self.down_data.clear(),
+        }
+
+        Ok(transferred)
+    }
+}
+
+/// A client connected to the socket of a channel.
+#[derive(Debug)]
+struct Client {
+    stream: Stream,
+    /// Data of the up channel, which was not sent to the client yet.
+    pending_data: Vec<u8>,
+}
+
+impl Client {
+    /// Receives the available data of the client, until `down_data` holds [`MAX_DOWN_DATA`] bytes.
+    /// Returns `false` if the client disconnected.
+    fn receive(&mut self, buffer: &mut [u8], down_data: &mut Vec<u8>, received: &mut bool) -> bool {
+        loop {
+            let available = MAX_DOWN_DATA
+                .saturating_sub(down_data.len())
+                .min(buffer.len());
+            if available == 0 {
+                return true;
+            }
+
+            match self.stream.read(&mut buffer[..available]) {
+                Ok(0) => return false,
+                Ok(count) => {
+                    down_data.extend_from_slice(&buffer[..count]);
+                    *received = true;
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => return true,
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => {
+                    log::warn!("Error reading from RTT client: {}", error);
+                    return false;
+                }
+            }
+        }
+    }
+
+    /// Sends as much of the pending data as the client accepts. Returns `false` if the client disconnected.
+    fn send(&mut self) -> bool {
+        while !self.pending_data.is_empty() {
+            match self.stream.write(&self.pending_data) {
+                Ok(0) => return false,
+                Ok(count) => {
+                    self.pending_data.drain(..count);
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => {
+                    log::warn!("Error writing to RTT client: {}", error);
+                    return false;
+                }
+            }
+        }
+
+        if self.pending_data.len() > MAX_PENDING_DATA {
+            log::warn!("RTT client does not receive its data, disconnecting it");
+            return false;
+        }
+
+        true
+    }
+}
+
+/// A non-blocking listening socket.
+#[derive(Debug)]
+struct Listener {
+    kind: ListenerKind,
+    /// The address of the socket, as shown to the user.
+    address: String,
+}
+
+#[derive(Debug)]
+enum ListenerKind {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixSocket),
+}
+
+impl Listener {
+    /// Creates the socket of channel `number` at `address`.
+    #[cfg_attr(not(unix), allow(unused_variables))]
+    fn bind(address: &RttServerAddress, number: usize, name: Option<&str>) -> io::Result<Self> {
+        match address {
+            RttServerAddress::Tcp(address) => {
+                let port = u16::try_from(number)
+                    .ok()
+                    .and_then(|number| address.port().checked_add(number))
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            ErrorKind::InvalidInput,
+                            format!("No TCP port available for RTT channel {}", number),
+                        )
+                    })?;
+
+                let listener = TcpListener::bind(SocketAddr::new(address.ip(), port))?;
+                listener.set_nonblocking(true)?;
+
+                Ok(Self {
+                    address: listener.local_addr()?.to_string(),
+                    kind: ListenerKind::Tcp(listener),
+                })
+            }
+            #[cfg(unix)]
+            RttServerAddress::Unix(directory) => {
+                let file_name = match name {
+                    Some(name) => {
+                        let name: String = name
+                            .chars()
+                            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                            .collect();
+                        format!("{}-{}.sock", number, name)
+                    }
+                    None => format!("{}.sock", number),
+                };
+                let path = directory.join(file_name);
+
+                fs::create_dir_all(directory)?;
+
+                // A socket left over from a previous run would prevent binding the address.
+                // Other files are not removed, binding fails for them instead.
+                match fs::symlink_metadata(&path) {
+                    Ok(metadata) if metadata.file_type().is_socket() => fs::remove_file(&path)?,
+                    _ => {}
+                }
+
+                let listener = UnixListener::bind(&path)?;
+                listener.set_nonblocking(true)?;
+
+                Ok(Self {
+                    address: path.display().to_string(),
+                    kind: ListenerKind::Unix(UnixSocket { listener, path }),
+                })
+            }
+        }
+    }
+
+    /// Accepts a new client, if there is one.
+    fn accept(&self) -> io::Result<Option<Stream>> {
+        let stream = match &self.kind {
+            ListenerKind::Tcp(listener) => listener.accept().map(|(stream, _)| {
+                stream.set_nonblocking(true)?;
+                Ok(Stream::Tcp(stream))
+            }),
+            #[cfg(unix)]
+            ListenerKind::Unix(socket) => socket.listener.accept().map(|(stream, _)| {
+                stream.set_nonblocking(true)?;
+                Ok(Stream::Unix(stream))
+            }),
+        };
+
+        match stream {
+            Ok(stream) => stream.map(Some),
+            Err(error) if error.kind() == ErrorKind::WouldBlock => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// A Unix domain socket, whose file is removed when the socket is closed.
+#[cfg(unix)]
+#[derive(Debug)]
+struct UnixSocket {
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+#[cfg(unix)]
+impl Drop for UnixSocket {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[derive(Debug)]
+enum Stream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.read(buf),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.write(buf),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.flush(),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::net::SocketAddrV4;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn parse_address() {
+        assert_eq!(
+            "1234".parse::<RttServerAddress>(),
+            Ok(RttServerAddress::Tcp(SocketAddr::from((
+                Ipv4Addr::LOCALHOST,
+                1234
+            ))))
+        );
+        assert_eq!(
+            "0.0.0.0:19021".parse::<RttServerAddress>(),
+            Ok(RttServerAddress::Tcp(SocketAddr::V4(SocketAddrV4::new(
+                Ipv4Addr::UNSPECIFIED,
+                19021
+            ))))
+        );
+        #[cfg(unix)]
+        assert_eq!(
+            "unix:/tmp/rtt".parse::<RttServerAddress>(),
+            Ok(RttServerAddress::Unix(PathBuf::from("/tmp/rtt")))
+        );
+        assert!("localhost".parse::<RttServerAddress>().is_err());
+        assert!("70000".parse::<RttServerAddress>().is_err());
+    }
+
+    /// Waits until the listener accepts a client.
+    fn accept(listener: &Listener) -> Stream {
+        let started = Instant::now();
+
+        loop {
+            if let Some(stream) = listener.accept().unwrap() {
+                return stream;
+            }
+
+            assert!(started.elapsed() < Duration::from_secs(5), "No client");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn accept_tcp_client() {
+        let address = RttServerAddress::Tcp(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)));
+        let listener = Listener::bind(&address, 0, Some("Terminal")).unwrap();
+
+        // No client connected yet.
+        assert!(listener.accept().unwrap().is_none());
+
+        let mut remote = TcpStream::connect(&listener.address).unwrap();
+        let mut client = Client {
+            stream: accept(&listener),
+            pending_data: b"up".to_vec(),
+        };
+
+        assert!(client.send());
+        let mut received = [0u8; 2];
+        remote.read_exact(&mut received).unwrap();
+        assert_eq!(&received, b"up");
+
+        remote.write_all(b"down").unwrap();
+        let mut buffer = vec![0u8; 16];
+        let mut down_data = Vec::new();
+        let mut transferred = false;
+        let started = Instant::now();
+        while down_data.len() < 4 && started.elapsed() < Duration::from_secs(5) {
+            assert!(client.receive(&mut buffer, &mut down_data, &mut transferred));
+        }
+        assert_eq!(down_data, b"down");
+        assert!(transferred);
+
+        drop(remote);
+        let started = Instant::now();
+        while client.receive(&mut buffer, &mut down_data, &mut transferred) {
+            assert!(started.elapsed() < Duration::from_secs(5), "No disconnect");
+        }
+    }
+
+    #[test]
+    fn down_data_is_limited() {
+        let address = RttServerAddress::Tcp(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)));
+        let listener = Listener::bind(&address, 0, None).unwrap();
+
+        let mut remote = TcpStream::connect(&listener.address).unwrap();
+        let mut client = Client {
+            stream: accept(&listener),
+            pending_data: Vec::new(),
+        };
+
+        remote.write_all(b"data").unwrap();
+
+        // The client is not read while the down data is full.
+        let mut buffer = vec![0u8; 16];
+        let mut down_data = vec![0u8; MAX_DOWN_DATA];
+        let mut transferred = false;
+        assert!(client.receive(&mut buffer, &mut down_data, &mut transferred));
+        assert_eq!(down_data.len(), MAX_DOWN_DATA);
+        assert!(!transferred);
+
+        down_data.truncate(MAX_DOWN_DATA - 2);
+        let started = Instant::now();
+        while down_data.len() < MAX_DOWN_DATA && started.elapsed() < Duration::from_secs(5) {
+            assert!(client.receive(&mut buffer, &mut down_data, &mut transferred));
+        }
+        assert_eq!(&down_data[MAX_DOWN_DATA - 2..], b"da");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_socket_does_not_replace_other_files() {
+        let directory =
+            std::env::temp_dir().join(format!("probe-rs-rtt-test-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let address = RttServerAddress::Unix(directory.clone());
+
+        // A socket left over from a previous run is replaced.
+        let listener = Listener::bind(&address, 0, None).unwrap();
+        std::mem::forget(listener);
+        let listener = Listener::bind(&address, 0, None).unwrap();
+        drop(listener);
+        assert!(!directory.join("0.sock").exists());
+
+        let file = directory.join("1-Data.sock");
+        fs::write(&file, b"not a socket").unwrap();
+        assert!(Listener::bind(&address, 1, Some("Data")).is_err());
+        assert_eq!(fs::read(&file).unwrap(), b"not a socket");
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
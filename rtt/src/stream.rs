@@ -0,0 +1,566 @@
+//! Streams RTT channels on a background thread.
+//!
+//! The stream polls the up channels and writes the down channels, so the caller only deals with
+//! [`std::sync::mpsc`] channels. The session is shared with the thread, and the thread only locks
+//! it while the channels are polled, so the caller can use the session in between.
+//!
+//! While the core is halted, the target does not access the channels, so the stream pauses itself
+//! once it read the data which was written before the core halted.
+
+use crate::{DownChannel, Error, UpChannel};
+use probe_rs::{Core, Session};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, TryLockError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+/// Data read from an up channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RttData {
+    /// The time at which the host read the data from the target.
+    pub timestamp: SystemTime,
+    /// The data, which is never empty.
+    pub data: Vec<u8>,
+}
+
+/// Configures the channels of an [`RttStream`] before it is started.
+#[derive(Debug)]
+pub struct RttStreamBuilder {
+    poll_interval: Duration,
+    channels: StreamedChannels,
+}
+
+impl RttStreamBuilder {
+    /// Streams the data of `channel`, and returns the receiver of the data.
+    ///
+    /// Once the receiver is dropped, the channel is no longer read, and its data stays in the
+    /// target buffer.
+    pub fn up_channel(&mut self, channel: UpChannel) -> Receiver<RttData> {
+        let (sender, receiver) = mpsc::channel();
+        self.channels
+            .up_channels
+            .push(StreamedUpChannel { channel, sender });
+        receiver
+    }
+
+    /// Writes the data sent through the returned sender to `channel`.
+    ///
+    /// Data which does not fit into the target buffer is kept, and written once the target
+    /// consumed the previous data.
+    pub fn down_channel(&mut self, channel: DownChannel) -> Sender<Vec<u8>> {
+        let (sender, receiver) = mpsc::channel();
+        self.channels.down_channels.push(StreamedDownChannel {
+            channel,
+            receiver,
+            pending_data: Vec::new(),
+        });
+        sender
+    }
+
+    /// Sets the time the thread waits after polling the channels without transferring any data.
+    /// The default is 10ms.
+    ///
+    /// If accessing the target fails, the thread waits twice as long after each failed attempt,
+    /// up to one second.
+    pub fn poll_interval(&mut self, poll_interval: Duration) -> &mut Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Starts a thread which streams the channels, and accesses core `core_index` of `session`.
+    pub fn start(
+        self,
+        session: Arc<Mutex<Session>>,
+        core_index: usize,
+    ) -> Result<RttStream, Error> {
+        let control = Arc::new(StreamControl {
+            paused: AtomicBool::new(false),
+            stop: AtomicBool::new(false),
+            running: AtomicBool::new(true),
+        });
+
+        let thread_control = control.clone();
+        let poll_interval = self.poll_interval;
+        let mut channels = self.channels;
+        let thread = thread::Builder::new()
+            .name("rtt-stream".to_string())
+            .spawn(move || {
+                channels.run(&session, core_index, poll_interval, &thread_control);
+                thread_control.running.store(false, Ordering::Release);
+            })?;
+
+        Ok(RttStream {
+            control,
+            thread: Some(thread),
+        })
+    }
+}
+
+/// Access to the channels of a target.
+///
+/// This is implemented for [`Core`], and by the tests to stream channels without a target.
+trait ChannelAccess {
+    fn read(&mut self, channel: &UpChannel, buffer: &mut [u8]) -> Result<usize, Error>;
+
+    fn write(&mut self, channel: &DownChannel, data: &[u8]) -> Result<usize, Error>;
+
+    fn halted(&mut self) -> Result<bool, Error>;
+}
+
+impl ChannelAccess for Core<'_> {
+    fn read(&mut self, channel: &UpChannel, buffer: &mut [u8]) -> Result<usize, Error> {
+        channel.read(self, buffer)
+    }
+
+    fn write(&mut self, channel: &DownChannel, data: &[u8]) -> Result<usize, Error> {
+        channel.write(self, data)
+    }
+
+    fn halted(&mut self) -> Result<bool, Error> {
+        Ok(self.core_halted()?)
+    }
+}
+
+/// The streamed channels, and the data which was not transferred yet.
+#[derive(Debug)]
+struct StreamedChannels {
+    up_channels: Vec<StreamedUpChannel>,
+    down_channels: Vec<StreamedDownChannel>,
+    buffer: Vec<u8>,
+    /// `true` if the channels were polled without transferring any data while the core is halted.
+    drained_while_halted: bool,
+}
+
+impl StreamedChannels {
+    /// The longest time the thread waits before it accesses the target again after an error.
+    const MAX_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+    fn run(
+        &mut self,
+        session: &Mutex<Session>,
+        core_index: usize,
+        poll_interval: Duration,
+        control: &StreamControl,
+    ) {
+        let mut retry_delay = poll_interval;
+
+        while !control.stop.load(Ordering::Acquire) {
+            let result = {
+                // The thread does not wait for the session, so the stream can be stopped by a
+                // caller which holds the lock.
+                let mut session = match session.try_lock() {
+                    Ok(session) => session,
+                    Err(TryLockError::Poisoned(error)) => error.into_inner(),
+                    Err(TryLockError::WouldBlock) => {
+                        thread::sleep(poll_interval);
+                        continue;
+                    }
+                };
+
+                // The flag is checked while the session is locked, so once the session was locked
+                // after `RttStream::pause`, the channels are not accessed anymore.
+                if control.paused.load(Ordering::Acquire) {
+                    Ok(false)
+                } else {
+                    session
+                        .core(core_index)
+                        .map_err(Error::from)
+                        .and_then(|mut core| self.poll(&mut core))
+                }
+            };
+
+            match result {
+                Ok(transferred) => {
+                    retry_delay = poll_interval;
+                    if !transferred {
+                        thread::sleep(poll_interval);
+                    }
+                }
+                // The probe may be unavailable for a while, e.g. while the target is reset, so
+                // the channels are polled again, with an increasing delay.
+                Err(error) => {
+                    log::warn!(
+                        "Unable to stream the RTT channels, trying again in {:?}: {}",
+                        retry_delay,
+                        error
+                    );
+                    thread::sleep(retry_delay);
+                    retry_delay = (retry_delay * 2).min(Self::MAX_RETRY_DELAY);
+                }
+            }
+        }
+    }
+
+    /// Transfers the available data of all channels. Returns `true` if any data was transferred.
+    fn poll(&mut self, target: &mut impl ChannelAccess) -> Result<bool, Error> {
+        let halted = target.halted()?;
+        if halted && self.drained_while_halted {
+            return Ok(false);
+        }
+
+        let mut transferred = false;
+
+        // The channels are kept if accessing the target fails, so they can be polled again.
+        let mut index = 0;
+        while index < self.up_channels.len() {
+            let up_channel = &self.up_channels[index];
+            let count = target.read(&up_channel.channel, &mut self.buffer)?;
+            if count > 0 {
+                transferred = true;
+
+                let data = RttData {
+                    timestamp: SystemTime::now(),
+                    data: self.buffer[..count].to_vec(),
+                };
+                if up_channel.sender.send(data).is_err() {
+                    log::debug!(
+                        "The receiver of RTT channel {} is gone, it is no longer read",
+                        up_channel.channel.number()
+                    );
+                    self.up_channels.remove(index);
+                    continue;
+                }
+            }
+
+            index += 1;
+        }
+
+        for down_channel in self.down_channels.iter_mut() {
+            for data in down_channel.receiver.try_iter() {
+                down_channel.pending_data.extend_from_slice(&data);
+            }
+
+            if !down_channel.pending_data.is_empty() {
+                let count = target.write(&down_channel.channel, &down_channel.pending_data)?;
+                down_channel.pending_data.drain(..count);
+                transferred |= count > 0;
+            }
+        }
+
+        self.drained_while_halted = halted && !transferred;
+
+        Ok(transferred)
+    }
+}
+
+#[derive(Debug)]
+struct StreamedUpChannel {
+    channel: UpChannel,
+    sender: Sender<RttData>,
+}
+
+#[derive(Debug)]
+struct StreamedDownChannel {
+    channel: DownChannel,
+    receiver: Receiver<Vec<u8>>,
+    /// Data which did not fit into the target buffer yet.
+    pending_data: Vec<u8>,
+}
+
+/// The state shared between an [`RttStream`] and its thread.
+#[derive(Debug)]
+struct StreamControl {
+    paused: AtomicBool,
+    stop: AtomicBool,
+    running: AtomicBool,
+}
+
+/// Streams RTT channels on a background thread.
+///
+/// ```no_run
+/// use std::sync::{Arc, Mutex};
+/// use probe_rs::{Probe, Permissions};
+/// use probe_rs_rtt::{Rtt, RttStream};
+///
+/// let probe = Probe::list_all()[0].open()?;
+/// let mut session = probe.attach("somechip", Permissions::default())?;
+/// let memory_map = session.target().memory_map.clone();
+///
+/// let mut core = session.core(0)?;
+/// let mut rtt = Rtt::attach(&mut core, &memory_map)?;
+/// drop(core);
+///
+/// let session = Arc::new(Mutex::new(session));
+/// let mut stream = RttStream::builder();
+/// let output = rtt.up_channels().take(0).map(|channel| stream.up_channel(channel));
+/// let input = rtt.down_channels().take(0).map(|channel| stream.down_channel(channel));
+/// let stream = stream.start(session.clone(), 0)?;
+///
+/// if let Some(input) = input {
+///     input.send(b"Hello, computer!\n".to_vec())?;
+/// }
+///
+/// if let Some(output) = output {
+///     for data in output.iter().take(10) {
+///         println!("{:?}: {:?}", data.timestamp, data.data);
+///     }
+/// }
+///
+/// // Don't read the target memory while the flash is written.
+/// stream.paused(|| {
+///     let session = session.lock().unwrap();
+///     // ...
+/// });
+///
+/// stream.stop();
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// The thread stops when the stream is stopped or dropped. Errors accessing the target are logged,
+/// and the channels are polled again after a delay. The receivers of the up channels are
+/// disconnected once the thread stopped.
+#[derive(Debug)]
+pub struct RttStream {
+    control: Arc<StreamControl>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl RttStream {
+    /// Creates a builder for a stream.
+    pub fn builder() -> RttStreamBuilder {
+        RttStreamBuilder {
+            poll_interval: Duration::from_millis(10),
+            channels: StreamedChannels {
+                up_channels: Vec::new(),
+                down_channels: Vec::new(),
+                buffer: vec![0; 1024],
+                drained_while_halted: false,
+            },
+        }
+    }
+
+    /// Stops accessing the target, e.g. while the flash is written.
+    ///
+    /// The thread finishes a poll which is in progress, so the channels are no longer accessed
+    /// once the caller locked the session. Data sent to the down channels is kept until the
+    /// stream is resumed.
+    pub fn pause(&self) {
+        self.control.paused.store(true, Ordering::Release);
+    }
+
+    /// Continues accessing the target after [`RttStream::pause`].
+    pub fn resume(&self) {
+        self.control.paused.store(false, Ordering::Release);
+    }
+
+    /// Calls `operation` while the stream is paused, see [`RttStream::pause`].
+    ///
+    /// The stream continues afterwards, unless it was paused before.
+    pub fn paused<R>(&self, operation: impl FnOnce() -> R) -> R {
+        let was_paused = self.control.paused.swap(true, Ordering::AcqRel);
+        let result = operation();
+        if !was_paused {
+            self.resume();
+        }
+        result
+    }
+
+    /// Returns `true` if the stream is paused.
+    pub fn is_paused(&self) -> bool {
+        self.control.paused.load(Ordering::Acquire)
+    }
+
+    /// Returns `true` if the thread is still running.
+    pub fn is_running(&self) -> bool {
+        self.control.running.load(Ordering::Acquire)
+    }
+
+    /// Stops the thread, and waits until it stopped.
+    pub fn stop(mut self) {
+        self.join();
+    }
+
+    fn join(&mut self) {
+        self.control.stop.store(true, Ordering::Release);
+
+        // If the thread panicked, the panic was already reported.
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for RttStream {
+    fn drop(&mut self) {
+        self.join();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::channel::Channel;
+    use std::collections::HashMap;
+
+    /// A target whose up channels contain the given data, and whose down channels accept
+    /// `down_capacity` bytes.
+    #[derive(Default)]
+    struct FakeTarget {
+        up_data: HashMap<usize, Vec<u8>>,
+        down_data: HashMap<usize, Vec<u8>>,
+        down_capacity: usize,
+        halted: bool,
+    }
+
+    impl ChannelAccess for FakeTarget {
+        fn read(&mut self, channel: &UpChannel, buffer: &mut [u8]) -> Result<usize, Error> {
+            let data = self.up_data.entry(channel.number()).or_default();
+            let count = data.len().min(buffer.len());
+            buffer[..count].copy_from_slice(&data[..count]);
+            data.drain(..count);
+            Ok(count)
+        }
+
+        fn write(&mut self, channel: &DownChannel, data: &[u8]) -> Result<usize, Error> {
+            let written = self.down_data.entry(channel.number()).or_default();
+            let count = data
+                .len()
+                .min(self.down_capacity.saturating_sub(written.len()));
+            written.extend_from_slice(&data[..count]);
+            Ok(count)
+        }
+
+        fn halted(&mut self) -> Result<bool, Error> {
+            Ok(self.halted)
+        }
+    }
+
+    fn up_channel(number: usize) -> UpChannel {
+        UpChannel(Channel::test_channel(number))
+    }
+
+    fn down_channel(number: usize) -> DownChannel {
+        DownChannel(Channel::test_channel(number))
+    }
+
+    #[test]
+    fn up_channels_have_their_own_receivers() {
+        let mut builder = RttStream::builder();
+        let receiver_0 = builder.up_channel(up_channel(0));
+        let receiver_1 = builder.up_channel(up_channel(1));
+        let mut channels = builder.channels;
+
+        let mut target = FakeTarget::default();
+        target.up_data.insert(0, b"zero".to_vec());
+        target.up_data.insert(1, b"one".to_vec());
+
+        assert!(channels.poll(&mut target).unwrap());
+        assert_eq!(receiver_0.try_recv().unwrap().data, b"zero");
+        assert_eq!(receiver_1.try_recv().unwrap().data, b"one");
+
+        // Nothing is sent for channels without data.
+        target.up_data.insert(1, b"more".to_vec());
+        assert!(channels.poll(&mut target).unwrap());
+        assert!(receiver_0.try_recv().is_err());
+        assert_eq!(receiver_1.try_recv().unwrap().data, b"more");
+
+        assert!(!channels.poll(&mut target).unwrap());
+    }
+
+    #[test]
+    fn up_channel_data_is_timestamped() {
+        let mut builder = RttStream::builder();
+        let receiver = builder.up_channel(up_channel(0));
+        let mut channels = builder.channels;
+
+        let mut target = FakeTarget::default();
+        target.up_data.insert(0, b"first".to_vec());
+
+        let before = SystemTime::now();
+        channels.poll(&mut target).unwrap();
+        let after = SystemTime::now();
+
+        let data = receiver.try_recv().unwrap();
+        assert!(before <= data.timestamp && data.timestamp <= after);
+    }
+
+    #[test]
+    fn dropped_receiver_stops_reading() {
+        let mut builder = RttStream::builder();
+        drop(builder.up_channel(up_channel(0)));
+        let mut channels = builder.channels;
+
+        let mut target = FakeTarget::default();
+        target.up_data.insert(0, b"first".to_vec());
+        channels.poll(&mut target).unwrap();
+
+        target.up_data.insert(0, b"second".to_vec());
+        assert!(!channels.poll(&mut target).unwrap());
+        assert_eq!(target.up_data[&0], b"second");
+    }
+
+    #[test]
+    fn down_channel_data_is_kept_until_it_fits() {
+        let mut builder = RttStream::builder();
+        let sender_0 = builder.down_channel(down_channel(0));
+        let sender_1 = builder.down_channel(down_channel(1));
+        let mut channels = builder.channels;
+
+        let mut target = FakeTarget {
+            down_capacity: 4,
+            ..Default::default()
+        };
+
+        sender_0.send(b"abc".to_vec()).unwrap();
+        sender_0.send(b"def".to_vec()).unwrap();
+        sender_1.send(b"x".to_vec()).unwrap();
+        assert!(channels.poll(&mut target).unwrap());
+        assert_eq!(target.down_data[&0], b"abcd");
+        assert_eq!(target.down_data[&1], b"x");
+
+        // The target consumed the data.
+        target.down_data.clear();
+        assert!(channels.poll(&mut target).unwrap());
+        assert_eq!(target.down_data[&0], b"ef");
+
+        target.down_data.clear();
+        assert!(!channels.poll(&mut target).unwrap());
+        assert!(target.down_data.is_empty());
+    }
+
+    #[test]
+    fn halted_core_is_drained_once() {
+        let mut builder = RttStream::builder();
+        let receiver = builder.up_channel(up_channel(0));
+        let mut channels = builder.channels;
+
+        let mut target = FakeTarget {
+            halted: true,
+            ..Default::default()
+        };
+        target.up_data.insert(0, b"before the halt".to_vec());
+
+        assert!(channels.poll(&mut target).unwrap());
+        assert_eq!(receiver.try_recv().unwrap().data, b"before the halt");
+        assert!(!channels.poll(&mut target).unwrap());
+
+        // The channels are not accessed until the core runs again.
+        target.up_data.insert(0, b"after the halt".to_vec());
+        assert!(!channels.poll(&mut target).unwrap());
+        assert!(receiver.try_recv().is_err());
+
+        target.halted = false;
+        assert!(channels.poll(&mut target).unwrap());
+        assert_eq!(receiver.try_recv().unwrap().data, b"after the halt");
+    }
+
+    #[test]
+    fn paused_restores_the_previous_state() {
+        let stream = RttStream {
+            control: Arc::new(StreamControl {
+                paused: AtomicBool::new(false),
+                stop: AtomicBool::new(false),
+                running: AtomicBool::new(false),
+            }),
+            thread: None,
+        };
+
+        assert!(stream.paused(|| stream.is_paused()));
+        assert!(!stream.is_paused());
+
+        stream.pause();
+        stream.paused(|| {});
+        assert!(stream.is_paused());
+    }
+}
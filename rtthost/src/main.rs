@@ -1,12 +1,14 @@
 use probe_rs::Permissions;
-use probe_rs::{config::TargetSelector, DebugProbeInfo, Probe};
-use probe_rs_rtt::{Channels, Rtt, RttChannel, ScanRegion};
+use probe_rs::{config::TargetSelector, Core, DebugProbeInfo, Probe};
+use probe_rs_rtt::{Channels, Rtt, RttChannel, RttServer, RttServerAddress, RttStream, ScanRegion};
 
 use clap::Parser;
 use std::io::prelude::*;
 use std::io::{stdin, stdout};
-use std::sync::mpsc::{channel, Receiver};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::Duration;
 
 #[derive(Debug, PartialEq, Eq)]
 enum ProbeInfo {
@@ -98,6 +100,12 @@ struct Opts {
         parse(try_from_str=parse_scan_region),
         help = "Memory region to scan for control block. You can specify either an exact starting address '0x1000' or a range such as '0x0000..0x1000'. Both decimal and hex are accepted.")]
     scan_region: ScanRegion,
+
+    #[clap(
+        long,
+        help = "Serve each channel on a socket instead of using stdin and stdout. Specify a port, where channel N is served on the port plus N, an '<ip>:<port>' address, or 'unix:<directory>' for Unix domain sockets."
+    )]
+    server: Option<RttServerAddress>,
 }
 
 fn main() {
@@ -189,6 +197,10 @@ fn run() -> i32 {
         return 0;
     }
 
+    if let Some(address) = &opts.server {
+        return run_server(&mut core, &mut rtt, address);
+    }
+
     let up_channel = if let Some(up) = opts.up {
         let chan = rtt.up_channels().take(up);
 
@@ -215,24 +227,28 @@ fn run() -> i32 {
         rtt.down_channels().take(0)
     };
 
-    let stdin = down_channel.as_ref().map(|_| stdin_channel());
-
     eprintln!("Found control block at 0x{:08x}", rtt.ptr());
 
-    let mut up_buf = [0u8; 1024];
-    let mut down_buf = vec![];
+    // The stream accesses the core on its own thread.
+    drop(core);
+    let mut stream = RttStream::builder();
 
-    loop {
-        if let Some(up_channel) = up_channel.as_ref() {
-            let count = match up_channel.read(&mut core, up_buf.as_mut()) {
-                Ok(count) => count,
-                Err(err) => {
-                    eprintln!("\nError reading from RTT: {}", err);
-                    return 1;
-                }
-            };
+    let up_receiver = up_channel.map(|up_channel| stream.up_channel(up_channel));
+    if let Some(down_channel) = down_channel {
+        stdin_channel(stream.down_channel(down_channel));
+    }
 
-            match stdout().write_all(&up_buf[..count]) {
+    let stream = match stream.start(Arc::new(Mutex::new(session)), 0) {
+        Ok(stream) => stream,
+        Err(err) => {
+            eprintln!("Error starting RTT: {}", err);
+            return 1;
+        }
+    };
+
+    if let Some(up_receiver) = up_receiver {
+        for up_data in up_receiver.iter() {
+            match stdout().write_all(&up_data.data) {
                 Ok(_) => {
                     stdout().flush().ok();
                 }
@@ -242,24 +258,46 @@ fn run() -> i32 {
                 }
             }
         }
+    }
 
-        if let (Some(down_channel), Some(stdin)) = (down_channel.as_ref(), &stdin) {
-            if let Ok(bytes) = stdin.try_recv() {
-                down_buf.extend_from_slice(bytes.as_slice());
-            }
+    // Without an up channel, the standard input is written until the program is stopped.
+    // The stream only stops on its own if its thread panicked.
+    while stream.is_running() {
+        thread::sleep(Duration::from_millis(100));
+    }
 
-            if !down_buf.is_empty() {
-                let count = match down_channel.write(&mut core, down_buf.as_mut()) {
-                    Ok(count) => count,
-                    Err(err) => {
-                        eprintln!("\nError writing to RTT: {}", err);
-                        return 1;
-                    }
-                };
+    stream.stop();
+    eprintln!("\nRTT streaming stopped unexpectedly");
+    1
+}
 
-                if count > 0 {
-                    down_buf.drain(..count);
-                }
+fn run_server(core: &mut Core, rtt: &mut Rtt, address: &RttServerAddress) -> i32 {
+    eprintln!("Found control block at 0x{:08x}", rtt.ptr());
+
+    let mut server = match RttServer::new(rtt, address) {
+        Ok(server) => server,
+        Err(err) => {
+            eprintln!("Error starting RTT server: {}", err);
+            return 1;
+        }
+    };
+
+    for (number, name, address) in server.channels() {
+        eprintln!(
+            "  {}: {} on {}",
+            number,
+            name.unwrap_or("(no name)"),
+            address
+        );
+    }
+
+    loop {
+        match server.poll(core) {
+            Ok(true) => {}
+            Ok(false) => thread::sleep(Duration::from_millis(10)),
+            Err(err) => {
+                eprintln!("\nError serving RTT: {}", err);
+                return 1;
             }
         }
     }
@@ -299,16 +337,18 @@ fn list_channels(channels: &Channels<impl RttChannel>) {
     }
 }
 
-fn stdin_channel() -> Receiver<Vec<u8>> {
-    let (tx, rx) = channel();
-
+/// Forwards the standard input to `tx` on a separate thread, as reading it blocks.
+fn stdin_channel(tx: Sender<Vec<u8>>) {
     thread::spawn(move || {
         let mut buf = [0u8; 1024];
 
         loop {
             match stdin().read(&mut buf[..]) {
                 Ok(count) => {
-                    tx.send(buf[..count].to_vec()).unwrap();
+                    // The stream stopped, so nobody consumes the input anymore.
+                    if tx.send(buf[..count].to_vec()).is_err() {
+                        break;
+                    }
                 }
                 Err(err) => {
                     eprintln!("Error reading from stdin, input disabled: {}", err);
@@ -317,6 +357,4 @@ fn stdin_channel() -> Receiver<Vec<u8>> {
             }
         }
     });
-
-    rx
 }
@@ -39,7 +39,7 @@ pub fn test_stepping(core: &mut Core, memory_regions: &[MemoryRegion]) -> Result
 
     let core_information = core.step()?;
 
-    assert_eq!(core_information.pc, code_load_address + 2);
+    assert_eq!(core_information.pc, u64::from(code_load_address) + 2);
 
     let core_status = core.status()?;
 
@@ -157,6 +157,8 @@ fn create_core(processor: &Processor) -> Result<ProbeCore> {
             Architecture::Arm => CoreAccessOptions::Arm(ArmCoreAccessOptions {
                 ap: processor.ap,
                 psel: 0,
+                debug_base: None,
+                cti_base: None,
             }),
             Architecture::Riscv => CoreAccessOptions::Riscv(RiscvCoreAccessOptions {}),
         },